@@ -83,6 +83,15 @@ impl tk::Model for Model {
             .save(folder, name)
     }
 
+    fn serialize_parts(&self) -> tk::Result<Vec<(String, Vec<u8>)>> {
+        self.model
+            .as_ref()
+            .ok_or("Uninitialized Model")?
+            .read()
+            .unwrap()
+            .serialize_parts()
+    }
+
     fn get_trainer(&self) -> Self::Trainer {
         self.model
             .as_ref()
@@ -92,6 +101,10 @@ impl tk::Model for Model {
             .get_trainer()
             .into()
     }
+
+    fn unk_token(&self) -> Option<String> {
+        self.model.as_ref()?.read().unwrap().unk_token()
+    }
 }
 
 declare_types! {
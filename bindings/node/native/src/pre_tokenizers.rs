@@ -164,7 +164,7 @@ fn whitespace(mut cx: FunctionContext) -> JsResult<JsPreTokenizer> {
 fn whitespace_split(mut cx: FunctionContext) -> JsResult<JsPreTokenizer> {
     let mut pretok = JsPreTokenizer::new::<_, JsPreTokenizer, _>(&mut cx, vec![])?;
     let guard = cx.lock();
-    pretok.borrow_mut(&guard).pretok = Some(tk::pre_tokenizers::whitespace::WhitespaceSplit.into());
+    pretok.borrow_mut(&guard).pretok = Some(tk::pre_tokenizers::whitespace::WhitespaceSplit::default().into());
     Ok(pretok)
 }
 
@@ -320,10 +320,10 @@ mod test {
         }
 
         let js_seq: JsPreTokenizerWrapper =
-            Sequence::new(vec![WhitespaceSplit.into(), Whitespace::default().into()]).into();
+            Sequence::new(vec![WhitespaceSplit::default().into(), Whitespace::default().into()]).into();
         let js_wrapper_ser = serde_json::to_string(&js_seq).unwrap();
         let rs_wrapped = PreTokenizerWrapper::Sequence(Sequence::new(vec![
-            WhitespaceSplit.into(),
+            WhitespaceSplit::default().into(),
             Whitespace::default().into(),
         ]));
         let rs_ser = serde_json::to_string(&rs_wrapped).unwrap();
@@ -335,7 +335,7 @@ mod test {
         let js_ser = serde_json::to_string(&js_seq).unwrap();
         assert_eq!(js_wrapper_ser, js_ser);
 
-        let rs_seq = Sequence::new(vec![WhitespaceSplit.into(), Whitespace::default().into()]);
+        let rs_seq = Sequence::new(vec![WhitespaceSplit::default().into(), Whitespace::default().into()]);
         let rs_ser = serde_json::to_string(&rs_seq).unwrap();
         assert_eq!(js_wrapper_ser, rs_ser);
     }
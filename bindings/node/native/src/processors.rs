@@ -22,6 +22,13 @@ impl tk::PostProcessor for Processor {
             .added_tokens(is_pair)
     }
 
+    fn has_exact_added_tokens(&self) -> bool {
+        self.processor
+            .as_ref()
+            .expect("Uninitialized PostProcessor")
+            .has_exact_added_tokens()
+    }
+
     fn process_encodings(
         &self,
         encodings: Vec<Encoding>,
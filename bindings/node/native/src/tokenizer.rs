@@ -665,7 +665,8 @@ declare_types! {
             let guard = cx.lock();
             let added = this.borrow_mut(&guard)
                 .tokenizer.write().unwrap()
-                .add_tokens(&tokens);
+                .add_tokens(&tokens)
+                .map_err(|e| Error(format!("{}", e)))?;
 
             Ok(cx.number(added as f64).upcast())
         }
@@ -682,7 +683,8 @@ declare_types! {
             let guard = cx.lock();
             let added = this.borrow_mut(&guard)
                 .tokenizer.write().unwrap()
-                .add_special_tokens(&tokens);
+                .add_special_tokens(&tokens)
+                .map_err(|e| Error(format!("{}", e)))?;
 
             Ok(cx.number(added as f64).upcast())
         }
@@ -836,7 +838,8 @@ declare_types! {
             let model = model.borrow(&guard).clone();
             this.borrow_mut(&guard)
                 .tokenizer.write().unwrap()
-                .with_model(model);
+                .with_model(model)
+                .map_err(|e| Error(format!("{}", e)))?;
 
             Ok(cx.undefined().upcast())
         }
@@ -47,6 +47,12 @@ impl PyDecoder {
                 DecoderWrapper::Sequence(_) => {
                     Py::new(py, (PySequenceDecoder {}, base))?.into_py(py)
                 }
+                // No dedicated python class for these yet
+                DecoderWrapper::ByteFallback(_)
+                | DecoderWrapper::Fuse(_)
+                | DecoderWrapper::Replace(_)
+                | DecoderWrapper::Strip(_)
+                | DecoderWrapper::Conditional(_) => Py::new(py, base)?.into_py(py),
             },
         })
     }
@@ -40,6 +40,7 @@ impl PyModel {
             ModelWrapper::WordPiece(_) => Py::new(py, (PyWordPiece {}, base))?.into_py(py),
             ModelWrapper::WordLevel(_) => Py::new(py, (PyWordLevel {}, base))?.into_py(py),
             ModelWrapper::Unigram(_) => Py::new(py, (PyUnigram {}, base))?.into_py(py),
+            ModelWrapper::HashedModel(_) => Py::new(py, base)?.into_py(py),
         })
     }
 }
@@ -71,9 +72,17 @@ impl Model for PyModel {
         self.model.read().unwrap().save(folder, name)
     }
 
+    fn serialize_parts(&self) -> tk::Result<Vec<(String, Vec<u8>)>> {
+        self.model.read().unwrap().serialize_parts()
+    }
+
     fn get_trainer(&self) -> Self::Trainer {
         self.model.read().unwrap().get_trainer().into()
     }
+
+    fn unk_token(&self) -> Option<String> {
+        self.model.read().unwrap().unk_token()
+    }
 }
 
 impl<I> From<I> for PyModel
@@ -84,6 +84,13 @@ impl PyNormalizer {
                     }
                     NormalizerWrapper::Replace(_) => Py::new(py, (PyReplace {}, base))?.into_py(py),
                     NormalizerWrapper::Nmt(_) => Py::new(py, (PyNmt {}, base))?.into_py(py),
+                    NormalizerWrapper::CollapseWhitespace(_) => Py::new(py, base)?.into_py(py),
+                    NormalizerWrapper::Unicode(_) => Py::new(py, base)?.into_py(py),
+                    NormalizerWrapper::CleanText(_) => Py::new(py, base)?.into_py(py),
+                    NormalizerWrapper::HandleChineseChars(_) => Py::new(py, base)?.into_py(py),
+                    NormalizerWrapper::BertStripAccents(_) => Py::new(py, base)?.into_py(py),
+                    NormalizerWrapper::FullWidthToHalfWidth(_) => Py::new(py, base)?.into_py(py),
+                    NormalizerWrapper::Confusables(_) => Py::new(py, base)?.into_py(py),
                 },
             },
         })
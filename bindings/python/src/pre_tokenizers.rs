@@ -89,6 +89,9 @@ impl PyPreTokenizer {
                         PreTokenizerWrapper::UnicodeScripts(_) => {
                             Py::new(py, (PyUnicodeScripts {}, base))?.into_py(py)
                         }
+                        PreTokenizerWrapper::FixedLength(_) => Py::new(py, base)?.into_py(py),
+                        PreTokenizerWrapper::CaptureSplit(_) => Py::new(py, base)?.into_py(py),
+                        PreTokenizerWrapper::Bracketed(_) => Py::new(py, base)?.into_py(py),
                     },
                 }
             }
@@ -313,7 +316,7 @@ pub struct PyWhitespaceSplit {}
 impl PyWhitespaceSplit {
     #[new]
     fn new() -> (Self, PyPreTokenizer) {
-        (PyWhitespaceSplit {}, WhitespaceSplit.into())
+        (PyWhitespaceSplit {}, WhitespaceSplit::default().into())
     }
 }
 
@@ -745,11 +748,11 @@ mod test {
         }
 
         let py_seq: PyPreTokenizerWrapper =
-            Sequence::new(vec![Whitespace::default().into(), WhitespaceSplit.into()]).into();
+            Sequence::new(vec![Whitespace::default().into(), WhitespaceSplit::default().into()]).into();
         let py_wrapper_ser = serde_json::to_string(&py_seq).unwrap();
         let rs_wrapped = PreTokenizerWrapper::Sequence(Sequence::new(vec![
             Whitespace::default().into(),
-            WhitespaceSplit.into(),
+            WhitespaceSplit::default().into(),
         ]));
         let rs_ser = serde_json::to_string(&rs_wrapped).unwrap();
         assert_eq!(py_wrapper_ser, rs_ser);
@@ -50,6 +50,8 @@ impl PyPostProcessor {
                 Py::new(py, (PyTemplateProcessing {}, base))?.into_py(py)
             }
             PostProcessorWrapper::Sequence(_) => Py::new(py, (PySequence {}, base))?.into_py(py),
+            PostProcessorWrapper::Truncate(_) => Py::new(py, base)?.into_py(py),
+            PostProcessorWrapper::EnsureBosEos(_) => Py::new(py, base)?.into_py(py),
         })
     }
 }
@@ -59,6 +61,10 @@ impl PostProcessor for PyPostProcessor {
         self.processor.added_tokens(is_pair)
     }
 
+    fn has_exact_added_tokens(&self) -> bool {
+        self.processor.has_exact_added_tokens()
+    }
+
     fn process_encodings(
         &self,
         encodings: Vec<Encoding>,
@@ -1092,7 +1092,7 @@ impl PyTokenizer {
             })
             .collect::<PyResult<Vec<_>>>()?;
 
-        Ok(self.tokenizer.add_tokens(&tokens))
+        ToPyResult(self.tokenizer.add_tokens(&tokens)).into()
     }
 
     /// Add the given special tokens to the Tokenizer.
@@ -1129,7 +1129,7 @@ impl PyTokenizer {
             })
             .collect::<PyResult<Vec<_>>>()?;
 
-        Ok(self.tokenizer.add_special_tokens(&tokens))
+        ToPyResult(self.tokenizer.add_special_tokens(&tokens)).into()
     }
 
     /// Train the Tokenizer using the given files.
@@ -1305,8 +1305,8 @@ impl PyTokenizer {
 
     /// Set the :class:`~tokenizers.models.Model`
     #[setter]
-    fn set_model(&mut self, model: PyRef<PyModel>) {
-        self.tokenizer.with_model(model.clone());
+    fn set_model(&mut self, model: PyRef<PyModel>) -> PyResult<()> {
+        ToPyResult(self.tokenizer.with_model(model.clone()).map(|_| ())).into()
     }
 
     /// The `optional` :class:`~tokenizers.normalizers.Normalizer` in use by the Tokenizer
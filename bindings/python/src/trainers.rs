@@ -41,6 +41,7 @@ impl PyTrainer {
             TrainerWrapper::UnigramTrainer(_) => {
                 Py::new(py, (PyUnigramTrainer {}, base))?.into_py(py)
             }
+            TrainerWrapper::HashedTrainer(_) => Py::new(py, base)?.into_py(py),
         })
     }
 }
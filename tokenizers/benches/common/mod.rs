@@ -65,7 +65,7 @@ pub fn iter_bench_train<T, M, N, PT, PP, D>(
     files: Vec<String>,
 ) -> Duration
 where
-    T: Trainer<Model = M> + Sync,
+    T: Trainer<Model = M> + Send + Sync,
     M: Model + Send + Sync,
     N: Normalizer + Send + Sync,
     PT: PreTokenizer + Send + Sync,
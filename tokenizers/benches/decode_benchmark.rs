@@ -0,0 +1,56 @@
+#[macro_use]
+extern crate criterion;
+
+use std::collections::HashMap;
+
+use criterion::black_box;
+use criterion::Criterion;
+use tokenizers::models::wordlevel::WordLevel;
+use tokenizers::pre_tokenizers::metaspace::Metaspace;
+use tokenizers::Tokenizer;
+
+static VOCAB_SIZE: u32 = 10_000;
+static N_IDS: u32 = 100_000;
+
+/// A `WordLevel` tokenizer with a `Metaspace` decoder, which can decode without allocating
+/// each token
+fn create_tokenizer() -> Tokenizer {
+    let vocab: HashMap<String, u32> = (0..VOCAB_SIZE)
+        .map(|id| (format!("▁token{}", id), id))
+        .collect();
+    let model = WordLevel::builder()
+        .vocab(vocab)
+        .unk_token("▁token0".into())
+        .build()
+        .unwrap();
+    let mut tokenizer = Tokenizer::new(model);
+    tokenizer.with_decoder(Metaspace::default());
+    tokenizer
+}
+
+pub fn bench_decode(c: &mut Criterion) {
+    let tokenizer = create_tokenizer();
+    let ids = (0..N_IDS)
+        .map(|i| i * 7_919 % VOCAB_SIZE)
+        .collect::<Vec<_>>();
+
+    c.bench_function("WordLevel decode", |b| {
+        b.iter(|| black_box(tokenizer.decode(ids.clone(), false).unwrap()))
+    });
+
+    let mut out = String::new();
+    c.bench_function("WordLevel decode_into, reused buffer", |b| {
+        b.iter(|| {
+            out.clear();
+            tokenizer.decode_into(&ids, false, &mut out).unwrap();
+            black_box(out.len())
+        })
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(20);
+    targets = bench_decode
+}
+criterion_main!(benches);
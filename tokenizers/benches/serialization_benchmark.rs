@@ -0,0 +1,62 @@
+#[macro_use]
+extern crate criterion;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use criterion::black_box;
+use criterion::Criterion;
+use tokenizers::models::bpe::BPE;
+use tokenizers::Tokenizer;
+
+static VOCAB_SIZE: usize = 150_000;
+
+/// A `BPE` tokenizer with a large vocabulary, each token past the alphabet being the merge
+/// of a previous one and a letter
+fn create_tokenizer() -> Tokenizer {
+    let mut tokens: Vec<String> = ('a'..='z').map(|c| c.to_string()).collect();
+    let mut vocab: HashMap<String, u32> = tokens
+        .iter()
+        .enumerate()
+        .map(|(id, token)| (token.clone(), id as u32))
+        .collect();
+    let mut merges = vec![];
+    let mut i = 0;
+    while tokens.len() < VOCAB_SIZE {
+        let left = tokens[i / 26].clone();
+        let right = tokens[i % 26].clone();
+        let merged = format!("{}{}", left, right);
+        i += 1;
+        if vocab.contains_key(&merged) {
+            continue;
+        }
+        vocab.insert(merged.clone(), tokens.len() as u32);
+        tokens.push(merged);
+        merges.push((left, right));
+    }
+    let model = BPE::builder()
+        .vocab_and_merges(vocab, merges)
+        .build()
+        .unwrap();
+    Tokenizer::new(model)
+}
+
+pub fn bench_serialization(c: &mut Criterion) {
+    let tokenizer = create_tokenizer();
+    let json = tokenizer.to_string(false).unwrap();
+    let binary = tokenizer.to_binary().unwrap();
+
+    c.bench_function("BPE load from JSON", |b| {
+        b.iter(|| black_box(Tokenizer::from_str(&json).unwrap()))
+    });
+    c.bench_function("BPE load from binary", |b| {
+        b.iter(|| black_box(Tokenizer::from_binary(&binary).unwrap()))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_serialization
+}
+criterion_main!(benches);
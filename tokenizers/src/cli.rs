@@ -34,9 +34,9 @@ fn shell(vocab: &str, merges: &str) -> Result<()> {
         .with_pre_tokenizer(ByteLevel::default())
         .with_decoder(ByteLevel::default());
 
-    tokenizer.add_tokens(&[AddedToken::from(String::from("ing"), false).single_word(false)]);
+    tokenizer.add_tokens(&[AddedToken::from(String::from("ing"), false).single_word(false)])?;
     tokenizer
-        .add_special_tokens(&[AddedToken::from(String::from("[ENT]"), true).single_word(true)]);
+        .add_special_tokens(&[AddedToken::from(String::from("[ENT]"), true).single_word(true)])?;
 
     let stdin = io::stdin();
     let mut handle = stdin.lock();
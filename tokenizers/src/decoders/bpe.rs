@@ -1,19 +1,33 @@
+use crate::decoders::wordpiece;
 use crate::tokenizer::{Decoder, Result};
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Clone, Debug, Serialize)]
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
 /// Allows decoding Original BPE by joining all the tokens and then replacing
 /// the suffix used to identify end-of-words by whitespaces
 #[serde(tag = "type")]
 #[non_exhaustive]
 pub struct BPEDecoder {
     pub suffix: String,
+    /// Whether to cleanup some tokenization artifacts (spaces before punctuation, ...)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cleanup: bool,
+    /// The string inserted between the words, `" "` by default
+    #[serde(
+        default = "wordpiece::default_word_separator",
+        skip_serializing_if = "wordpiece::is_default_word_separator"
+    )]
+    pub word_separator: String,
 }
 
 impl BPEDecoder {
     pub fn new(suffix: String) -> Self {
-        Self { suffix }
+        Self {
+            suffix,
+            cleanup: false,
+            word_separator: wordpiece::default_word_separator(),
+        }
     }
 }
 
@@ -25,14 +39,81 @@ impl Default for BPEDecoder {
 
 impl Decoder for BPEDecoder {
     fn decode_chain(&self, tokens: Vec<String>) -> Result<Vec<String>> {
-        let n = tokens.len() - 1;
-        Ok(tokens
+        let n = tokens.len().saturating_sub(1);
+        let tokens = tokens
             .into_iter()
             .enumerate()
             .map(|(i, token)| {
-                let replacement = if i == n { "" } else { " " };
+                let replacement = if i == n { "" } else { &self.word_separator };
                 token.replace(&self.suffix, replacement)
             })
-            .collect())
+            .collect::<Vec<_>>();
+
+        if self.cleanup {
+            // The spaces end the tokens here, so we need to see them all at once
+            Ok(vec![wordpiece::cleanup(&tokens.concat())])
+        } else {
+            Ok(tokens)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode() {
+        let tokens = vec![
+            "obj</w>".to_string(),
+            ".</w>".to_string(),
+            "fi".to_string(),
+            "eld</w>".to_string(),
+            "!</w>".to_string(),
+        ];
+
+        let mut decoder = BPEDecoder::default();
+        assert_eq!(decoder.decode(tokens.clone()).unwrap(), "obj . field !");
+
+        decoder.cleanup = true;
+        assert_eq!(decoder.decode(tokens.clone()).unwrap(), "obj. field!");
+        assert_eq!(decoder.decode(vec![]).unwrap(), "");
+
+        decoder.cleanup = false;
+        decoder.word_separator = "".into();
+        assert_eq!(decoder.decode(tokens).unwrap(), "obj.field!");
+    }
+
+    #[test]
+    fn serialization() {
+        let mut decoder = BPEDecoder::default();
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        assert_eq!(serialized, r#"{"type":"BPEDecoder","suffix":"</w>"}"#);
+        assert_eq!(
+            serde_json::from_str::<BPEDecoder>(&serialized).unwrap(),
+            decoder
+        );
+
+        decoder.cleanup = true;
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"BPEDecoder","suffix":"</w>","cleanup":true}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<BPEDecoder>(&serialized).unwrap(),
+            decoder
+        );
+
+        decoder.word_separator = " | ".into();
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"BPEDecoder","suffix":"</w>","cleanup":true,"word_separator":" | "}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<BPEDecoder>(&serialized).unwrap(),
+            decoder
+        );
     }
 }
@@ -0,0 +1,156 @@
+use crate::tokenizer::{Decoder, Result};
+use crate::utils::macro_rules_attribute;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// ByteFallback is a simple trick which converts tokens looking like `<0x61>`
+/// to pure bytes, and attempts to make them into a string. If the tokens
+/// cannot be decoded you will get � instead for each inconvertable byte token
+#[macro_rules_attribute(impl_serde_type!)]
+pub struct ByteFallback;
+
+impl Default for ByteFallback {
+    fn default() -> Self {
+        Self
+    }
+}
+
+impl ByteFallback {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Parses a token of the form `<0xHH>` into its byte value
+    fn as_byte(token: &str) -> Option<u8> {
+        if token.len() == 6 && token.starts_with("<0x") && token.ends_with('>') {
+            u8::from_str_radix(&token[3..5], 16).ok()
+        } else {
+            None
+        }
+    }
+}
+
+/// Decodes the accumulated bytes as UTF-8, using one replacement character
+/// per byte if they don't form a valid sequence
+fn flush(bytes: &mut Vec<u8>, tokens: &mut Vec<String>) {
+    if bytes.is_empty() {
+        return;
+    }
+    match String::from_utf8(std::mem::take(bytes)) {
+        Ok(string) => tokens.push(string),
+        Err(err) => {
+            for _ in 0..err.as_bytes().len() {
+                tokens.push("\u{FFFD}".to_string());
+            }
+        }
+    }
+}
+
+/// Decodes the accumulated bytes at the end of `decoded`, like `flush` does, but keeping
+/// the buffer of `bytes` for the next ones
+fn flush_into(bytes: &mut Vec<u8>, decoded: &mut String) {
+    match std::str::from_utf8(bytes) {
+        Ok(string) => decoded.push_str(string),
+        Err(_) => {
+            for _ in 0..bytes.len() {
+                decoded.push('\u{FFFD}');
+            }
+        }
+    }
+    bytes.clear();
+}
+
+impl Decoder for ByteFallback {
+    fn decode_chain(&self, tokens: Vec<String>) -> Result<Vec<String>> {
+        let mut new_tokens: Vec<String> = Vec::with_capacity(tokens.len());
+        let mut bytes: Vec<u8> = vec![];
+
+        for token in tokens {
+            if let Some(byte) = Self::as_byte(&token) {
+                bytes.push(byte);
+            } else {
+                flush(&mut bytes, &mut new_tokens);
+                new_tokens.push(token);
+            }
+        }
+        flush(&mut bytes, &mut new_tokens);
+
+        Ok(new_tokens)
+    }
+
+    fn decode_batch(&self, sequences: Vec<Vec<String>>) -> Result<Vec<String>> {
+        // The same buffer is used for the bytes of all the sequences
+        let mut bytes: Vec<u8> = vec![];
+        Ok(sequences
+            .into_iter()
+            .map(|tokens| {
+                let mut decoded = String::new();
+                for token in tokens {
+                    if let Some(byte) = Self::as_byte(&token) {
+                        bytes.push(byte);
+                    } else {
+                        flush_into(&mut bytes, &mut decoded);
+                        decoded.push_str(&token);
+                    }
+                }
+                flush_into(&mut bytes, &mut decoded);
+                decoded
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode() {
+        let decoder = ByteFallback::new();
+        let res = decoder
+            .decode_chain(vec!["Hey".into(), "friend!".into()])
+            .unwrap();
+        assert_eq!(res, vec!["Hey", "friend!"]);
+
+        let res = decoder.decode_chain(vec!["<0x61>".into()]).unwrap();
+        assert_eq!(res, vec!["a"]);
+
+        // A split emoji is reassembled
+        let res = decoder
+            .decode_chain(vec![
+                "Done".into(),
+                "<0xE2>".into(),
+                "<0x9C>".into(),
+                "<0x85>".into(),
+                "!".into(),
+            ])
+            .unwrap();
+        assert_eq!(res, vec!["Done", "✅", "!"]);
+        assert_eq!(
+            decoder
+                .decode(vec!["<0xE2>".into(), "<0x9C>".into(), "<0x85>".into()])
+                .unwrap(),
+            "✅"
+        );
+
+        // Invalid sequences fall back to the replacement character
+        let res = decoder
+            .decode_chain(vec!["<0xE2>".into(), "<0x9C>".into(), "a".into()])
+            .unwrap();
+        assert_eq!(res, vec!["�", "�", "a"]);
+
+        // Things looking like byte tokens that aren't are left untouched
+        let res = decoder
+            .decode_chain(vec!["<0xZZ>".into(), "<0x612>".into()])
+            .unwrap();
+        assert_eq!(res, vec!["<0xZZ>", "<0x612>"]);
+    }
+
+    #[test]
+    fn serialization() {
+        let decoder = ByteFallback::new();
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        assert_eq!(serialized, r#"{"type":"ByteFallback"}"#);
+        let deserialized: ByteFallback = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, decoder);
+    }
+}
@@ -0,0 +1,151 @@
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::decoders::DecoderWrapper;
+use crate::tokenizer::{Decoder, Result};
+
+/// Routes the tokens through different decoders, depending on whether they match the
+/// `predicate` regex. Each run of consecutive matching tokens goes through the `then`
+/// decoder, and each run of the other ones through the `otherwise` decoder if any, or is
+/// kept as is. The decoded runs then follow each other in the original order.
+///
+/// Each run is decoded on its own, so a decoder treating the first token differently, like
+/// `Metaspace`, does so with the first token of each run.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type")]
+pub struct Conditional {
+    predicate: String,
+    #[serde(skip)]
+    regex: Regex,
+    then: Box<DecoderWrapper>,
+    otherwise: Option<Box<DecoderWrapper>>,
+}
+
+impl<'de> Deserialize<'de> for Conditional {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        enum Type {
+            Conditional,
+        }
+
+        #[derive(Deserialize)]
+        pub struct ConditionalHelper {
+            #[serde(rename = "type")]
+            _type: Type,
+            predicate: String,
+            then: DecoderWrapper,
+            #[serde(default)]
+            otherwise: Option<DecoderWrapper>,
+        }
+
+        let helper = ConditionalHelper::deserialize(deserializer)?;
+        Self::new(&helper.predicate, helper.then, helper.otherwise)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl Conditional {
+    pub fn new(
+        predicate: &str,
+        then: DecoderWrapper,
+        otherwise: Option<DecoderWrapper>,
+    ) -> Result<Self> {
+        Ok(Self {
+            predicate: predicate.to_owned(),
+            regex: Regex::new(predicate)?,
+            then: Box::new(then),
+            otherwise: otherwise.map(Box::new),
+        })
+    }
+
+    fn decode_run(&self, matching: bool, run: Vec<String>) -> Result<Vec<String>> {
+        match (matching, &self.otherwise) {
+            (true, _) => self.then.decode_chain(run),
+            (false, Some(otherwise)) => otherwise.decode_chain(run),
+            (false, None) => Ok(run),
+        }
+    }
+}
+
+impl Decoder for Conditional {
+    fn decode_chain(&self, tokens: Vec<String>) -> Result<Vec<String>> {
+        let mut decoded = Vec::with_capacity(tokens.len());
+        let mut run = vec![];
+        let mut run_matches = false;
+        for token in tokens {
+            let matches = self.regex.is_match(&token);
+            if matches != run_matches && !run.is_empty() {
+                decoded.extend(self.decode_run(run_matches, std::mem::take(&mut run))?);
+            }
+            run_matches = matches;
+            run.push(token);
+        }
+        if !run.is_empty() {
+            decoded.extend(self.decode_run(run_matches, run)?);
+        }
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoders::byte_fallback::ByteFallback;
+    use crate::normalizers::replace::Replace;
+
+    const BYTE: &str = r"^<0x[0-9A-Fa-f]{2}>$";
+
+    fn tokens(tokens: &[&str]) -> Vec<String> {
+        tokens.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn conditional() {
+        let decoder = Conditional::new(
+            BYTE,
+            ByteFallback::new().into(),
+            Some(Replace::new("▁", " ").unwrap().into()),
+        )
+        .unwrap();
+        // `<0x5F>` is a `_`, and `<0xE2>` + `<0x9C>` + `<0x85>` the bytes of `✅`
+        let input = tokens(&[
+            "<0xE2>", "<0x9C>", "<0x85>", "▁done", "▁for", "<0x5F>", "▁now",
+        ]);
+        assert_eq!(
+            decoder.decode_chain(input.clone()).unwrap(),
+            ["✅", " done", " for", "_", " now"]
+        );
+        assert_eq!(decoder.decode(input).unwrap(), "✅ done for_ now");
+
+        // The `▁` decoded from the bytes doesn't go through the other branch
+        let input = tokens(&["<0xE2>", "<0x96>", "<0x81>", "▁a"]);
+        assert_eq!(decoder.decode(input).unwrap(), "▁ a");
+
+        // Without the other branch, the tokens are kept as is
+        let decoder = Conditional::new(BYTE, ByteFallback::new().into(), None).unwrap();
+        let input = tokens(&["▁a", "<0x61>", "<0x62>", "▁c"]);
+        assert_eq!(decoder.decode_chain(input).unwrap(), ["▁a", "ab", "▁c"]);
+        assert!(decoder.decode_chain(vec![]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn serialization() {
+        let decoder = Conditional::new(BYTE, ByteFallback::new().into(), None).unwrap();
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"Conditional","predicate":"^<0x[0-9A-Fa-f]{2}>$","then":{"type":"ByteFallback"},"otherwise":null}"#
+        );
+        let deserialized: DecoderWrapper = serde_json::from_str(&serialized).unwrap();
+        assert!(matches!(deserialized, DecoderWrapper::Conditional(_)));
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), serialized);
+
+        assert!(serde_json::from_str::<Conditional>(
+            r#"{"type":"Conditional","predicate":"(","then":{"type":"Fuse"}}"#
+        )
+        .is_err());
+    }
+}
@@ -41,6 +41,18 @@ impl Default for CTC {
     }
 }
 
+/// Replaces all the occurences of `from` by `to` in `s`, like `str::replace` does, but at
+/// the end of `out` rather than in a new `String`
+fn replace_into(s: &str, from: &str, to: &str, out: &mut String) {
+    let mut last_end = 0;
+    for (start, part) in s.match_indices(from) {
+        out.push_str(&s[last_end..start]);
+        out.push_str(to);
+        last_end = start + part.len();
+    }
+    out.push_str(&s[last_end..]);
+}
+
 impl Decoder for CTC {
     fn decode_chain(&self, tokens: Vec<String>) -> Result<Vec<String>> {
         Ok(tokens
@@ -60,6 +72,36 @@ impl Decoder for CTC {
             })
             .collect())
     }
+
+    fn decode_batch(&self, sequences: Vec<Vec<String>>) -> Result<Vec<String>> {
+        // Each replacement goes from one of these buffers to the other, for all the tokens
+        let mut replaced = String::new();
+        let mut buffer = String::new();
+        let mut replace = |replaced: &mut String, from: &str, to: &str| {
+            buffer.clear();
+            replace_into(replaced, from, to, &mut buffer);
+            std::mem::swap(replaced, &mut buffer);
+        };
+
+        Ok(sequences
+            .into_iter()
+            .map(|tokens| {
+                let mut decoded = String::new();
+                for token in tokens.into_iter().dedup() {
+                    replaced.clear();
+                    replace_into(&token, &self.pad_token, "", &mut replaced);
+                    if self.cleanup {
+                        for (from, to) in wordpiece::CLEANUP_REPLACEMENTS {
+                            replace(&mut replaced, from, to);
+                        }
+                        replace(&mut replaced, &self.word_delimiter_token, " ");
+                    }
+                    decoded.push_str(&replaced);
+                }
+                decoded
+            })
+            .collect())
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,77 @@
+use std::borrow::Cow;
+
+use crate::tokenizer::{Decoder, Result};
+use crate::utils::macro_rules_attribute;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Fuse simply fuses every token into a single string.
+/// This is usually the last step of decoding, but it can also be used in a
+/// `Sequence` so that the following decoders operate on the whole string at once.
+#[macro_rules_attribute(impl_serde_type!)]
+pub struct Fuse;
+
+impl Default for Fuse {
+    fn default() -> Self {
+        Self
+    }
+}
+
+impl Fuse {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Decoder for Fuse {
+    fn decode_chain(&self, tokens: Vec<String>) -> Result<Vec<String>> {
+        Ok(vec![tokens.concat()])
+    }
+
+    fn decode_into<'v>(
+        &self,
+        ids: &[u32],
+        vocab_lookup: &dyn Fn(u32) -> Option<Cow<'v, str>>,
+        out: &mut String,
+    ) -> Result<()> {
+        for token in ids.iter().filter_map(|id| vocab_lookup(*id)) {
+            out.push_str(&token);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode() {
+        let decoder = Fuse::new();
+        let res = decoder
+            .decode_chain(vec!["Hey".into(), " friend!".into()])
+            .unwrap();
+        assert_eq!(res, vec!["Hey friend!"]);
+
+        let vocab = ["Hey", " friend!"];
+        let mut out = String::new();
+        decoder
+            .decode_into(
+                &[0, 2, 1],
+                &|id| vocab.get(id as usize).map(|t| Cow::Borrowed(*t)),
+                &mut out,
+            )
+            .unwrap();
+        assert_eq!(out, "Hey friend!");
+
+        assert_eq!(decoder.decode_chain(vec![]).unwrap(), vec![""]);
+    }
+
+    #[test]
+    fn serialization() {
+        let decoder = Fuse::new();
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        assert_eq!(serialized, r#"{"type":"Fuse"}"#);
+        let deserialized: Fuse = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, decoder);
+    }
+}
@@ -1,18 +1,29 @@
 pub mod bpe;
+pub mod byte_fallback;
+pub mod conditional;
 pub mod ctc;
+pub mod fuse;
 pub mod sequence;
+pub mod strip;
 pub mod wordpiece;
 
 // Re-export these as decoders
+pub use super::normalizers::replace;
 pub use super::pre_tokenizers::byte_level;
 pub use super::pre_tokenizers::metaspace;
 
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
 use crate::decoders::bpe::BPEDecoder;
+use crate::decoders::byte_fallback::ByteFallback;
+use crate::decoders::conditional::Conditional;
 use crate::decoders::ctc::CTC;
+use crate::decoders::fuse::Fuse;
 use crate::decoders::sequence::Sequence;
+use crate::decoders::strip::Strip;
 use crate::decoders::wordpiece::WordPiece;
+use crate::normalizers::replace::Replace;
 use crate::pre_tokenizers::byte_level::ByteLevel;
 use crate::pre_tokenizers::metaspace::Metaspace;
 use crate::{Decoder, Result};
@@ -26,6 +37,11 @@ pub enum DecoderWrapper {
     Metaspace(Metaspace),
     CTC(CTC),
     Sequence(Sequence),
+    ByteFallback(ByteFallback),
+    Fuse(Fuse),
+    Replace(Replace),
+    Strip(Strip),
+    Conditional(Conditional),
 }
 
 impl Decoder for DecoderWrapper {
@@ -37,6 +53,48 @@ impl Decoder for DecoderWrapper {
             Self::WordPiece(wp) => wp.decode_chain(tokens),
             Self::CTC(ctc) => ctc.decode_chain(tokens),
             Self::Sequence(seq) => seq.decode_chain(tokens),
+            Self::ByteFallback(bf) => bf.decode_chain(tokens),
+            Self::Fuse(fuse) => fuse.decode_chain(tokens),
+            Self::Replace(replace) => replace.decode_chain(tokens),
+            Self::Strip(strip) => strip.decode_chain(tokens),
+            Self::Conditional(conditional) => conditional.decode_chain(tokens),
+        }
+    }
+
+    fn decode_batch(&self, sequences: Vec<Vec<String>>) -> Result<Vec<String>> {
+        match self {
+            Self::BPE(bpe) => bpe.decode_batch(sequences),
+            Self::ByteLevel(bl) => bl.decode_batch(sequences),
+            Self::Metaspace(ms) => ms.decode_batch(sequences),
+            Self::WordPiece(wp) => wp.decode_batch(sequences),
+            Self::CTC(ctc) => ctc.decode_batch(sequences),
+            Self::Sequence(seq) => seq.decode_batch(sequences),
+            Self::ByteFallback(bf) => bf.decode_batch(sequences),
+            Self::Fuse(fuse) => fuse.decode_batch(sequences),
+            Self::Replace(replace) => replace.decode_batch(sequences),
+            Self::Strip(strip) => strip.decode_batch(sequences),
+            Self::Conditional(conditional) => conditional.decode_batch(sequences),
+        }
+    }
+
+    fn decode_into<'v>(
+        &self,
+        ids: &[u32],
+        vocab_lookup: &dyn Fn(u32) -> Option<Cow<'v, str>>,
+        out: &mut String,
+    ) -> Result<()> {
+        match self {
+            Self::BPE(bpe) => bpe.decode_into(ids, vocab_lookup, out),
+            Self::ByteLevel(bl) => bl.decode_into(ids, vocab_lookup, out),
+            Self::Metaspace(ms) => ms.decode_into(ids, vocab_lookup, out),
+            Self::WordPiece(wp) => wp.decode_into(ids, vocab_lookup, out),
+            Self::CTC(ctc) => ctc.decode_into(ids, vocab_lookup, out),
+            Self::Sequence(seq) => seq.decode_into(ids, vocab_lookup, out),
+            Self::ByteFallback(bf) => bf.decode_into(ids, vocab_lookup, out),
+            Self::Fuse(fuse) => fuse.decode_into(ids, vocab_lookup, out),
+            Self::Replace(replace) => replace.decode_into(ids, vocab_lookup, out),
+            Self::Strip(strip) => strip.decode_into(ids, vocab_lookup, out),
+            Self::Conditional(conditional) => conditional.decode_into(ids, vocab_lookup, out),
         }
     }
 }
@@ -47,3 +105,65 @@ impl_enum_from!(Metaspace, DecoderWrapper, Metaspace);
 impl_enum_from!(WordPiece, DecoderWrapper, WordPiece);
 impl_enum_from!(CTC, DecoderWrapper, CTC);
 impl_enum_from!(Sequence, DecoderWrapper, Sequence);
+impl_enum_from!(ByteFallback, DecoderWrapper, ByteFallback);
+impl_enum_from!(Fuse, DecoderWrapper, Fuse);
+impl_enum_from!(Replace, DecoderWrapper, Replace);
+impl_enum_from!(Strip, DecoderWrapper, Strip);
+impl_enum_from!(Conditional, DecoderWrapper, Conditional);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_batch() {
+        let decoders: Vec<DecoderWrapper> = vec![
+            BPEDecoder::default().into(),
+            ByteLevel::default().into(),
+            WordPiece::default().into(),
+            Metaspace::default().into(),
+            CTC::default().into(),
+            CTC::new("<pad>".into(), "|".into(), false).into(),
+            Sequence::new(vec![
+                ByteFallback::new().into(),
+                Fuse::new().into(),
+                Strip::new(' ', 1, 0).into(),
+            ])
+            .into(),
+            ByteFallback::new().into(),
+            Fuse::new().into(),
+            Replace::new("▁", " ").unwrap().into(),
+            Strip::new('▁', 1, 1).into(),
+            Conditional::new("^<0x", ByteFallback::new().into(), Some(Fuse::new().into()))
+                .unwrap()
+                .into(),
+        ];
+        let sequences: Vec<Vec<String>> = vec![
+            vec![],
+            vec!["▁Hey", "</w>", "friend", "##s", "!", "Ġthere", "."],
+            vec![
+                "<pad>", "h", "h", "<pad>", "i", "|", "y", "o", "u", "<pad>", " .",
+            ],
+            vec![
+                "<0xE2>", "<0x9C>", "<0x85>", "▁done", "<0xE2>", "<0x9C>", "▁", "do", "n't",
+            ],
+            vec!["<0x61>"],
+        ]
+        .into_iter()
+        .map(|tokens| tokens.into_iter().map(String::from).collect())
+        .collect();
+
+        for decoder in decoders {
+            let expected = sequences
+                .iter()
+                .map(|tokens| decoder.decode(tokens.clone()).unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                decoder.decode_batch(sequences.clone()).unwrap(),
+                expected,
+                "{:?}",
+                decoder
+            );
+        }
+    }
+}
@@ -13,6 +13,25 @@ impl Sequence {
     pub fn new(decoders: Vec<DecoderWrapper>) -> Self {
         Self { decoders }
     }
+
+    pub fn get_decoders(&self) -> &[DecoderWrapper] {
+        &self.decoders
+    }
+
+    /// Add a decoder at the end of the sequence
+    pub fn push(&mut self, decoder: DecoderWrapper) {
+        self.decoders.push(decoder);
+    }
+
+    /// Insert a decoder at the given position, shifting the ones after it
+    pub fn insert(&mut self, index: usize, decoder: DecoderWrapper) {
+        self.decoders.insert(index, decoder);
+    }
+
+    /// Remove the decoder at the given position, and return it
+    pub fn remove(&mut self, index: usize) -> DecoderWrapper {
+        self.decoders.remove(index)
+    }
 }
 
 impl Decoder for Sequence {
@@ -28,6 +47,8 @@ impl Decoder for Sequence {
 mod tests {
     use super::*;
     use crate::decoders::ctc::CTC;
+    use crate::decoders::fuse::Fuse;
+    use crate::normalizers::replace::{Replace, ReplacePattern};
     use crate::pre_tokenizers::metaspace::Metaspace;
 
     #[test]
@@ -44,4 +65,32 @@ mod tests {
         let out_tokens = decoder.decode(tokens).unwrap();
         assert_eq!(out_tokens, "Hi you");
     }
+
+    #[test]
+    fn sequence_fuse_replace() {
+        let replace = Replace::new(ReplacePattern::Regex(r"\s+".into()), " ").unwrap();
+        let tokens: Vec<String> = vec!["Hey ", " my", "  ", "friend"]
+            .into_iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        // Each token on its own
+        let decoder = Sequence::new(vec![replace.clone().into()]);
+        assert_eq!(decoder.decode(tokens.clone()).unwrap(), "Hey  my friend");
+
+        // The whole string once fused
+        let decoder = Sequence::new(vec![Fuse::new().into(), replace.into()]);
+        assert_eq!(
+            decoder.decode_chain(tokens).unwrap(),
+            vec!["Hey my friend".to_string()]
+        );
+
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"Sequence","decoders":[{"type":"Fuse"},{"type":"Replace","pattern":{"Regex":"\\s+"},"content":" "}]}"#
+        );
+        let deserialized: Sequence = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), serialized);
+    }
 }
@@ -0,0 +1,87 @@
+use crate::tokenizer::{Decoder, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+/// Strip removes up to `start` leading and `stop` trailing occurrences of the `content`
+/// character from each token, like the markers some models add around their tokens.
+#[serde(tag = "type")]
+pub struct Strip {
+    pub content: char,
+    pub start: usize,
+    pub stop: usize,
+}
+
+impl Strip {
+    pub fn new(content: char, start: usize, stop: usize) -> Self {
+        Self {
+            content,
+            start,
+            stop,
+        }
+    }
+
+    fn strip<'a>(&self, token: &'a str) -> &'a str {
+        let mut token = token;
+        for _ in 0..self.start {
+            match token.strip_prefix(self.content) {
+                Some(stripped) => token = stripped,
+                None => break,
+            }
+        }
+        for _ in 0..self.stop {
+            match token.strip_suffix(self.content) {
+                Some(stripped) => token = stripped,
+                None => break,
+            }
+        }
+        token
+    }
+}
+
+impl Decoder for Strip {
+    fn decode_chain(&self, tokens: Vec<String>) -> Result<Vec<String>> {
+        Ok(tokens
+            .iter()
+            .map(|token| self.strip(token).to_owned())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoders::DecoderWrapper;
+
+    #[test]
+    fn decode() {
+        let decoder = Strip::new('▁', 1, 0);
+        let res = decoder
+            .decode_chain(vec!["▁Hey".into(), "▁▁friend".into(), "!".into()])
+            .unwrap();
+        assert_eq!(res, vec!["Hey", "▁friend", "!"]);
+        assert_eq!(
+            decoder
+                .decode(vec!["▁Hey".into(), "▁friend".into()])
+                .unwrap(),
+            "Heyfriend"
+        );
+
+        let decoder = Strip::new('H', 1, 2);
+        let res = decoder
+            .decode_chain(vec!["HHey".into(), "HoHHH".into(), "H".into()])
+            .unwrap();
+        assert_eq!(res, vec!["Hey", "oH", ""]);
+    }
+
+    #[test]
+    fn serialization() {
+        let decoder = Strip::new('▁', 1, 0);
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"Strip","content":"▁","start":1,"stop":0}"#
+        );
+        let deserialized: DecoderWrapper = serde_json::from_str(&serialized).unwrap();
+        assert!(matches!(deserialized, DecoderWrapper::Strip(s) if s == decoder));
+    }
+}
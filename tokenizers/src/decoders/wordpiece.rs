@@ -12,11 +12,61 @@ pub struct WordPiece {
     pub prefix: String,
     /// Whether to cleanup some tokenization artifacts (spaces before punctuation, ...)
     pub cleanup: bool,
+    /// The suffix of the subwords followed by another one. When set, it is used instead of
+    /// the `prefix`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+    /// The prefix of the first subword of each word. When set, it is used instead of the
+    /// `prefix`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leading_prefix: Option<String>,
+    /// The string inserted between the words, `" "` by default
+    #[serde(
+        default = "default_word_separator",
+        skip_serializing_if = "is_default_word_separator"
+    )]
+    pub word_separator: String,
+}
+
+pub(crate) fn default_word_separator() -> String {
+    " ".into()
+}
+
+pub(crate) fn is_default_word_separator(separator: &str) -> bool {
+    separator == " "
 }
 
 impl WordPiece {
     pub fn new(prefix: String, cleanup: bool) -> Self {
-        Self { prefix, cleanup }
+        Self {
+            prefix,
+            cleanup,
+            suffix: None,
+            leading_prefix: None,
+            word_separator: default_word_separator(),
+        }
+    }
+
+    /// A decoder for the subwords marked with a suffix, like `end@@ ing`
+    pub fn with_suffix(suffix: String, cleanup: bool) -> Self {
+        Self {
+            prefix: String::new(),
+            cleanup,
+            suffix: Some(suffix),
+            leading_prefix: None,
+            word_separator: default_word_separator(),
+        }
+    }
+
+    /// A decoder for the words marked with a prefix on their first subword, like `▁end ing`
+    pub fn with_leading_prefix(leading_prefix: String, cleanup: bool) -> Self {
+        Self {
+            prefix: String::new(),
+            cleanup,
+            suffix: None,
+            leading_prefix: Some(leading_prefix),
+            word_separator: default_word_separator(),
+        }
     }
 }
 
@@ -25,35 +75,62 @@ impl Default for WordPiece {
         Self {
             prefix: "##".to_owned(),
             cleanup: true,
+            suffix: None,
+            leading_prefix: None,
+            word_separator: default_word_separator(),
         }
     }
 }
+/// The replacements applied, in order, by [`cleanup`]. These mostly remove the spaces
+/// before punctuation, and take care of some abbreviated english forms.
+pub const CLEANUP_REPLACEMENTS: &[(&str, &str)] = &[
+    (" .", "."),
+    (" ?", "?"),
+    (" !", "!"),
+    (" ,", ","),
+    (" ' ", "'"),
+    (" n't", "n't"),
+    (" 'm", "'m"),
+    (" do not", " don't"),
+    (" 's", "'s"),
+    (" 've", "'ve"),
+    (" 're", "'re"),
+];
+
+/// Apply the [`CLEANUP_REPLACEMENTS`] to the given decoded text. This is lossy, since the
+/// spaces it removes may have been in the original text, and only fits English.
 pub fn cleanup(dirty_input: &str) -> String {
-    dirty_input
-        .replace(" .", ".")
-        .replace(" ?", "?")
-        .replace(" !", "!")
-        .replace(" ,", ",")
-        .replace(" ' ", "'")
-        .replace(" n't", "n't")
-        .replace(" 'm", "'m")
-        .replace(" do not", " don't")
-        .replace(" 's", "'s")
-        .replace(" 've", "'ve")
-        .replace(" 're", "'re")
+    CLEANUP_REPLACEMENTS
+        .iter()
+        .fold(dirty_input.to_owned(), |s, (from, to)| s.replace(from, to))
 }
 
 impl Decoder for WordPiece {
     fn decode_chain(&self, mut tokens: Vec<String>) -> Result<Vec<String>> {
+        let n = tokens.len();
         tokens
             .iter_mut()
             .enumerate()
             .map(|(i, token)| {
-                if i != 0 {
+                if let Some(suffix) = &self.suffix {
+                    if let Some(stripped) = token.strip_suffix(suffix.as_str()) {
+                        *token = stripped.to_owned();
+                    } else if i != n - 1 {
+                        token.push_str(&self.word_separator);
+                    }
+                } else if let Some(leading_prefix) = &self.leading_prefix {
+                    if let Some(stripped) = token.strip_prefix(leading_prefix.as_str()) {
+                        *token = if i == 0 {
+                            stripped.to_owned()
+                        } else {
+                            format!("{}{}", self.word_separator, stripped)
+                        };
+                    }
+                } else if i != 0 {
                     if token.starts_with(&self.prefix) {
                         *token = token.replacen(&self.prefix, "", 1);
                     } else {
-                        *token = format!(" {}", token);
+                        *token = format!("{}{}", self.word_separator, token);
                     }
                 }
                 if self.cleanup {
@@ -87,4 +164,97 @@ mod tests {
             "##uelo Araújo Noguera"
         );
     }
+
+    #[test]
+    fn wordpiece_decoder_cleanup() {
+        let tokens = vec![
+            "obj".to_string(),
+            ".".to_string(),
+            "field".to_string(),
+            "is".to_string(),
+            "n't".to_string(),
+        ];
+
+        let decoder = WordPiece::new("##".to_string(), true);
+        assert_eq!(decoder.decode(tokens.clone()).unwrap(), "obj. field isn't");
+
+        // No punctuation special-casing at all without the cleanup
+        let decoder = WordPiece::new("##".to_string(), false);
+        assert_eq!(decoder.decode(tokens).unwrap(), "obj . field is n't");
+    }
+
+    #[test]
+    fn wordpiece_decoder_suffix() {
+        let decoder = WordPiece::with_suffix("@@".to_string(), false);
+        let tokens = ["The", "end@@", "ing", "is", "n@@", "ear", "##ly", "there@@"];
+        assert_eq!(
+            decoder
+                .decode(tokens.iter().map(|t| t.to_string()).collect())
+                .unwrap(),
+            "The ending is near ##ly there"
+        );
+
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"WordPiece","prefix":"","cleanup":false,"suffix":"@@"}"#
+        );
+        let deserialized: WordPiece = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.suffix, decoder.suffix);
+        // Without any suffix, it stays as before
+        assert_eq!(
+            serde_json::to_string(&WordPiece::default()).unwrap(),
+            r###"{"type":"WordPiece","prefix":"##","cleanup":true}"###
+        );
+    }
+
+    #[test]
+    fn wordpiece_decoder_leading_prefix() {
+        let decoder = WordPiece::with_leading_prefix("▁".to_string(), false);
+        let tokens = ["▁The", "▁end", "ing", "▁is", "▁n", "ear", "ly"];
+        assert_eq!(
+            decoder
+                .decode(tokens.iter().map(|t| t.to_string()).collect())
+                .unwrap(),
+            "The ending is nearly"
+        );
+
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"WordPiece","prefix":"","cleanup":false,"leading_prefix":"▁"}"#
+        );
+        let deserialized: WordPiece = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.leading_prefix, decoder.leading_prefix);
+    }
+
+    #[test]
+    fn wordpiece_decoder_word_separator() {
+        let tokens = ["Ara", "##új", "##o", "No", "##guera"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>();
+        let mut decoder = WordPiece::new("##".to_string(), false);
+        decoder.word_separator = " | ".into();
+        assert_eq!(decoder.decode(tokens.clone()).unwrap(), "Araújo | Noguera");
+        decoder.word_separator = "".into();
+        assert_eq!(decoder.decode(tokens).unwrap(), "AraújoNoguera");
+
+        let mut decoder = WordPiece::with_suffix("@@".to_string(), false);
+        decoder.word_separator = "|".into();
+        let tokens = ["end@@", "ing", "is"].iter().map(|t| t.to_string());
+        assert_eq!(decoder.decode(tokens.collect()).unwrap(), "ending|is");
+
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"WordPiece","prefix":"","cleanup":false,"suffix":"@@","word_separator":"|"}"#
+        );
+        let deserialized: WordPiece = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.word_separator, "|");
+        let deserialized: WordPiece =
+            serde_json::from_str(r###"{"type":"WordPiece","prefix":"##","cleanup":true}"###)
+                .unwrap();
+        assert_eq!(deserialized.word_separator, " ");
+    }
 }
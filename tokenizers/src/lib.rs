@@ -124,6 +124,9 @@
 //! **progressbar**: The progress bar visualization is enabled by default. It might be disabled if
 //!   compilation for certain targets is not supported by the [termios](https://crates.io/crates/termios)
 //!   dependency of the [indicatif](https://crates.io/crates/indicatif) progress bar.
+//!
+//! **trace**: Adds `Tokenizer::encode_traced`, that measures the time spent in each stage of
+//!   the encoding. It is disabled by default.
 
 #[macro_use]
 extern crate log;
@@ -33,6 +33,9 @@ pub enum Error {
     /// Dropout not between 0 and 1.
     #[error("Dropout should be between 0 and 1")]
     InvalidDropout,
+    /// Both a continuing subword prefix and suffix were provided
+    #[error("Only one of continuing_subword_prefix and continuing_subword_suffix can be set")]
+    PrefixAndSuffix,
 }
 
 /// Provides access to the `FirstLastIterator` to any Iterator
@@ -77,6 +80,7 @@ where
 }
 
 // Re-export
+pub use crate::utils::cache::CacheStats;
 pub use model::*;
 pub use trainer::*;
 use word::*;
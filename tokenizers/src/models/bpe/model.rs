@@ -1,15 +1,21 @@
-use super::{super::OrderedVocabIter, trainer::BpeTrainer, Error, Pair, Word};
+use super::{super::save_parts, super::OrderedVocabIter, trainer::BpeTrainer, Error, Pair, Word};
+use crate::models::{UnkError, UnkPolicy};
 use crate::tokenizer::{Model, Result, Token};
-use crate::utils::cache::{Cache, DEFAULT_CACHE_CAPACITY};
+use crate::utils::cache::{Cache, CacheStats, DEFAULT_CACHE_CAPACITY};
+use crate::utils::fnv1a;
 use crate::utils::iter::ResultShunt;
+use rand::{thread_rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use serde_json::Value;
 use std::borrow::Cow;
 use std::{
+    collections::hash_map::Entry,
     collections::HashMap,
     fs::File,
     io::prelude::*,
     io::{BufRead, BufReader},
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
 pub type Vocab = HashMap<String, u32>;
@@ -23,8 +29,11 @@ struct Config {
     merges: Merges,
     cache_capacity: usize,
     dropout: Option<f32>,
+    dropout_seed: Option<u64>,
     unk_token: Option<String>,
+    unk_policy: Option<UnkPolicy>,
     continuing_subword_prefix: Option<String>,
+    continuing_subword_suffix: Option<String>,
     end_of_word_suffix: Option<String>,
     fuse_unk: bool,
 }
@@ -43,8 +52,11 @@ impl Default for BpeBuilder {
                 merges: vec![],
                 cache_capacity: DEFAULT_CACHE_CAPACITY,
                 dropout: None,
+                dropout_seed: None,
                 unk_token: None,
+                unk_policy: None,
                 continuing_subword_prefix: None,
+                continuing_subword_suffix: None,
                 end_of_word_suffix: None,
                 fuse_unk: false,
             },
@@ -87,6 +99,13 @@ impl BpeBuilder {
         self
     }
 
+    /// Set the seed used to sample the dropout, which makes it deterministic.
+    #[must_use]
+    pub fn dropout_seed(mut self, seed: u64) -> Self {
+        self.config.dropout_seed = Some(seed);
+        self
+    }
+
     /// Set the `UNK` token for the vocab.
     #[must_use]
     pub fn unk_token(mut self, unk_token: String) -> Self {
@@ -94,6 +113,14 @@ impl BpeBuilder {
         self
     }
 
+    /// Set how unknown input is handled. `UnkPolicy::Token` is equivalent to `unk_token`.
+    /// When not set, unknown input is replaced by the `unk_token` if any, or dropped otherwise.
+    #[must_use]
+    pub fn unk_policy(mut self, unk_policy: UnkPolicy) -> Self {
+        self.config.unk_policy = Some(unk_policy);
+        self
+    }
+
     /// Set the `continuing_subword_prefix` option.
     #[must_use]
     pub fn continuing_subword_prefix(mut self, prefix: String) -> Self {
@@ -101,6 +128,14 @@ impl BpeBuilder {
         self
     }
 
+    /// Set the `continuing_subword_suffix` option. It can't be used along with the
+    /// `continuing_subword_prefix`.
+    #[must_use]
+    pub fn continuing_subword_suffix(mut self, suffix: String) -> Self {
+        self.config.continuing_subword_suffix = Some(suffix);
+        self
+    }
+
     /// Set the `end_of_word_suffix` option.
     #[must_use]
     pub fn end_of_word_suffix(mut self, prefix: String) -> Self {
@@ -124,6 +159,12 @@ impl BpeBuilder {
             }
         }
 
+        if self.config.continuing_subword_prefix.is_some()
+            && self.config.continuing_subword_suffix.is_some()
+        {
+            return Err(Error::PrefixAndSuffix.into());
+        }
+
         // Read files if necessary
         if let Some((vocab, merges)) = self.config.files {
             let (v, m) = BPE::read_file(&vocab, &merges)?;
@@ -143,11 +184,16 @@ impl BpeBuilder {
         };
 
         let vocab = self.config.vocab;
-        let prefix_len = if let Some(prefix) = &self.config.continuing_subword_prefix {
-            prefix.len()
-        } else {
-            0
-        };
+        let prefix = self
+            .config
+            .continuing_subword_prefix
+            .as_deref()
+            .unwrap_or("");
+        let suffix = self
+            .config
+            .continuing_subword_suffix
+            .as_deref()
+            .unwrap_or("");
         let merge_map: MergeMap = self
             .config
             .merges
@@ -160,7 +206,11 @@ impl BpeBuilder {
                 let b_id = vocab
                     .get(&b)
                     .ok_or_else(|| Error::MergeTokenOutOfVocabulary(b.to_owned()))?;
-                let new_token = format!("{}{}", a, &b[prefix_len..]);
+                let new_token = format!(
+                    "{}{}",
+                    a.strip_suffix(suffix).unwrap_or(&a),
+                    b.strip_prefix(prefix).unwrap_or(&b)
+                );
                 let new_id = vocab
                     .get(&new_token)
                     .ok_or(Error::MergeTokenOutOfVocabulary(new_token))?;
@@ -170,38 +220,103 @@ impl BpeBuilder {
 
         // merges.insert(pair, (rank as u32, *new_id));
 
+        let unk_policy = match self.config.unk_policy {
+            Some(unk_policy) => unk_policy,
+            None => self
+                .config
+                .unk_token
+                .clone()
+                .map_or(UnkPolicy::Drop, UnkPolicy::Token),
+        };
+        let unk_token = match &unk_policy {
+            UnkPolicy::Token(unk) => Some(unk.clone()),
+            _ => self.config.unk_token,
+        };
+
         Ok(BPE {
-            vocab,
-            vocab_r,
-            merges: merge_map,
+            vocab: Arc::new(vocab),
+            vocab_r: Arc::new(vocab_r),
+            merges: Arc::new(merge_map),
             cache,
             dropout: self.config.dropout,
-            unk_token: self.config.unk_token,
+            dropout_seed: self.config.dropout_seed,
+            unk_token,
+            unk_policy,
             continuing_subword_prefix: self.config.continuing_subword_prefix,
+            continuing_subword_suffix: self.config.continuing_subword_suffix,
             end_of_word_suffix: self.config.end_of_word_suffix,
             fuse_unk: self.config.fuse_unk,
         })
     }
 }
 
+/// How `BPE::extend_with` brings the vocabulary and merges of another model in. In any case,
+/// the existing tokens keep their ids, and the existing merges keep their rank, even when the
+/// other model ranks them differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExtendStrategy {
+    /// Append all the tokens of the other model that are not already present, in the order of
+    /// their ids, and then its merges, in the order of their ranks.
+    #[default]
+    AppendNew,
+    /// Like `AppendNew`, but only the first `n` missing tokens get appended. The merges that
+    /// would produce or use any other token are skipped.
+    AppendFirst(usize),
+}
+
+/// What `BPE::extend_with` brought in the model
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtendReport {
+    /// The number of tokens appended to the vocabulary
+    pub added_tokens: usize,
+    /// The number of merges appended to the existing ones
+    pub added_merges: usize,
+    /// The merges of the other model that were skipped, because some of their tokens are
+    /// missing from the vocabulary
+    pub skipped_merges: Vec<(String, String)>,
+}
+
+/// How a `BPE` model covers a corpus, given by `BPE::coverage_report`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CoverageReport {
+    /// The fraction of the tokens that are the unknown token
+    pub unk_rate: f64,
+    /// The average number of tokens of a word
+    pub avg_tokens_per_word: f64,
+    /// The fraction of the words that became a single token, other than the unknown one
+    pub single_token_word_fraction: f64,
+    /// The number of words in the corpus, the empty ones excluded
+    pub total_words: usize,
+}
+
 /// A [Byte Pair Encoding](https://www.aclweb.org/anthology/P16-1162/) model.
 #[derive(PartialEq)]
 pub struct BPE {
-    /// The vocabulary assigns a number to each token.
-    pub(crate) vocab: Vocab,
-    /// Reversed vocabulary, to rebuild sentences.
-    pub(crate) vocab_r: VocabR,
-    /// Contains the mapping between Pairs and their (rank, new_id).
-    pub(crate) merges: MergeMap,
+    /// The vocabulary assigns a number to each token. It is shared between clones.
+    pub(crate) vocab: Arc<Vocab>,
+    /// Reversed vocabulary, to rebuild sentences. It is shared between clones.
+    pub(crate) vocab_r: Arc<VocabR>,
+    /// Contains the mapping between Pairs and their (rank, new_id). It is shared between clones.
+    pub(crate) merges: Arc<MergeMap>,
     /// Contains the cache for optimizing the encoding step.
     cache: Option<Cache<String, Word>>,
     /// Dropout probability for merges. 0 = no dropout is the default. At 1.0, tokenization will
     /// perform no merges, so the result will just be characters.
     pub dropout: Option<f32>,
+    /// An optional seed for the dropout. When set, each word gets its own random generator,
+    /// seeded from this seed and the word itself, so that a given word is always tokenized
+    /// the same way, whatever the platform or the version of Rust.
+    pub dropout_seed: Option<u64>,
     /// The unknown token to be used when we encounter an unknown char
     pub unk_token: Option<String>,
+    /// How unknown chars are handled. With `UnkPolicy::Token`, this holds the same token
+    /// as `unk_token`
+    pub unk_policy: UnkPolicy,
     /// An optional prefix to use on any subword that exist only behind another one
     pub continuing_subword_prefix: Option<String>,
+    /// An optional suffix to use on any subword that has another one behind it, like the
+    /// `@@` of subword-nmt. It replaces the `continuing_subword_prefix`.
+    pub continuing_subword_suffix: Option<String>,
     /// An optional suffix to caracterize and end-of-word subword
     pub end_of_word_suffix: Option<String>,
     /// Do multiple unk tokens get fused
@@ -212,8 +327,11 @@ impl std::fmt::Debug for BPE {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
         fmt.debug_struct("BPE")
             .field("dropout", &self.dropout)
+            .field("dropout_seed", &self.dropout_seed)
             .field("unk_token", &self.unk_token)
+            .field("unk_policy", &self.unk_policy)
             .field("continuing_subword_prefix", &self.continuing_subword_prefix)
+            .field("continuing_subword_suffix", &self.continuing_subword_suffix)
             .field("end_of_word_suffix", &self.end_of_word_suffix)
             .field("fuse_unk", &self.fuse_unk)
             .field("vocab", &self.vocab.len())
@@ -230,7 +348,9 @@ impl Default for BPE {
 
 impl Clone for BPE {
     // `Clone` can't be derive because it's not implemented for `Cache`.
-    // To keep things simple when we clone, the new BPE will start with a fresh cache.
+    // To keep things simple when we clone, the new BPE will start with a fresh cache. The
+    // vocabulary and merges are shared with the clone, which makes it cheap, and are only
+    // copied if one of them gets modified.
     fn clone(&self) -> Self {
         let fresh_cache = self.cache.as_ref().map(|cache| cache.fresh());
         Self {
@@ -239,8 +359,11 @@ impl Clone for BPE {
             merges: self.merges.clone(),
             cache: fresh_cache,
             dropout: self.dropout,
+            dropout_seed: self.dropout_seed,
             unk_token: self.unk_token.clone(),
+            unk_policy: self.unk_policy.clone(),
             continuing_subword_prefix: self.continuing_subword_prefix.clone(),
+            continuing_subword_suffix: self.continuing_subword_suffix.clone(),
             end_of_word_suffix: self.end_of_word_suffix.clone(),
             fuse_unk: self.fuse_unk,
         }
@@ -319,25 +442,175 @@ impl BPE {
         Ok((vocab, merges))
     }
 
-    /// Reset the cache.
+    /// Reset the cache, along with its statistics.
     pub fn clear_cache(&self) {
         if let Some(ref cache) = self.cache {
             cache.clear()
         }
     }
 
+    /// Get the statistics of the cache since it was created or last cleared. They are
+    /// all zero when the caching is disabled.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache
+            .as_ref()
+            .map(|cache| cache.stats())
+            .unwrap_or_default()
+    }
+
     pub fn get_vocab(&self) -> Vocab {
-        self.vocab.clone()
+        (*self.vocab).clone()
+    }
+
+    /// The number of merges. Once trained, it's the number of merges the training computed.
+    pub fn get_merges_count(&self) -> usize {
+        self.merges.len()
+    }
+
+    /// The rank of the merge of `a` with `b`, if they get merged. The merges with the lowest
+    /// ranks are applied first.
+    pub fn merge_rank(&self, a: &str, b: &str) -> Option<u32> {
+        let pair = (*self.vocab.get(a)?, *self.vocab.get(b)?);
+        self.merges.get(&pair).map(|(rank, _)| *rank)
+    }
+
+    /// Iterate over all the merges, in no particular order. Each of them comes with the
+    /// pair of tokens it merges, its rank, and the token it produces.
+    pub fn merges_iter(&self) -> impl Iterator<Item = ((String, String), (u32, String))> + '_ {
+        self.merges.iter().map(move |((a, b), (rank, new_id))| {
+            (
+                (self.vocab_r[a].clone(), self.vocab_r[b].clone()),
+                (*rank, self.vocab_r[new_id].clone()),
+            )
+        })
     }
 
     pub fn get_unk_token(&self) -> &Option<String> {
         &self.unk_token
     }
 
+    pub fn get_unk_policy(&self) -> &UnkPolicy {
+        &self.unk_policy
+    }
+
     pub fn get_continuing_subword_prefix(&self) -> &Option<String> {
         &self.continuing_subword_prefix
     }
 
+    pub fn get_continuing_subword_suffix(&self) -> &Option<String> {
+        &self.continuing_subword_suffix
+    }
+
+    /// Tokenize each word of the `corpus`, like some held-out words after training, to report
+    /// how well the vocabulary covers them. The words are used as is, so they should be
+    /// normalized and pre-tokenized like the ones used for training.
+    ///
+    /// The unknown chars dropped with `UnkPolicy::Drop` can't be counted, and a word that
+    /// fails with `UnkPolicy::Error` counts as a single unknown token.
+    pub fn coverage_report<I>(&self, corpus: I) -> CoverageReport
+    where
+        I: Iterator<Item = String>,
+    {
+        let unk_id = self
+            .unk_token
+            .as_ref()
+            .and_then(|unk| self.vocab.get(unk))
+            .copied();
+        let (mut words, mut tokens, mut unks, mut single_tokens) = (0, 0, 0, 0);
+        for word in corpus.filter(|word| !word.is_empty()) {
+            words += 1;
+            match self.tokenize(&word) {
+                Ok(word_tokens) => {
+                    tokens += word_tokens.len();
+                    let word_unks = word_tokens.iter().filter(|t| Some(t.id) == unk_id).count();
+                    unks += word_unks;
+                    if word_tokens.len() == 1 && word_unks == 0 {
+                        single_tokens += 1;
+                    }
+                }
+                Err(_) => {
+                    tokens += 1;
+                    unks += 1;
+                }
+            }
+        }
+
+        let ratio = |count: usize, total: usize| {
+            if total == 0 {
+                0.0
+            } else {
+                count as f64 / total as f64
+            }
+        };
+        CoverageReport {
+            unk_rate: ratio(unks, tokens),
+            avg_tokens_per_word: ratio(tokens, words),
+            single_token_word_fraction: ratio(single_tokens, words),
+            total_words: words,
+        }
+    }
+
+    /// Extend this model with the vocabulary and merges of `other`, like some domain specific
+    /// tokens, without having to train it again.
+    ///
+    /// The new tokens get ids after all the existing ones, so when this model is used in a
+    /// `Tokenizer` with some added tokens, their ids might now overlap with the new ones.
+    pub fn extend_with(&mut self, other: &BPE, strategy: ExtendStrategy) -> ExtendReport {
+        let mut report = ExtendReport::default();
+        let max_new_tokens = match strategy {
+            ExtendStrategy::AppendNew => usize::MAX,
+            ExtendStrategy::AppendFirst(n) => n,
+        };
+
+        let mut other_tokens = other.vocab_r.iter().collect::<Vec<_>>();
+        other_tokens.sort_unstable_by_key(|(id, _)| **id);
+        let mut next_id = self.vocab_r.keys().max().map_or(0, |id| id + 1);
+        let vocab = Arc::make_mut(&mut self.vocab);
+        let vocab_r = Arc::make_mut(&mut self.vocab_r);
+        for (_, token) in other_tokens {
+            if report.added_tokens == max_new_tokens {
+                break;
+            }
+            if !vocab.contains_key(token) {
+                vocab.insert(token.clone(), next_id);
+                vocab_r.insert(next_id, token.clone());
+                next_id += 1;
+                report.added_tokens += 1;
+            }
+        }
+
+        let mut other_merges = other.merges.iter().collect::<Vec<_>>();
+        other_merges.sort_unstable_by_key(|(_, (rank, _))| *rank);
+        let mut next_rank = self
+            .merges
+            .values()
+            .map(|(rank, _)| *rank)
+            .max()
+            .map_or(0, |rank| rank + 1);
+        let merges = Arc::make_mut(&mut self.merges);
+        for ((a, b), (_, new_id)) in other_merges {
+            let (a, b) = (&other.vocab_r[a], &other.vocab_r[b]);
+            let ids = (
+                vocab.get(a),
+                vocab.get(b),
+                vocab.get(&other.vocab_r[new_id]),
+            );
+            match ids {
+                (Some(a), Some(b), Some(new_id)) => {
+                    if let Entry::Vacant(merge) = merges.entry((*a, *b)) {
+                        merge.insert((next_rank, *new_id));
+                        next_rank += 1;
+                        report.added_merges += 1;
+                    }
+                }
+                _ => report.skipped_merges.push((a.clone(), b.clone())),
+            }
+        }
+
+        self.clear_cache();
+        report
+    }
+
     fn merge_word(&self, w: &str) -> Result<Word> {
         let mut indices = w.char_indices().map(|(idx, _)| idx).peekable();
         let mut word = Word::with_capacity(w.len());
@@ -360,6 +633,12 @@ impl BPE {
                     s = format!("{}{}", prefix, s).into()
                 }
             }
+            // Add the `continuing_subword_suffix` if relevant
+            if !is_last {
+                if let Some(ref suffix) = self.continuing_subword_suffix {
+                    s = format!("{}{}", s, suffix).into()
+                }
+            }
             // Add the `end_of_word_suffix` if relevant
             if is_last {
                 if let Some(ref suffix) = self.end_of_word_suffix {
@@ -373,7 +652,12 @@ impl BPE {
                     unk = None;
                 }
                 word.add(*id, byte_len);
-            } else if let Some(unk_token) = &self.unk_token {
+            } else if let UnkPolicy::Error = self.unk_policy {
+                return Err(Box::new(UnkError {
+                    substring: w[i..i + byte_len].to_owned(),
+                    offset: i,
+                }));
+            } else if let UnkPolicy::Token(unk_token) = &self.unk_policy {
                 unk = match (unk, self.fuse_unk) {
                     (Some((unk_id, unk_len)), true) => {
                         // Fuse unk
@@ -403,7 +687,13 @@ impl BPE {
             word.add(unk_id, unk_len);
         }
 
-        word.merge_all(&self.merges, self.dropout);
+        match (self.dropout, self.dropout_seed) {
+            (Some(_), Some(seed)) => {
+                let mut rng = ChaCha8Rng::seed_from_u64(seed ^ fnv1a(w, 0));
+                word.merge_all(&self.merges, self.dropout, &mut rng)
+            }
+            _ => word.merge_all(&self.merges, self.dropout, &mut thread_rng()),
+        }
 
         Ok(word)
     }
@@ -432,7 +722,7 @@ impl Model for BPE {
     type Trainer = BpeTrainer;
 
     fn get_vocab(&self) -> HashMap<String, u32> {
-        self.vocab.clone()
+        (*self.vocab).clone()
     }
 
     fn get_vocab_size(&self) -> usize {
@@ -460,53 +750,51 @@ impl Model for BPE {
         self.vocab_r.get(&id).cloned()
     }
 
+    fn id_to_token_cow(&self, id: u32) -> Option<Cow<'_, str>> {
+        self.vocab_r
+            .get(&id)
+            .map(|token| Cow::Borrowed(token.as_str()))
+    }
+
     fn save(&self, folder: &Path, name: Option<&str>) -> Result<Vec<PathBuf>> {
-        let vocab_file_name = match name {
-            Some(name) => format!("{}-vocab.json", name),
-            None => "vocab.json".to_string(),
-        };
+        save_parts(folder, name, self.serialize_parts()?)
+    }
 
-        // Write vocab.json
-        let vocab_path: PathBuf = [folder, Path::new(vocab_file_name.as_str())]
-            .iter()
-            .collect();
-        let mut vocab_file = File::create(&vocab_path)?;
+    fn serialize_parts(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        // vocab.json
         let order_vocab_iter = OrderedVocabIter::new(&self.vocab_r);
-        let serialized = serde_json::to_string(&order_vocab_iter)?;
-        vocab_file.write_all(serialized.as_bytes())?;
-
-        // Write merges.txt
-        let merges_file_name = match name {
-            Some(name) => format!("{}-merges.txt", name),
-            None => "merges.txt".to_string(),
-        };
+        let vocab = serde_json::to_vec(&order_vocab_iter)?;
 
-        let merges_path: PathBuf = [folder, Path::new(merges_file_name.as_str())]
-            .iter()
-            .collect();
-        let mut merges_file = File::create(&merges_path)?;
+        // merges.txt
         let mut merges: Vec<(&Pair, &u32)> = self
             .merges
             .iter()
             .map(|(pair, (rank, _))| (pair, rank))
             .collect();
         merges.sort_unstable_by_key(|k| *k.1);
-        merges_file.write_all(b"#version: 0.2 - Trained by `huggingface/tokenizers`\n")?;
-        merges_file.write_all(
-            &merges
-                .into_iter()
-                .flat_map(|(pair, _)| {
-                    format!("{} {}\n", self.vocab_r[&pair.0], self.vocab_r[&pair.1]).into_bytes()
-                })
-                .collect::<Vec<_>>()[..],
-        )?;
+        let mut merges_content = b"#version: 0.2 - Trained by `huggingface/tokenizers`\n".to_vec();
+        for (pair, _) in merges {
+            merges_content.extend(
+                format!("{} {}\n", self.vocab_r[&pair.0], self.vocab_r[&pair.1]).into_bytes(),
+            );
+        }
 
-        Ok(vec![vocab_path, merges_path])
+        Ok(vec![
+            ("vocab.json".to_string(), vocab),
+            ("merges.txt".to_string(), merges_content),
+        ])
     }
 
     fn get_trainer(&self) -> BpeTrainer {
         BpeTrainer::default()
     }
+
+    fn unk_token(&self) -> Option<String> {
+        match &self.unk_policy {
+            UnkPolicy::Token(unk_token) => Some(unk_token.clone()),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -593,6 +881,154 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_unk_fused_multibyte() {
+        let vocab: Vocab = [("<unk>".into(), 0), ("a".into(), 1)]
+            .iter()
+            .cloned()
+            .collect();
+        let builder = || {
+            BpeBuilder::default()
+                .vocab_and_merges(vocab.clone(), vec![])
+                .unk_token("<unk>".to_string())
+        };
+
+        let bpe = builder().fuse_unk(true).build().unwrap();
+        assert_eq!(
+            bpe.tokenize("ééééé").unwrap(),
+            vec![Token::new(0u32, "<unk>".into(), (0, 10))]
+        );
+        let bpe = builder().build().unwrap();
+        assert_eq!(bpe.tokenize("ééééé").unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_unk_offsets() {
+        let vocab: Vocab = [("a".into(), 0), ("b".into(), 1), ("<unk>".into(), 2)]
+            .iter()
+            .cloned()
+            .collect();
+        let builder = || {
+            BpeBuilder::default()
+                .vocab_and_merges(vocab.clone(), vec![])
+                .unk_token("<unk>".to_string())
+        };
+
+        // Each unknown character gets its own byte span, with the id of the unk token
+        let bpe = builder().build().unwrap();
+        assert_eq!(
+            bpe.tokenize("aéøb").unwrap(),
+            vec![
+                Token::new(0u32, "a".into(), (0, 1)),
+                Token::new(2u32, "<unk>".into(), (1, 3)),
+                Token::new(2u32, "<unk>".into(), (3, 5)),
+                Token::new(1u32, "b".into(), (5, 6)),
+            ]
+        );
+        let bpe = builder().fuse_unk(true).build().unwrap();
+        assert_eq!(
+            bpe.tokenize("aéøb").unwrap(),
+            vec![
+                Token::new(0u32, "a".into(), (0, 1)),
+                Token::new(2u32, "<unk>".into(), (1, 5)),
+                Token::new(1u32, "b".into(), (5, 6)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unk_policy() {
+        let vocab: Vocab = [("<unk>".into(), 0), ("a".into(), 1), ("b".into(), 2)]
+            .iter()
+            .cloned()
+            .collect();
+        let bpe = BpeBuilder::default()
+            .vocab_and_merges(vocab.clone(), vec![])
+            .build()
+            .unwrap();
+        assert_eq!(bpe.unk_policy, UnkPolicy::Drop);
+        let tokens = bpe.tokenize("acb").unwrap();
+        assert_eq!(
+            tokens.iter().map(|t| t.id).collect::<Vec<_>>(),
+            vec![1u32, 2u32]
+        );
+
+        let bpe = BpeBuilder::default()
+            .vocab_and_merges(vocab.clone(), vec![])
+            .unk_policy(UnkPolicy::Token("<unk>".into()))
+            .build()
+            .unwrap();
+        assert_eq!(bpe.unk_token, Some("<unk>".into()));
+        let tokens = bpe.tokenize("acb").unwrap();
+        assert_eq!(tokens[1], Token::new(0u32, "<unk>".into(), (1, 2)));
+
+        let bpe = BpeBuilder::default()
+            .vocab_and_merges(vocab, vec![])
+            .unk_token("<unk>".into())
+            .unk_policy(UnkPolicy::Error)
+            .build()
+            .unwrap();
+        let error = bpe.tokenize("abéc").unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<UnkError>(),
+            Some(&UnkError {
+                substring: "é".into(),
+                offset: 2
+            })
+        );
+
+        // The policy survives serialization
+        let serialized = serde_json::to_string(&bpe).unwrap();
+        assert!(serialized.contains(r#""unk_policy":"Error""#));
+        let deserialized: BPE = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.unk_policy, UnkPolicy::Error);
+    }
+
+    #[test]
+    fn test_coverage_report() {
+        let vocab: Vocab = [
+            ("<unk>".into(), 0),
+            ("a".into(), 1),
+            ("b".into(), 2),
+            ("ab".into(), 3),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        let merges = vec![("a".to_string(), "b".to_string())];
+        let corpus = || ["ab", "abab", "a", "xab", ""].iter().map(|s| s.to_string());
+
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab.clone(), merges.clone())
+            .unk_token("<unk>".into())
+            .build()
+            .unwrap();
+        assert_eq!(
+            bpe.coverage_report(corpus()),
+            CoverageReport {
+                unk_rate: 1.0 / 6.0,
+                avg_tokens_per_word: 1.5,
+                single_token_word_fraction: 0.5,
+                total_words: 4,
+            }
+        );
+
+        // The words that fail count as a single unknown token
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab, merges)
+            .unk_policy(UnkPolicy::Error)
+            .build()
+            .unwrap();
+        let report = bpe.coverage_report(corpus());
+        assert_eq!(report.unk_rate, 0.2);
+        assert_eq!(report.avg_tokens_per_word, 1.25);
+
+        assert_eq!(
+            bpe.coverage_report(std::iter::empty()),
+            CoverageReport::default()
+        );
+    }
+
     #[test]
     // Test tokenization. With dropout set to 0 tokenization is deterministic,
     // so we know exactly what the result should be.
@@ -658,6 +1094,182 @@ mod tests {
         bpe.dropout = Some(0.5);
         let tokens = bpe.tokenize("unrelated").unwrap();
         assert!(!tokens.is_empty() && tokens.len() <= 9);
+
+        // With a seed, the same word always gets the same tokens
+        bpe.dropout_seed = Some(42);
+        let tokens = bpe.tokenize("unrelated").unwrap();
+        for _ in 0..20 {
+            assert_eq!(bpe.tokenize("unrelated").unwrap(), tokens);
+        }
+        assert_eq!(bpe.clone().tokenize("unrelated").unwrap(), tokens);
+        let serialized = serde_json::to_string(&bpe).unwrap();
+        assert!(serialized.contains(r#""dropout_seed":42"#));
+        let deserialized: BPE = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.tokenize("unrelated").unwrap(), tokens);
+        // The same tokens as any other build, with any version of Rust
+        bpe.dropout_seed = Some(6);
+        assert_eq!(
+            bpe.tokenize("unrelated")
+                .unwrap()
+                .iter()
+                .map(|t| t.value.as_str())
+                .collect::<Vec<_>>(),
+            ["un", "re", "l", "a", "t", "ed"]
+        );
+        bpe.dropout_seed = Some(42);
+        // But the seed still changes the sampling
+        assert!((0..20).any(|seed| {
+            bpe.dropout_seed = Some(seed);
+            bpe.tokenize("unrelated").unwrap() != tokens
+        }));
+    }
+
+    #[test]
+    fn test_clone_shares_vocab() {
+        let vocab: Vocab = [("a", 0), ("b", 1), ("c", 2), ("ab", 3), ("abc", 4)]
+            .iter()
+            .map(|(t, i)| (t.to_string(), *i))
+            .collect();
+        let merges = vec![("a".into(), "b".into()), ("ab".into(), "c".into())];
+        let bpe = BPE::new(vocab, merges);
+
+        let workers = (0..4)
+            .map(|_| {
+                let worker = bpe.clone();
+                assert!(Arc::ptr_eq(&bpe.vocab, &worker.vocab));
+                std::thread::spawn(move || {
+                    (0..100)
+                        .map(|_| worker.tokenize("abcab").unwrap())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        for worker in workers {
+            for tokens in worker.join().unwrap() {
+                assert_eq!(
+                    tokens,
+                    vec![
+                        Token::new(4, "abc".into(), (0, 3)),
+                        Token::new(3, "ab".into(), (3, 5))
+                    ]
+                );
+            }
+        }
+
+        // Modifying a clone doesn't change the original
+        let mut clone = bpe.clone();
+        assert!(Arc::ptr_eq(&bpe.vocab, &clone.vocab));
+        assert!(Arc::ptr_eq(&bpe.merges, &clone.merges));
+        clone.extend_with(
+            &BPE::new([("d".to_string(), 0)].iter().cloned().collect(), vec![]),
+            ExtendStrategy::AppendNew,
+        );
+        assert!(!Arc::ptr_eq(&bpe.vocab, &clone.vocab));
+        assert_eq!(bpe.get_vocab_size(), 5);
+        assert_eq!(clone.get_vocab_size(), 6);
+    }
+
+    #[test]
+    fn test_cache_stats() {
+        let vocab: Vocab = [("a", 0), ("b", 1), ("c", 2), ("ab", 3), ("abc", 4)]
+            .iter()
+            .map(|(t, i)| (t.to_string(), *i))
+            .collect();
+        let merges: Merges = vec![("a".into(), "b".into()), ("ab".into(), "c".into())];
+        let bpe = BPE::new(vocab.clone(), merges.clone());
+        assert_eq!(bpe.cache_stats(), CacheStats::default());
+
+        for word in ["abc", "ab", "abc", "abc", "ab"] {
+            bpe.tokenize(word).unwrap();
+        }
+        assert_eq!(
+            bpe.cache_stats(),
+            CacheStats {
+                hits: 3,
+                misses: 2,
+                size: 2
+            }
+        );
+
+        bpe.clear_cache();
+        assert_eq!(bpe.cache_stats(), CacheStats::default());
+        bpe.tokenize("abc").unwrap();
+        assert_eq!(bpe.cache_stats().misses, 1);
+
+        // Without cache, the stats stay at zero
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab, merges)
+            .cache_capacity(0)
+            .build()
+            .unwrap();
+        bpe.tokenize("abc").unwrap();
+        bpe.tokenize("abc").unwrap();
+        bpe.clear_cache();
+        assert_eq!(bpe.cache_stats(), CacheStats::default());
+    }
+
+    #[test]
+    fn test_extend_with() {
+        let vocab = |tokens: &[&str]| -> Vocab {
+            tokens
+                .iter()
+                .enumerate()
+                .map(|(i, t)| (t.to_string(), i as u32))
+                .collect()
+        };
+        let merges = |merges: &[(&str, &str)]| -> Merges {
+            merges
+                .iter()
+                .map(|(a, b)| (a.to_string(), b.to_string()))
+                .collect()
+        };
+        let base = BPE::new(vocab(&["a", "b", "c", "ab"]), merges(&[("a", "b")]));
+        let other = BPE::new(
+            vocab(&["a", "b", "d", "bd", "abd", "ab"]),
+            merges(&[("b", "d"), ("a", "bd"), ("a", "b")]),
+        );
+
+        let mut extended = base.clone();
+        let report = extended.extend_with(&other, ExtendStrategy::AppendNew);
+        assert_eq!(
+            report,
+            ExtendReport {
+                added_tokens: 3,
+                added_merges: 2,
+                skipped_merges: vec![],
+            }
+        );
+        // Existing ids and merges are kept
+        assert_eq!(extended.token_to_id("ab"), Some(3));
+        assert_eq!(extended.token_to_id("d"), Some(4));
+        assert_eq!(extended.token_to_id("abd"), Some(6));
+        assert_eq!(extended.merges[&(0, 1)], (0, 3));
+        assert_eq!(extended.merges[&(1, 4)], (1, 5));
+        let tokens = extended.tokenize("bd").unwrap();
+        assert_eq!(tokens, vec![Token::new(5, "bd".into(), (0, 2))]);
+        // The existing `a b` merge still comes first
+        let tokens = extended.tokenize("abd").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(3, "ab".into(), (0, 2)),
+                Token::new(4, "d".into(), (2, 3))
+            ]
+        );
+
+        // Extending again doesn't do anything
+        let report = extended.extend_with(&other, ExtendStrategy::AppendNew);
+        assert_eq!(report, ExtendReport::default());
+
+        let mut extended = base;
+        let report = extended.extend_with(&other, ExtendStrategy::AppendFirst(1));
+        assert_eq!(report.added_tokens, 1);
+        assert_eq!(report.added_merges, 0);
+        assert_eq!(
+            report.skipped_merges,
+            vec![("b".into(), "d".into()), ("a".into(), "bd".into())]
+        );
+        assert_eq!(extended.get_vocab_size(), 5);
     }
 
     #[test]
@@ -735,6 +1347,96 @@ mod tests {
         );
     }
 
+    #[test]
+    fn merges_lookup() {
+        let vocab: Vocab = vec![
+            ("a".to_string(), 0),
+            ("##b".to_string(), 1),
+            ("##c".to_string(), 2),
+            ("ab".to_string(), 3),
+            ("abc".to_string(), 4),
+        ]
+        .into_iter()
+        .collect();
+        let merges = vec![
+            ("a".to_string(), "##b".to_string()),
+            ("ab".to_string(), "##c".to_string()),
+        ];
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab, merges)
+            .continuing_subword_prefix("##".to_string())
+            .build()
+            .unwrap();
+
+        assert_eq!(bpe.merge_rank("a", "##b"), Some(0));
+        assert_eq!(bpe.merge_rank("ab", "##c"), Some(1));
+        assert_eq!(bpe.merge_rank("##b", "a"), None);
+        assert_eq!(bpe.merge_rank("a", "##d"), None);
+
+        let mut merges = bpe.merges_iter().collect::<Vec<_>>();
+        merges.sort_by_key(|(_, (rank, _))| *rank);
+        assert_eq!(
+            merges,
+            vec![
+                (("a".into(), "##b".into()), (0, "ab".into())),
+                (("ab".into(), "##c".into()), (1, "abc".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bpe_with_continuing_subword_suffix() {
+        let vocab: Vocab = vec![
+            ("[UNK]".to_string(), 0),
+            ("e@@".to_string(), 1),
+            ("n@@".to_string(), 2),
+            ("d".to_string(), 3),
+            ("en@@".to_string(), 4),
+            ("end".to_string(), 5),
+            ("d@@".to_string(), 6),
+        ]
+        .into_iter()
+        .collect();
+        let merges = vec![
+            ("e@@".to_string(), "n@@".to_string()),
+            ("en@@".to_string(), "d".to_string()),
+        ];
+
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab.clone(), merges.clone())
+            .unk_token("[UNK]".to_string())
+            .continuing_subword_suffix("@@".to_string())
+            .build()
+            .unwrap();
+        let tokens = |word| {
+            bpe.tokenize(word)
+                .unwrap()
+                .into_iter()
+                .map(|t| t.value)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(tokens("end"), vec!["end"]);
+        assert_eq!(tokens("dend"), vec!["d@@", "end"]);
+        assert_eq!(tokens("en"), vec!["e@@", "[UNK]"]);
+
+        // It round-trips through JSON
+        let serialized = serde_json::to_string(&bpe).unwrap();
+        assert!(serialized.contains(r#""continuing_subword_suffix":"@@""#));
+        let deserialized: BPE = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, bpe);
+
+        // It can't be used with a prefix
+        let res = BPE::builder()
+            .vocab_and_merges(vocab, merges)
+            .continuing_subword_prefix("##".to_string())
+            .continuing_subword_suffix("@@".to_string())
+            .build();
+        assert!(matches!(
+            res.unwrap_err().downcast_ref::<Error>(),
+            Some(Error::PrefixAndSuffix)
+        ));
+    }
+
     #[test]
     // Ensure `MergeTokenOutOfVocabulary` error is returned when it should be.
     fn test_bpe_from_file_merge_token_oov() {
@@ -1,4 +1,7 @@
-use super::{super::OrderedVocabIter, convert_merges_to_hashmap, BpeBuilder, Pair, BPE};
+use super::{
+    super::{OrderedVocabIter, UnkPolicy},
+    convert_merges_to_hashmap, BpeBuilder, Pair, BPE,
+};
 use serde::{
     de::{Error, MapAccess, Visitor},
     ser::SerializeStruct,
@@ -16,8 +19,22 @@ impl Serialize for BPE {
         // Start by small fields
         model.serialize_field("type", "BPE")?;
         model.serialize_field("dropout", &self.dropout)?;
+        if self.dropout_seed.is_some() {
+            model.serialize_field("dropout_seed", &self.dropout_seed)?;
+        }
         model.serialize_field("unk_token", &self.unk_token)?;
+        // The policy is only needed when it can't be deduced from the `unk_token`
+        let implied_policy = self
+            .unk_token
+            .clone()
+            .map_or(UnkPolicy::Drop, UnkPolicy::Token);
+        if self.unk_policy != implied_policy {
+            model.serialize_field("unk_policy", &self.unk_policy)?;
+        }
         model.serialize_field("continuing_subword_prefix", &self.continuing_subword_prefix)?;
+        if self.continuing_subword_suffix.is_some() {
+            model.serialize_field("continuing_subword_suffix", &self.continuing_subword_suffix)?;
+        }
         model.serialize_field("end_of_word_suffix", &self.end_of_word_suffix)?;
         model.serialize_field("fuse_unk", &self.fuse_unk)?;
 
@@ -51,8 +68,11 @@ impl<'de> Deserialize<'de> for BPE {
             &[
                 "type",
                 "dropout",
+                "dropout_seed",
                 "unk_token",
+                "unk_policy",
                 "continuing_subword_prefix",
+                "continuing_subword_suffix",
                 "end_of_word_suffix",
                 "fuse_unk",
                 "vocab",
@@ -85,16 +105,27 @@ impl<'de> Visitor<'de> for BPEVisitor {
                         builder = builder.dropout(dropout);
                     }
                 }
+                "dropout_seed" => {
+                    if let Some(seed) = map.next_value()? {
+                        builder = builder.dropout_seed(seed);
+                    }
+                }
                 "unk_token" => {
                     if let Some(unk) = map.next_value()? {
                         builder = builder.unk_token(unk);
                     }
                 }
+                "unk_policy" => builder = builder.unk_policy(map.next_value()?),
                 "continuing_subword_prefix" => {
                     if let Some(prefix) = map.next_value()? {
                         builder = builder.continuing_subword_prefix(prefix);
                     }
                 }
+                "continuing_subword_suffix" => {
+                    if let Some(suffix) = map.next_value()? {
+                        builder = builder.continuing_subword_suffix(suffix);
+                    }
+                }
                 "end_of_word_suffix" => {
                     if let Some(suffix) = map.next_value()? {
                         builder = builder.end_of_word_suffix(suffix);
@@ -107,7 +138,7 @@ impl<'de> Visitor<'de> for BPEVisitor {
                 }
                 "vocab" => vocab = Some(map.next_value()?),
                 "merges" => merges = Some(map.next_value()?),
-                "type" => match map.next_value()? {
+                "type" => match map.next_value::<String>()?.as_ref() {
                     "BPE" => {}
                     u => {
                         return Err(serde::de::Error::invalid_value(
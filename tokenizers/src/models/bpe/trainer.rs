@@ -1,31 +1,56 @@
 #![allow(clippy::map_entry)]
 
 use super::{Pair, WithFirstLastIterator, Word, BPE};
+use crate::models::pin_token_ids;
 use crate::parallelism::*;
-use crate::tokenizer::{AddedToken, Result, Trainer};
-use crate::utils::progress::{ProgressBar, ProgressStyle};
+use crate::tokenizer::{AddedToken, Result, Trainer, TrainerProgress};
+use crate::utils::progress::{Progress, ProgressCallback};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::Arc;
+
+/// How to choose between two pairs with the same count while computing the merges
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum MergeTieBreak {
+    /// The pair whose tokens come first in lexicographic order wins
+    Lexicographic,
+    /// The pair whose tokens were added to the vocabulary first wins
+    #[default]
+    FirstSeen,
+}
 
 #[derive(Debug, Eq)]
 struct Merge {
     pair: Pair,
     count: u32,
+    /// The count of the pair in the target tokens not yet merged, which go first
+    target_count: u32,
     pos: HashSet<usize>,
+    /// The content of the pair, only used with `MergeTieBreak::Lexicographic`
+    tie: Option<(String, String)>,
 }
 impl PartialEq for Merge {
     fn eq(&self, other: &Self) -> bool {
-        self.count == other.count && self.pair == other.pair
+        self.target_count == other.target_count
+            && self.count == other.count
+            && self.pair == other.pair
     }
 }
 impl PartialOrd for Merge {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        if self.count != other.count {
+        if self.target_count != other.target_count {
+            Some(self.target_count.cmp(&other.target_count))
+        } else if self.count != other.count {
             Some(self.count.cmp(&other.count))
         } else {
             // Here we want ascending order
-            Some(other.pair.cmp(&self.pair))
+            Some(
+                other
+                    .tie
+                    .cmp(&self.tie)
+                    .then_with(|| other.pair.cmp(&self.pair)),
+            )
         }
     }
 }
@@ -40,10 +65,17 @@ struct Config {
     vocab_size: usize,
     show_progress: bool,
     special_tokens: Vec<AddedToken>,
+    special_token_ids: HashMap<String, u32>,
     limit_alphabet: Option<usize>,
     initial_alphabet: HashSet<char>,
     continuing_subword_prefix: Option<String>,
     end_of_word_suffix: Option<String>,
+    merge_tie_break: MergeTieBreak,
+    max_merges: Option<usize>,
+    max_word_count_entries: Option<usize>,
+    max_token_length: Option<usize>,
+    target_tokens: HashMap<String, u32>,
+    progress: ProgressCallback,
 }
 
 /// A `BpeTrainerBuilder` can be used to create a `BpeTrainer` with a custom
@@ -60,10 +92,17 @@ impl Default for BpeTrainerBuilder {
                 vocab_size: 30000,
                 show_progress: true,
                 special_tokens: vec![],
+                special_token_ids: HashMap::new(),
                 limit_alphabet: None,
                 initial_alphabet: HashSet::new(),
                 continuing_subword_prefix: None,
                 end_of_word_suffix: None,
+                merge_tie_break: MergeTieBreak::default(),
+                max_merges: None,
+                max_word_count_entries: None,
+                max_token_length: None,
+                target_tokens: HashMap::new(),
+                progress: ProgressCallback::default(),
             },
         }
     }
@@ -103,6 +142,19 @@ impl BpeTrainerBuilder {
         self
     }
 
+    /// Add some special tokens, each with the id it must have in the trained vocabulary. All
+    /// the other tokens fill the remaining ids.
+    #[must_use]
+    pub fn special_tokens_with_ids(mut self, tokens: Vec<(AddedToken, u32)>) -> Self {
+        for (token, id) in tokens {
+            self.config
+                .special_token_ids
+                .insert(token.content.clone(), id);
+            self.config.special_tokens.push(token);
+        }
+        self
+    }
+
     /// Set whether to limit the alphabet
     #[must_use]
     pub fn limit_alphabet(mut self, limit: usize) -> Self {
@@ -131,6 +183,53 @@ impl BpeTrainerBuilder {
         self
     }
 
+    /// Set how to choose between pairs with the same count
+    #[must_use]
+    pub fn merge_tie_break(mut self, tie_break: MergeTieBreak) -> Self {
+        self.config.merge_tie_break = tie_break;
+        self
+    }
+
+    /// Set the maximum number of merges, to stop the training even if the vocabulary is
+    /// still smaller than `vocab_size`
+    #[must_use]
+    pub fn max_merges(mut self, max: usize) -> Self {
+        self.config.max_merges = Some(max);
+        self
+    }
+
+    /// Set the maximum number of distinct words to count while feeding, to bound the memory
+    /// used on huge corpora. See `BpeTrainer::max_word_count_entries` for the approximation
+    /// this implies.
+    #[must_use]
+    pub fn max_word_count_entries(mut self, max: usize) -> Self {
+        self.config.max_word_count_entries = Some(max);
+        self
+    }
+
+    /// Set the maximum length of the tokens, in chars, that the merges can produce
+    #[must_use]
+    pub fn max_token_length(mut self, max: usize) -> Self {
+        self.config.max_token_length = Some(max);
+        self
+    }
+
+    /// Set the tokens, with their frequencies, that the training should reproduce as single
+    /// tokens. See `BpeTrainer::from_target_tokens`.
+    #[must_use]
+    pub fn target_tokens(mut self, tokens: HashMap<String, u32>) -> Self {
+        self.config.target_tokens = tokens;
+        self
+    }
+
+    /// Set the `TrainerProgress` that will receive the progress of the training, instead
+    /// of the progress bar
+    #[must_use]
+    pub fn progress(mut self, progress: Option<Box<dyn TrainerProgress>>) -> Self {
+        self.config.progress = progress.into();
+        self
+    }
+
     /// Constructs the final BpeTrainer
     pub fn build(self) -> BpeTrainer {
         BpeTrainer {
@@ -138,11 +237,19 @@ impl BpeTrainerBuilder {
             vocab_size: self.config.vocab_size,
             show_progress: self.config.show_progress,
             special_tokens: self.config.special_tokens,
+            special_token_ids: self.config.special_token_ids,
             limit_alphabet: self.config.limit_alphabet,
             initial_alphabet: self.config.initial_alphabet,
             continuing_subword_prefix: self.config.continuing_subword_prefix,
             end_of_word_suffix: self.config.end_of_word_suffix,
+            merge_tie_break: self.config.merge_tie_break,
+            max_merges: self.config.max_merges,
+            max_word_count_entries: self.config.max_word_count_entries,
+            max_token_length: self.config.max_token_length,
+            target_tokens: self.config.target_tokens,
+            progress: self.config.progress,
             words: HashMap::new(),
+            pre_split_words: HashMap::new(),
         }
     }
 }
@@ -174,6 +281,10 @@ pub struct BpeTrainer {
     pub show_progress: bool,
     /// A list of special tokens that the model should know of
     pub special_tokens: Vec<AddedToken>,
+    /// The ids that some of the special tokens must have, the other tokens filling the
+    /// remaining ids
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub special_token_ids: HashMap<String, u32>,
     /// Whether to limit the number of initial tokens that can be kept before computing merges
     pub limit_alphabet: Option<usize>,
     /// The initial alphabet we want absolutely to include. This allows to cover
@@ -183,8 +294,96 @@ pub struct BpeTrainer {
     pub continuing_subword_prefix: Option<String>,
     /// An optional suffix to caracterize and end-of-word subword
     pub end_of_word_suffix: Option<String>,
-
+    /// How to choose between pairs with the same count
+    #[serde(default)]
+    pub merge_tie_break: MergeTieBreak,
+    /// The maximum number of merges to compute. The training stops as soon as either this
+    /// number of merges, or `vocab_size` is reached. The number of merges actually computed
+    /// is given by `BPE::get_merges_count` once trained.
+    #[serde(default)]
+    pub max_merges: Option<usize>,
+    /// The maximum number of distinct words counted by `feed`. Each time there are more of
+    /// them, only the most frequent half is kept: the words less frequent than the one in
+    /// the middle are forgotten, so the frequency floor rises with the counts. This is an
+    /// approximation, as a forgotten word starts back from zero when seen again, but the
+    /// frequent words always survive, and so do the merges they produce.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_word_count_entries: Option<usize>,
+    /// The maximum length of a token produced by a merge, in chars, including its
+    /// `continuing_subword_prefix` or `end_of_word_suffix`. The pairs that would produce a
+    /// longer token are skipped, so the next best pair gets merged instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_token_length: Option<usize>,
+    /// Some tokens, with their frequencies, to reproduce as single tokens. The merges that
+    /// build them are computed before any other one, from the most frequent tokens.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub target_tokens: HashMap<String, u32>,
+
+    #[serde(skip)]
+    progress: ProgressCallback,
     words: HashMap<String, u32>,
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        with = "pre_split_words"
+    )]
+    pre_split_words: HashMap<Vec<String>, u32>,
+}
+
+/// The words given to `feed_pre_split` can't be the keys of a JSON object, so they get
+/// serialized as a list of `[fragments, count]` pairs, sorted to keep the output stable
+mod pre_split_words {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S>(words: &HashMap<Vec<String>, u32>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut words = words.iter().collect::<Vec<_>>();
+        words.sort_unstable();
+        words.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<HashMap<Vec<String>, u32>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<(Vec<String>, u32)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+/// A word along with its count, given as the list of fragments that merges can't cross
+type Fragments<'a> = (&'a [String], u32);
+
+/// Keep only the most frequent half of the words once there are more than `max_entries` of
+/// them. The words as frequent as the one in the middle are kept too, unless there are still
+/// too many words, in which case they all get dropped.
+fn prune_word_counts(words: &mut HashMap<String, u32>, max_entries: usize) {
+    if words.len() <= max_entries {
+        return;
+    }
+    let mut counts = words.values().copied().collect::<Vec<_>>();
+    let middle = (max_entries / 2).max(1) - 1;
+    let floor = *counts.select_nth_unstable_by(middle, |a, b| b.cmp(a)).1;
+    words.retain(|_, count| *count >= floor);
+    if words.len() > max_entries {
+        words.retain(|_, count| *count > floor);
+    }
+}
+
+/// The counts of the given pair in the words, and in the target tokens
+fn counts_of(
+    pair_counts: &HashMap<Pair, i32>,
+    target_pair_counts: &HashMap<Pair, i32>,
+    pair: &Pair,
+) -> (i32, i32) {
+    (
+        pair_counts.get(pair).copied().unwrap_or(0),
+        target_pair_counts.get(pair).copied().unwrap_or(0),
+    )
 }
 
 impl Default for BpeTrainer {
@@ -206,37 +405,22 @@ impl BpeTrainer {
         BpeTrainerBuilder::new()
     }
 
-    /// Setup a progress bar if asked to show progress
-    fn setup_progress(&self) -> Option<ProgressBar> {
-        if self.show_progress {
-            let p = ProgressBar::new(0);
-            p.set_style(
-                ProgressStyle::default_bar()
-                    .template("[{elapsed_precise}] {msg:<40!} {wide_bar} {pos:<9!}/{len:>9!}"),
-            );
-            Some(p)
-        } else {
-            None
-        }
-    }
-
-    /// Set the progress bar in the finish state
-    fn finalize_progress(&self, p: &Option<ProgressBar>, final_len: usize) {
-        if let Some(p) = p {
-            p.set_length(final_len as u64);
-            p.finish();
-            println!();
-        }
-    }
-
-    /// Update the progress bar with the new provided length and message
-    fn update_progress(&self, p: &Option<ProgressBar>, len: usize, message: &str) {
-        if let Some(p) = p {
-            p.set_message(message);
-            p.set_length(len as u64);
-            p.set_draw_delta(len as u64 / 100);
-            p.reset();
-        }
+    /// A trainer reproducing the given tokens, along with their frequencies, typically
+    /// those of a larger tokenizer to distill. Each target token is learned like a word of
+    /// its own, and the merges that build the target tokens come first, driven by the
+    /// frequencies of the target tokens that aren't built yet. The general merges are then
+    /// learned from the words fed as usual, until `vocab_size` is reached. So the most
+    /// frequent target tokens end up in the vocabulary, as long as their chars are in the
+    /// alphabet and the vocabulary is large enough.
+    ///
+    /// This differs from giving the target tokens to `train_from_counter`, which counts
+    /// them as any other word: a token then only gets in the vocabulary if the most frequent
+    /// pairs happen to build it.
+    pub fn from_target_tokens(target_tokens: HashMap<String, u32>, vocab_size: usize) -> Self {
+        Self::builder()
+            .vocab_size(vocab_size)
+            .target_tokens(target_tokens)
+            .build()
     }
 
     /// Add the provided special tokens to the initial vocabulary
@@ -252,14 +436,16 @@ impl BpeTrainer {
     /// Compute the initial alphabet and limit it if relevant
     fn compute_alphabet(
         &self,
-        wc: &HashMap<String, u32>,
+        wc: &[Fragments],
         w2id: &mut HashMap<String, u32>,
         id2w: &mut Vec<String>,
+        p: &Progress,
     ) {
         // Compute the alphabet from seen words
         let mut alphabet: HashMap<char, usize> = HashMap::new();
-        for (word, count) in wc {
-            for c in word.chars() {
+        for (fragments, count) in wc {
+            p.inc(1);
+            for c in fragments.iter().flat_map(|f| f.chars()) {
                 alphabet
                     .entry(c)
                     .and_modify(|cnt| *cnt += *count as usize)
@@ -299,8 +485,13 @@ impl BpeTrainer {
 
         // Keep the initial alphabet (sorted for determinism)
         kept.sort_unstable_by_key(|k| (*k.0) as u32);
-        kept.into_iter().for_each(|(c, _)| {
-            let s = c.to_string();
+        let mut symbols = kept.iter().map(|(c, _)| c.to_string()).collect::<Vec<_>>();
+        // Each char can also continue a word, so it needs its prefixed form too
+        if let Some(prefix) = &self.continuing_subword_prefix {
+            let prefixed = kept.iter().map(|(c, _)| format!("{}{}", prefix, c));
+            symbols.extend(prefixed.collect::<Vec<_>>());
+        }
+        symbols.into_iter().for_each(|s| {
             if !w2id.contains_key(&s) {
                 id2w.push(s.clone());
                 w2id.insert(s, (id2w.len() - 1) as u32);
@@ -308,62 +499,105 @@ impl BpeTrainer {
         });
     }
 
-    /// Tokenize words and add subwords to the vocabulary when relevant
+    /// Tokenize words and add subwords to the vocabulary when relevant.
+    ///
+    /// Each fragment of a word becomes its own `Word` so that no merge can happen
+    /// across fragments, while the prefix and suffix still depend on the position
+    /// in the whole word.
     fn tokenize_words(
         &self,
-        wc: &HashMap<String, u32>,
+        wc: &[Fragments],
         w2id: &mut HashMap<String, u32>,
         id2w: &mut Vec<String>,
-        p: &Option<ProgressBar>,
+        p: &Progress,
     ) -> (Vec<Word>, Vec<u32>) {
         let mut words: Vec<Word> = Vec::with_capacity(wc.len());
         let mut counts: Vec<u32> = Vec::with_capacity(wc.len());
 
-        for (word, count) in wc {
-            let mut current_word = Word::new();
-            counts.push(*count);
+        for (fragments, count) in wc {
+            let n_fragments = fragments.len();
+            for (i, fragment) in fragments.iter().enumerate() {
+                let current_word =
+                    self.tokenize_fragment(fragment, i == 0, i + 1 == n_fragments, w2id, id2w);
+                words.push(current_word);
+                counts.push(*count);
+            }
+
+            p.inc(1);
+        }
 
-            for (is_first, is_last, c) in word.chars().with_first_and_last() {
-                let mut s = c.to_string();
-                if w2id.contains_key(&s) {
-                    // Found the initial char in the authorized alphabet
+        (words, counts)
+    }
 
-                    // Add the `continuing_subword_prefix` if relevant
-                    if !is_first {
-                        if let Some(prefix) = &self.continuing_subword_prefix {
-                            s = format!("{}{}", prefix, s);
-                        }
+    /// Tokenize a fragment of a word, given whether it starts and/or ends it
+    fn tokenize_fragment(
+        &self,
+        fragment: &str,
+        starts_word: bool,
+        ends_word: bool,
+        w2id: &mut HashMap<String, u32>,
+        id2w: &mut Vec<String>,
+    ) -> Word {
+        let mut current_word = Word::new();
+
+        for (is_first, is_last, c) in fragment.chars().with_first_and_last() {
+            let mut s = c.to_string();
+            if w2id.contains_key(&s) {
+                // Found the initial char in the authorized alphabet
+
+                // Add the `continuing_subword_prefix` if relevant
+                if !(is_first && starts_word) {
+                    if let Some(prefix) = &self.continuing_subword_prefix {
+                        s = format!("{}{}", prefix, s);
                     }
-                    // Add the `end_of_word_suffix` if relevant
-                    if is_last {
-                        if let Some(suffix) = &self.end_of_word_suffix {
-                            s = format!("{}{}", s, suffix);
-                        }
+                }
+                // Add the `end_of_word_suffix` if relevant
+                if is_last && ends_word {
+                    if let Some(suffix) = &self.end_of_word_suffix {
+                        s = format!("{}{}", s, suffix);
                     }
+                }
 
-                    // Insert the new formed string if necessary
-                    if !w2id.contains_key(&s) {
-                        id2w.push(s.clone());
-                        w2id.insert(s.clone(), (id2w.len() - 1) as u32);
-                    }
-                    current_word.add(w2id[&s], 1); // We do not care about the len here
+                // Insert the new formed string if necessary
+                if !w2id.contains_key(&s) {
+                    id2w.push(s.clone());
+                    w2id.insert(s.clone(), (id2w.len() - 1) as u32);
                 }
+                current_word.add(w2id[&s], 1); // We do not care about the len here
             }
-            words.push(current_word);
+        }
 
-            if let Some(p) = p {
-                p.inc(1);
+        current_word
+    }
+
+    /// Build the entry for the given pair in the merges queue
+    fn make_merge(
+        &self,
+        pair: Pair,
+        (count, target_count): (i32, i32),
+        pos: HashSet<usize>,
+        id2w: &[String],
+    ) -> Merge {
+        let tie = match self.merge_tie_break {
+            MergeTieBreak::Lexicographic => {
+                Some((id2w[pair.0 as usize].clone(), id2w[pair.1 as usize].clone()))
             }
+            MergeTieBreak::FirstSeen => None,
+        };
+        Merge {
+            pair,
+            count: count.max(0) as u32,
+            target_count: target_count.max(0) as u32,
+            pos,
+            tie,
         }
-
-        (words, counts)
     }
 
     fn count_pairs(
         &self,
         words: &[Word],
         counts: &[u32],
-        p: &Option<ProgressBar>,
+        p: &Progress,
     ) -> (HashMap<Pair, i32>, HashMap<Pair, HashSet<usize>>) {
         words
             .maybe_par_iter()
@@ -395,9 +629,7 @@ impl BpeTrainer {
                     *pair_counts.get_mut(&cur_pair).unwrap() += count as i32;
                 }
 
-                if let Some(p) = &p {
-                    p.inc(1);
-                }
+                p.inc(1);
 
                 (pair_counts, where_to_update)
             })
@@ -422,11 +654,68 @@ impl BpeTrainer {
         &self,
         word_counts: &HashMap<String, u32>,
         model: &mut BPE,
+    ) -> Result<Vec<AddedToken>> {
+        let word_counts = word_counts
+            .iter()
+            .map(|(word, count)| (std::slice::from_ref(word), *count))
+            .collect::<Vec<_>>();
+        self.train_fragments(&word_counts, model)
+    }
+
+    /// Train using words that are already split in fragments. Each word is given as the
+    /// list of its fragments, and no merge will ever cross the boundary between two of them.
+    /// The `continuing_subword_prefix` and `end_of_word_suffix` still apply to the whole word.
+    pub fn do_train_pre_split(
+        &self,
+        word_counts: &HashMap<Vec<String>, u32>,
+        model: &mut BPE,
+    ) -> Result<Vec<AddedToken>> {
+        let word_counts = word_counts
+            .iter()
+            .map(|(fragments, count)| (fragments.as_slice(), *count))
+            .collect::<Vec<_>>();
+        self.train_fragments(&word_counts, model)
+    }
+
+    /// Provide words that are already split in fragments, bypassing the pre-tokenization.
+    /// They will be used by `train` along with the words given to `feed`, and no merge
+    /// will cross the boundary between two fragments. The counts add up with the ones of
+    /// the previous calls, so the words can be given in several chunks.
+    pub fn feed_pre_split(&mut self, word_counts: HashMap<Vec<String>, u32>) {
+        for (fragments, count) in word_counts {
+            *self.pre_split_words.entry(fragments).or_insert(0) += count;
+        }
+    }
+
+    /// The words given to `feed`, with their counts
+    pub(crate) fn word_counts(&self) -> &HashMap<String, u32> {
+        &self.words
+    }
+
+    fn train_fragments(
+        &self,
+        word_counts: &[Fragments],
+        model: &mut BPE,
     ) -> Result<Vec<AddedToken>> {
         let mut word_to_id: HashMap<String, u32> = HashMap::with_capacity(self.vocab_size);
         let mut id_to_word: Vec<String> = Vec::with_capacity(self.vocab_size);
 
-        let progress = self.setup_progress();
+        // The words come from a `HashMap`, so we sort them to always assign the same ids,
+        // and thus produce the same merges, given the same input
+        let mut word_counts = word_counts.to_vec();
+        word_counts.sort_unstable();
+        // The target tokens come last, each as a word of a single fragment
+        let mut target_tokens = self
+            .target_tokens
+            .iter()
+            .map(|(token, count)| (std::slice::from_ref(token), *count))
+            .collect::<Vec<_>>();
+        target_tokens.sort_unstable();
+        let n_targets = target_tokens.len();
+        word_counts.extend(target_tokens);
+        let word_counts = word_counts.as_slice();
+
+        let progress = Progress::new(self.show_progress, &self.progress);
 
         //
         // 1. Add all special tokens to the vocabulary
@@ -436,58 +725,68 @@ impl BpeTrainer {
         //
         // 2. Compute the initial alphabet
         //
-        self.compute_alphabet(word_counts, &mut word_to_id, &mut id_to_word);
+        progress.start("Compute alphabet", word_counts.len());
+        self.compute_alphabet(word_counts, &mut word_to_id, &mut id_to_word, &progress);
+        progress.finish(word_counts.len());
 
         //
         // 3. Tokenize words
         //
-        self.update_progress(&progress, word_counts.len(), "Tokenize words");
-        let (words, counts) =
+        progress.start("Tokenize words", word_counts.len());
+        let (words, mut counts) =
             self.tokenize_words(word_counts, &mut word_to_id, &mut id_to_word, &progress);
-        self.finalize_progress(&progress, words.len());
+        progress.finish(words.len());
+        // The target tokens only count for the target merges
+        let first_target = words.len() - n_targets;
+        let target_counts = counts.split_off(first_target);
+        counts.resize(words.len(), 0);
 
         //
         // 4. Count pairs in words
         //
-        self.update_progress(&progress, words.len(), "Count pairs");
+        progress.start("Count pairs", words.len() + n_targets);
         let (mut pair_counts, mut where_to_update) = self.count_pairs(&words, &counts, &progress);
+        let (mut target_pair_counts, _) =
+            self.count_pairs(&words[first_target..], &target_counts, &progress);
         // Insert them in the queue
         let mut queue = BinaryHeap::with_capacity(pair_counts.len());
         where_to_update.drain().for_each(|(pair, pos)| {
-            let count = pair_counts[&pair];
-            if count > 0 {
-                queue.push(Merge {
-                    pair,
-                    count: count as u32,
-                    pos,
-                });
+            let pair_count = counts_of(&pair_counts, &target_pair_counts, &pair);
+            if pair_count.0 > 0 || pair_count.1 > 0 {
+                queue.push(self.make_merge(pair, pair_count, pos, &id_to_word));
             }
         });
-        self.finalize_progress(&progress, words.len());
+        progress.finish(words.len() + n_targets);
 
         //
         // 5. Do merges
         //
-        self.update_progress(&progress, self.vocab_size, "Compute merges");
+        progress.start("Compute merges", self.vocab_size);
         let mut merges: Vec<(Pair, u32)> = vec![];
         loop {
-            // Stop as soon as we have a big enough vocabulary
+            // Stop as soon as we have a big enough vocabulary, or enough merges
             if word_to_id.len() >= self.vocab_size {
                 break;
             }
+            if self.max_merges.is_some_and(|max| merges.len() >= max) {
+                break;
+            }
 
             if queue.is_empty() {
                 break;
             }
 
             let mut top = queue.pop().unwrap();
-            if top.count != pair_counts[&top.pair] as u32 {
-                top.count = pair_counts[&top.pair] as u32;
+            let (count, target_count) = counts_of(&pair_counts, &target_pair_counts, &top.pair);
+            let (count, target_count) = (count.max(0) as u32, target_count.max(0) as u32);
+            if top.count != count || top.target_count != target_count {
+                top.count = count;
+                top.target_count = target_count;
                 queue.push(top);
                 continue;
             }
 
-            if top.count < 1 || self.min_frequency > top.count {
+            if top.target_count < 1 && (top.count < 1 || self.min_frequency > top.count) {
                 break;
             }
 
@@ -502,6 +801,12 @@ impl BpeTrainer {
                 }
             }
             let new_token = format!("{}{}", part_a, part_b);
+            if self
+                .max_token_length
+                .is_some_and(|max| new_token.chars().count() > max)
+            {
+                continue;
+            }
 
             // Insert new token if it does not already exist
             let new_token_id = word_to_id
@@ -519,12 +824,12 @@ impl BpeTrainer {
                 .pos
                 .maybe_par_iter()
                 .flat_map(|i| {
-                    let w = &words[*i] as *const _ as *mut _;
+                    let word = &words[*i] as *const _ as *mut Word;
                     // We can merge each of these words in parallel here because each position
                     // can be there only once (HashSet). So this is safe.
                     unsafe {
-                        let word: &mut Word = &mut (*w);
-                        word.merge(top.pair.0, top.pair.1, new_token_id)
+                        (*word)
+                            .merge(top.pair.0, top.pair.1, new_token_id)
                             .into_iter()
                             .map(|c| (c, *i))
                             .collect::<Vec<_>>()
@@ -539,6 +844,13 @@ impl BpeTrainer {
                     .entry(pair)
                     .and_modify(|c| *c += count)
                     .or_insert(count);
+                if iw >= first_target {
+                    let count = change * target_counts[iw - first_target] as i32;
+                    target_pair_counts
+                        .entry(pair)
+                        .and_modify(|c| *c += count)
+                        .or_insert(count);
+                }
                 if change > 0 {
                     where_to_update
                         .entry(pair)
@@ -553,34 +865,44 @@ impl BpeTrainer {
                 }
             }
             where_to_update.drain().for_each(|(pair, pos)| {
-                let count = pair_counts[&pair];
-                if count > 0 {
-                    queue.push(Merge {
-                        pair,
-                        count: count as u32,
-                        pos,
-                    });
+                let pair_count = counts_of(&pair_counts, &target_pair_counts, &pair);
+                if pair_count.0 > 0 || pair_count.1 > 0 {
+                    queue.push(self.make_merge(pair, pair_count, pos, &id_to_word));
                 }
             });
 
-            if let Some(p) = &progress {
-                p.inc(1);
-            }
+            progress.inc(1);
+        }
+        progress.finish(merges.len());
+
+        //
+        // 6. Give the special tokens their fixed id
+        //
+        let new_ids = pin_token_ids(&id_to_word, &self.special_token_ids)?;
+        word_to_id
+            .values_mut()
+            .for_each(|id| *id = new_ids[*id as usize]);
+        for ((a, b), new_token_id) in merges.iter_mut() {
+            *a = new_ids[*a as usize];
+            *b = new_ids[*b as usize];
+            *new_token_id = new_ids[*new_token_id as usize];
         }
-        self.finalize_progress(&progress, merges.len());
 
         // Transfer new vocab & options to model
-        model.vocab = word_to_id;
-        model.vocab_r = model
-            .vocab
-            .iter()
-            .map(|(key, val)| (*val, key.to_owned()))
-            .collect();
-        model.merges = merges
-            .into_iter()
-            .enumerate()
-            .map(|(i, (pair, new_token_id))| (pair, (i as u32, new_token_id)))
-            .collect();
+        model.vocab_r = Arc::new(
+            word_to_id
+                .iter()
+                .map(|(key, val)| (*val, key.to_owned()))
+                .collect(),
+        );
+        model.vocab = Arc::new(word_to_id);
+        model.merges = Arc::new(
+            merges
+                .into_iter()
+                .enumerate()
+                .map(|(i, (pair, new_token_id))| (pair, (i as u32, new_token_id)))
+                .collect(),
+        );
 
         if let Some(prefix) = &self.continuing_subword_prefix {
             model.continuing_subword_prefix = Some(prefix.to_owned());
@@ -602,7 +924,17 @@ impl Trainer for BpeTrainer {
 
     /// Train a BPE model
     fn train(&self, model: &mut BPE) -> Result<Vec<AddedToken>> {
-        self.do_train(&self.words, model)
+        let word_counts = self
+            .words
+            .iter()
+            .map(|(word, count)| (std::slice::from_ref(word), *count))
+            .chain(
+                self.pre_split_words
+                    .iter()
+                    .map(|(fragments, count)| (fragments.as_slice(), *count)),
+            )
+            .collect::<Vec<_>>();
+        self.train_fragments(&word_counts, model)
     }
 
     /// Whether we should show progress
@@ -616,6 +948,7 @@ impl Trainer for BpeTrainer {
         S: AsRef<str> + Send,
         F: Fn(&str) -> Result<Vec<String>> + Sync,
     {
+        let max_entries = self.max_word_count_entries;
         let words: Result<HashMap<String, u32>> = iterator
             .maybe_par_bridge()
             .map(|sequence| {
@@ -633,6 +966,9 @@ impl Trainer for BpeTrainer {
                     for (k, v) in ws? {
                         acc.entry(k).and_modify(|c| *c += v).or_insert(v);
                     }
+                    if let Some(max_entries) = max_entries {
+                        prune_word_counts(&mut acc, max_entries);
+                    }
                     Ok(acc)
                 },
             );
@@ -649,7 +985,7 @@ impl Trainer for BpeTrainer {
 
 #[cfg(test)]
 mod tests {
-    use super::{BpeTrainer, Pair, BPE};
+    use super::{BpeTrainer, MergeTieBreak, Pair, BPE};
     use std::collections::HashMap;
 
     #[test]
@@ -709,7 +1045,7 @@ mod tests {
         .iter()
         .cloned()
         .collect();
-        assert_eq!(model.vocab, expected_vocab);
+        assert_eq!(*model.vocab, expected_vocab);
 
         // The keys in `merges` are pairs of symbols, the values are tuples of (rank, id),
         // where 'rank' determines the order in which this merge will be applied during
@@ -723,6 +1059,488 @@ mod tests {
         .iter()
         .cloned()
         .collect();
-        assert_eq!(model.merges, expected_merges);
+        assert_eq!(*model.merges, expected_merges);
+    }
+
+    #[test]
+    fn test_continuing_subword_prefix() {
+        use crate::decoders::wordpiece::WordPiece;
+        use crate::tokenizer::{Decoder, Model};
+
+        let word_counts: HashMap<String, u32> = [
+            ("unhappy".into(), 2),
+            ("happy".into(), 3),
+            ("un".into(), 1),
+            ("#tag".into(), 1),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        let trainer = BpeTrainer::builder()
+            .show_progress(false)
+            .continuing_subword_prefix("##".into())
+            .build();
+        let mut model = BPE::default();
+        trainer.do_train(&word_counts, &mut model).unwrap();
+
+        // Each char of the alphabet can start or continue a word
+        for c in "unhapy#tg".chars() {
+            assert!(model.vocab.contains_key(&c.to_string()));
+            assert!(model.vocab.contains_key(&format!("##{}", c)));
+        }
+        // The prefix is only ever at the start of the learned tokens
+        for token in model.vocab.keys() {
+            let content = token.strip_prefix("##").unwrap_or(token);
+            assert!(!content.contains("##"), "{}", token);
+        }
+        for ((a, b), (_, new_id)) in model.merges.iter() {
+            let (a, b) = (&model.vocab_r[a], &model.vocab_r[b]);
+            assert!(b.starts_with("##"));
+            assert_eq!(model.vocab_r[new_id], format!("{}{}", a, &b[2..]));
+        }
+
+        // Decoding the subwords of some unseen words gives them back
+        let decoder = WordPiece::new("##".into(), false);
+        for word in ["happyun", "unhat", "#tagun"] {
+            let tokens = model
+                .tokenize(word)
+                .unwrap()
+                .into_iter()
+                .map(|t| t.value)
+                .collect::<Vec<_>>();
+            assert!(tokens.len() > 1);
+            assert_eq!(decoder.decode(tokens).unwrap(), word);
+        }
+    }
+
+    #[test]
+    fn test_train_pre_split() {
+        let word_counts: HashMap<Vec<String>, u32> = [
+            (vec!["un".into(), "do".into()], 3),
+            (vec!["un".into(), "tie".into()], 2),
+            (vec!["tie".into()], 1),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        let trainer = BpeTrainer::builder()
+            .show_progress(false)
+            .continuing_subword_prefix("##".into())
+            .build();
+        let mut model = BPE::default();
+        trainer
+            .do_train_pre_split(&word_counts, &mut model)
+            .unwrap();
+
+        // Merges happen inside the fragments, but never across them
+        let mut tokens = model.vocab.keys().cloned().collect::<Vec<_>>();
+        tokens.sort();
+        assert_eq!(
+            tokens,
+            vec![
+                "##d", "##do", "##e", "##i", "##ie", "##n", "##o", "##t", "##tie", "##u", "d", "e",
+                "i", "n", "o", "t", "tie", "u", "un"
+            ]
+        );
+
+        // Training from the same words, once joined, does merge across the fragments
+        let word_counts: HashMap<String, u32> = [("undo".into(), 3), ("untie".into(), 2)]
+            .iter()
+            .cloned()
+            .collect();
+        let mut model = BPE::default();
+        trainer.do_train(&word_counts, &mut model).unwrap();
+        assert!(model.vocab.contains_key("undo"));
+        assert!(model.vocab.contains_key("untie"));
+    }
+
+    #[test]
+    fn test_feed_pre_split() {
+        use crate::tokenizer::Trainer;
+
+        let mut trainer = BpeTrainer::builder()
+            .show_progress(false)
+            .end_of_word_suffix("</w>".into())
+            .build();
+        trainer.feed_with_counter([("ab".to_string(), 1)].iter().cloned().collect());
+        let pre_split = |count| {
+            [(vec!["ab".to_string(), "c".to_string()], count)]
+                .iter()
+                .cloned()
+                .collect()
+        };
+        // In two chunks, the counts adding up
+        trainer.feed_pre_split(pre_split(1));
+        trainer.feed_pre_split(pre_split(1));
+        assert_eq!(trainer.pre_split_words, pre_split(2));
+
+        // The fed words are kept along with the rest of the trainer
+        let serialized = serde_json::to_string(&trainer).unwrap();
+        assert!(serialized.contains(r#""pre_split_words":[[["ab","c"],2]]"#));
+        assert_eq!(
+            serde_json::from_str::<BpeTrainer>(&serialized).unwrap(),
+            trainer
+        );
+
+        let mut model = BPE::default();
+        trainer.train(&mut model).unwrap();
+
+        // The suffix only goes on the last fragment of each word
+        let mut tokens = model.vocab.keys().cloned().collect::<Vec<_>>();
+        tokens.sort();
+        assert_eq!(
+            tokens,
+            vec!["a", "ab", "ab</w>", "b", "b</w>", "c", "c</w>"]
+        );
+    }
+
+    /// The merges of the model, in order, as strings
+    fn ordered_merges(model: &BPE) -> Vec<(String, String)> {
+        let mut merges = model.merges.iter().collect::<Vec<_>>();
+        merges.sort_by_key(|(_, (rank, _))| *rank);
+        merges
+            .into_iter()
+            .map(|(pair, _)| {
+                (
+                    model.vocab_r[&pair.0].clone(),
+                    model.vocab_r[&pair.1].clone(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_tie_break() {
+        // All the pairs have the same count here
+        let word_counts: HashMap<String, u32> = [
+            ("zy".into(), 2),
+            ("dc".into(), 2),
+            ("xw".into(), 2),
+            ("ba".into(), 2),
+            ("vu".into(), 2),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+
+        for tie_break in [MergeTieBreak::Lexicographic, MergeTieBreak::FirstSeen].to_vec() {
+            let train = || {
+                let trainer = BpeTrainer::builder()
+                    .show_progress(false)
+                    .continuing_subword_prefix("##".into())
+                    .merge_tie_break(tie_break)
+                    .build();
+                let mut model = BPE::default();
+                trainer.do_train(&word_counts, &mut model).unwrap();
+                ordered_merges(&model)
+            };
+
+            // Training twice on the same input gives the same merges, in the same order
+            let merges = train();
+            assert_eq!(merges.len(), 5);
+            assert_eq!(merges, train());
+        }
+
+        let trainer = BpeTrainer::builder()
+            .show_progress(false)
+            .continuing_subword_prefix("##".into())
+            .merge_tie_break(MergeTieBreak::Lexicographic)
+            .build();
+        let mut model = BPE::default();
+        trainer.do_train(&word_counts, &mut model).unwrap();
+        assert_eq!(
+            ordered_merges(&model),
+            vec![
+                ("b".to_string(), "##a".to_string()),
+                ("d".to_string(), "##c".to_string()),
+                ("v".to_string(), "##u".to_string()),
+                ("x".to_string(), "##w".to_string()),
+                ("z".to_string(), "##y".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_merges() {
+        let word_counts: HashMap<String, u32> = [
+            ("roses".into(), 1),
+            ("are".into(), 2),
+            ("red".into(), 1),
+            ("voilets".into(), 1),
+            ("blue".into(), 1),
+            ("BERT".into(), 2),
+            ("is".into(), 1),
+            ("big".into(), 1),
+            ("and".into(), 1),
+            ("so".into(), 1),
+            ("GPT-2".into(), 1),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        let train = |max_merges: usize, vocab_size: usize| {
+            let trainer = BpeTrainer::builder()
+                .show_progress(false)
+                .max_merges(max_merges)
+                .vocab_size(vocab_size)
+                .build();
+            let mut model = BPE::default();
+            trainer.do_train(&word_counts, &mut model).unwrap();
+            model
+        };
+
+        let unlimited = train(usize::MAX, 1000);
+        assert!(unlimited.get_merges_count() > 3);
+        let alphabet = unlimited.get_vocab().len() - unlimited.get_merges_count();
+        // Whichever comes first
+        let model = train(3, 1000);
+        assert_eq!(model.get_merges_count(), 3);
+        assert_eq!(model.get_vocab().len(), alphabet + 3);
+        let model = train(3, alphabet + 1);
+        assert_eq!(model.get_merges_count(), 1);
+        let model = train(0, 1000);
+        assert_eq!(model.get_merges_count(), 0);
+    }
+
+    #[test]
+    fn test_max_word_count_entries() {
+        use crate::tokenizer::Trainer;
+
+        // Two frequent words, and a long tail of words seen once
+        let sequences = (0..1000)
+            .map(|i| format!("hello world hello tail{}", i))
+            .collect::<Vec<_>>();
+        let train = |trainer: &mut BpeTrainer| {
+            trainer
+                .feed(sequences.iter(), |s| {
+                    Ok(s.split(' ').map(|w| w.to_owned()).collect())
+                })
+                .unwrap();
+            let mut model = BPE::default();
+            trainer.train(&mut model).unwrap();
+            model
+        };
+        let first_merges = |model: &BPE| {
+            let mut merges = model.merges_iter().collect::<Vec<_>>();
+            merges.sort_by_key(|(_, (rank, _))| *rank);
+            merges
+                .into_iter()
+                .take(4)
+                .map(|(_, (_, new_token))| new_token)
+                .collect::<Vec<_>>()
+        };
+
+        let mut unlimited = BpeTrainer::builder().show_progress(false).build();
+        let full = train(&mut unlimited);
+        assert_eq!(unlimited.word_counts().len(), 1002);
+
+        let mut limited = BpeTrainer::builder()
+            .show_progress(false)
+            .max_word_count_entries(20)
+            .build();
+        let pruned = train(&mut limited);
+        let word_counts = limited.word_counts();
+        assert!(word_counts.len() <= 20);
+        // The head of the distribution is counted exactly
+        assert_eq!(word_counts["hello"], 2000);
+        assert_eq!(word_counts["world"], 1000);
+        assert_eq!(first_merges(&pruned), first_merges(&full));
+        assert!(pruned.get_vocab().contains_key("hello"));
+        assert!(pruned.get_vocab().contains_key("world"));
+
+        let serialized = serde_json::to_string(&limited).unwrap();
+        assert!(serialized.contains(r#""max_word_count_entries":20"#));
+    }
+
+    #[test]
+    fn test_max_token_length() {
+        use crate::AddedToken;
+
+        let word_counts: HashMap<String, u32> = [
+            ("abababababab".into(), 100),
+            ("xxxxxxxxxxxxxxxx".into(), 50),
+            ("hello".into(), 10),
+            ("helloworld".into(), 5),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        let special = "<very-long-special-token>";
+        let train = |trainer: BpeTrainer| {
+            let mut model = BPE::default();
+            trainer.do_train(&word_counts, &mut model).unwrap();
+            model
+        };
+        let longest = |model: &BPE| {
+            model
+                .get_vocab()
+                .keys()
+                .filter(|token| token.as_str() != special)
+                .map(|token| token.chars().count())
+                .max()
+                .unwrap()
+        };
+
+        let full = train(BpeTrainer::builder().show_progress(false).build());
+        assert!(longest(&full) > 5);
+
+        let trainer = BpeTrainer::builder()
+            .show_progress(false)
+            .special_tokens(vec![AddedToken::from(special, true)])
+            .max_token_length(5)
+            .build();
+        let serialized = serde_json::to_string(&trainer).unwrap();
+        assert!(serialized.contains(r#""max_token_length":5"#));
+        let limited = train(trainer);
+        assert_eq!(longest(&limited), 5);
+        // The next best pairs still get merged
+        assert!(limited.get_vocab().contains_key("hello"));
+        assert!(limited.get_vocab().contains_key("abab"));
+        assert!(limited.get_vocab().contains_key(special));
+    }
+
+    #[test]
+    fn test_target_tokens() {
+        use crate::tokenizer::Model;
+
+        let word_counts: HashMap<String, u32> = [("low".into(), 10), ("lower".into(), 5)]
+            .iter()
+            .cloned()
+            .collect();
+        let targets: HashMap<String, u32> = [("xyz".into(), 3), ("wer".into(), 2)]
+            .iter()
+            .cloned()
+            .collect();
+        let train = |vocab_size| {
+            let mut trainer = BpeTrainer::from_target_tokens(targets.clone(), vocab_size);
+            trainer.show_progress = false;
+            let mut model = BPE::default();
+            trainer.do_train(&word_counts, &mut model).unwrap();
+            model
+        };
+
+        // The 8 chars, then the most frequent target token
+        let model = train(10);
+        assert!(model.get_vocab().contains_key("xyz"));
+        assert!(!model.get_vocab().contains_key("wer"));
+        assert!(!model.get_vocab().contains_key("lo"));
+        let tokens = model.tokenize("xyz").unwrap();
+        assert_eq!(tokens.len(), 1);
+
+        // Then the other one, and only then the general merges
+        let model = train(13);
+        assert!(model.get_vocab().contains_key("wer"));
+        assert!(model.get_vocab().contains_key("lo"));
+        assert_eq!(model.tokenize("lower").unwrap().len(), 2);
+
+        // Without them, the target tokens never show up
+        let mut model = BPE::default();
+        BpeTrainer::builder()
+            .show_progress(false)
+            .vocab_size(13)
+            .build()
+            .do_train(&word_counts, &mut model)
+            .unwrap();
+        assert!(!model.get_vocab().contains_key("xyz"));
+    }
+
+    #[test]
+    fn test_progress_callback() {
+        use crate::tokenizer::TrainerProgress;
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct Recorder(Arc<Mutex<Vec<String>>>);
+        impl TrainerProgress for Recorder {
+            fn on_phase_start(&self, phase: &str, total: usize) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("start {} {}", phase, total));
+            }
+            fn on_advance(&self, phase: &str, current: usize, total: usize) {
+                assert!(current <= total, "{}: {} > {}", phase, current, total);
+            }
+            fn on_phase_end(&self, phase: &str, _total: usize) {
+                self.0.lock().unwrap().push(format!("end {}", phase));
+            }
+        }
+
+        let events = Arc::new(Mutex::new(vec![]));
+        let word_counts: HashMap<String, u32> = [("roses".into(), 1), ("are".into(), 2)]
+            .iter()
+            .cloned()
+            .collect();
+        let trainer = BpeTrainer::builder()
+            .vocab_size(100)
+            .progress(Some(Box::new(Recorder(events.clone()))))
+            .build();
+        let mut model = BPE::default();
+        trainer.do_train(&word_counts, &mut model).unwrap();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                "start Compute alphabet 2",
+                "end Compute alphabet",
+                "start Tokenize words 2",
+                "end Tokenize words",
+                "start Count pairs 2",
+                "end Count pairs",
+                "start Compute merges 100",
+                "end Compute merges",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_special_tokens_with_ids() {
+        use crate::tokenizer::{AddedToken, Model};
+
+        let word_counts: HashMap<String, u32> = [("are".into(), 2), ("is".into(), 2)]
+            .iter()
+            .cloned()
+            .collect();
+        let trainer = BpeTrainer::builder()
+            .show_progress(false)
+            .special_tokens(vec![AddedToken::from("[MASK]", true)])
+            .special_tokens_with_ids(vec![
+                (AddedToken::from("</s>", true), 3),
+                (AddedToken::from("<s>", true), 2),
+                (AddedToken::from("<unk>", true), 1),
+                (AddedToken::from("<pad>", true), 0),
+            ])
+            .build();
+        let mut model = BPE::default();
+        trainer.do_train(&word_counts, &mut model).unwrap();
+
+        let ids = [
+            "<pad>", "<unk>", "<s>", "</s>", "[MASK]", "a", "e", "i", "r", "s",
+        ]
+        .iter()
+        .map(|token| model.token_to_id(token))
+        .collect::<Vec<_>>();
+        assert_eq!(ids, (0..10).map(Some).collect::<Vec<_>>());
+        // The merges use the new ids
+        let tokens = model.tokenize("are").unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(model.id_to_token(tokens[0].id).as_deref(), Some("are"));
+
+        let conflict = BpeTrainer::builder()
+            .show_progress(false)
+            .special_tokens_with_ids(vec![
+                (AddedToken::from("<s>", true), 0),
+                (AddedToken::from("<pad>", true), 0),
+            ])
+            .build();
+        assert!(conflict.do_train(&word_counts, &mut model).is_err());
+        // The model is left untouched on error
+        assert_eq!(model.token_to_id("[MASK]"), Some(4));
+
+        let out_of_range = BpeTrainer::builder()
+            .show_progress(false)
+            .special_tokens_with_ids(vec![(AddedToken::from("<s>", true), 100)])
+            .build();
+        assert!(out_of_range.do_train(&word_counts, &mut model).is_err());
     }
 }
@@ -0,0 +1,169 @@
+use super::save_parts;
+use crate::tokenizer::{Model, Result, Token};
+use crate::utils::{fnv1a, macro_rules_attribute};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+mod trainer;
+
+// Re-export
+pub use trainer::*;
+
+/// A model without any vocabulary, mapping each pre-token to one of `num_buckets` ids using
+/// a hash of its content (feature hashing). The hash is stable across runs and platforms, so a
+/// given token always gets the same id for the same `seed`, but different tokens can share an
+/// id, and ids can't be converted back to tokens.
+///
+/// The model doesn't own any token, so `token_to_id` always gives `None`. This way, the added
+/// tokens get some ids from `num_buckets` on, and never share an id with a hashed word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[macro_rules_attribute(impl_serde_type!)]
+pub struct HashedModel {
+    pub num_buckets: u32,
+    pub seed: u64,
+}
+
+impl HashedModel {
+    pub fn new(num_buckets: u32, seed: u64) -> Self {
+        Self { num_buckets, seed }
+    }
+
+    /// The id of the given token: the 64 bits FNV-1a hash of its bytes, starting from a basis
+    /// mixed with the `seed`, modulo `num_buckets`
+    fn bucket(&self, token: &str) -> u32 {
+        (fnv1a(token, self.seed) % u64::from(self.num_buckets)) as u32
+    }
+}
+
+impl Model for HashedModel {
+    type Trainer = HashedTrainer;
+
+    fn tokenize(&self, sequence: &str) -> Result<Vec<Token>> {
+        if self.num_buckets == 0 {
+            return Err("HashedModel needs at least one bucket".into());
+        }
+        if sequence.is_empty() {
+            return Ok(vec![]);
+        }
+        Ok(vec![Token {
+            id: self.bucket(sequence),
+            value: sequence.to_owned(),
+            offsets: (0, sequence.len()),
+        }])
+    }
+
+    fn token_to_id(&self, _token: &str) -> Option<u32> {
+        None
+    }
+
+    fn id_to_token(&self, _id: u32) -> Option<String> {
+        None
+    }
+
+    fn get_vocab(&self) -> HashMap<String, u32> {
+        HashMap::new()
+    }
+
+    fn get_vocab_size(&self) -> usize {
+        self.num_buckets as usize
+    }
+
+    fn save(&self, folder: &Path, name: Option<&str>) -> Result<Vec<PathBuf>> {
+        save_parts(folder, name, self.serialize_parts()?)
+    }
+
+    fn serialize_parts(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        Ok(vec![("hashed.json".to_string(), serde_json::to_vec(self)?)])
+    }
+
+    fn get_trainer(&self) -> Self::Trainer {
+        HashedTrainer::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ModelWrapper;
+
+    #[test]
+    fn tokenize() {
+        let model = HashedModel::new(1000, 0);
+        let tokens = model.tokenize("hello").unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].value, "hello");
+        assert_eq!(tokens[0].offsets, (0, 5));
+        assert_eq!(model.token_to_id("hello"), None);
+        assert_eq!(model.id_to_token(tokens[0].id), None);
+        assert!(model.tokenize("").unwrap().is_empty());
+
+        // Always the same id for the same token and seed, and always in range
+        for word in ["hello", "world", "héllo", "a", "ab", "ba", "😀"] {
+            let id = model.bucket(word);
+            assert!(id < 1000);
+            assert_eq!(HashedModel::new(1000, 0).bucket(word), id);
+        }
+        assert_ne!(model.bucket("ab"), model.bucket("ba"));
+        assert_ne!(
+            model.bucket("hello"),
+            HashedModel::new(1000, 42).bucket("hello")
+        );
+        // The 64 bits FNV-1a hash of "a" is 0xaf63dc4c8601ec8c
+        assert_eq!(
+            HashedModel::new(u32::MAX, 0).bucket("a"),
+            (0xaf63_dc4c_8601_ec8c_u64 % u64::from(u32::MAX)) as u32
+        );
+
+        assert_eq!(model.get_vocab_size(), 1000);
+        assert!(HashedModel::new(0, 0).tokenize("hello").is_err());
+    }
+
+    #[test]
+    fn added_tokens_after_buckets() {
+        use crate::models::TrainerWrapper;
+        use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+        use crate::tokenizer::{AddedToken, Tokenizer};
+
+        let model = HashedModel::new(50, 0);
+        // A word falling in the same bucket as the special token would
+        let word = (0..)
+            .map(|i| format!("w{}", i))
+            .find(|word| model.bucket(word) == model.bucket("[CLS]"))
+            .unwrap();
+        let bucket = model.bucket(&word);
+
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(WhitespaceSplit::default());
+        tokenizer
+            .add_special_tokens(&[AddedToken::from("[CLS]", true)])
+            .unwrap();
+        assert_eq!(tokenizer.token_to_id("[CLS]"), Some(50));
+
+        let encoding = tokenizer.encode(format!("[CLS] {}", word), false).unwrap();
+        assert_eq!(encoding.get_ids(), [50, bucket]);
+        assert_eq!(tokenizer.decode(vec![bucket], false).unwrap(), "");
+        assert_eq!(tokenizer.decode(vec![50, bucket], true).unwrap(), "");
+        assert_eq!(tokenizer.decode(vec![50, bucket], false).unwrap(), "[CLS]");
+
+        // The special tokens of the trainer get their own ids too
+        let mut trainer: TrainerWrapper =
+            HashedTrainer::new(vec![AddedToken::from("[SEP]", true)]).into();
+        tokenizer
+            .train_from_counter(&mut trainer, HashMap::new())
+            .unwrap();
+        assert_eq!(tokenizer.token_to_id("[SEP]"), Some(51));
+    }
+
+    #[test]
+    fn serialization() {
+        let model = HashedModel::new(1000, 42);
+        let serialized = serde_json::to_string(&model).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"HashedModel","num_buckets":1000,"seed":42}"#
+        );
+        let deserialized: ModelWrapper = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, ModelWrapper::HashedModel(model));
+    }
+}
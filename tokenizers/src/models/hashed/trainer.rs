@@ -0,0 +1,42 @@
+use super::HashedModel;
+use crate::{AddedToken, Result, Trainer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A `HashedModel` has no vocabulary to learn, so this trainer only returns its special
+/// tokens, to be added to the `Tokenizer`.
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HashedTrainer {
+    /// A list of special tokens that the model should know of
+    pub special_tokens: Vec<AddedToken>,
+}
+
+impl HashedTrainer {
+    pub fn new(special_tokens: Vec<AddedToken>) -> Self {
+        Self { special_tokens }
+    }
+}
+
+impl Trainer for HashedTrainer {
+    type Model = HashedModel;
+
+    fn should_show_progress(&self) -> bool {
+        false
+    }
+
+    fn train(&self, _model: &mut HashedModel) -> Result<Vec<AddedToken>> {
+        Ok(self.special_tokens.clone())
+    }
+
+    fn feed<I, S, F>(&mut self, _iterator: I, _process: F) -> Result<()>
+    where
+        I: Iterator<Item = S> + Send,
+        S: AsRef<str> + Send,
+        F: Fn(&str) -> Result<Vec<String>> + Sync,
+    {
+        Ok(())
+    }
+
+    fn feed_with_counter(&mut self, _counter: HashMap<String, u32>) {}
+}
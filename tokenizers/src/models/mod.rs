@@ -1,16 +1,21 @@
 //! Popular tokenizer models.
 
 pub mod bpe;
+pub mod hashed;
 pub mod unigram;
 pub mod wordlevel;
 pub mod wordpiece;
 
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fs::{read_to_string, File};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize, Serializer};
 
 use crate::models::bpe::{BpeTrainer, BPE};
+use crate::models::hashed::{HashedModel, HashedTrainer};
 use crate::models::unigram::{Unigram, UnigramTrainer};
 use crate::models::wordlevel::{WordLevel, WordLevelTrainer};
 use crate::models::wordpiece::{WordPiece, WordPieceTrainer};
@@ -51,6 +56,104 @@ impl<'a> Serialize for OrderedVocabIter<'a> {
     }
 }
 
+/// Write the parts given by `Model::serialize_parts` in the given folder, using the given
+/// `prefix` for their file names.
+pub(crate) fn save_parts(
+    folder: &Path,
+    prefix: Option<&str>,
+    parts: Vec<(String, Vec<u8>)>,
+) -> Result<Vec<PathBuf>> {
+    parts
+        .into_iter()
+        .map(|(name, content)| {
+            let file_name = match prefix {
+                Some(prefix) => format!("{}-{}", prefix, name),
+                None => name,
+            };
+            let path: PathBuf = [folder, Path::new(file_name.as_str())].iter().collect();
+            File::create(&path)?.write_all(&content)?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// How a model handles a piece of input that can't be found in its vocabulary.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+pub enum UnkPolicy {
+    /// Emit the given token in place of the unknown input
+    Token(String),
+    /// Silently skip the unknown input
+    Drop,
+    /// Fail with an [`UnkError`] naming the unknown input
+    Error,
+}
+
+/// Error returned by the models when they encounter some unknown input while using
+/// [`UnkPolicy::Error`]. The `offset` is relative to the sequence given to `tokenize`, but it
+/// gets shifted to a byte offset in the original sequence when the error comes from encoding
+/// with a tokenizer.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[error("Unknown substring `{substring}` at offset {offset}")]
+pub struct UnkError {
+    pub substring: String,
+    pub offset: usize,
+}
+
+/// Error returned by the trainers when the special tokens can't get the ids they were given
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum SpecialTokenIdError {
+    #[error("The special tokens `{0}` and `{1}` can't both have the id {2}")]
+    Conflict(String, String, u32),
+    #[error("The special token `{0}` can't have the id {1}, the vocabulary has {2} tokens")]
+    OutOfRange(String, u32, usize),
+}
+
+/// Compute the final id of each token of a trained vocabulary, given in the order of their
+/// current ids. The tokens in `fixed_ids` get the id they were given, and all the others fill
+/// the remaining ids, in the same order. The tokens of `fixed_ids` missing from the
+/// vocabulary are ignored.
+pub(crate) fn pin_token_ids<S: AsRef<str>>(
+    tokens: &[S],
+    fixed_ids: &HashMap<String, u32>,
+) -> Result<Vec<u32>> {
+    let mut pinned: Vec<(u32, &str)> = tokens
+        .iter()
+        .filter_map(|token| {
+            let token = token.as_ref();
+            fixed_ids.get(token).map(|id| (*id, token))
+        })
+        .collect();
+    pinned.sort_unstable();
+
+    let mut taken = vec![false; tokens.len()];
+    for (i, (id, token)) in pinned.iter().enumerate() {
+        if *id as usize >= tokens.len() {
+            return Err(Box::new(SpecialTokenIdError::OutOfRange(
+                token.to_string(),
+                *id,
+                tokens.len(),
+            )));
+        }
+        if i > 0 && pinned[i - 1].0 == *id {
+            return Err(Box::new(SpecialTokenIdError::Conflict(
+                pinned[i - 1].1.to_owned(),
+                token.to_string(),
+                *id,
+            )));
+        }
+        taken[*id as usize] = true;
+    }
+
+    let mut free_ids = (0..tokens.len() as u32).filter(|id| !taken[*id as usize]);
+    Ok(tokens
+        .iter()
+        .map(|token| match fixed_ids.get(token.as_ref()) {
+            Some(id) => *id,
+            None => free_ids.next().unwrap(),
+        })
+        .collect())
+}
+
 #[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 #[serde(untagged)]
 pub enum ModelWrapper {
@@ -60,12 +163,14 @@ pub enum ModelWrapper {
     WordPiece(WordPiece),
     WordLevel(WordLevel),
     Unigram(Unigram),
+    HashedModel(HashedModel),
 }
 
 impl_enum_from!(WordLevel, ModelWrapper, WordLevel);
 impl_enum_from!(WordPiece, ModelWrapper, WordPiece);
 impl_enum_from!(BPE, ModelWrapper, BPE);
 impl_enum_from!(Unigram, ModelWrapper, Unigram);
+impl_enum_from!(HashedModel, ModelWrapper, HashedModel);
 
 impl Model for ModelWrapper {
     type Trainer = TrainerWrapper;
@@ -76,6 +181,7 @@ impl Model for ModelWrapper {
             Self::WordPiece(t) => t.tokenize(tokens),
             Self::BPE(t) => t.tokenize(tokens),
             Self::Unigram(t) => t.tokenize(tokens),
+            Self::HashedModel(t) => t.tokenize(tokens),
         }
     }
 
@@ -85,6 +191,7 @@ impl Model for ModelWrapper {
             Self::WordPiece(t) => t.token_to_id(token),
             Self::BPE(t) => t.token_to_id(token),
             Self::Unigram(t) => t.token_to_id(token),
+            Self::HashedModel(t) => t.token_to_id(token),
         }
     }
 
@@ -94,6 +201,17 @@ impl Model for ModelWrapper {
             Self::WordPiece(t) => t.id_to_token(id),
             Self::BPE(t) => t.id_to_token(id),
             Self::Unigram(t) => t.id_to_token(id),
+            Self::HashedModel(t) => t.id_to_token(id),
+        }
+    }
+
+    fn id_to_token_cow(&self, id: u32) -> Option<Cow<'_, str>> {
+        match self {
+            Self::WordLevel(t) => t.id_to_token_cow(id),
+            Self::WordPiece(t) => t.id_to_token_cow(id),
+            Self::BPE(t) => t.id_to_token_cow(id),
+            Self::Unigram(t) => t.id_to_token_cow(id),
+            Self::HashedModel(t) => t.id_to_token_cow(id),
         }
     }
 
@@ -103,6 +221,7 @@ impl Model for ModelWrapper {
             Self::WordPiece(t) => t.get_vocab(),
             Self::BPE(t) => t.get_vocab(),
             Self::Unigram(t) => t.get_vocab(),
+            Self::HashedModel(t) => t.get_vocab(),
         }
     }
 
@@ -112,6 +231,7 @@ impl Model for ModelWrapper {
             Self::WordPiece(t) => t.get_vocab_size(),
             Self::BPE(t) => t.get_vocab_size(),
             Self::Unigram(t) => t.get_vocab_size(),
+            Self::HashedModel(t) => t.get_vocab_size(),
         }
     }
 
@@ -121,6 +241,17 @@ impl Model for ModelWrapper {
             Self::WordPiece(t) => t.save(folder, name),
             Self::BPE(t) => t.save(folder, name),
             Self::Unigram(t) => t.save(folder, name),
+            Self::HashedModel(t) => t.save(folder, name),
+        }
+    }
+
+    fn serialize_parts(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        match self {
+            Self::WordLevel(t) => t.serialize_parts(),
+            Self::WordPiece(t) => t.serialize_parts(),
+            Self::BPE(t) => t.serialize_parts(),
+            Self::Unigram(t) => t.serialize_parts(),
+            Self::HashedModel(t) => t.serialize_parts(),
         }
     }
 
@@ -130,7 +261,52 @@ impl Model for ModelWrapper {
             Self::WordPiece(t) => t.get_trainer().into(),
             Self::BPE(t) => t.get_trainer().into(),
             Self::Unigram(t) => t.get_trainer().into(),
+            Self::HashedModel(t) => t.get_trainer().into(),
+        }
+    }
+
+    fn unk_token(&self) -> Option<String> {
+        match self {
+            Self::WordLevel(t) => t.unk_token(),
+            Self::WordPiece(t) => t.unk_token(),
+            // `BPE` has its own `get_unk_token`, returning the raw `unk_token`
+            Self::BPE(t) => t.unk_token(),
+            Self::Unigram(t) => t.unk_token(),
+            Self::HashedModel(t) => t.unk_token(),
+        }
+    }
+}
+
+impl ModelWrapper {
+    /// The types of model that can be deserialized
+    const TYPES: &'static [&'static str] =
+        &["BPE", "WordPiece", "WordLevel", "Unigram", "HashedModel"];
+
+    /// Load only the model of the `Tokenizer` saved in the given file, without building its
+    /// normalizer, pre-tokenizer, post-processor or decoder.
+    pub fn from_tokenizer_file<P: AsRef<Path>>(file: P) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct ModelSection {
+            model: serde_json::Value,
+        }
+
+        let content = read_to_string(file)?;
+        let section: ModelSection = serde_json::from_str(&content)?;
+        // The models from before the "type" field get matched by their fields instead
+        if let Some(kind) = section.model.get("type") {
+            let known = kind
+                .as_str()
+                .is_some_and(|kind| Self::TYPES.contains(&kind));
+            if !known {
+                return Err(format!(
+                    "Unknown model type {}, expected one of {}",
+                    kind,
+                    Self::TYPES.join(", ")
+                )
+                .into());
+            }
         }
+        Ok(serde_json::from_value(section.model)?)
     }
 }
 
@@ -140,6 +316,7 @@ pub enum TrainerWrapper {
     WordPieceTrainer(WordPieceTrainer),
     WordLevelTrainer(WordLevelTrainer),
     UnigramTrainer(UnigramTrainer),
+    HashedTrainer(HashedTrainer),
 }
 
 impl Trainer for TrainerWrapper {
@@ -151,6 +328,7 @@ impl Trainer for TrainerWrapper {
             Self::WordPieceTrainer(wpt) => wpt.should_show_progress(),
             Self::WordLevelTrainer(wpt) => wpt.should_show_progress(),
             Self::UnigramTrainer(wpt) => wpt.should_show_progress(),
+            Self::HashedTrainer(ht) => ht.should_show_progress(),
         }
     }
 
@@ -172,6 +350,10 @@ impl Trainer for TrainerWrapper {
                 ModelWrapper::Unigram(u) => t.train(u),
                 _ => Err("UnigramTrainer can only train a Unigram".into()),
             },
+            Self::HashedTrainer(t) => match model {
+                ModelWrapper::HashedModel(h) => t.train(h),
+                _ => Err("HashedTrainer can only train a HashedModel".into()),
+            },
         }
     }
 
@@ -186,6 +368,7 @@ impl Trainer for TrainerWrapper {
             Self::WordPieceTrainer(wpt) => wpt.feed(iterator, process),
             Self::WordLevelTrainer(wpt) => wpt.feed(iterator, process),
             Self::UnigramTrainer(wpt) => wpt.feed(iterator, process),
+            Self::HashedTrainer(ht) => ht.feed(iterator, process),
         }
     }
 
@@ -196,14 +379,40 @@ impl Trainer for TrainerWrapper {
             Self::WordPieceTrainer(wpt) => wpt.feed_with_counter(counter),
             Self::WordLevelTrainer(wpt) => wpt.feed_with_counter(counter),
             Self::UnigramTrainer(wpt) => wpt.feed_with_counter(counter),
+            Self::HashedTrainer(ht) => ht.feed_with_counter(counter),
         }
     }
 }
 
+impl TrainerWrapper {
+    /// Instantiate a trainer from the given file, as saved by `save`
+    pub fn from_file<P: AsRef<Path>>(file: P) -> Result<Self> {
+        let content = read_to_string(file)?;
+        let trainer = serde_json::from_str(&content)?;
+        Ok(trainer)
+    }
+
+    /// Save the trainer with all its parameters at the given path, to reproduce a training
+    /// later. The word counts it was fed with, if any, are saved too.
+    pub fn save<P: AsRef<Path>>(&self, path: P, pretty: bool) -> Result<()> {
+        let serialized = if pretty {
+            serde_json::to_string_pretty(self)?
+        } else {
+            serde_json::to_string(self)?
+        };
+
+        let mut file = File::create(path)?;
+        file.write_all(serialized.as_bytes())?;
+
+        Ok(())
+    }
+}
+
 impl_enum_from!(BpeTrainer, TrainerWrapper, BpeTrainer);
 impl_enum_from!(WordPieceTrainer, TrainerWrapper, WordPieceTrainer);
 impl_enum_from!(UnigramTrainer, TrainerWrapper, UnigramTrainer);
 impl_enum_from!(WordLevelTrainer, TrainerWrapper, WordLevelTrainer);
+impl_enum_from!(HashedTrainer, TrainerWrapper, HashedTrainer);
 
 #[cfg(test)]
 mod tests {
@@ -218,6 +427,96 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn model_from_tokenizer_file() {
+        let model =
+            ModelWrapper::from_tokenizer_file("tests/fixtures/hf_dir/tokenizer.json").unwrap();
+        assert!(matches!(model, ModelWrapper::WordLevel(_)));
+        assert_eq!(model.get_vocab_size(), 4);
+        assert_eq!(model.token_to_id("hello"), Some(2));
+
+        let dir = std::env::temp_dir().join("tokenizers_model_from_tokenizer_file_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("tokenizer.json");
+        let content = std::fs::read_to_string("tests/fixtures/hf_dir/tokenizer.json").unwrap();
+        std::fs::write(&path, content.replace("\"WordLevel\"", "\"Bigram\"")).unwrap();
+        let err = ModelWrapper::from_tokenizer_file(&path).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown model type \"Bigram\", expected one of BPE, WordPiece, WordLevel, Unigram, HashedModel"
+        );
+
+        std::fs::write(&path, r#"{"version": "1.0"}"#).unwrap();
+        assert!(ModelWrapper::from_tokenizer_file(&path).is_err());
+    }
+
+    #[test]
+    fn trainer_save_and_load() {
+        let trainer = BpeTrainer::builder()
+            .vocab_size(1234)
+            .min_frequency(3)
+            .show_progress(false)
+            .special_tokens(vec![AddedToken::from("[UNK]", true)])
+            .limit_alphabet(100)
+            .initial_alphabet(['a', 'é'].iter().copied().collect())
+            .continuing_subword_prefix("##".into())
+            .end_of_word_suffix("</w>".into())
+            .build();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trainer.json");
+        TrainerWrapper::from(trainer.clone())
+            .save(&path, true)
+            .unwrap();
+
+        match TrainerWrapper::from_file(&path).unwrap() {
+            TrainerWrapper::BpeTrainer(loaded) => assert_eq!(loaded, trainer),
+            _ => panic!("Expected a BpeTrainer"),
+        }
+    }
+
+    #[test]
+    fn serialize_parts_same_as_save() {
+        let vocab: HashMap<String, u32> = [("<unk>", 0), ("a", 1), ("b", 2), ("ab", 3)]
+            .iter()
+            .map(|(token, id)| (token.to_string(), *id))
+            .collect();
+        let models: Vec<ModelWrapper> = vec![
+            BPE::builder()
+                .vocab_and_merges(vocab.clone(), vec![("a".into(), "b".into())])
+                .build()
+                .unwrap()
+                .into(),
+            WordPiece::builder()
+                .vocab(vocab.clone())
+                .build()
+                .unwrap()
+                .into(),
+            WordLevel::builder().vocab(vocab).build().unwrap().into(),
+            Unigram::from(
+                vec![
+                    ("<unk>".into(), 0.0),
+                    ("a".into(), -1.0),
+                    ("b".into(), -2.0),
+                ],
+                Some(0),
+                None,
+            )
+            .unwrap()
+            .into(),
+        ];
+
+        for model in models {
+            let dir = tempfile::tempdir().unwrap();
+            let parts = model.serialize_parts().unwrap();
+            let files = model.save(dir.path(), Some("model")).unwrap();
+            assert_eq!(parts.len(), files.len());
+            for ((name, content), file) in parts.iter().zip(files) {
+                assert_eq!(file, dir.path().join(format!("model-{}", name)));
+                assert_eq!(&std::fs::read(file).unwrap(), content);
+            }
+        }
+    }
+
     #[test]
     fn incomplete_ordered_vocab() {
         let vocab_r: HashMap<u32, String> =
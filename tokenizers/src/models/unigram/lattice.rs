@@ -92,7 +92,7 @@ pub struct Node {
     length: usize,
     prev: Option<NodeRef>,
     backtrace_score: f64,
-    score: f64,
+    pub(super) score: f64,
 }
 
 impl PartialEq for Node {
@@ -378,6 +378,11 @@ impl<'a> Lattice<'a> {
     }
 
     pub fn sample(&self, theta: f64) -> Vec<NodeRef> {
+        self.sample_with_rng(theta, &mut thread_rng())
+    }
+
+    /// Sample like `sample`, drawing from the given random generator
+    pub fn sample_with_rng<R: Rng + ?Sized>(&self, theta: f64, rng: &mut R) -> Vec<NodeRef> {
         let len = self.len();
         if len == 0 {
             return vec![];
@@ -397,7 +402,6 @@ impl<'a> Lattice<'a> {
             }
         }
 
-        let mut rng = thread_rng();
         let mut results: Vec<NodeRef> = vec![];
         let mut probs: Vec<f64> = vec![];
         let mut z = alpha[self.eos_node().borrow().node_id];
@@ -410,7 +414,7 @@ impl<'a> Lattice<'a> {
                 probs.push((alpha[lid] + theta * lnode.borrow().score - z).exp())
             }
             let dist = WeightedIndex::new(&probs).unwrap();
-            let index = dist.sample(&mut rng);
+            let index = dist.sample(rng);
             node = Rc::clone(&self.end_nodes[pos][index]);
             if node == self.bos_node() {
                 break;
@@ -3,28 +3,42 @@ use super::{
     trainer::UnigramTrainer,
     trie::{Trie, TrieBuilder},
 };
+use crate::models::save_parts;
 use crate::tokenizer::{Model, Result, Token};
 use crate::utils::cache::Cache;
+use crate::utils::fnv1a;
 
+use rand::{thread_rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::convert::TryInto;
 use std::fs::read_to_string;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 type TokenMap = HashMap<String, u32>;
 type Vocab = Vec<(String, f64)>;
 
 /// A `Unigram` model to encode sentences.
 pub struct Unigram {
-    token_to_ids: TokenMap,
-    pub(crate) vocab: Vocab,
+    /// The vocabulary and its trie, shared between clones
+    token_to_ids: Arc<TokenMap>,
+    pub(crate) vocab: Arc<Vocab>,
     cache: Cache<String, Vec<String>>,
-    trie: Trie<u8>,
+    trie: Arc<Trie<u8>>,
     pub min_score: f64,
     pub(super) unk_id: Option<usize>,
     pub(super) bos_id: usize,
     pub(super) eos_id: usize,
     pub(super) with_traits: bool,
+    /// When set, `tokenize` samples the segmentation of each sentence with this smoothing,
+    /// like `encode_sample` does, instead of always using the best one
+    pub sampling_alpha: Option<f64>,
+    /// An optional seed for the sampling. When set, each sentence gets its own random
+    /// generator, seeded from this seed and the sentence itself, so that a given sentence
+    /// is always tokenized the same way, whatever the platform or the version of Rust.
+    pub sampling_seed: Option<u64>,
 
     fuse_unk: bool,
     is_optimized: bool,
@@ -38,6 +52,7 @@ impl PartialEq for Unigram {
 impl Clone for Unigram {
     // `Clone` can't be derive because it's not implemented for `Cache`.
     // To keep things simple when we clone, the new Unigram will start with a fresh cache.
+    // The vocabulary and the trie are shared with the clone, and only copied if modified.
     fn clone(&self) -> Self {
         let fresh_cache = self.cache.fresh();
         Self {
@@ -50,6 +65,8 @@ impl Clone for Unigram {
             bos_id: self.bos_id,
             eos_id: self.eos_id,
             with_traits: self.with_traits,
+            sampling_alpha: self.sampling_alpha,
+            sampling_seed: self.sampling_seed,
             fuse_unk: self.fuse_unk,
             is_optimized: self.is_optimized,
         }
@@ -78,6 +95,16 @@ pub enum UnigramError {
     MissingUnkId,
 }
 
+/// Error returned by [`Unigram::encode_strict`] when a sentence can't be segmented using
+/// only the pieces of the vocabulary. The `offset` is the byte offset of the `substring`
+/// in the sentence.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[error("No piece of the vocabulary covers `{substring}` at offset {offset}")]
+pub struct UnsegmentableError {
+    pub substring: String,
+    pub offset: usize,
+}
+
 impl Default for Unigram {
     fn default() -> Self {
         let vocab = vec![("<unk>".to_string(), 0.0)];
@@ -123,15 +150,17 @@ impl Unigram {
         let is_optimized = true;
 
         Ok(Self {
-            vocab,
-            token_to_ids,
-            trie,
+            vocab: Arc::new(vocab),
+            token_to_ids: Arc::new(token_to_ids),
+            trie: Arc::new(trie),
             min_score,
             bos_id,
             eos_id,
             unk_id,
             fuse_unk,
             with_traits: with_traits.unwrap_or(false),
+            sampling_alpha: None,
+            sampling_seed: None,
             cache: Cache::default(),
             is_optimized,
         })
@@ -332,27 +361,235 @@ impl Unigram {
         let mut lattice = Lattice::from(sentence, self.bos_id, self.eos_id);
         self.populate_nodes(&mut lattice);
         if self.fuse_unk {
-            let mut results = vec![];
-            let mut token = String::new();
-            for node in lattice.viterbi().iter() {
-                let item = lattice.piece(&node.borrow());
-                if node.borrow().id == self.unk_id.ok_or(UnigramError::MissingUnkId)? {
-                    token.push_str(&item);
-                } else {
-                    if !token.is_empty() {
-                        results.push(token);
-                        token = String::new();
-                    }
-                    results.push(item.to_string());
+            let nodes = lattice.viterbi();
+            self.fuse_unk_pieces(
+                nodes
+                    .iter()
+                    .map(|node| (node.borrow().id, lattice.piece(&node.borrow()))),
+            )
+        } else {
+            Ok(lattice.tokens())
+        }
+    }
+
+    /// Merge each run of consecutive unknown pieces into a single one
+    fn fuse_unk_pieces<I>(&self, pieces: I) -> Result<Vec<String>>
+    where
+        I: Iterator<Item = (usize, String)>,
+    {
+        let mut results = vec![];
+        let mut token = String::new();
+        for (id, item) in pieces {
+            if id == self.unk_id.ok_or(UnigramError::MissingUnkId)? {
+                token.push_str(&item);
+            } else {
+                if !token.is_empty() {
+                    results.push(token);
+                    token = String::new();
                 }
+                results.push(item);
             }
-            if !token.is_empty() {
-                results.push(token);
+        }
+        if !token.is_empty() {
+            results.push(token);
+        }
+        Ok(results)
+    }
+
+    /// Encode the given sentence in a segmentation sampled from all the possible ones, each
+    /// with a probability proportional to `exp(score / alpha)`, as done by SentencePiece for
+    /// the subword regularization. The smaller `alpha`, the more likely the best
+    /// segmentation, which is always given with an `alpha` of 0. The `seed` makes the
+    /// sampling reproducible.
+    pub fn encode_sample(
+        &self,
+        sentence: &str,
+        alpha: f64,
+        seed: Option<u64>,
+    ) -> Result<Vec<String>> {
+        if alpha <= 0.0 {
+            return self.encode(sentence);
+        }
+        if sentence.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut lattice = Lattice::from(sentence, self.bos_id, self.eos_id);
+        self.populate_nodes(&mut lattice);
+        let nodes = match seed {
+            Some(seed) => {
+                lattice.sample_with_rng(1.0 / alpha, &mut ChaCha8Rng::seed_from_u64(seed))
             }
-            Ok(results)
+            None => lattice.sample_with_rng(1.0 / alpha, &mut thread_rng()),
+        };
+        let pieces = nodes
+            .iter()
+            .map(|node| (node.borrow().id, lattice.piece(&node.borrow())));
+        if self.fuse_unk {
+            self.fuse_unk_pieces(pieces)
         } else {
-            Ok(lattice.tokens())
+            Ok(pieces.map(|(_, piece)| piece).collect())
+        }
+    }
+
+    /// Encode the given sentence in its `n` most likely segmentations, along with their
+    /// log-probabilities. They are sorted from the most likely one, the ties being ordered
+    /// by their pieces, and there can be fewer than `n` of them when the sentence doesn't
+    /// have that many segmentations.
+    /// ```
+    /// use tokenizers::models::unigram::Unigram;
+    ///
+    /// let pieces = vec![
+    ///     ("<unk>".to_string(), 0.0),
+    ///     ("a".to_string(), -1.0),
+    ///     ("b".to_string(), -1.0),
+    ///     ("ab".to_string(), -1.5),
+    /// ];
+    /// let model = Unigram::from(pieces, Some(0), None).unwrap();
+    /// let result = model.encode_nbest("ab", 2).unwrap();
+    /// assert_eq!(result[0], (vec!["ab".to_string()], -1.5));
+    /// assert_eq!(result[1], (vec!["a".to_string(), "b".to_string()], -2.0));
+    /// ```
+    pub fn encode_nbest(&self, sentence: &str, n: usize) -> Result<Vec<(Vec<String>, f64)>> {
+        if sentence.is_empty() || n == 0 {
+            return Ok(vec![]);
+        }
+        let mut lattice = Lattice::from(sentence, self.bos_id, self.eos_id);
+        self.populate_nodes(&mut lattice);
+        let mut results = lattice
+            .nbest(n)
+            .into_iter()
+            .map(|nodes| {
+                let score = nodes.iter().map(|node| node.borrow().score).sum();
+                let pieces = nodes
+                    .iter()
+                    .map(|node| (node.borrow().id, lattice.piece(&node.borrow())));
+                let tokens = if self.fuse_unk {
+                    self.fuse_unk_pieces(pieces)?
+                } else {
+                    pieces.map(|(_, piece)| piece).collect()
+                };
+                Ok((tokens, score))
+            })
+            .collect::<Result<Vec<(Vec<String>, f64)>>>()?;
+        results.sort_by(|(a, a_score), (b, b_score)| {
+            b_score
+                .partial_cmp(a_score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.cmp(b))
+        });
+        Ok(results)
+    }
+
+    /// Encode the given sentence like `encode`, but without ever using the unknown token. This
+    /// gives the ids of the best segmentation made only of pieces of the vocabulary, or fails
+    /// with the first char that can't be covered when there is none.
+    pub fn encode_strict(
+        &self,
+        sentence: &str,
+    ) -> std::result::Result<Vec<u32>, UnsegmentableError> {
+        let size = sentence.len();
+        // The best path ending at each position, as (score, starts_at, id)
+        let mut best_path_ends_at: Vec<Option<(f64, usize, u32)>> = vec![None; size + 1];
+        let mut furthest = 0;
+        for starts_at in 0..size {
+            let best_path_score_till_here = match best_path_ends_at[starts_at] {
+                Some((score, _, _)) => score,
+                None if starts_at == 0 => 0.0,
+                None => continue,
+            };
+            for tok_bytes in self
+                .trie
+                .common_prefix_search(sentence.bytes().skip(starts_at))
+            {
+                let key_pos = starts_at + tok_bytes.len();
+                let id = self.token_to_ids[&sentence[starts_at..key_pos]];
+                let score = best_path_score_till_here + self.vocab[id as usize].1;
+                if !matches!(best_path_ends_at[key_pos], Some((best, _, _)) if best >= score) {
+                    best_path_ends_at[key_pos] = Some((score, starts_at, id));
+                }
+                furthest = furthest.max(key_pos);
+            }
+        }
+
+        if furthest < size {
+            let c = sentence[furthest..].chars().next().unwrap();
+            return Err(UnsegmentableError {
+                substring: c.to_string(),
+                offset: furthest,
+            });
+        }
+
+        let mut ids = vec![];
+        let mut ends_at = size;
+        while let Some((_, starts_at, id)) = best_path_ends_at[ends_at] {
+            ids.push(id);
+            ends_at = starts_at;
+        }
+        ids.reverse();
+        Ok(ids)
+    }
+
+    /// Get the vocabulary of the model as pairs of `(token, score)`, in id order.
+    ///
+    /// Along with [`Unigram::get_unk_id`], this gives back what's needed to build the same
+    /// model with [`Unigram::from`], for example after editing some scores.
+    pub fn get_vocab_with_scores(&self) -> Vec<(String, f64)> {
+        (*self.vocab).clone()
+    }
+
+    /// Get the id of the unknown token, if there is one
+    pub fn get_unk_id(&self) -> Option<usize> {
+        self.unk_id
+    }
+
+    /// Round the score of every piece to the given number of decimals, to make the
+    /// serialized model smaller.
+    ///
+    /// The segmentations only change when two of them end up with the same score: the
+    /// Viterbi search keeps the first one it finds, even if the other one used to score
+    /// slightly higher.
+    pub fn round_scores(&mut self, decimals: u8) {
+        let factor = 10f64.powi(decimals.into());
+        for (_, score) in Arc::make_mut(&mut self.vocab).iter_mut() {
+            *score = (*score * factor).round() / factor;
+        }
+        self.min_score = self
+            .vocab
+            .iter()
+            .map(|(_, score)| *score)
+            .fold(f64::INFINITY, f64::min);
+        self.cache = self.cache.fresh();
+    }
+
+    /// Shrink the vocabulary down to `target_vocab_size` pieces, dropping the ones whose
+    /// removal increases the least the loss on the given `corpus`, the same way the
+    /// `UnigramTrainer` prunes its pieces. The scores get re-estimated on the `corpus` along
+    /// the way.
+    ///
+    /// The `corpus` is made of words that are used as is, so they should be normalized and
+    /// pre-tokenized like the ones used for training. The unknown token and the
+    /// single-character pieces are always kept, so this fails if they are more than
+    /// `target_vocab_size`. Any other token absent from the `corpus`, like the special
+    /// tokens, might get pruned.
+    pub fn prune_to<I>(&mut self, target_vocab_size: usize, corpus: I) -> Result<()>
+    where
+        I: Iterator<Item = String>,
+    {
+        if target_vocab_size >= self.len() {
+            return Ok(());
+        }
+        let mut words: HashMap<String, u32> = HashMap::new();
+        for word in corpus {
+            *words.entry(word).or_default() += 1;
         }
+        let trainer = UnigramTrainer::builder()
+            .show_progress(false)
+            .vocab_size(target_vocab_size.try_into()?)
+            .unk_token(self.unk_id.map(|id| self.vocab[id].0.clone()))
+            .with_traits(Some(self.with_traits))
+            .build()?;
+        *self = trainer.prune_model(self, words.into_iter().collect())?;
+        Ok(())
     }
 
     /// Iterate of vocabulary of the model as a pair of `(token, score)`.
@@ -399,7 +636,7 @@ impl Model for Unigram {
     type Trainer = UnigramTrainer;
 
     fn get_vocab(&self) -> HashMap<String, u32> {
-        self.token_to_ids.clone()
+        (*self.token_to_ids).clone()
     }
 
     fn get_vocab_size(&self) -> usize {
@@ -407,7 +644,13 @@ impl Model for Unigram {
     }
 
     fn tokenize(&self, sentence: &str) -> Result<Vec<Token>> {
-        let str_tokens = self.encode(sentence)?;
+        let str_tokens = match self.sampling_alpha {
+            Some(alpha) => {
+                let seed = self.sampling_seed.map(|seed| seed ^ fnv1a(sentence, 0));
+                self.encode_sample(sentence, alpha, seed)?
+            }
+            None => self.encode(sentence)?,
+        };
         let mut offset = 0;
         let mut tokens = Vec::with_capacity(str_tokens.len());
         for string in str_tokens {
@@ -431,22 +674,29 @@ impl Model for Unigram {
         self.vocab.get(id as usize).map(|item| item.0.clone())
     }
 
+    fn id_to_token_cow(&self, id: u32) -> Option<Cow<'_, str>> {
+        self.vocab
+            .get(id as usize)
+            .map(|item| Cow::Borrowed(item.0.as_str()))
+    }
+
     fn save(&self, folder: &Path, name: Option<&str>) -> Result<Vec<PathBuf>> {
-        let name = match name {
-            Some(name) => format!("{}-unigram.json", name),
-            None => "unigram.json".to_string(),
-        };
-        let mut fullpath = PathBuf::new();
-        fullpath.push(folder);
-        fullpath.push(name);
+        save_parts(folder, name, self.serialize_parts()?)
+    }
+
+    fn serialize_parts(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        // The vocab with its scores and the unk id
         let string = serde_json::to_string_pretty(self)?;
-        std::fs::write(&fullpath, string)?;
-        Ok(vec![fullpath])
+        Ok(vec![("unigram.json".to_string(), string.into_bytes())])
     }
 
     fn get_trainer(&self) -> Self::Trainer {
         UnigramTrainer::default()
     }
+
+    fn unk_token(&self) -> Option<String> {
+        self.unk_id.map(|id| self.vocab[id].0.clone())
+    }
 }
 
 #[cfg(test)]
@@ -523,6 +773,106 @@ mod tests {
         assert_eq!(result, vec!["abcd"]);
     }
 
+    #[test]
+    fn test_encode_nbest() {
+        let sentencepieces = vec![
+            ("<unk>".to_string(), 0.0),
+            ("a".to_string(), -1.0),
+            ("b".to_string(), -1.5),
+            ("c".to_string(), -2.5),
+            ("ab".to_string(), -2.5),
+            ("bc".to_string(), -3.0),
+            ("abc".to_string(), -4.5),
+        ];
+        let model = Unigram::from(sentencepieces, Some(0), None).unwrap();
+        let tokens = |t: &[&str]| t.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+
+        let nbest = model.encode_nbest("abc", 2).unwrap();
+        assert_eq!(
+            nbest,
+            vec![(tokens(&["a", "bc"]), -4.0), (tokens(&["abc"]), -4.5)]
+        );
+        assert_eq!(nbest[0].0, model.encode("abc").unwrap());
+
+        // "ab c" and "a b c" have the same score, so they are sorted by their pieces
+        let nbest = model.encode_nbest("abc", 10).unwrap();
+        assert_eq!(
+            nbest,
+            vec![
+                (tokens(&["a", "bc"]), -4.0),
+                (tokens(&["abc"]), -4.5),
+                (tokens(&["a", "b", "c"]), -5.0),
+                (tokens(&["ab", "c"]), -5.0),
+            ]
+        );
+        assert!(model.encode_nbest("abc", 0).unwrap().is_empty());
+        assert!(model.encode_nbest("", 2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_encode_sample() {
+        use std::collections::HashSet;
+
+        let sentencepieces = vec![
+            ("<unk>".to_string(), 0.0),
+            ("a".to_string(), -1.0),
+            ("b".to_string(), -1.5),
+            ("c".to_string(), -2.5),
+            ("ab".to_string(), -2.5),
+            ("bc".to_string(), -3.0),
+            ("abc".to_string(), -4.5),
+        ];
+        let mut model = Unigram::from(sentencepieces, Some(0), None).unwrap();
+        let best = model.encode("abc").unwrap();
+
+        // The same seed always gives the same segmentation
+        for seed in 0..10 {
+            assert_eq!(
+                model.encode_sample("abc", 1.0, Some(seed)).unwrap(),
+                model.encode_sample("abc", 1.0, Some(seed)).unwrap()
+            );
+        }
+        let samples = (0..100)
+            .map(|seed| model.encode_sample("abc", 1.0, Some(seed)).unwrap())
+            .collect::<HashSet<_>>();
+        assert!(samples.len() > 1);
+        assert!(samples.contains(&best));
+
+        // With a tiny alpha, it's always the best one
+        for seed in 0..10 {
+            assert_eq!(model.encode_sample("abc", 0.01, Some(seed)).unwrap(), best);
+        }
+        assert_eq!(model.encode_sample("abc", 0.0, None).unwrap(), best);
+        assert!(model.encode_sample("", 1.0, None).unwrap().is_empty());
+
+        // The model can sample each time it tokenizes
+        model.sampling_alpha = Some(1.0);
+        let samples = (0..100)
+            .map(|_| model.tokenize("abc").unwrap().len())
+            .collect::<HashSet<_>>();
+        assert!(samples.len() > 1);
+        model.sampling_seed = Some(42);
+        let tokens = model.tokenize("abc").unwrap();
+        for _ in 0..10 {
+            assert_eq!(model.tokenize("abc").unwrap(), tokens);
+        }
+
+        // The same segmentations as any other build, with any version of Rust
+        assert_eq!(
+            model.encode_sample("abcabc", 1.0, Some(2)).unwrap(),
+            ["ab", "c", "a", "b", "c"]
+        );
+        assert_eq!(
+            model
+                .tokenize("abcabc")
+                .unwrap()
+                .iter()
+                .map(|t| t.value.as_str())
+                .collect::<Vec<_>>(),
+            ["a", "bc", "a", "bc"]
+        );
+    }
+
     #[test]
     fn test_encode2() {
         let sentencepieces = vec![
@@ -577,4 +927,152 @@ mod tests {
             assert_eq!(model.encode("abqrcd").unwrap(), vec!["ab", "q", "r", "cd"]);
         }
     }
+
+    #[test]
+    fn test_encode_strict() {
+        let sentencepieces = vec![
+            ("<unk>".to_string(), 0.0),
+            ("a".to_string(), 0.0),
+            ("b".to_string(), 0.0),
+            ("c".to_string(), 0.0),
+            ("é".to_string(), 0.0),
+            ("ab".to_string(), 2.0),
+            ("abc".to_string(), 5.0),
+        ];
+        let model = Unigram::from(sentencepieces, Some(0), None).unwrap();
+
+        assert_eq!(model.encode_strict("abcab"), Ok(vec![6, 5]));
+        assert_eq!(model.encode_strict("éa"), Ok(vec![4, 1]));
+        assert_eq!(model.encode_strict(""), Ok(vec![]));
+        assert_eq!(
+            model.encode_strict("abxa"),
+            Err(UnsegmentableError {
+                substring: "x".into(),
+                offset: 2
+            })
+        );
+        assert_eq!(
+            model.encode_strict("aèb"),
+            Err(UnsegmentableError {
+                substring: "è".into(),
+                offset: 1
+            })
+        );
+        // The normal encode still uses the unknown token
+        assert_eq!(model.encode("abxa").unwrap(), vec!["ab", "x", "a"]);
+    }
+
+    #[test]
+    fn test_round_scores() {
+        let sentencepieces = vec![
+            ("<unk>".to_string(), 0.0),
+            ("a".to_string(), -2.719841268238152),
+            ("b".to_string(), -3.148372911004581),
+            ("c".to_string(), -3.333333333333333),
+            ("ab".to_string(), -1.418532264940225),
+            ("bc".to_string(), -4.123105625617661),
+            ("abc".to_string(), -7.071067811865475),
+        ];
+        let model = Unigram::from(sentencepieces, Some(0), None).unwrap();
+        let mut rounded = model.clone();
+        assert!(Arc::ptr_eq(&model.vocab, &rounded.vocab));
+        rounded.round_scores(2);
+        // Only the scores got copied, the original ones are untouched
+        assert!(!Arc::ptr_eq(&model.vocab, &rounded.vocab));
+        assert!(Arc::ptr_eq(&model.trie, &rounded.trie));
+        assert_eq!(model.vocab[1].1, -2.719841268238152);
+
+        let vocab = rounded.get_vocab_with_scores();
+        assert_eq!(vocab[1].1, -2.72);
+        assert_eq!(vocab[4].1, -1.42);
+        assert_eq!(vocab[6].1, -7.07);
+        assert_eq!(rounded.min_score, -7.07);
+        for sentence in ["abc", "abcabx", "cbabab", "bca", ""] {
+            assert_eq!(
+                rounded.encode(sentence).unwrap(),
+                model.encode(sentence).unwrap()
+            );
+        }
+
+        // Each of the 6 rounded scores loses more than 10 digits
+        let size = |model: &Unigram| serde_json::to_string(model).unwrap().len();
+        assert!(size(&rounded) + 60 < size(&model));
+    }
+
+    #[test]
+    fn test_prune_to() {
+        let sentencepieces = vec![
+            ("<unk>".to_string(), 0.0),
+            ("ab".to_string(), -2.0),
+            ("abc".to_string(), -3.0),
+            ("bc".to_string(), -3.5),
+            ("ca".to_string(), -4.0),
+            ("cab".to_string(), -4.5),
+            ("a".to_string(), -5.0),
+            ("b".to_string(), -5.0),
+            ("c".to_string(), -5.0),
+        ];
+        let model = Unigram::from(sentencepieces, Some(0), None).unwrap();
+        let corpus = || {
+            ["ab", "ab", "ab", "abab", "abc", "cab", "c"]
+                .iter()
+                .map(|s| s.to_string())
+        };
+
+        let mut pruned = model.clone();
+        pruned.prune_to(6, corpus()).unwrap();
+        assert_eq!(pruned.len(), 6);
+        assert_eq!(pruned.get_unk_id(), Some(0));
+        assert_eq!(pruned.id_to_token(0).unwrap(), "<unk>");
+        for token in ["a", "b", "c", "ab"] {
+            assert!(pruned.token_to_id(token).is_some(), "{} was pruned", token);
+        }
+        for word in corpus() {
+            let tokens = pruned.encode(&word).unwrap();
+            assert_eq!(tokens.concat(), word);
+        }
+
+        // It can't drop the required pieces, and needs a corpus
+        assert!(model.clone().prune_to(3, corpus()).is_err());
+        assert!(model.clone().prune_to(6, std::iter::empty()).is_err());
+
+        let mut unchanged = model.clone();
+        unchanged.prune_to(9, corpus()).unwrap();
+        assert_eq!(unchanged, model);
+    }
+
+    #[test]
+    fn test_get_vocab_with_scores() {
+        let sentencepieces = vec![
+            ("<unk>".to_string(), 0.0),
+            ("a".to_string(), -2.0),
+            ("b".to_string(), -2.5),
+            ("c".to_string(), -3.0),
+            ("ab".to_string(), -1.5),
+            ("bc".to_string(), -1.0),
+            ("abc".to_string(), -4.25),
+        ];
+        let model = Unigram::from(sentencepieces.clone(), Some(0), None).unwrap();
+        let vocab = model.get_vocab_with_scores();
+        assert_eq!(vocab, sentencepieces);
+        for (id, (token, _)) in vocab.iter().enumerate() {
+            assert_eq!(model.token_to_id(token), Some(id as u32));
+        }
+
+        let reloaded = Unigram::from(vocab.clone(), model.get_unk_id(), None).unwrap();
+        assert_eq!(reloaded, model);
+        for sentence in ["abc", "abcabx", "cbabab", ""] {
+            assert_eq!(
+                reloaded.encode(sentence).unwrap(),
+                model.encode(sentence).unwrap()
+            );
+        }
+
+        // Editing a score changes the segmentation accordingly
+        let mut edited = vocab;
+        edited[6].1 = 0.0;
+        let edited = Unigram::from(edited, model.get_unk_id(), None).unwrap();
+        assert_eq!(model.encode("abc").unwrap(), vec!["a", "bc"]);
+        assert_eq!(edited.encode("abc").unwrap(), vec!["abc"]);
+    }
 }
@@ -15,7 +15,13 @@ impl Serialize for Unigram {
         model.serialize_field("type", "Unigram")?;
         model.serialize_field("unk_id", &self.unk_id)?;
         model.serialize_field("with_traits", &self.with_traits)?;
-        model.serialize_field("vocab", &self.vocab)?;
+        model.serialize_field("vocab", &*self.vocab)?;
+        if self.sampling_alpha.is_some() {
+            model.serialize_field("sampling_alpha", &self.sampling_alpha)?;
+        }
+        if self.sampling_seed.is_some() {
+            model.serialize_field("sampling_seed", &self.sampling_seed)?;
+        }
 
         model.end()
     }
@@ -45,6 +51,8 @@ impl<'de> Visitor<'de> for UnigramVisitor {
         let mut vocab: Option<Vec<(String, f64)>> = None;
         let mut unk_id: Option<usize> = None;
         let mut with_traits: Option<bool> = None;
+        let mut sampling_alpha: Option<f64> = None;
+        let mut sampling_seed: Option<u64> = None;
         while let Some(key) = map.next_key::<String>()? {
             match key.as_ref() {
                 "unk_id" => {
@@ -54,7 +62,9 @@ impl<'de> Visitor<'de> for UnigramVisitor {
                     with_traits = map.next_value()?;
                 }
                 "vocab" => vocab = Some(map.next_value()?),
-                "type" => match map.next_value()? {
+                "sampling_alpha" => sampling_alpha = map.next_value()?,
+                "sampling_seed" => sampling_seed = map.next_value()?,
+                "type" => match map.next_value::<String>()?.as_ref() {
                     "Unigram" => {}
                     u => {
                         return Err(serde::de::Error::invalid_value(
@@ -67,8 +77,13 @@ impl<'de> Visitor<'de> for UnigramVisitor {
             }
         }
         match (vocab, unk_id, with_traits) {
-            (Some(vocab), unk_id, with_traits) => Ok(Unigram::from(vocab, unk_id, with_traits)
-                .map_err(|err| Error::custom(format!("Unable to load vocab {:?}", err)))?),
+            (Some(vocab), unk_id, with_traits) => {
+                let mut model = Unigram::from(vocab, unk_id, with_traits)
+                    .map_err(|err| Error::custom(format!("Unable to load vocab {:?}", err)))?;
+                model.sampling_alpha = sampling_alpha;
+                model.sampling_seed = sampling_seed;
+                Ok(model)
+            }
             (None, _, _) => Err(Error::custom("Missing vocab")),
         }
     }
@@ -100,6 +115,21 @@ mod test {
         assert_eq!(model, reconstructed);
     }
 
+    #[test]
+    fn test_serialization_sampling() {
+        let vocab = vec![("<unk>".to_string(), 0.0), ("a".to_string(), -0.5)];
+        let mut model = Unigram::from(vocab, Some(0), None).unwrap();
+        let data = serde_json::to_string(&model).unwrap();
+        assert!(!data.contains("sampling"));
+
+        model.sampling_alpha = Some(0.1);
+        model.sampling_seed = Some(42);
+        let data = serde_json::to_string(&model).unwrap();
+        let reconstructed: Unigram = serde_json::from_str(&data).unwrap();
+        assert_eq!(reconstructed.sampling_alpha, Some(0.1));
+        assert_eq!(reconstructed.sampling_seed, Some(42));
+    }
+
     #[test]
     fn test_serialization_no_unk_id() {
         let vocab = vec![("a".to_string(), -0.5)];
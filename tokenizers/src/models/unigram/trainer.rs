@@ -1,12 +1,14 @@
+use crate::models::pin_token_ids;
 use crate::models::unigram::{lattice::Lattice, model::Unigram};
-use crate::tokenizer::{AddedToken, Result, Trainer};
+use crate::tokenizer::{AddedToken, Result, Trainer, TrainerProgress};
 use crate::utils::parallelism::*;
-use crate::utils::progress::{ProgressBar, ProgressStyle};
+use crate::utils::progress::{Progress, ProgressCallback};
 use log::debug;
 use serde::{Deserialize, Serialize};
 use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
+use std::sync::{Arc, RwLock};
 
 // A token and a score
 type SentencePiece = (String, f64);
@@ -51,8 +53,21 @@ pub struct UnigramTrainer {
     pub shrinking_factor: f64,
     #[builder(default = "vec![]")]
     pub special_tokens: Vec<AddedToken>,
+    /// The ids that some of the special tokens must have, the other tokens filling the
+    /// remaining ids
+    #[builder(default, setter(custom))]
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub special_token_ids: HashMap<String, u32>,
+    /// The characters that must always be tokenizable, even when absent from the
+    /// training data. They are kept as single-character pieces, with a floor score
+    /// when they weren't seen.
     #[builder(default = "HashSet::new()")]
     pub initial_alphabet: HashSet<char>,
+    /// Some pieces, like common affixes, that must be part of the final vocabulary. They
+    /// are seeded and never pruned, and if the EM still drops them, they get a floor score.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_pieces: Vec<String>,
 
     #[builder(default = "None")]
     pub unk_token: Option<String>,
@@ -66,6 +81,21 @@ pub struct UnigramTrainer {
     seed_size: usize,
     #[builder(default = "HashMap::new()")]
     words: HashMap<String, u32>,
+    #[builder(default, setter(custom))]
+    #[serde(skip)]
+    progress: ProgressCallback,
+
+    /// Some held-out texts, on which the model gets evaluated each time it is pruned. The
+    /// results are available with `validation_curve` once the training is done.
+    #[builder(default)]
+    #[serde(default)]
+    pub validation_texts: Vec<String>,
+    #[builder(setter(skip))]
+    #[serde(skip)]
+    validation_words: HashMap<String, u32>,
+    #[builder(setter(skip))]
+    #[serde(skip)]
+    validation_curve: Arc<RwLock<Vec<(usize, f64)>>>,
 }
 
 impl Default for UnigramTrainer {
@@ -74,22 +104,55 @@ impl Default for UnigramTrainer {
     }
 }
 
+impl UnigramTrainerBuilder {
+    /// Set the `TrainerProgress` that will receive the progress of the training, instead
+    /// of the progress bar
+    pub fn progress(&mut self, progress: Option<Box<dyn TrainerProgress>>) -> &mut Self {
+        self.progress = Some(progress.into());
+        self
+    }
+
+    /// Add some special tokens, each with the id it must have in the trained vocabulary. All
+    /// the other tokens fill the remaining ids.
+    pub fn special_tokens_with_ids(&mut self, tokens: Vec<(AddedToken, u32)>) -> &mut Self {
+        let ids = self.special_token_ids.get_or_insert_with(HashMap::new);
+        let special_tokens = self.special_tokens.get_or_insert_with(Vec::new);
+        for (token, id) in tokens {
+            ids.insert(token.content.clone(), id);
+            special_tokens.push(token);
+        }
+        self
+    }
+}
+
 impl UnigramTrainer {
     pub fn builder() -> UnigramTrainerBuilder {
         UnigramTrainerBuilder::default()
     }
 
-    /// Setup a progress bar if asked to show progress
-    fn setup_progress(&self) -> Option<ProgressBar> {
-        if self.show_progress {
-            let p = ProgressBar::new(0);
-            p.set_style(
-                ProgressStyle::default_bar()
-                    .template("[{elapsed_precise}] {msg:<40!} {wide_bar} {pos:<9!}/{len:>9!}"),
-            );
-            Some(p)
+    /// The mean negative log-likelihood of the `validation_texts`, for each vocabulary size
+    /// visited during the last training, from the largest to the final one. This is empty if
+    /// there are no `validation_texts`.
+    pub fn validation_curve(&self) -> Vec<(usize, f64)> {
+        self.validation_curve.read().unwrap().clone()
+    }
+
+    fn validation_sentences(&self) -> Vec<Sentence> {
+        if self.validation_words.is_empty() {
+            // The texts were not processed like the training ones, so we use them as is
+            let mut sentences: HashMap<&str, u32> = HashMap::new();
+            for text in &self.validation_texts {
+                *sentences.entry(text).or_default() += 1;
+            }
+            sentences
+                .into_iter()
+                .map(|(s, count)| (s.to_owned(), count))
+                .collect()
         } else {
-            None
+            self.validation_words
+                .iter()
+                .map(|(s, count)| (s.to_owned(), *count))
+                .collect()
         }
     }
 
@@ -174,7 +237,16 @@ impl UnigramTrainer {
             special_tokens.insert(0, (self.unk_token.clone().unwrap(), 0.0));
         }
 
-        Unigram::from(special_tokens.into_iter().chain(pieces).collect(), unk_id, self.with_traits)
+        let pieces = special_tokens.into_iter().chain(pieces).collect::<Vec<_>>();
+        let tokens = pieces.iter().map(|(token, _)| token).collect::<Vec<_>>();
+        let ids = pin_token_ids(&tokens, &self.special_token_ids)?;
+        let mut pinned = vec![(String::new(), 0.0); pieces.len()];
+        for (piece, id) in pieces.into_iter().zip(ids.iter()) {
+            pinned[*id as usize] = piece;
+        }
+        let unk_id = unk_id.map(|id| ids[id] as usize);
+
+        Unigram::from(pinned, unk_id, self.with_traits)
     }
 
     fn required_chars(&self, word_counts: &[Sentence]) -> HashSet<String> {
@@ -183,12 +255,13 @@ impl UnigramTrainer {
             .flat_map(|(s, _count)| s.chars())
             .chain(self.initial_alphabet.iter().copied())
             .map(|c| c.to_string())
+            .chain(self.required_pieces.iter().cloned())
             .collect()
     }
     fn make_seed_sentence_pieces(
         &self,
         sentences: &[Sentence],
-        _progress: &Option<ProgressBar>,
+        _progress: &Progress,
     ) -> Vec<SentencePiece> {
         // Put all sentences in a string, separated by \0
         let total: usize = sentences
@@ -259,6 +332,16 @@ impl UnigramTrainer {
                 break;
             }
         }
+        // The required pieces get seeded even when too rare, as if they were seen once
+        let seeded: HashSet<String> = seed_sentencepieces
+            .iter()
+            .map(|(piece, _)| piece.clone())
+            .collect();
+        for piece in &self.required_pieces {
+            if !seeded.contains(piece) {
+                seed_sentencepieces.push((piece.clone(), piece.chars().count() as f64));
+            }
+        }
         to_log_prob(&mut seed_sentencepieces);
         seed_sentencepieces
     }
@@ -273,6 +356,7 @@ impl UnigramTrainer {
 
         let bos_id = pieces.len() + 1;
         let eos_id = pieces.len() + 2;
+        let required: HashSet<&str> = self.required_pieces.iter().map(String::as_str).collect();
 
         // First, segments the current sentencepieces to know
         // how each sentencepiece is resegmented if this sentencepiece is removed
@@ -335,6 +419,10 @@ impl UnigramTrainer {
             if id == 0 {
                 continue;
             }
+            if required.contains(token.as_str()) {
+                new_pieces.push((token.to_string(), *score));
+                continue;
+            }
             if freq[id] == 0.0 && !always_keep[id] {
                 // not found in Viterbi path. Can remove this entry safely.
                 continue;
@@ -393,24 +481,6 @@ impl UnigramTrainer {
         new_pieces.to_vec()
     }
 
-    /// Update the progress bar with the new provided length and message
-    fn update_progress(&self, p: &Option<ProgressBar>, len: usize, message: &str) {
-        if let Some(p) = p {
-            p.set_message(message);
-            p.set_length(len as u64);
-            p.set_draw_delta(len as u64 / 100);
-            p.reset();
-        }
-    }
-    /// Set the progress bar in the finish state
-    fn finalize_progress(&self, p: &Option<ProgressBar>, final_len: usize) {
-        if let Some(p) = p {
-            p.set_length(final_len as u64);
-            p.finish();
-            println!();
-        }
-    }
-
     fn run_e_step(&self, model: &Unigram, sentences: &[Sentence]) -> (f64, u32, Vec<f64>) {
         let mut expected: Vec<f64> = vec![0.0; model.len()];
         let mut objs: f64 = 0.0;
@@ -474,18 +544,18 @@ impl UnigramTrainer {
         sentences: Vec<Sentence>,
         model: &mut Unigram,
     ) -> Result<Vec<AddedToken>> {
-        let progress = self.setup_progress();
+        let progress = Progress::new(self.show_progress, &self.progress);
         //
         // 1. Compute frequent substrings
         // TODO Should be able to upgrade to u64 when needed
-        self.update_progress(&progress, sentences.len(), "Suffix array seeds");
+        progress.start("Suffix array seeds", sentences.len());
         let mut pieces: Vec<SentencePiece> =
             Vec::with_capacity(self.vocab_size.try_into().unwrap());
 
         // We use a UNK token when training, whatever the `self.unk_token`
         pieces.push(("<UNK>".into(), f64::NAN));
         pieces.extend(self.make_seed_sentence_pieces(&sentences, &progress));
-        self.finalize_progress(&progress, sentences.len());
+        progress.finish(sentences.len());
 
         // Useful to check compatibility with spm.
         debug!(
@@ -494,9 +564,35 @@ impl UnigramTrainer {
             sentences.len()
         );
 
+        // 2. Run E-M Loops to fine grain the pieces.
+        let required_chars = self.required_chars(&sentences);
+        let validation = self.validation_sentences();
+        let (new_model, mut validation_curve) =
+            self.run_em(pieces, &sentences, &validation, &progress)?;
+
+        // Finally, adjusts the size of sentencepices to be |vocab_size|.
+        *model = self.finalize(new_model, required_chars)?;
+        if !validation.is_empty() {
+            let (nll, _, _) = self.run_e_step(model, &validation);
+            validation_curve.push((model.len(), nll));
+        }
+        *self.validation_curve.write().unwrap() = validation_curve;
+
+        Ok(self.special_tokens.clone())
+    }
+
+    /// Run the E-M loops on the given `pieces`, pruning them until there are about
+    /// `vocab_size` of them left. This returns the resulting model, along with the
+    /// validation curve when there are some `validation` sentences.
+    fn run_em(
+        &self,
+        mut pieces: Vec<SentencePiece>,
+        sentences: &[Sentence],
+        validation: &[Sentence],
+        progress: &Progress,
+    ) -> Result<(Unigram, Vec<(usize, f64)>)> {
         let desired_vocab_size: usize = (self.vocab_size as usize * 11) / 10; // * 1.1
 
-        // 2. Run E-M Loops to fine grain the pieces.
         // We will shrink the vocab by shrinking_factor every loop on average
         // Some other pieces are dropped if logprob is too small
         // V = N * (f)**k
@@ -505,14 +601,14 @@ impl UnigramTrainer {
             / self.shrinking_factor.ln()) as usize
             + 1;
         let expected_updates = expected_loops * self.n_sub_iterations as usize;
-        self.update_progress(&progress, expected_updates, "EM training");
-        let required_chars = self.required_chars(&sentences);
+        progress.start("EM training", expected_updates);
+        let mut validation_curve = vec![];
         let mut new_model = Unigram::from(pieces.clone(), Some(0), None)?;
         loop {
             // Sub-EM iteration.
             for _iter in 0..self.n_sub_iterations {
                 // Executes E step
-                let (_objective, _num_tokens, expected) = self.run_e_step(&new_model, &sentences);
+                let (_objective, _num_tokens, expected) = self.run_e_step(&new_model, sentences);
 
                 // Executes M step.
                 pieces = self.run_m_step(&pieces, &expected);
@@ -525,13 +621,16 @@ impl UnigramTrainer {
                     new_model.len(),
                     _objective,
                     _num_tokens,
-                    _num_tokens as f64 / model.len() as f64
+                    _num_tokens as f64 / new_model.len() as f64
                 );
-                if let Some(p) = &progress {
-                    p.inc(1);
-                }
+                progress.inc(1);
             } // end of Sub EM iteration
 
+            if !validation.is_empty() {
+                let (nll, _, _) = self.run_e_step(&new_model, validation);
+                validation_curve.push((new_model.len(), nll));
+            }
+
             // Stops the iteration when the size of sentences reaches to the
             // desired symbol size.
             if pieces.len() <= desired_vocab_size {
@@ -539,15 +638,48 @@ impl UnigramTrainer {
             }
 
             // Prunes pieces.
-            pieces = self.prune_sentence_pieces(&new_model, &pieces, &sentences);
+            pieces = self.prune_sentence_pieces(&new_model, &pieces, sentences);
             new_model = Unigram::from(pieces.clone(), Some(0), None)?;
         }
-        self.finalize_progress(&progress, expected_updates);
+        progress.finish(expected_updates);
 
-        // Finally, adjusts the size of sentencepices to be |vocab_size|.
-        *model = self.finalize(new_model, required_chars)?;
+        Ok((new_model, validation_curve))
+    }
 
-        Ok(self.special_tokens.clone())
+    /// Prune the pieces of an already trained `model` down to the `vocab_size`, with the
+    /// same E-M loops as the training, on the given `sentences`. Its unknown token and its
+    /// single-character pieces are always kept.
+    pub(super) fn prune_model(&self, model: &Unigram, sentences: Vec<Sentence>) -> Result<Unigram> {
+        if sentences.is_empty() {
+            return Err("Cannot prune a Unigram model without any corpus".into());
+        }
+        let is_unk = |id: usize| model.unk_id == Some(id);
+        let required_chars: HashSet<String> = model
+            .iter()
+            .enumerate()
+            .filter(|(id, (token, _))| !is_unk(*id) && token.chars().count() == 1)
+            .map(|(_, (token, _))| token.clone())
+            .collect();
+        let required = required_chars.len() + usize::from(model.unk_id.is_some());
+        if (self.vocab_size as usize) < required {
+            return Err(format!(
+                "Cannot prune the Unigram model to {} tokens, {} of them must be kept",
+                self.vocab_size, required
+            )
+            .into());
+        }
+
+        let mut pieces: Vec<SentencePiece> = vec![("<UNK>".into(), f64::NAN)];
+        pieces.extend(
+            model
+                .iter()
+                .enumerate()
+                .filter(|(id, _)| !is_unk(*id))
+                .map(|(_, piece)| piece.clone()),
+        );
+        let progress = Progress::new(self.show_progress, &self.progress);
+        let (new_model, _) = self.run_em(pieces, &sentences, &[], &progress)?;
+        self.finalize(new_model, required_chars)
     }
 }
 
@@ -593,6 +725,14 @@ impl Trainer for UnigramTrainer {
             );
 
         self.words = words?;
+
+        let mut validation_words = HashMap::new();
+        for text in &self.validation_texts {
+            for word in process(text)? {
+                *validation_words.entry(word).or_default() += 1;
+            }
+        }
+        self.validation_words = validation_words;
         Ok(())
     }
     
@@ -605,6 +745,7 @@ impl Trainer for UnigramTrainer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tokenizer::Model;
     use assert_approx_eq::assert_approx_eq;
     use std::iter::FromIterator;
 
@@ -623,7 +764,7 @@ mod tests {
         let required_chars = trainer.required_chars(&sentences);
         assert_eq!(required_chars.len(), 13);
 
-        let progress = None;
+        let progress = Progress::new(false, &ProgressCallback::default());
         let table = trainer.make_seed_sentence_pieces(&sentences, &progress);
 
         let target_strings = vec![
@@ -676,6 +817,110 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_initial_alphabet_survives_pruning() {
+        let trainer = UnigramTrainerBuilder::default()
+            .show_progress(false)
+            .vocab_size(20)
+            .initial_alphabet(HashSet::from_iter(vec!['ä']))
+            .unk_token(Some("<unk>".into()))
+            .build()
+            .unwrap();
+
+        let mut unigram = Unigram::default();
+        trainer
+            .do_train(
+                vec![
+                    ("Katze".into(), 10),
+                    ("Katzen".into(), 6),
+                    ("kalt".into(), 4),
+                    ("Kanne".into(), 2),
+                ],
+                &mut unigram,
+            )
+            .unwrap();
+
+        // `ä` was never seen, but it can be tokenized on its own
+        let a_umlaut = unigram.token_to_id("ä").unwrap();
+        let tokens = unigram.tokenize("Kätze").unwrap();
+        assert!(tokens.iter().any(|token| token.id == a_umlaut));
+        assert!(tokens
+            .iter()
+            .all(|token| Some(token.id) != unigram.token_to_id("<unk>")));
+    }
+
+    #[test]
+    fn test_required_pieces() {
+        let sentences: Vec<Sentence> = vec![
+            ("Katze".into(), 10),
+            ("Katzen".into(), 6),
+            ("kalt".into(), 4),
+            ("Kanne".into(), 2),
+            ("Kling".into(), 1),
+        ];
+        let train = |required_pieces: Vec<String>| {
+            let trainer = UnigramTrainerBuilder::default()
+                .show_progress(false)
+                .vocab_size(20)
+                .required_pieces(required_pieces)
+                .build()
+                .unwrap();
+            let mut unigram = Unigram::default();
+            trainer.do_train(sentences.clone(), &mut unigram).unwrap();
+            unigram
+        };
+
+        // `ing` is too rare to survive on its own
+        let unigram = train(vec![]);
+        assert_eq!(unigram.token_to_id("ing"), None);
+
+        let unigram = train(vec!["ing".into()]);
+        let ing = unigram.token_to_id("ing").unwrap();
+        assert!(unigram.get_vocab_size() <= 20);
+        let tokens = unigram.tokenize("Kalting").unwrap();
+        assert!(tokens.iter().any(|token| token.id == ing));
+    }
+
+    #[test]
+    fn test_validation_curve() {
+        // Some words made of a few syllables, with various frequencies
+        let syllables = ["ka", "to", "ri", "mu", "sen", "lo", "pa", "di", "ne", "su"];
+        let mut state: usize = 17;
+        let mut next = |n: usize| {
+            state = (state * 1_103_515_245 + 12_345) % 2_147_483_648;
+            (state >> 8) % n
+        };
+        let words = (0..300)
+            .map(|_| {
+                let word = (0..next(3) + 2)
+                    .map(|_| syllables[next(syllables.len())])
+                    .collect::<String>();
+                (word, next(5) as u32 + 1)
+            })
+            .collect::<Vec<_>>();
+        let trainer = UnigramTrainerBuilder::default()
+            .show_progress(false)
+            .vocab_size(30)
+            .validation_texts(vec!["karimu".into(), "senlopa".into(), "toditoka".into()])
+            .build()
+            .unwrap();
+        assert!(trainer.validation_curve().is_empty());
+
+        let mut unigram = Unigram::default();
+        trainer.do_train(words, &mut unigram).unwrap();
+        let curve = trainer.validation_curve();
+        assert!(curve.len() > 2);
+        // The pruning visits smaller and smaller vocabularies, and then we get the final one
+        let (pruning, last) = curve.split_at(curve.len() - 1);
+        assert!(pruning.windows(2).all(|w| w[0].0 > w[1].0));
+        assert_eq!(last[0].0, unigram.get_vocab_size());
+        // The likelihood only changes slowly from one step to the other
+        assert!(curve.iter().all(|(_, nll)| nll.is_finite() && *nll > 0.0));
+        assert!(curve
+            .windows(2)
+            .all(|w| (w[0].1 - w[1].1).abs() < 0.1 * w[0].1));
+    }
+
     #[test]
     fn test_unk_token() {
         // 1. Should add `unk_token` as first special token
@@ -757,6 +1002,41 @@ mod tests {
         assert_eq!(pieces.next(), Some(&("[CLS]".into(), 0.0)));
     }
 
+    #[test]
+    fn test_special_tokens_with_ids() {
+        let trainer = UnigramTrainerBuilder::default()
+            .show_progress(false)
+            .unk_token(Some("<unk>".into()))
+            .special_tokens(vec![AddedToken::from("[SEP]", true)])
+            .special_tokens_with_ids(vec![
+                (AddedToken::from("<unk>", true), 1),
+                (AddedToken::from("[CLS]", true), 0),
+            ])
+            .build()
+            .unwrap();
+
+        let mut unigram = Unigram::default();
+        trainer
+            .do_train(vec![("The".into(), 12), ("are".into(), 11)], &mut unigram)
+            .unwrap();
+
+        let tokens = unigram.iter().take(3).map(|(t, _)| t).collect::<Vec<_>>();
+        assert_eq!(tokens, vec!["[CLS]", "<unk>", "[SEP]"]);
+        assert_eq!(unigram.tokenize("Z").unwrap()[0].id, 1);
+
+        let trainer = UnigramTrainerBuilder::default()
+            .show_progress(false)
+            .special_tokens_with_ids(vec![
+                (AddedToken::from("[SEP]", true), 0),
+                (AddedToken::from("[CLS]", true), 0),
+            ])
+            .build()
+            .unwrap();
+        assert!(trainer
+            .do_train(vec![("The".into(), 12)], &mut unigram)
+            .is_err());
+    }
+
     #[test]
     fn test_to_log_prob() {
         let mut a = vec![("".to_string(), 1.0), ("".to_string(), 2.0)];
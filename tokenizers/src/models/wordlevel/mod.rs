@@ -1,10 +1,13 @@
-use super::OrderedVocabIter;
+use super::{save_parts, OrderedVocabIter, UnkError, UnkPolicy};
 use crate::tokenizer::{Model, Result, Token};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufReader, Read, Write};
+use std::io::{BufReader, Read};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 mod serialization;
 mod trainer;
@@ -22,10 +25,24 @@ pub enum Error {
     BadVocabulary,
 }
 
+/// What a `WordLevel` does with a word that is not in its vocabulary, before resorting to
+/// its `unk_policy`
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone, Default)]
+pub enum WordLevelFallback {
+    /// The whole word is unknown
+    #[default]
+    Unk,
+    /// The word gets covered by the longest pieces of at most `n` chars found in the
+    /// vocabulary, from left to right. Only the chars that none of them cover are unknown.
+    CharNgram { n: usize },
+}
+
 struct Config {
     files: Option<String>,
     vocab: HashMap<String, u32>,
     unk_token: String,
+    unk_policy: Option<UnkPolicy>,
+    fallback: WordLevelFallback,
 }
 
 /// A `WordLevelBuilder` can be used to create a `WordLevel`
@@ -41,6 +58,8 @@ impl Default for WordLevelBuilder {
                 files: None,
                 vocab: HashMap::new(),
                 unk_token: String::from("<unk>"),
+                unk_policy: None,
+                fallback: WordLevelFallback::Unk,
             },
         }
     }
@@ -73,6 +92,20 @@ impl WordLevelBuilder {
         self
     }
 
+    /// Set how unknown words are handled. `UnkPolicy::Token` is equivalent to `unk_token`.
+    #[must_use]
+    pub fn unk_policy(mut self, unk_policy: UnkPolicy) -> Self {
+        self.config.unk_policy = Some(unk_policy);
+        self
+    }
+
+    /// Set what happens to the words that are not in the vocabulary.
+    #[must_use]
+    pub fn fallback(mut self, fallback: WordLevelFallback) -> Self {
+        self.config.fallback = fallback;
+        self
+    }
+
     /// Contructs a `WordLevel` model that uses the `WordLevelBuilder`'s configuration.
     pub fn build(mut self) -> Result<WordLevel> {
         if let Some(vocab) = self.config.files {
@@ -86,25 +119,44 @@ impl WordLevelBuilder {
             .map(|(key, val)| (*val, key.to_owned()))
             .collect();
 
+        let unk_policy = match self.config.unk_policy {
+            Some(unk_policy) => unk_policy,
+            None => UnkPolicy::Token(self.config.unk_token.clone()),
+        };
+        let unk_token = match &unk_policy {
+            UnkPolicy::Token(unk) => unk.clone(),
+            _ => self.config.unk_token,
+        };
+
         Ok(WordLevel {
-            vocab: self.config.vocab,
-            vocab_r,
-            unk_token: self.config.unk_token,
+            vocab: Arc::new(self.config.vocab),
+            vocab_r: Arc::new(vocab_r),
+            unk_token,
+            unk_policy,
+            fallback: self.config.fallback,
         })
     }
 }
 
 #[derive(PartialEq, Clone, Eq)]
 pub struct WordLevel {
-    vocab: HashMap<String, u32>,
-    vocab_r: HashMap<u32, String>,
+    /// The vocabulary, shared between clones
+    vocab: Arc<HashMap<String, u32>>,
+    vocab_r: Arc<HashMap<u32, String>>,
     pub unk_token: String,
+    /// How unknown words are handled. With `UnkPolicy::Token`, this holds the same token
+    /// as `unk_token`
+    pub unk_policy: UnkPolicy,
+    /// What happens to the words that are not in the vocabulary
+    pub fallback: WordLevelFallback,
 }
 
 impl std::fmt::Debug for WordLevel {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
         fmt.debug_struct("WordLevel")
             .field("unk_token", &self.unk_token)
+            .field("unk_policy", &self.unk_policy)
+            .field("fallback", &self.fallback)
             .field("vocab", &self.vocab.len())
             .finish()
     }
@@ -143,14 +195,81 @@ impl WordLevel {
         let vocab = WordLevel::read_file(vocab_path)?;
         Self::builder().vocab(vocab).unk_token(unk_token).build()
     }
+
+    /// Handle the unknown `substring` found at `offset` in the word, according to the
+    /// `unk_policy`
+    fn unk(&self, substring: &str, offset: usize) -> Result<Option<Token>> {
+        match &self.unk_policy {
+            UnkPolicy::Token(unk_token) => {
+                if let Some(&unk_id) = self.vocab.get(unk_token) {
+                    Ok(Some(Token {
+                        id: unk_id,
+                        value: unk_token.to_owned(),
+                        offsets: (offset, offset + substring.len()),
+                    }))
+                } else {
+                    Err(Box::new(Error::MissingUnkToken))
+                }
+            }
+            UnkPolicy::Drop => Ok(None),
+            UnkPolicy::Error => Err(Box::new(UnkError {
+                substring: substring.to_owned(),
+                offset,
+            })),
+        }
+    }
+
+    /// Cover the given word with the longest pieces of at most `n` chars from the vocabulary,
+    /// from left to right. The consecutive chars that can't be covered are unknown together.
+    fn tokenize_ngrams(&self, word: &str, n: usize) -> Result<Vec<Token>> {
+        let mut boundaries = word.char_indices().map(|(i, _)| i).collect::<Vec<_>>();
+        boundaries.push(word.len());
+
+        let mut tokens = vec![];
+        let mut unk_start = None;
+        let mut start = 0;
+        while start + 1 < boundaries.len() {
+            let found = (1..=n.min(boundaries.len() - 1 - start))
+                .rev()
+                .find_map(|len| {
+                    let piece = &word[boundaries[start]..boundaries[start + len]];
+                    self.vocab.get(piece).map(|&id| (id, piece, len))
+                });
+            match found {
+                Some((id, piece, len)) => {
+                    if let Some(unk_start) = unk_start.take() {
+                        let offset = boundaries[unk_start];
+                        tokens.extend(self.unk(&word[offset..boundaries[start]], offset)?);
+                    }
+                    tokens.push(Token {
+                        id,
+                        value: piece.to_owned(),
+                        offsets: (boundaries[start], boundaries[start + len]),
+                    });
+                    start += len;
+                }
+                None => {
+                    unk_start.get_or_insert(start);
+                    start += 1;
+                }
+            }
+        }
+        if let Some(unk_start) = unk_start {
+            let offset = boundaries[unk_start];
+            tokens.extend(self.unk(&word[offset..], offset)?);
+        }
+        Ok(tokens)
+    }
 }
 
 impl Default for WordLevel {
     fn default() -> Self {
         Self {
-            vocab: HashMap::new(),
-            vocab_r: HashMap::new(),
+            vocab: Arc::default(),
+            vocab_r: Arc::default(),
             unk_token: String::from("<unk>"),
+            unk_policy: UnkPolicy::Token(String::from("<unk>")),
+            fallback: WordLevelFallback::Unk,
         }
     }
 }
@@ -160,19 +279,16 @@ impl Model for WordLevel {
 
     fn tokenize(&self, token: &str) -> Result<Vec<Token>> {
         if let Some(&id) = self.vocab.get(token) {
-            Ok(vec![Token {
+            return Ok(vec![Token {
                 id,
                 value: token.to_owned(),
                 offsets: (0, token.len()),
-            }])
-        } else if let Some(&unk_id) = self.vocab.get(&self.unk_token) {
-            Ok(vec![Token {
-                id: unk_id,
-                value: self.unk_token.to_owned(),
-                offsets: (0, token.len()),
-            }])
-        } else {
-            Err(Box::new(Error::MissingUnkToken))
+            }]);
+        }
+
+        match self.fallback {
+            WordLevelFallback::CharNgram { n } if n > 0 => self.tokenize_ngrams(token, n),
+            _ => Ok(self.unk(token, 0)?.into_iter().collect()),
         }
     }
 
@@ -184,8 +300,14 @@ impl Model for WordLevel {
         self.vocab_r.get(&id).cloned()
     }
 
+    fn id_to_token_cow(&self, id: u32) -> Option<Cow<'_, str>> {
+        self.vocab_r
+            .get(&id)
+            .map(|token| Cow::Borrowed(token.as_str()))
+    }
+
     fn get_vocab(&self) -> HashMap<String, u32> {
-        self.vocab.clone()
+        (*self.vocab).clone()
     }
 
     fn get_vocab_size(&self) -> usize {
@@ -193,26 +315,25 @@ impl Model for WordLevel {
     }
 
     fn save(&self, folder: &Path, name: Option<&str>) -> Result<Vec<PathBuf>> {
-        let vocab_file_name = match name {
-            Some(name) => format!("{}-vocab.json", name),
-            None => "vocab.json".to_string(),
-        };
+        save_parts(folder, name, self.serialize_parts()?)
+    }
 
-        // Write vocab.json
-        let vocab_path: PathBuf = [folder, Path::new(vocab_file_name.as_str())]
-            .iter()
-            .collect();
-        let mut vocab_file = File::create(&vocab_path)?;
+    fn serialize_parts(&self) -> Result<Vec<(String, Vec<u8>)>> {
         let order_vocab_iter = OrderedVocabIter::new(&self.vocab_r);
-        let serialized = serde_json::to_string(&order_vocab_iter)?;
-        vocab_file.write_all(serialized.as_bytes())?;
-
-        Ok(vec![vocab_path])
+        let vocab = serde_json::to_vec(&order_vocab_iter)?;
+        Ok(vec![("vocab.json".to_string(), vocab)])
     }
 
     fn get_trainer(&self) -> Self::Trainer {
         WordLevelTrainer::default()
     }
+
+    fn unk_token(&self) -> Option<String> {
+        match &self.unk_policy {
+            UnkPolicy::Token(unk_token) => Some(unk_token.clone()),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -237,6 +358,40 @@ mod tests {
         assert_eq!(tokens, vec![Token::new(1u32, "a".into(), (0, 1)),]);
     }
 
+    #[test]
+    fn test_clone_shares_vocab() {
+        let vocab: Vocab = [("<unk>".into(), 0), ("a".into(), 1)]
+            .iter()
+            .cloned()
+            .collect();
+        let wordlevel = WordLevelBuilder::default().vocab(vocab).build().unwrap();
+        let clone = wordlevel.clone();
+        assert!(Arc::ptr_eq(&wordlevel.vocab, &clone.vocab));
+        assert!(Arc::ptr_eq(&wordlevel.vocab_r, &clone.vocab_r));
+        assert_eq!(
+            clone.tokenize("a").unwrap(),
+            wordlevel.tokenize("a").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_unk_offsets() {
+        let vocab: Vocab = [("a".into(), 0), ("b".into(), 1), ("<unk>".into(), 2)]
+            .iter()
+            .cloned()
+            .collect();
+        let wordlevel = WordLevelBuilder::default()
+            .vocab(vocab)
+            .unk_token("<unk>".to_string())
+            .build()
+            .unwrap();
+        // The whole word, in bytes, with the id of the unk token
+        assert_eq!(
+            wordlevel.tokenize("héllo").unwrap(),
+            vec![Token::new(2u32, "<unk>".into(), (0, 6))]
+        );
+    }
+
     #[test]
     fn test_tokenize_missing_unk_token() {
         let vocab: Vocab = [("a".into(), 0), ("b".into(), 1)].iter().cloned().collect();
@@ -247,4 +402,90 @@ mod tests {
         let error = wordlevel.tokenize("c").err().unwrap();
         assert!(error.is::<Error>());
     }
+
+    #[test]
+    fn test_tokenize_unk_policy() {
+        let vocab: Vocab = [("<unk>".into(), 0), ("a".into(), 1)]
+            .iter()
+            .cloned()
+            .collect();
+        let wordlevel = WordLevelBuilder::default()
+            .vocab(vocab.clone())
+            .unk_policy(UnkPolicy::Drop)
+            .build()
+            .unwrap();
+        assert_eq!(wordlevel.tokenize("c").unwrap(), vec![]);
+        assert_eq!(
+            wordlevel.tokenize("a").unwrap(),
+            vec![Token::new(1u32, "a".into(), (0, 1))]
+        );
+
+        let wordlevel = WordLevelBuilder::default()
+            .vocab(vocab)
+            .unk_policy(UnkPolicy::Error)
+            .build()
+            .unwrap();
+        let error = wordlevel.tokenize("cd").unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<UnkError>(),
+            Some(&UnkError {
+                substring: "cd".into(),
+                offset: 0
+            })
+        );
+        assert_eq!(error.to_string(), "Unknown substring `cd` at offset 0");
+    }
+
+    #[test]
+    fn test_tokenize_char_ngram_fallback() {
+        let vocab: Vocab = [
+            ("<unk>".into(), 0),
+            ("walk".into(), 1),
+            ("wal".into(), 2),
+            ("ki".into(), 3),
+            ("ng".into(), 4),
+            ("s".into(), 5),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        let wordlevel = WordLevelBuilder::default()
+            .vocab(vocab.clone())
+            .fallback(WordLevelFallback::CharNgram { n: 3 })
+            .build()
+            .unwrap();
+        // The known words are still whole, and `walk` is too long to be a piece
+        assert_eq!(
+            wordlevel.tokenize("walk").unwrap(),
+            vec![Token::new(1u32, "walk".into(), (0, 4))]
+        );
+        assert_eq!(
+            wordlevel.tokenize("walking").unwrap(),
+            vec![
+                Token::new(2u32, "wal".into(), (0, 3)),
+                Token::new(3u32, "ki".into(), (3, 5)),
+                Token::new(4u32, "ng".into(), (5, 7)),
+            ]
+        );
+        // The consecutive unknown chars make one unknown token, with offsets in bytes
+        assert_eq!(
+            wordlevel.tokenize("walkés").unwrap(),
+            vec![
+                Token::new(2u32, "wal".into(), (0, 3)),
+                Token::new(0u32, "<unk>".into(), (3, 6)),
+                Token::new(5u32, "s".into(), (6, 7)),
+            ]
+        );
+
+        let wordlevel = WordLevelBuilder::default()
+            .vocab(vocab)
+            .unk_policy(UnkPolicy::Drop)
+            .fallback(WordLevelFallback::CharNgram { n: 3 })
+            .build()
+            .unwrap();
+        assert_eq!(
+            wordlevel.tokenize("xs").unwrap(),
+            vec![Token::new(5u32, "s".into(), (1, 2))]
+        );
+    }
 }
@@ -1,4 +1,7 @@
-use super::{super::OrderedVocabIter, WordLevel, WordLevelBuilder};
+use super::{
+    super::{OrderedVocabIter, UnkPolicy},
+    WordLevel, WordLevelBuilder, WordLevelFallback,
+};
 use serde::{
     de::{MapAccess, Visitor},
     ser::SerializeStruct,
@@ -16,6 +19,13 @@ impl Serialize for WordLevel {
         model.serialize_field("type", "WordLevel")?;
         model.serialize_field("vocab", &ordered_vocab)?;
         model.serialize_field("unk_token", &self.unk_token)?;
+        // The policy is only needed when it can't be deduced from the `unk_token`
+        if self.unk_policy != UnkPolicy::Token(self.unk_token.clone()) {
+            model.serialize_field("unk_policy", &self.unk_policy)?;
+        }
+        if self.fallback != WordLevelFallback::Unk {
+            model.serialize_field("fallback", &self.fallback)?;
+        }
         model.end()
     }
 }
@@ -27,7 +37,7 @@ impl<'de> Deserialize<'de> for WordLevel {
     {
         deserializer.deserialize_struct(
             "WordLevel",
-            &["type", "vocab", "unk_token"],
+            &["type", "vocab", "unk_token", "unk_policy", "fallback"],
             WordLevelVisitor,
         )
     }
@@ -57,7 +67,9 @@ impl<'de> Visitor<'de> for WordLevelVisitor {
             match key.as_ref() {
                 "vocab" => builder = builder.vocab(map.next_value()?),
                 "unk_token" => builder = builder.unk_token(map.next_value()?),
-                "type" => match map.next_value()? {
+                "unk_policy" => builder = builder.unk_policy(map.next_value()?),
+                "fallback" => builder = builder.fallback(map.next_value()?),
+                "type" => match map.next_value::<String>()?.as_ref() {
                     "WordLevel" => {}
                     u => {
                         return Err(serde::de::Error::invalid_value(
@@ -83,7 +95,7 @@ impl<'de> Visitor<'de> for WordLevelVisitor {
 
 #[cfg(test)]
 mod tests {
-    use crate::models::wordlevel::{Vocab, WordLevel, WordLevelBuilder};
+    use crate::models::wordlevel::{Vocab, WordLevel, WordLevelBuilder, WordLevelFallback};
 
     #[test]
     fn serde() {
@@ -92,6 +104,14 @@ mod tests {
 
         assert_eq!(serde_json::to_string(&wl).unwrap(), wl_s);
         assert_eq!(serde_json::from_str::<WordLevel>(wl_s).unwrap(), wl);
+
+        let wl = WordLevelBuilder::default()
+            .fallback(WordLevelFallback::CharNgram { n: 3 })
+            .build()
+            .unwrap();
+        let wl_s = r#"{"type":"WordLevel","vocab":{},"unk_token":"<unk>","fallback":{"CharNgram":{"n":3}}}"#;
+        assert_eq!(serde_json::to_string(&wl).unwrap(), wl_s);
+        assert_eq!(serde_json::from_str::<WordLevel>(wl_s).unwrap(), wl);
     }
 
     #[test]
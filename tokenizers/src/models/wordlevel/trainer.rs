@@ -1,6 +1,8 @@
 use super::WordLevel;
+use crate::models::pin_token_ids;
 use crate::utils::parallelism::*;
-use crate::{AddedToken, Result, Trainer};
+use crate::utils::progress::{Progress, ProgressCallback};
+use crate::{AddedToken, Result, Trainer, TrainerProgress};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::collections::HashMap;
@@ -20,9 +22,17 @@ pub struct WordLevelTrainer {
     /// A list of special tokens that the model should know of
     #[builder(default)]
     pub special_tokens: Vec<AddedToken>,
+    /// The ids that some of the special tokens must have, the other tokens filling the
+    /// remaining ids
+    #[builder(default, setter(custom))]
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub special_token_ids: HashMap<String, u32>,
 
     #[builder(default, private)]
     words: HashMap<String, u32>,
+    #[builder(default, setter(custom))]
+    #[serde(skip)]
+    progress: ProgressCallback,
 }
 
 impl Default for WordLevelTrainer {
@@ -31,6 +41,26 @@ impl Default for WordLevelTrainer {
     }
 }
 
+impl WordLevelTrainerBuilder {
+    /// Set the `TrainerProgress` that will receive the progress of the training
+    pub fn progress(&mut self, progress: Option<Box<dyn TrainerProgress>>) -> &mut Self {
+        self.progress = Some(progress.into());
+        self
+    }
+
+    /// Add some special tokens, each with the id it must have in the trained vocabulary. All
+    /// the other tokens fill the remaining ids.
+    pub fn special_tokens_with_ids(&mut self, tokens: Vec<(AddedToken, u32)>) -> &mut Self {
+        let ids = self.special_token_ids.get_or_insert_with(HashMap::new);
+        let special_tokens = self.special_tokens.get_or_insert_with(Vec::new);
+        for (token, id) in tokens {
+            ids.insert(token.content.clone(), id);
+            special_tokens.push(token);
+        }
+        self
+    }
+}
+
 impl WordLevelTrainer {
     pub fn builder() -> WordLevelTrainerBuilder {
         WordLevelTrainerBuilder::default()
@@ -41,6 +71,9 @@ impl WordLevelTrainer {
         word_counts: &HashMap<String, u32>,
         model: &mut WordLevel,
     ) -> Result<Vec<AddedToken>> {
+        // This is quick enough to never need a progress bar
+        let progress = Progress::new(false, &self.progress);
+        progress.start("Compute vocab", word_counts.len());
         let mut ordered_counts = word_counts.iter().collect::<Vec<_>>();
 
         //sort the word counts first by inverse counts and then by word, in order
@@ -55,27 +88,27 @@ impl WordLevelTrainer {
 
         ordered_counts.sort_by(cmp);
 
-        let word_level = WordLevel::builder()
-            .vocab(
-                self.special_tokens
-                    .iter()
-                    .map(|token| token.content.clone())
-                    .chain(
-                        ordered_counts
-                            .into_iter()
-                            .filter(|(_, n)| **n >= self.min_frequency)
-                            .map(|(w, _)| w.to_owned()),
-                    )
-                    .take(self.vocab_size)
-                    .enumerate()
-                    .map(|(i, w)| (w, i as u32))
-                    .collect(),
+        let tokens = self
+            .special_tokens
+            .iter()
+            .map(|token| token.content.clone())
+            .chain(
+                ordered_counts
+                    .into_iter()
+                    .filter(|(_, n)| **n >= self.min_frequency)
+                    .map(|(w, _)| w.to_owned()),
             )
+            .take(self.vocab_size)
+            .collect::<Vec<_>>();
+        let ids = pin_token_ids(&tokens, &self.special_token_ids)?;
+        let word_level = WordLevel::builder()
+            .vocab(tokens.into_iter().zip(ids).collect())
             .build()?;
 
         // Transfer the vocab
         model.vocab = word_level.vocab;
         model.vocab_r = word_level.vocab_r;
+        progress.finish(word_counts.len());
 
         Ok(self.special_tokens.clone())
     }
@@ -166,7 +199,7 @@ mod tests {
         .iter()
         .cloned()
         .collect();
-        assert_eq!(model.vocab, expected_vocab);
+        assert_eq!(*model.vocab, expected_vocab);
 
         // If we specify a min_frequency
         trainer.min_frequency = 15;
@@ -182,6 +215,42 @@ mod tests {
         .cloned()
         .collect();
 
-        assert_eq!(model.vocab, expected_vocab);
+        assert_eq!(*model.vocab, expected_vocab);
+    }
+
+    #[test]
+    fn test_special_tokens_with_ids() {
+        let word_counts: HashMap<String, u32> = [("the".into(), 25), ("are".into(), 10)]
+            .iter()
+            .cloned()
+            .collect();
+        let trainer = WordLevelTrainer::builder()
+            .special_tokens(vec![AddedToken::from("[MASK]", true)])
+            .special_tokens_with_ids(vec![
+                (AddedToken::from("<s>", true), 2),
+                (AddedToken::from("<pad>", true), 0),
+            ])
+            .build()
+            .unwrap();
+        let mut model = WordLevel::default();
+        trainer.do_train(&word_counts, &mut model).unwrap();
+
+        let expected_vocab: HashMap<String, u32> = [
+            ("<pad>".into(), 0),
+            ("[MASK]".into(), 1),
+            ("<s>".into(), 2),
+            ("the".into(), 3),
+            ("are".into(), 4),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        assert_eq!(*model.vocab, expected_vocab);
+
+        let trainer = WordLevelTrainer::builder()
+            .special_tokens_with_ids(vec![(AddedToken::from("<s>", true), 5)])
+            .build()
+            .unwrap();
+        assert!(trainer.do_train(&word_counts, &mut model).is_err());
     }
 }
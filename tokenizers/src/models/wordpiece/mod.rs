@@ -2,14 +2,15 @@
 //! model.
 
 use crate::models::bpe::BPE;
+use crate::models::{save_parts, UnkError, UnkPolicy};
 use crate::tokenizer::{Model, Result, Token};
 use std::{
     borrow::Cow,
     collections::HashMap,
     fs::File,
-    io::prelude::*,
     io::{BufRead, BufReader},
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
 mod serialization;
@@ -20,6 +21,15 @@ pub use trainer::*;
 pub enum Error {
     #[error("WordPiece error: Missing [UNK] token from the vocabulary")]
     MissingUnkToken,
+    #[error(
+        "WordPiece error: Only one of continuing_subword_prefix and continuing_subword_suffix \
+        can be set"
+    )]
+    PrefixAndSuffix,
+    #[error(
+        "WordPiece error: Only one of continuing_subword_prefix and leading_prefix can be set"
+    )]
+    PrefixAndLeadingPrefix,
 }
 
 type Vocab = HashMap<String, u32>;
@@ -29,7 +39,10 @@ struct Config {
     files: Option<String>,
     vocab: Vocab,
     unk_token: String,
-    continuing_subword_prefix: String,
+    unk_policy: Option<UnkPolicy>,
+    continuing_subword_prefix: Option<String>,
+    continuing_subword_suffix: Option<String>,
+    leading_prefix: Option<String>,
     max_input_chars_per_word: usize,
 }
 
@@ -45,7 +58,10 @@ impl Default for WordPieceBuilder {
                 files: None,
                 vocab: HashMap::new(),
                 unk_token: String::from("[UNK]"),
-                continuing_subword_prefix: String::from("##"),
+                unk_policy: None,
+                continuing_subword_prefix: None,
+                continuing_subword_suffix: None,
+                leading_prefix: None,
                 max_input_chars_per_word: 100,
             },
         }
@@ -79,10 +95,34 @@ impl WordPieceBuilder {
         self
     }
 
+    /// Set how unknown words are handled. `UnkPolicy::Token` is equivalent to `unk_token`.
+    #[must_use]
+    pub fn unk_policy(mut self, unk_policy: UnkPolicy) -> Self {
+        self.config.unk_policy = Some(unk_policy);
+        self
+    }
+
     /// Set the prefix for continuing subwords.
     #[must_use]
     pub fn continuing_subword_prefix(mut self, continuing_subword_prefix: String) -> Self {
-        self.config.continuing_subword_prefix = continuing_subword_prefix;
+        self.config.continuing_subword_prefix = Some(continuing_subword_prefix);
+        self
+    }
+
+    /// Set the suffix for the subwords followed by another one, like the `@@` of subword-nmt.
+    /// It replaces the default prefix, and can't be used with a non-empty one.
+    #[must_use]
+    pub fn continuing_subword_suffix(mut self, continuing_subword_suffix: String) -> Self {
+        self.config.continuing_subword_suffix = Some(continuing_subword_suffix);
+        self
+    }
+
+    /// Set the prefix for the first subword of each word, like `▁`, leaving the continuing
+    /// ones bare. It replaces the default `continuing_subword_prefix`, and can't be used with
+    /// a non-empty one.
+    #[must_use]
+    pub fn leading_prefix(mut self, leading_prefix: Option<String>) -> Self {
+        self.config.leading_prefix = leading_prefix;
         self
     }
 
@@ -106,11 +146,39 @@ impl WordPieceBuilder {
             .map(|(key, val)| (*val, key.to_owned()))
             .collect();
 
+        let unk_policy = match self.config.unk_policy {
+            Some(unk_policy) => unk_policy,
+            None => UnkPolicy::Token(self.config.unk_token.clone()),
+        };
+        let unk_token = match &unk_policy {
+            UnkPolicy::Token(unk) => unk.clone(),
+            _ => self.config.unk_token,
+        };
+
+        let replaces_prefix =
+            self.config.continuing_subword_suffix.is_some() || self.config.leading_prefix.is_some();
+        let continuing_subword_prefix = match self.config.continuing_subword_prefix {
+            Some(prefix)
+                if !prefix.is_empty() && self.config.continuing_subword_suffix.is_some() =>
+            {
+                return Err(Error::PrefixAndSuffix.into())
+            }
+            Some(prefix) if !prefix.is_empty() && self.config.leading_prefix.is_some() => {
+                return Err(Error::PrefixAndLeadingPrefix.into())
+            }
+            Some(prefix) => prefix,
+            None if replaces_prefix => String::new(),
+            None => String::from("##"),
+        };
+
         Ok(WordPiece {
-            vocab: self.config.vocab,
-            vocab_r,
-            unk_token: self.config.unk_token,
-            continuing_subword_prefix: self.config.continuing_subword_prefix,
+            vocab: Arc::new(self.config.vocab),
+            vocab_r: Arc::new(vocab_r),
+            unk_token,
+            unk_policy,
+            continuing_subword_prefix,
+            continuing_subword_suffix: self.config.continuing_subword_suffix,
+            leading_prefix: self.config.leading_prefix,
             max_input_chars_per_word: self.config.max_input_chars_per_word,
         })
     }
@@ -121,10 +189,23 @@ impl WordPieceBuilder {
 /// model.
 #[derive(Clone, PartialEq, Eq)]
 pub struct WordPiece {
-    vocab: Vocab,
-    vocab_r: VocabR,
+    /// The vocabulary, shared between clones
+    vocab: Arc<Vocab>,
+    vocab_r: Arc<VocabR>,
+    /// The token used for the words that can't be tokenized. Such a word always becomes a
+    /// single unknown token, whatever its length, so these never need to be fused like with
+    /// the `fuse_unk` option of BPE.
     pub unk_token: String,
+    /// How unknown words are handled. With `UnkPolicy::Token`, this holds the same token
+    /// as `unk_token`
+    pub unk_policy: UnkPolicy,
     pub continuing_subword_prefix: String,
+    /// The suffix of the subwords followed by another one. When set, the
+    /// `continuing_subword_prefix` is empty.
+    pub continuing_subword_suffix: Option<String>,
+    /// The prefix of the first subword of each word. When set, the
+    /// `continuing_subword_prefix` is empty.
+    pub leading_prefix: Option<String>,
     pub max_input_chars_per_word: usize,
 }
 
@@ -132,7 +213,10 @@ impl std::fmt::Debug for WordPiece {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
         fmt.debug_struct("WordPiece")
             .field("unk_token", &self.unk_token)
+            .field("unk_policy", &self.unk_policy)
             .field("continuing_subword_prefix", &self.continuing_subword_prefix)
+            .field("continuing_subword_suffix", &self.continuing_subword_suffix)
+            .field("leading_prefix", &self.leading_prefix)
             .field("max_input_chars_per_word", &self.max_input_chars_per_word)
             .field("vocab", &self.vocab.len())
             .finish()
@@ -142,10 +226,13 @@ impl std::fmt::Debug for WordPiece {
 impl Default for WordPiece {
     fn default() -> Self {
         Self {
-            vocab: HashMap::new(),
-            vocab_r: HashMap::new(),
+            vocab: Arc::default(),
+            vocab_r: Arc::default(),
             unk_token: String::from("[UNK]"),
+            unk_policy: UnkPolicy::Token(String::from("[UNK]")),
             continuing_subword_prefix: String::from("##"),
+            continuing_subword_suffix: None,
+            leading_prefix: None,
             max_input_chars_per_word: 100,
         }
     }
@@ -181,19 +268,41 @@ impl WordPiece {
         let mut wp = Self::builder().vocab(bpe.get_vocab()).build().unwrap();
         if let Some(unk) = bpe.get_unk_token() {
             wp.unk_token = unk.to_owned();
+            wp.unk_policy = UnkPolicy::Token(unk.to_owned());
         }
         if let Some(prefix) = bpe.get_continuing_subword_prefix() {
             wp.continuing_subword_prefix = prefix.to_owned();
         }
+        if let Some(suffix) = bpe.get_continuing_subword_suffix() {
+            wp.continuing_subword_prefix = String::new();
+            wp.continuing_subword_suffix = Some(suffix.to_owned());
+        }
         wp
     }
+
+    /// Handle the part of `sequence` starting at `offset`, that we couldn't tokenize, following
+    /// the `unk_policy`.
+    fn handle_unk(&self, sequence: &str, offset: usize) -> Result<Vec<Token>> {
+        match &self.unk_policy {
+            UnkPolicy::Token(unk_token) => Ok(vec![Token {
+                value: unk_token.clone(),
+                id: *self.vocab.get(unk_token).ok_or(Error::MissingUnkToken)?,
+                offsets: (0, sequence.len()),
+            }]),
+            UnkPolicy::Drop => Ok(vec![]),
+            UnkPolicy::Error => Err(Box::new(UnkError {
+                substring: sequence[offset..].to_owned(),
+                offset,
+            })),
+        }
+    }
 }
 
 impl Model for WordPiece {
     type Trainer = WordPieceTrainer;
 
     fn get_vocab(&self) -> HashMap<String, u32> {
-        self.vocab.clone()
+        (*self.vocab).clone()
     }
 
     fn get_vocab_size(&self) -> usize {
@@ -203,14 +312,7 @@ impl Model for WordPiece {
     fn tokenize(&self, sequence: &str) -> Result<Vec<Token>> {
         let char_len = sequence.chars().count();
         if char_len > self.max_input_chars_per_word {
-            return Ok(vec![Token {
-                value: self.unk_token.clone(),
-                id: *self
-                    .vocab
-                    .get(&self.unk_token)
-                    .ok_or(Error::MissingUnkToken)?,
-                offsets: (0, sequence.len()),
-            }]);
+            return self.handle_unk(sequence, 0);
         }
 
         let mut is_bad = false;
@@ -222,10 +324,18 @@ impl Model for WordPiece {
             let mut cur_str = None;
 
             while start < end {
-                let mut substr: Cow<str> = Cow::Borrowed(&sequence[start..end]);
+                let piece = &sequence[start..end];
+                let mut substr: Cow<str> = Cow::Borrowed(piece);
 
                 if start > 0 {
                     substr = Cow::Owned(format!("{}{}", self.continuing_subword_prefix, substr));
+                } else if let Some(leading_prefix) = &self.leading_prefix {
+                    substr = Cow::Owned(format!("{}{}", leading_prefix, substr));
+                }
+                if end < sequence.len() {
+                    if let Some(suffix) = &self.continuing_subword_suffix {
+                        substr = Cow::Owned(format!("{}{}", substr, suffix));
+                    }
                 }
                 if self.vocab.contains_key(substr.as_ref()) {
                     cur_str = Some(Token {
@@ -235,7 +345,7 @@ impl Model for WordPiece {
                     });
                     break;
                 }
-                end -= substr.chars().last().map_or(1, |c| c.len_utf8());
+                end -= piece.chars().last().map_or(1, |c| c.len_utf8());
             }
 
             if cur_str.is_none() {
@@ -248,14 +358,7 @@ impl Model for WordPiece {
         }
 
         if is_bad {
-            Ok(vec![Token {
-                value: self.unk_token.clone(),
-                id: *self
-                    .vocab
-                    .get(&self.unk_token)
-                    .ok_or(Error::MissingUnkToken)?,
-                offsets: (0, sequence.len()),
-            }])
+            self.handle_unk(sequence, start)
         } else {
             Ok(sub_tokens)
         }
@@ -269,32 +372,36 @@ impl Model for WordPiece {
         self.vocab_r.get(&id).cloned()
     }
 
+    fn id_to_token_cow(&self, id: u32) -> Option<Cow<'_, str>> {
+        self.vocab_r
+            .get(&id)
+            .map(|token| Cow::Borrowed(token.as_str()))
+    }
+
     fn save(&self, folder: &Path, name: Option<&str>) -> Result<Vec<PathBuf>> {
-        let vocab_file_name = match name {
-            Some(name) => format!("{}-vocab.txt", name),
-            None => "vocab.txt".to_string(),
-        };
+        save_parts(folder, name, self.serialize_parts()?)
+    }
 
-        // Write vocab.txt
-        let vocab_path: PathBuf = [folder, Path::new(vocab_file_name.as_str())]
-            .iter()
-            .collect();
-        let mut vocab_file = File::create(&vocab_path)?;
+    fn serialize_parts(&self) -> Result<Vec<(String, Vec<u8>)>> {
         let mut vocab: Vec<(&String, &u32)> = self.vocab.iter().collect();
         vocab.sort_unstable_by_key(|k| *k.1);
-        vocab_file.write_all(
-            &vocab
-                .into_iter()
-                .flat_map(|(token, _)| format!("{}\n", token).as_bytes().to_owned())
-                .collect::<Vec<_>>()[..],
-        )?;
-
-        Ok(vec![vocab_path])
+        let vocab = vocab
+            .into_iter()
+            .flat_map(|(token, _)| format!("{}\n", token).into_bytes())
+            .collect();
+        Ok(vec![("vocab.txt".to_string(), vocab)])
     }
 
     fn get_trainer(&self) -> Self::Trainer {
         WordPieceTrainer::builder().build()
     }
+
+    fn unk_token(&self) -> Option<String> {
+        match &self.unk_policy {
+            UnkPolicy::Token(unk_token) => Some(unk_token.clone()),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -305,4 +412,211 @@ mod tests {
     fn test_error_display() {
         assert!(format!("{}", Error::MissingUnkToken).contains("Missing [UNK] token"));
     }
+
+    #[test]
+    fn test_unk_policy() {
+        let vocab: Vocab = [("[UNK]".into(), 0), ("a".into(), 1), ("##b".into(), 2)]
+            .iter()
+            .cloned()
+            .collect();
+        let builder = || WordPiece::builder().vocab(vocab.clone());
+
+        let wp = builder().build().unwrap();
+        assert_eq!(wp.unk_policy, UnkPolicy::Token("[UNK]".into()));
+        assert_eq!(
+            wp.tokenize("abc").unwrap(),
+            vec![Token::new(0, "[UNK]".into(), (0, 3))]
+        );
+
+        let wp = builder().unk_policy(UnkPolicy::Drop).build().unwrap();
+        assert_eq!(wp.tokenize("abc").unwrap(), vec![]);
+        assert_eq!(
+            wp.tokenize("ab").unwrap(),
+            vec![
+                Token::new(1, "a".into(), (0, 1)),
+                Token::new(2, "##b".into(), (1, 2))
+            ]
+        );
+
+        let wp = builder().unk_policy(UnkPolicy::Error).build().unwrap();
+        let err = wp.tokenize("abc").unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<UnkError>(),
+            Some(&UnkError {
+                substring: "c".into(),
+                offset: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_unk_single_token() {
+        let vocab: Vocab = [("[UNK]".into(), 0), ("a".into(), 1)]
+            .iter()
+            .cloned()
+            .collect();
+        let wp = WordPiece::builder().vocab(vocab).build().unwrap();
+        assert_eq!(
+            wp.tokenize("ééééé").unwrap(),
+            vec![Token::new(0, "[UNK]".into(), (0, 10))]
+        );
+        assert_eq!(
+            wp.tokenize("aéééé").unwrap(),
+            vec![Token::new(0, "[UNK]".into(), (0, 9))]
+        );
+    }
+
+    #[test]
+    fn test_clone_shares_vocab() {
+        let vocab: Vocab = [("[UNK]".into(), 0), ("a".into(), 1), ("##b".into(), 2)]
+            .iter()
+            .cloned()
+            .collect();
+        let wp = WordPiece::builder().vocab(vocab).build().unwrap();
+        let clone = wp.clone();
+        assert!(Arc::ptr_eq(&wp.vocab, &clone.vocab));
+        assert!(Arc::ptr_eq(&wp.vocab_r, &clone.vocab_r));
+        assert_eq!(clone.tokenize("ab").unwrap(), wp.tokenize("ab").unwrap());
+    }
+
+    #[test]
+    fn test_unk_offsets() {
+        let vocab: Vocab = [("a".into(), 0), ("##b".into(), 1), ("[UNK]".into(), 2)]
+            .iter()
+            .cloned()
+            .collect();
+        let wp = WordPiece::builder()
+            .vocab(vocab)
+            .max_input_chars_per_word(3)
+            .build()
+            .unwrap();
+        // Some unknown part at the end
+        assert_eq!(
+            wp.tokenize("abé").unwrap(),
+            vec![Token::new(2, "[UNK]".into(), (0, 4))]
+        );
+        // A word too long, even if all its pieces are known
+        assert_eq!(
+            wp.tokenize("abbb").unwrap(),
+            vec![Token::new(2, "[UNK]".into(), (0, 4))]
+        );
+    }
+
+    #[test]
+    fn test_continuing_subword_suffix() {
+        let vocab: Vocab = [
+            ("[UNK]".into(), 0),
+            ("end@@".into(), 1),
+            ("ing".into(), 2),
+            ("end".into(), 3),
+            ("é@@".into(), 4),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        let wp = WordPiece::builder()
+            .vocab(vocab.clone())
+            .continuing_subword_suffix("@@".into())
+            .build()
+            .unwrap();
+        assert_eq!(wp.continuing_subword_prefix, "");
+        assert_eq!(
+            wp.tokenize("ending").unwrap(),
+            vec![
+                Token::new(1, "end@@".into(), (0, 3)),
+                Token::new(2, "ing".into(), (3, 6))
+            ]
+        );
+        assert_eq!(
+            wp.tokenize("éend").unwrap(),
+            vec![
+                Token::new(4, "é@@".into(), (0, 2)),
+                Token::new(3, "end".into(), (2, 5))
+            ]
+        );
+        // Only the last piece goes without the suffix
+        assert_eq!(
+            wp.tokenize("endé").unwrap(),
+            vec![Token::new(0, "[UNK]".into(), (0, 5))]
+        );
+
+        // An empty prefix is the only one compatible with the suffix
+        let builder = || {
+            WordPiece::builder()
+                .vocab(vocab.clone())
+                .continuing_subword_suffix("@@".into())
+        };
+        assert!(builder()
+            .continuing_subword_prefix("".into())
+            .build()
+            .is_ok());
+        let err = builder()
+            .continuing_subword_prefix("##".into())
+            .build()
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::PrefixAndSuffix)
+        ));
+    }
+
+    #[test]
+    fn test_leading_prefix() {
+        use crate::decoders::wordpiece::WordPiece as WordPieceDecoder;
+        use crate::tokenizer::Decoder;
+
+        let vocab: Vocab = [
+            ("[UNK]".into(), 0),
+            ("▁end".into(), 1),
+            ("ing".into(), 2),
+            ("end".into(), 3),
+            ("▁the".into(), 4),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        let wp = WordPiece::builder()
+            .vocab(vocab.clone())
+            .leading_prefix(Some("▁".into()))
+            .build()
+            .unwrap();
+        assert_eq!(wp.continuing_subword_prefix, "");
+        let tokenize = |word| wp.tokenize(word).unwrap();
+        assert_eq!(
+            tokenize("ending"),
+            vec![
+                Token::new(1, "▁end".into(), (0, 3)),
+                Token::new(2, "ing".into(), (3, 6))
+            ]
+        );
+        assert_eq!(
+            tokenize("endend"),
+            vec![
+                Token::new(1, "▁end".into(), (0, 3)),
+                Token::new(3, "end".into(), (3, 6))
+            ]
+        );
+        assert_eq!(tokenize("ing"), vec![Token::new(0, "[UNK]".into(), (0, 3))]);
+
+        // The matching decoder gives the words back
+        let tokens = ["the", "ending", "endend"]
+            .iter()
+            .flat_map(|word| tokenize(word))
+            .map(|token| token.value)
+            .collect();
+        let decoder = WordPieceDecoder::with_leading_prefix("▁".into(), false);
+        assert_eq!(decoder.decode(tokens).unwrap(), "the ending endend");
+
+        // It can't be used with a continuing prefix
+        let err = WordPiece::builder()
+            .vocab(vocab)
+            .continuing_subword_prefix("##".into())
+            .leading_prefix(Some("▁".into()))
+            .build()
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::PrefixAndLeadingPrefix)
+        ));
+    }
 }
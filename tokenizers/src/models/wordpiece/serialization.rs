@@ -1,4 +1,7 @@
-use super::{super::OrderedVocabIter, WordPiece, WordPieceBuilder};
+use super::{
+    super::{OrderedVocabIter, UnkPolicy},
+    WordPiece, WordPieceBuilder,
+};
 use serde::{
     de::{MapAccess, Visitor},
     ser::SerializeStruct,
@@ -16,7 +19,17 @@ impl Serialize for WordPiece {
         // Small fields first
         model.serialize_field("type", "WordPiece")?;
         model.serialize_field("unk_token", &self.unk_token)?;
+        // The policy is only needed when it can't be deduced from the `unk_token`
+        if self.unk_policy != UnkPolicy::Token(self.unk_token.clone()) {
+            model.serialize_field("unk_policy", &self.unk_policy)?;
+        }
         model.serialize_field("continuing_subword_prefix", &self.continuing_subword_prefix)?;
+        if let Some(suffix) = &self.continuing_subword_suffix {
+            model.serialize_field("continuing_subword_suffix", suffix)?;
+        }
+        if let Some(leading_prefix) = &self.leading_prefix {
+            model.serialize_field("leading_prefix", leading_prefix)?;
+        }
         model.serialize_field("max_input_chars_per_word", &self.max_input_chars_per_word)?;
 
         // Then large ones
@@ -37,7 +50,10 @@ impl<'de> Deserialize<'de> for WordPiece {
             &[
                 "type",
                 "unk_token",
+                "unk_policy",
                 "continuing_subword_prefix",
+                "continuing_subword_suffix",
+                "leading_prefix",
                 "max_input_chars_per_word",
                 "vocab",
             ],
@@ -72,14 +88,19 @@ impl<'de> Visitor<'de> for WordPieceVisitor {
         while let Some(key) = map.next_key::<String>()? {
             match key.as_ref() {
                 "unk_token" => builder = builder.unk_token(map.next_value()?),
+                "unk_policy" => builder = builder.unk_policy(map.next_value()?),
                 "continuing_subword_prefix" => {
                     builder = builder.continuing_subword_prefix(map.next_value()?)
                 }
+                "continuing_subword_suffix" => {
+                    builder = builder.continuing_subword_suffix(map.next_value()?)
+                }
+                "leading_prefix" => builder = builder.leading_prefix(map.next_value()?),
                 "max_input_chars_per_word" => {
                     builder = builder.max_input_chars_per_word(map.next_value()?)
                 }
                 "vocab" => builder = builder.vocab(map.next_value()?),
-                "type" => match map.next_value()? {
+                "type" => match map.next_value::<String>()?.as_ref() {
                     "WordPiece" => {}
                     u => {
                         return Err(serde::de::Error::invalid_value(
@@ -120,6 +141,36 @@ mod tests {
 
         assert_eq!(serde_json::to_string(&wp).unwrap(), wp_s);
         assert_eq!(serde_json::from_str::<WordPiece>(wp_s).unwrap(), wp);
+
+        let wp = WordPiece::builder()
+            .continuing_subword_suffix("@@".into())
+            .build()
+            .unwrap();
+        let wp_s = "{\
+            \"type\":\"WordPiece\",\
+            \"unk_token\":\"[UNK]\",\
+            \"continuing_subword_prefix\":\"\",\
+            \"continuing_subword_suffix\":\"@@\",\
+            \"max_input_chars_per_word\":100,\
+            \"vocab\":{}\
+        }";
+        assert_eq!(serde_json::to_string(&wp).unwrap(), wp_s);
+        assert_eq!(serde_json::from_str::<WordPiece>(wp_s).unwrap(), wp);
+
+        let wp = WordPiece::builder()
+            .leading_prefix(Some("▁".into()))
+            .build()
+            .unwrap();
+        let wp_s = "{\
+            \"type\":\"WordPiece\",\
+            \"unk_token\":\"[UNK]\",\
+            \"continuing_subword_prefix\":\"\",\
+            \"leading_prefix\":\"▁\",\
+            \"max_input_chars_per_word\":100,\
+            \"vocab\":{}\
+        }";
+        assert_eq!(serde_json::to_string(&wp).unwrap(), wp_s);
+        assert_eq!(serde_json::from_str::<WordPiece>(wp_s).unwrap(), wp);
     }
 
     #[test]
@@ -144,5 +195,18 @@ mod tests {
             .unwrap_err()
             .to_string()
             .starts_with("invalid value: string \"WordLevel\", expected WordPiece"));
+
+        let prefix_and_suffix = "{\
+            \"type\":\"WordPiece\",\
+            \"unk_token\":\"[UNK]\",\
+            \"continuing_subword_prefix\":\"##\",\
+            \"continuing_subword_suffix\":\"@@\",\
+            \"max_input_chars_per_word\":100,\
+            \"vocab\":{}\
+        }";
+        assert!(serde_json::from_str::<WordPiece>(prefix_and_suffix)
+            .unwrap_err()
+            .to_string()
+            .contains("Only one of continuing_subword_prefix and continuing_subword_suffix"));
     }
 }
@@ -1,19 +1,22 @@
 use super::WordPiece;
 use crate::models::bpe::{BpeTrainer, BpeTrainerBuilder, BPE};
-use crate::tokenizer::{AddedToken, Result, Trainer};
+use crate::tokenizer::{AddedToken, Result, Trainer, TrainerProgress};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashSet, HashMap};
+use std::sync::Mutex;
 
 /// A `WordPieceTrainerBuilder` can be used to create a `WordPieceTrainer` with a custom
 /// configuration.
 pub struct WordPieceTrainerBuilder {
     bpe_trainer_builder: BpeTrainerBuilder,
+    emit_word_table: bool,
 }
 
 impl Default for WordPieceTrainerBuilder {
     fn default() -> Self {
         Self {
             bpe_trainer_builder: BpeTrainerBuilder::new().continuing_subword_prefix("##".into()),
+            emit_word_table: false,
         }
     }
 }
@@ -52,6 +55,13 @@ impl WordPieceTrainerBuilder {
         self
     }
 
+    /// Add some special tokens, each with the id it must have in the trained vocabulary
+    #[must_use]
+    pub fn special_tokens_with_ids(mut self, tokens: Vec<(AddedToken, u32)>) -> Self {
+        self.bpe_trainer_builder = self.bpe_trainer_builder.special_tokens_with_ids(tokens);
+        self
+    }
+
     /// Set whether to limit the alphabet
     #[must_use]
     pub fn limit_alphabet(mut self, limit: usize) -> Self {
@@ -80,10 +90,29 @@ impl WordPieceTrainerBuilder {
         self
     }
 
+    /// Set the `TrainerProgress` that will receive the progress of the training, instead
+    /// of the progress bar
+    #[must_use]
+    pub fn progress(mut self, progress: Option<Box<dyn TrainerProgress>>) -> Self {
+        self.bpe_trainer_builder = self.bpe_trainer_builder.progress(progress);
+        self
+    }
+
+    /// Set whether to also keep the table of the frequent whole words when training
+    #[must_use]
+    pub fn emit_word_table(mut self, emit: bool) -> Self {
+        self.emit_word_table = emit;
+        self
+    }
+
     /// Constructs the final BpeTrainer
     pub fn build(self) -> WordPieceTrainer {
         let bpe_trainer = self.bpe_trainer_builder.build();
-        WordPieceTrainer { bpe_trainer }
+        WordPieceTrainer {
+            bpe_trainer,
+            emit_word_table: self.emit_word_table,
+            word_table: Mutex::new(HashMap::new()),
+        }
     }
 }
 
@@ -91,6 +120,12 @@ impl WordPieceTrainerBuilder {
 #[derive(Default, Deserialize, Serialize)]
 pub struct WordPieceTrainer {
     bpe_trainer: BpeTrainer,
+    /// Whether `train` also keeps the whole words seen at least `min_frequency` times,
+    /// given by `get_word_table` afterwards
+    #[serde(default)]
+    emit_word_table: bool,
+    #[serde(skip)]
+    word_table: Mutex<HashMap<String, u32>>,
 }
 
 impl WordPieceTrainer {
@@ -158,6 +193,24 @@ impl WordPieceTrainer {
         self.bpe_trainer.end_of_word_suffix = suffix;
     }
 
+    pub fn emit_word_table(&self) -> bool {
+        self.emit_word_table
+    }
+
+    pub fn set_emit_word_table(&mut self, emit: bool) {
+        self.emit_word_table = emit;
+    }
+
+    /// The whole words seen at least `min_frequency` times during the last training, with
+    /// their counts. This can be used to build a `WordLevel` for the most common words. It
+    /// stays empty unless `emit_word_table` is set.
+    pub fn get_word_table(&self) -> HashMap<String, u32> {
+        self.word_table
+            .lock()
+            .map(|table| table.clone())
+            .unwrap_or_default()
+    }
+
     pub fn builder() -> WordPieceTrainerBuilder {
         WordPieceTrainerBuilder::default()
     }
@@ -165,6 +218,18 @@ impl WordPieceTrainer {
     pub fn train(&self, model: &mut WordPiece) -> Result<Vec<AddedToken>> {
         let mut bpe = BPE::default();
         let special_tokens = self.bpe_trainer.train(&mut bpe)?;
+        if self.emit_word_table {
+            let table = self
+                .bpe_trainer
+                .word_counts()
+                .iter()
+                .filter(|(_, count)| **count >= self.bpe_trainer.min_frequency)
+                .map(|(word, count)| (word.clone(), *count))
+                .collect();
+            if let Ok(mut word_table) = self.word_table.lock() {
+                *word_table = table;
+            }
+        }
         let new_wordpiece = WordPiece::from_bpe(&bpe);
 
         // Transfer the vocab
@@ -202,3 +267,40 @@ impl Trainer for WordPieceTrainer {
         self.bpe_trainer.feed_with_counter(counter)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Model;
+
+    #[test]
+    fn word_table() {
+        let words = [("hello", 5), ("world", 3), ("hell", 1), ("low", 2)]
+            .iter()
+            .map(|(word, count)| (word.to_string(), *count))
+            .collect::<HashMap<_, _>>();
+        let train = |emit_word_table: bool| {
+            let mut trainer = WordPieceTrainer::builder()
+                .show_progress(false)
+                .min_frequency(2)
+                .emit_word_table(emit_word_table)
+                .build();
+            trainer.feed_with_counter(words.clone());
+            let mut model = WordPiece::default();
+            trainer.train(&mut model).unwrap();
+            (trainer, model)
+        };
+
+        let (trainer, model) = train(true);
+        let expected = [("hello", 5), ("world", 3), ("low", 2)]
+            .iter()
+            .map(|(word, count)| (word.to_string(), *count))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(trainer.get_word_table(), expected);
+
+        // The vocab is the same without it
+        let (trainer, model_without) = train(false);
+        assert!(trainer.get_word_table().is_empty());
+        assert_eq!(model.get_vocab(), model_without.get_vocab());
+    }
+}
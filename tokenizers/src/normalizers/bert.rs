@@ -1,6 +1,10 @@
+use crate::normalizers::{Lowercase, NormalizerWrapper, Sequence};
 use crate::tokenizer::{NormalizedString, Normalizer, Result};
+use crate::utils::macro_rules_attribute;
 
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::iter::FromIterator;
+use std::ops::RangeInclusive;
 use unicode_categories::UnicodeCategories;
 
 /// Checks whether a character is whitespace
@@ -47,8 +51,113 @@ fn is_chinese_char(c: char) -> bool {
     )
 }
 
-#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
+/// The block of the combining diacritical marks
+const COMBINING_MARKS: RangeInclusive<char> = '\u{300}'..='\u{36f}';
+
+/// A set of combining diacritical marks, from the U+0300 to U+036F block. These are the
+/// accents that NFD decomposes out of most of the Latin, Greek and Cyrillic letters, like
+/// the acute accent U+0301 of `é`.
+///
+/// It is serialized as the list of its marks.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CombiningMarks(u128);
+
+impl CombiningMarks {
+    /// All the marks of the block
+    pub const ALL: Self = Self((1 << 112) - 1);
+
+    /// An empty set
+    pub fn new() -> Self {
+        Self(0)
+    }
+
+    fn bit(mark: char) -> Option<u128> {
+        COMBINING_MARKS
+            .contains(&mark)
+            .then(|| 1 << (mark as u32 - *COMBINING_MARKS.start() as u32))
+    }
+
+    /// Add the given mark to the set, returning `false` if it isn't part of the block
+    pub fn insert(&mut self, mark: char) -> bool {
+        match Self::bit(mark) {
+            Some(bit) => {
+                self.0 |= bit;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove the given mark from the set
+    pub fn remove(&mut self, mark: char) {
+        if let Some(bit) = Self::bit(mark) {
+            self.0 &= !bit;
+        }
+    }
+
+    /// Whether the given char is one of the marks of this set
+    pub fn contains(&self, c: char) -> bool {
+        matches!(Self::bit(c), Some(bit) if self.0 & bit != 0)
+    }
+
+    /// The marks of this set, in order
+    pub fn iter(&self) -> impl Iterator<Item = char> + '_ {
+        COMBINING_MARKS.filter(move |c| self.contains(*c))
+    }
+}
+
+impl FromIterator<char> for CombiningMarks {
+    /// The chars that are not combining diacritical marks are ignored
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        let mut marks = Self::new();
+        for mark in iter {
+            marks.insert(mark);
+        }
+        marks
+    }
+}
+
+impl Serialize for CombiningMarks {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for CombiningMarks {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let mut marks = Self::new();
+        for mark in Vec::<char>::deserialize(deserializer)? {
+            if !marks.insert(mark) {
+                return Err(de::Error::custom(format!(
+                    "U+{:04X} is not a combining diacritical mark",
+                    mark as u32
+                )));
+            }
+        }
+        Ok(marks)
+    }
+}
+
+/// The normalizer used by BERT.
+///
+/// Apart from [`BertNormalizer::new`], it can be built with more control using
+/// [`BertNormalizer::builder`], for example to only strip some of the accents:
+///
+/// ```
+/// # use tokenizers::normalizers::bert::{BertNormalizer, CombiningMarks};
+/// // Keep the diaeresis of `ü`
+/// let mut marks = CombiningMarks::ALL;
+/// marks.remove('\u{308}');
+///
+/// let normalizer = BertNormalizer::builder()
+///     .lowercase(false)
+///     .strip_accents_marks(marks)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Copy, Clone, Debug, Deserialize, Serialize, Builder)]
 #[serde(tag = "type")]
+#[builder(default)]
 #[non_exhaustive]
 pub struct BertNormalizer {
     /// Whether to do the bert basic cleaning:
@@ -58,9 +167,15 @@ pub struct BertNormalizer {
     /// Whether to put spaces around chinese characters so they get split
     pub handle_chinese_chars: bool,
     /// Whether to strip accents
+    #[builder(setter(strip_option))]
     pub strip_accents: Option<bool>,
     /// Whether to lowercase the input
     pub lowercase: bool,
+    /// If set, only these marks get removed when stripping the accents, which is then
+    /// enabled unless `strip_accents` is explicitly `Some(false)`
+    #[builder(setter(strip_option))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strip_accents_marks: Option<CombiningMarks>,
 }
 
 impl Default for BertNormalizer {
@@ -70,6 +185,7 @@ impl Default for BertNormalizer {
             handle_chinese_chars: true,
             strip_accents: None,
             lowercase: true,
+            strip_accents_marks: None,
         }
     }
 }
@@ -86,16 +202,91 @@ impl BertNormalizer {
             handle_chinese_chars,
             strip_accents,
             lowercase,
+            strip_accents_marks: None,
         }
     }
 
-    fn do_clean_text(&self, normalized: &mut NormalizedString) {
+    pub fn builder() -> BertNormalizerBuilder {
+        BertNormalizerBuilder::default()
+    }
+
+    /// The steps run by this normalizer, enabled according to its flags and in the
+    /// canonical order:
+    /// [`CleanText`], [`HandleChineseChars`], [`BertStripAccents`] and [`Lowercase`]
+    pub fn steps(&self) -> Vec<NormalizerWrapper> {
+        let mut steps = vec![];
+        if self.clean_text {
+            steps.push(CleanText.into());
+        }
+        if self.handle_chinese_chars {
+            steps.push(HandleChineseChars.into());
+        }
+        if let Some(strip_accents) = self.strip_accents_step() {
+            steps.push(strip_accents.into());
+        }
+        if self.lowercase {
+            steps.push(Lowercase.into());
+        }
+        steps
+    }
+
+    /// A [`Sequence`] of the [steps](BertNormalizer::steps) run by this normalizer
+    pub fn to_sequence(&self) -> Sequence {
+        Sequence::new(self.steps())
+    }
+
+    fn strip_accents_step(&self) -> Option<BertStripAccents> {
+        let strip_accents = self
+            .strip_accents
+            .unwrap_or(self.strip_accents_marks.is_some() || self.lowercase);
+        strip_accents.then_some(BertStripAccents {
+            marks: self.strip_accents_marks,
+        })
+    }
+}
+
+impl Normalizer for BertNormalizer {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        if self.clean_text {
+            CleanText.normalize(normalized)?;
+        }
+        if self.handle_chinese_chars {
+            HandleChineseChars.normalize(normalized)?;
+        }
+        if let Some(strip_accents) = self.strip_accents_step() {
+            strip_accents.normalize(normalized)?;
+        }
+        if self.lowercase {
+            Lowercase.normalize(normalized)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The bert basic cleaning:
+///   1. Remove any control characters
+///   2. Replace all sorts of whitespace by the classic one ` `
+#[derive(Copy, Clone, Debug)]
+#[macro_rules_attribute(impl_serde_type!)]
+pub struct CleanText;
+
+impl Normalizer for CleanText {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
         normalized
             .filter(|c| !(c as usize == 0 || c as usize == 0xfffd || is_control(c)))
             .map(|c| if is_whitespace(c) { ' ' } else { c });
+        Ok(())
     }
+}
+
+/// Puts spaces around chinese characters so they get split
+#[derive(Copy, Clone, Debug)]
+#[macro_rules_attribute(impl_serde_type!)]
+pub struct HandleChineseChars;
 
-    fn do_handle_chinese_chars(&self, normalized: &mut NormalizedString) {
+impl Normalizer for HandleChineseChars {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
         let mut new_chars: Vec<(char, isize)> = vec![];
         normalized.for_each(|c| {
             if is_chinese_char(c) {
@@ -105,33 +296,200 @@ impl BertNormalizer {
             }
         });
         normalized.transform(new_chars.into_iter(), 0);
+        Ok(())
     }
+}
 
-    fn do_strip_accents(&self, normalized: &mut NormalizedString) {
-        normalized.nfd().filter(|c| !c.is_mark_nonspacing());
-    }
+/// Strips the accents the way BERT does: the input is decomposed with NFD, and the
+/// nonspacing marks are removed.
+///
+/// Unlike [`StripAccents`](crate::normalizers::StripAccents), this one doesn't remove
+/// the spacing and enclosing marks.
+#[derive(Copy, Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(tag = "type")]
+#[non_exhaustive]
+pub struct BertStripAccents {
+    /// If set, only these marks are removed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub marks: Option<CombiningMarks>,
+}
 
-    fn do_lowercase(&self, normalized: &mut NormalizedString) {
-        normalized.lowercase();
+impl BertStripAccents {
+    pub fn new(marks: Option<CombiningMarks>) -> Self {
+        Self { marks }
     }
 }
 
-impl Normalizer for BertNormalizer {
+impl Normalizer for BertStripAccents {
     fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
-        if self.clean_text {
-            self.do_clean_text(normalized);
-        }
-        if self.handle_chinese_chars {
-            self.do_handle_chinese_chars(normalized);
-        }
-        let strip_accents = self.strip_accents.unwrap_or(self.lowercase);
-        if strip_accents {
-            self.do_strip_accents(normalized);
+        match self.marks {
+            None => normalized.nfd().filter(|c| !c.is_mark_nonspacing()),
+            Some(marks) => normalized
+                .nfd()
+                .filter(|c| !(c.is_mark_nonspacing() && marks.contains(c))),
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normalizer::Range;
+
+    /// The marks used by Vietnamese but not by French, which both use the grave and acute
+    /// accents though
+    fn all_but_vietnamese_marks() -> CombiningMarks {
+        let mut marks = CombiningMarks::ALL;
+        // Tilde, hook above, horn and dot below
+        for mark in ['\u{303}', '\u{309}', '\u{31b}', '\u{323}'] {
+            marks.remove(mark);
         }
-        if self.lowercase {
-            self.do_lowercase(normalized);
+        marks
+    }
+
+    #[test]
+    fn strip_accents_marks() {
+        let original = "Crème brûlée và phở ελληνικά";
+
+        // The grave accent of `và` can't be told apart from the French one, and gets stripped
+        let normalizer = BertNormalizer::builder()
+            .lowercase(false)
+            .strip_accents_marks(all_but_vietnamese_marks())
+            .build()
+            .unwrap();
+        let mut n = NormalizedString::from(original);
+        normalizer.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "Creme brulee va pho\u{31b}\u{309} ελληνικα");
+
+        // Offsets still point to the accented chars in the original
+        let range = n.convert_offsets(Range::Normalized(6..12)).unwrap();
+        assert_eq!(&original[range], "brûlée");
+        let range = n.convert_offsets(Range::Normalized(16..23)).unwrap();
+        assert_eq!(&original[range], "phở");
+
+        // Without any mark, the input only gets decomposed
+        let normalizer = BertNormalizer::builder()
+            .lowercase(false)
+            .strip_accents_marks(CombiningMarks::new())
+            .build()
+            .unwrap();
+        let mut n = NormalizedString::from(original);
+        normalizer.normalize(&mut n).unwrap();
+        assert_eq!(
+            n.get(),
+            "Cre\u{300}me bru\u{302}le\u{301}e va\u{300} pho\u{31b}\u{309} ελληνικα\u{301}"
+        );
+
+        // An explicit `strip_accents` wins
+        let normalizer = BertNormalizer::builder()
+            .lowercase(false)
+            .strip_accents(false)
+            .strip_accents_marks(all_but_vietnamese_marks())
+            .build()
+            .unwrap();
+        let mut n = NormalizedString::from(original);
+        normalizer.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), original);
+    }
+
+    #[test]
+    fn combining_marks() {
+        let mut marks = CombiningMarks::new();
+        assert!(marks.insert('\u{301}'));
+        assert!(marks.insert('\u{36f}'));
+        assert!(!marks.insert('e'));
+        assert!(marks.contains('\u{301}') && !marks.contains('\u{300}'));
+        assert_eq!(marks.iter().collect::<Vec<_>>(), ['\u{301}', '\u{36f}']);
+        marks.remove('\u{36f}');
+        assert_eq!(marks, ['\u{301}', 'e'].iter().copied().collect());
+        assert_eq!(CombiningMarks::ALL.iter().count(), 112);
+    }
+
+    #[test]
+    fn serialization() {
+        let normalizer = BertNormalizer::default();
+        let serialized = serde_json::to_string(&normalizer).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"BertNormalizer","clean_text":true,"handle_chinese_chars":true,"strip_accents":null,"lowercase":true}"#
+        );
+
+        let marks = ['\u{301}', '\u{308}'].iter().copied().collect();
+        let normalizer = BertNormalizer::builder()
+            .strip_accents_marks(marks)
+            .build()
+            .unwrap();
+        let serialized = serde_json::to_string(&normalizer).unwrap();
+        assert!(serialized.ends_with("\"strip_accents_marks\":[\"\u{301}\",\"\u{308}\"]}"));
+        let deserialized: BertNormalizer = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.strip_accents_marks, Some(marks));
+
+        assert!(serde_json::from_str::<BertNormalizer>(
+            r#"{"type":"BertNormalizer","clean_text":true,"handle_chinese_chars":true,"strip_accents":null,"lowercase":true,"strip_accents_marks":["e"]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn steps() {
+        let inputs = [
+            "Héllo\u{0}\tWORLD\u{fffd}!",
+            "野口里佳 Noguchi Rika",
+            "Crème brûlée và phở ελληνικά",
+        ];
+        let normalizers = [
+            BertNormalizer::default(),
+            BertNormalizer::new(false, true, Some(true), false),
+            BertNormalizer::new(true, false, Some(false), true),
+            BertNormalizer::builder()
+                .strip_accents_marks(all_but_vietnamese_marks())
+                .build()
+                .unwrap(),
+        ];
+        for normalizer in &normalizers {
+            let sequence = normalizer.to_sequence();
+            for input in inputs {
+                let mut expected = NormalizedString::from(input);
+                normalizer.normalize(&mut expected).unwrap();
+                let mut n = NormalizedString::from(input);
+                sequence.normalize(&mut n).unwrap();
+                assert_eq!(n, expected);
+            }
         }
 
-        Ok(())
+        // The steps can be used on their own
+        let mut n = NormalizedString::from("野口 Rika\u{0}");
+        Sequence::new(vec![HandleChineseChars.into(), CleanText.into()])
+            .normalize(&mut n)
+            .unwrap();
+        assert_eq!(n.get(), " 野  口  Rika");
+        let mut n = NormalizedString::from("brûlée");
+        BertStripAccents::default().normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "brulee");
+    }
+
+    #[test]
+    fn steps_serialization() {
+        let sequence = BertNormalizer::builder()
+            .strip_accents_marks(['\u{301}'].iter().copied().collect())
+            .build()
+            .unwrap()
+            .to_sequence();
+        let serialized = serde_json::to_string(&sequence).unwrap();
+        assert_eq!(
+            serialized,
+            "{\"type\":\"Sequence\",\"normalizers\":[{\"type\":\"CleanText\"},{\"type\":\"HandleChineseChars\"},{\"type\":\"BertStripAccents\",\"marks\":[\"\u{301}\"]},{\"type\":\"Lowercase\"}]}"
+        );
+        let deserialized: Sequence = serde_json::from_str(&serialized).unwrap();
+        assert!(matches!(
+            deserialized.get_normalizers(),
+            [
+                NormalizerWrapper::CleanText(_),
+                NormalizerWrapper::HandleChineseChars(_),
+                NormalizerWrapper::BertStripAccents(BertStripAccents { marks: Some(_) }),
+                NormalizerWrapper::Lowercase(_),
+            ]
+        ));
     }
 }
@@ -0,0 +1,243 @@
+use crate::tokenizer::{NormalizedString, Normalizer, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// The least recently used entries come first in `order`
+#[derive(Debug, Default)]
+struct Lru {
+    entries: HashMap<String, (NormalizedString, u64)>,
+    order: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl Lru {
+    fn get(&mut self, key: &str) -> Option<NormalizedString> {
+        let tick = self.tick;
+        let (value, last_used) = self.entries.get_mut(key)?;
+        let key = self.order.remove(last_used)?;
+        *last_used = tick;
+        self.order.insert(tick, key);
+        self.tick += 1;
+        Some(value.clone())
+    }
+
+    fn insert(&mut self, key: String, value: NormalizedString, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+        if let Some((_, last_used)) = self.entries.remove(&key) {
+            self.order.remove(&last_used);
+        }
+        while self.entries.len() >= capacity {
+            let oldest = match self.order.keys().next() {
+                Some(oldest) => *oldest,
+                None => break,
+            };
+            if let Some(key) = self.order.remove(&oldest) {
+                self.entries.remove(&key);
+            }
+        }
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(key, (value, self.tick));
+        self.tick += 1;
+    }
+}
+
+/// An LRU cache of the results of a normalizer, keyed by the input string, keeping the
+/// `capacity` most recently used ones. It is used by the tokenizers with
+/// [`with_normalization_cache`](crate::TokenizerImpl::with_normalization_cache), and the
+/// normalizer **must be deterministic**: the same input must always give the same output.
+///
+/// The threads share the cache without ever waiting for each other: while a thread uses it,
+/// the others just normalize their strings without it. So, under contention, some inputs
+/// can miss the cache even though their result is in it, but the outputs stay the same.
+///
+/// A clone starts with an empty cache of the same capacity.
+#[derive(Debug)]
+pub struct NormalizationCache {
+    capacity: usize,
+    lru: Mutex<Lru>,
+    hits: AtomicUsize,
+}
+
+impl Clone for NormalizationCache {
+    fn clone(&self) -> Self {
+        Self::new(self.capacity)
+    }
+}
+
+impl NormalizationCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            lru: Mutex::new(Lru::default()),
+            hits: AtomicUsize::new(0),
+        }
+    }
+
+    /// The maximum number of normalized strings kept
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of times a normalized string got reused from the cache
+    pub fn get_hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The number of normalized strings currently kept
+    pub fn len(&self) -> usize {
+        self.lru.lock().map_or(0, |lru| lru.entries.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Empty the cache
+    pub fn clear(&self) {
+        if let Ok(mut lru) = self.lru.lock() {
+            *lru = Lru::default();
+        }
+    }
+
+    /// Normalize with `inner`, unless the result for this input is already in the cache.
+    /// Only the strings that weren't modified yet use the cache.
+    pub fn normalize<N: Normalizer + ?Sized>(
+        &self,
+        inner: &N,
+        normalized: &mut NormalizedString,
+    ) -> Result<()> {
+        if !normalized.is_unmodified() {
+            return inner.normalize(normalized);
+        }
+
+        // Like the cache of the models, we never wait for another thread to be done with it
+        // (see above)
+        let cached = self
+            .lru
+            .try_lock()
+            .ok()
+            .and_then(|mut lru| lru.get(normalized.get_original()));
+        if let Some(cached) = cached {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            normalized.set_normalized_from(&cached);
+            return Ok(());
+        }
+
+        let mut result = NormalizedString::from(normalized.get_original());
+        inner.normalize(&mut result)?;
+        normalized.set_normalized_from(&result);
+        if let Ok(mut lru) = self.lru.try_lock() {
+            lru.insert(result.get_original().to_owned(), result, self.capacity);
+        }
+        Ok(())
+    }
+}
+
+/// A borrowed normalizer, going through the given cache if any
+pub(crate) struct WithCache<'a, N> {
+    pub(crate) inner: &'a N,
+    pub(crate) cache: Option<&'a NormalizationCache>,
+}
+
+impl<N: Normalizer> Normalizer for WithCache<'_, N> {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        match self.cache {
+            Some(cache) => cache.normalize(self.inner, normalized),
+            None => self.inner.normalize(normalized),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normalizer::Range;
+    use crate::normalizers::{Lowercase, NormalizerWrapper, Sequence, StripAccents, NFKC, NFKD};
+
+    fn inner() -> NormalizerWrapper {
+        Sequence::new(vec![NFKD.into(), StripAccents.into(), Lowercase.into()]).into()
+    }
+
+    #[test]
+    fn same_as_uncached() {
+        let (inner, cache) = (inner(), NormalizationCache::new(2));
+        let inputs = [
+            "Héllo",
+            "ＷＯＲＬＤ",
+            "Héllo",
+            "ﬁne",
+            "Héllo",
+            "ＷＯＲＬＤ",
+            "",
+        ];
+        for input in inputs.iter().chain(inputs.iter()) {
+            let mut expected = NormalizedString::from(*input);
+            inner.normalize(&mut expected).unwrap();
+            let mut result = NormalizedString::from(*input);
+            cache.normalize(&inner, &mut result).unwrap();
+            assert_eq!(result, expected);
+        }
+        assert!(cache.get_hits() > 0);
+
+        // A slice of a bigger string keeps its own offsets
+        let mut whole = NormalizedString::from("Say Héllo");
+        let mut slice = whole.slice(Range::Original(4..10)).unwrap();
+        cache.normalize(&inner, &mut slice).unwrap();
+        assert_eq!(slice.get(), "hello");
+        assert_eq!(slice.offsets_original(), (4, 10));
+
+        // Already modified strings don't use the cache
+        whole.lowercase();
+        let hits = cache.get_hits();
+        cache.normalize(&inner, &mut whole).unwrap();
+        assert_eq!(whole.get(), "say hello");
+        assert_eq!(cache.get_hits(), hits);
+    }
+
+    #[test]
+    fn least_recently_used() {
+        let cache = NormalizationCache::new(2);
+        let normalize = |s: &str| {
+            let mut n = NormalizedString::from(s);
+            cache.normalize(&NFKC, &mut n).unwrap();
+        };
+        normalize("a");
+        normalize("b");
+        normalize("a");
+        // "b" is the least recently used, and gets evicted
+        normalize("c");
+        assert_eq!(cache.get_hits(), 1);
+        normalize("a");
+        assert_eq!(cache.get_hits(), 2);
+        normalize("b");
+        assert_eq!(cache.get_hits(), 2);
+
+        cache.clear();
+        normalize("b");
+        assert_eq!(cache.get_hits(), 2);
+    }
+
+    #[test]
+    fn repeated_inputs_hit_the_cache() {
+        let text = "Ｔhé qüick ＢＲＯＷＮ ﬁx jumps øver the lazy dög ".repeat(4);
+        let words = text.split(' ').collect::<Vec<_>>();
+        let distinct = words.iter().collect::<std::collections::HashSet<_>>().len();
+        let (inner, cache) = (inner(), NormalizationCache::new(100));
+
+        for _ in 0..50 {
+            for word in &words {
+                let mut n = NormalizedString::from(*word);
+                cache.normalize(&inner, &mut n).unwrap();
+            }
+        }
+        // Only the first occurrence of each distinct word is a miss
+        assert_eq!(cache.len(), distinct);
+        assert_eq!(cache.get_hits(), words.len() * 50 - distinct);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,198 @@
+use crate::tokenizer::{NormalizedString, Normalizer, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The built-in table, with the most common Cyrillic and Greek lookalikes of the Latin letters
+const DEFAULT_CONFUSABLES: &[(char, &str)] = &[
+    // Cyrillic
+    ('а', "a"),
+    ('е', "e"),
+    ('о', "o"),
+    ('р', "p"),
+    ('с', "c"),
+    ('у', "y"),
+    ('х', "x"),
+    ('һ', "h"),
+    ('і', "i"),
+    ('ј', "j"),
+    ('ѕ', "s"),
+    ('ԁ', "d"),
+    ('ԛ', "q"),
+    ('ԝ', "w"),
+    ('ӏ', "l"),
+    ('А', "A"),
+    ('В', "B"),
+    ('Е', "E"),
+    ('К', "K"),
+    ('М', "M"),
+    ('Н', "H"),
+    ('О', "O"),
+    ('Р', "P"),
+    ('С', "C"),
+    ('Т', "T"),
+    ('У', "Y"),
+    ('Х', "X"),
+    ('І', "I"),
+    ('Ј', "J"),
+    ('Ѕ', "S"),
+    // Greek
+    ('α', "a"),
+    ('ι', "i"),
+    ('ν', "v"),
+    ('ο', "o"),
+    ('ρ', "p"),
+    ('Α', "A"),
+    ('Β', "B"),
+    ('Ε', "E"),
+    ('Ζ', "Z"),
+    ('Η', "H"),
+    ('Ι', "I"),
+    ('Κ', "K"),
+    ('Μ', "M"),
+    ('Ν', "N"),
+    ('Ο', "O"),
+    ('Ρ', "P"),
+    ('Τ', "T"),
+    ('Υ', "Y"),
+    ('Χ', "X"),
+];
+
+lazy_static! {
+    static ref DEFAULT_TABLE: HashMap<char, &'static str> =
+        DEFAULT_CONFUSABLES.iter().copied().collect();
+}
+
+/// Replaces the characters that look like some other ones, like the Cyrillic `а`, by their
+/// skeleton, like the Latin `a`. This uses a built-in table covering the common Latin, Cyrillic
+/// and Greek confusables, on top of which some `custom` mappings can be given. A character
+/// can be mapped to several ones, or to none to remove it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub struct Confusables {
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "crate::utils::ordered_map"
+    )]
+    custom: HashMap<char, String>,
+}
+
+impl Confusables {
+    /// Use the given mappings in addition to the built-in ones, which they take precedence over
+    pub fn new(custom: HashMap<char, String>) -> Self {
+        Self { custom }
+    }
+
+    /// The custom mappings
+    pub fn get_custom(&self) -> &HashMap<char, String> {
+        &self.custom
+    }
+
+    /// The skeleton of the given char, if it has one
+    fn skeleton(&self, c: char) -> Option<&str> {
+        self.custom
+            .get(&c)
+            .map(String::as_str)
+            .or_else(|| DEFAULT_TABLE.get(&c).copied())
+    }
+}
+
+impl Normalizer for Confusables {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        let mut transforms: Vec<(char, isize)> = Vec::with_capacity(normalized.len());
+        let mut removed_start = 0;
+        for c in normalized.get().chars() {
+            let mut chars = match self.skeleton(c) {
+                Some(skeleton) => skeleton.chars(),
+                None => {
+                    transforms.push((c, 0));
+                    continue;
+                }
+            };
+            if let Some(first) = chars.next() {
+                transforms.push((first, 0));
+                transforms.extend(chars.map(|c| (c, 1)));
+            } else {
+                // This char gets removed
+                match transforms.last_mut() {
+                    Some((_, change)) => *change -= 1,
+                    None => removed_start += 1,
+                }
+            }
+        }
+        normalized.transform(transforms, removed_start);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::normalizer::Range;
+
+    fn normalize(normalizer: &Confusables, s: &str) -> NormalizedString {
+        let mut n = NormalizedString::from(s);
+        normalizer.normalize(&mut n).unwrap();
+        n
+    }
+
+    #[test]
+    fn default_table() {
+        // Cyrillic `р`, `а`, `у`, `Р`, `а`, `l` and Greek `ο`
+        let n = normalize(&Confusables::default(), "рау Раypаl gοοgle");
+        assert_eq!(n.get(), "pay Paypal google");
+        // Each 2-byte char is aligned with its replacement
+        assert_eq!(n.convert_offsets(Range::Normalized(0..3)), Some(0..6));
+        assert_eq!(n.convert_offsets(Range::Normalized(4..10)), Some(7..16));
+
+        assert_eq!(
+            normalize(&Confusables::default(), "déjà vu").get(),
+            "déjà vu"
+        );
+    }
+
+    #[test]
+    fn custom() {
+        let custom = vec![
+            ('а', "α".to_string()),
+            ('ﬁ', "fi".into()),
+            ('\u{200b}', "".into()),
+        ]
+        .into_iter()
+        .collect();
+        let normalizer = Confusables::new(custom);
+
+        let n = normalize(&normalizer, "\u{200b}а ﬁle\u{200b}е");
+        assert_eq!(n.get(), "α filee");
+        // The removed chars are not part of any offsets
+        assert_eq!(n.convert_offsets(Range::Normalized(0..2)), Some(3..5));
+        assert_eq!(n.convert_offsets(Range::Normalized(3..5)), Some(6..9));
+        assert_eq!(n.convert_offsets(Range::Normalized(5..7)), Some(9..11));
+        assert_eq!(n.convert_offsets(Range::Normalized(7..8)), Some(14..16));
+    }
+
+    #[test]
+    fn serialization() {
+        let normalizer = Confusables::default();
+        let serialized = serde_json::to_string(&normalizer).unwrap();
+        assert_eq!(serialized, r#"{"type":"Confusables"}"#);
+        assert_eq!(
+            serde_json::from_str::<Confusables>(&serialized).unwrap(),
+            normalizer
+        );
+
+        let custom = vec![('а', "b".to_string()), ('0', "o".into())]
+            .into_iter()
+            .collect();
+        let normalizer = Confusables::new(custom);
+        let serialized = serde_json::to_string(&normalizer).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"Confusables","custom":{"0":"o","а":"b"}}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<Confusables>(&serialized).unwrap(),
+            normalizer
+        );
+    }
+}
@@ -1,16 +1,26 @@
 pub mod bert;
+pub mod cached;
+pub mod confusables;
 pub mod precompiled;
 pub mod replace;
 pub mod strip;
 pub mod unicode;
 pub mod utils;
+pub mod whitespace;
 
-pub use crate::normalizers::bert::BertNormalizer;
+pub use crate::normalizers::bert::{
+    BertNormalizer, BertStripAccents, CleanText, CombiningMarks, HandleChineseChars,
+};
+pub use crate::normalizers::cached::NormalizationCache;
+pub use crate::normalizers::confusables::Confusables;
 pub use crate::normalizers::precompiled::Precompiled;
 pub use crate::normalizers::replace::Replace;
 pub use crate::normalizers::strip::{Strip, StripAccents};
-pub use crate::normalizers::unicode::{Nmt, NFC, NFD, NFKC, NFKD};
-pub use crate::normalizers::utils::{Lowercase, Sequence};
+pub use crate::normalizers::unicode::{
+    FullWidthToHalfWidth, Nmt, NormalizationForm, UnicodeNormalizer, NFC, NFD, NFKC, NFKD,
+};
+pub use crate::normalizers::utils::{Lowercase, Sequence, SequenceBuilder};
+pub use crate::normalizers::whitespace::CollapseWhitespace;
 
 use serde::{Deserialize, Serialize};
 
@@ -32,6 +42,13 @@ pub enum NormalizerWrapper {
     Nmt(Nmt),
     Precompiled(Precompiled),
     Replace(Replace),
+    CollapseWhitespace(CollapseWhitespace),
+    Unicode(UnicodeNormalizer),
+    CleanText(CleanText),
+    HandleChineseChars(HandleChineseChars),
+    BertStripAccents(BertStripAccents),
+    FullWidthToHalfWidth(FullWidthToHalfWidth),
+    Confusables(Confusables),
 }
 
 impl Normalizer for NormalizerWrapper {
@@ -49,6 +66,13 @@ impl Normalizer for NormalizerWrapper {
             Self::Nmt(lc) => lc.normalize(normalized),
             Self::Precompiled(lc) => lc.normalize(normalized),
             Self::Replace(lc) => lc.normalize(normalized),
+            Self::CollapseWhitespace(cw) => cw.normalize(normalized),
+            Self::Unicode(un) => un.normalize(normalized),
+            Self::CleanText(ct) => ct.normalize(normalized),
+            Self::HandleChineseChars(hc) => hc.normalize(normalized),
+            Self::BertStripAccents(sa) => sa.normalize(normalized),
+            Self::FullWidthToHalfWidth(fw) => fw.normalize(normalized),
+            Self::Confusables(c) => c.normalize(normalized),
         }
     }
 }
@@ -65,3 +89,14 @@ impl_enum_from!(Lowercase, NormalizerWrapper, Lowercase);
 impl_enum_from!(Nmt, NormalizerWrapper, Nmt);
 impl_enum_from!(Precompiled, NormalizerWrapper, Precompiled);
 impl_enum_from!(Replace, NormalizerWrapper, Replace);
+impl_enum_from!(CollapseWhitespace, NormalizerWrapper, CollapseWhitespace);
+impl_enum_from!(UnicodeNormalizer, NormalizerWrapper, Unicode);
+impl_enum_from!(CleanText, NormalizerWrapper, CleanText);
+impl_enum_from!(HandleChineseChars, NormalizerWrapper, HandleChineseChars);
+impl_enum_from!(BertStripAccents, NormalizerWrapper, BertStripAccents);
+impl_enum_from!(
+    FullWidthToHalfWidth,
+    NormalizerWrapper,
+    FullWidthToHalfWidth
+);
+impl_enum_from!(Confusables, NormalizerWrapper, Confusables);
@@ -1,4 +1,4 @@
-use crate::tokenizer::{NormalizedString, Normalizer, Result};
+use crate::tokenizer::{Decoder, NormalizedString, Normalizer, Result};
 use crate::utils::SysRegex;
 use serde::{Deserialize, Serialize};
 
@@ -83,6 +83,25 @@ impl Normalizer for Replace {
     }
 }
 
+impl Decoder for Replace {
+    fn decode_chain(&self, tokens: Vec<String>) -> Result<Vec<String>> {
+        Ok(tokens
+            .into_iter()
+            .map(|token| {
+                let mut new_token = String::with_capacity(token.len());
+                let mut last = 0;
+                for (start, end) in self.regex.find_iter(&token) {
+                    new_token.push_str(&token[last..start]);
+                    new_token.push_str(&self.content);
+                    last = end;
+                }
+                new_token.push_str(&token[last..]);
+                new_token
+            })
+            .collect())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -112,6 +131,15 @@ mod tests {
         assert_eq!(&n.get(), &normalized);
     }
 
+    #[test]
+    fn replace_decoder() {
+        let decoder = Replace::new("_", " ").unwrap();
+        let res = decoder
+            .decode_chain(vec!["Hey_".into(), "friend_!".into()])
+            .unwrap();
+        assert_eq!(res, vec!["Hey ", "friend !"]);
+    }
+
     #[test]
     fn serialization() {
         let replace = Replace::new("Hello", "Hey").unwrap();
@@ -1,5 +1,6 @@
 use crate::tokenizer::{NormalizedString, Normalizer, Result};
 use crate::utils::macro_rules_attribute;
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Default, Copy, Clone, Debug)]
 #[macro_rules_attribute(impl_serde_type!)]
@@ -41,6 +42,81 @@ impl Normalizer for NFKC {
     }
 }
 
+/// The Unicode normalization forms
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NormalizationForm {
+    NFC,
+    NFD,
+    NFKC,
+    NFKD,
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Unknown normalization form `{0}`, expected one of NFC, NFD, NFKC or NFKD")]
+pub struct UnknownNormalizationForm(String);
+
+impl std::str::FromStr for NormalizationForm {
+    type Err = UnknownNormalizationForm;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "NFC" => Ok(Self::NFC),
+            "NFD" => Ok(Self::NFD),
+            "NFKC" => Ok(Self::NFKC),
+            "NFKD" => Ok(Self::NFKD),
+            _ => Err(UnknownNormalizationForm(s.to_owned())),
+        }
+    }
+}
+
+/// Applies any of the Unicode normalization forms, chosen with `form`. This gives exactly the
+/// same result as the dedicated normalizer of each form.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename = "Unicode")]
+pub struct UnicodeNormalizer {
+    pub form: NormalizationForm,
+}
+
+impl UnicodeNormalizer {
+    pub fn new(form: NormalizationForm) -> Self {
+        Self { form }
+    }
+}
+
+impl<'de> Deserialize<'de> for UnicodeNormalizer {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        enum Type {
+            Unicode,
+        }
+
+        #[derive(Deserialize)]
+        struct UnicodeNormalizerHelper {
+            #[serde(rename = "type")]
+            _type: Type,
+            form: NormalizationForm,
+        }
+
+        let helper = UnicodeNormalizerHelper::deserialize(deserializer)?;
+        Ok(Self::new(helper.form))
+    }
+}
+
+impl Normalizer for UnicodeNormalizer {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        match self.form {
+            NormalizationForm::NFC => normalized.nfc(),
+            NormalizationForm::NFD => normalized.nfd(),
+            NormalizationForm::NFKC => normalized.nfkc(),
+            NormalizationForm::NFKD => normalized.nfkd(),
+        };
+        Ok(())
+    }
+}
+
 fn do_nmt(normalized: &mut NormalizedString) {
     // Ascii Control characters
     normalized
@@ -82,6 +158,23 @@ impl Normalizer for Nmt {
     }
 }
 
+/// Replaces the full-width forms of the ASCII characters (U+FF01 to U+FF5E) by their ASCII
+/// equivalent, and the ideographic space by a regular space. Unlike NFKC, nothing else is
+/// changed.
+#[derive(Default, Copy, Clone, Debug)]
+#[macro_rules_attribute(impl_serde_type!)]
+pub struct FullWidthToHalfWidth;
+impl Normalizer for FullWidthToHalfWidth {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        normalized.map(|c| match c as u32 {
+            0xFF01..=0xFF5E => char::from_u32(c as u32 - 0xFF01 + 0x21).unwrap_or(c),
+            0x3000 => ' ',
+            _ => c,
+        });
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,4 +193,83 @@ mod tests {
 
         assert_eq!(n.alignments_original(), vec![(0, 2), (0, 2), (0, 2)]);
     }
+
+    #[test]
+    fn unicode_normalizer() {
+        let original = "\u{fb01} e\u{301} \u{e9} \u{2460}";
+        let normalize = |normalizer: &dyn Normalizer| {
+            let mut n = NormalizedString::from(original);
+            normalizer.normalize(&mut n).unwrap();
+            n
+        };
+
+        let dedicated: [(NormalizationForm, &dyn Normalizer); 4] = [
+            (NormalizationForm::NFC, &NFC),
+            (NormalizationForm::NFD, &NFD),
+            (NormalizationForm::NFKC, &NFKC),
+            (NormalizationForm::NFKD, &NFKD),
+        ];
+        for (form, normalizer) in dedicated.iter() {
+            assert_eq!(
+                normalize(&UnicodeNormalizer::new(*form)),
+                normalize(*normalizer)
+            );
+        }
+    }
+
+    #[test]
+    fn full_width_to_half_width() {
+        use crate::tokenizer::normalizer::Range;
+
+        let mut n = NormalizedString::from("ＡＢＣ\u{3000}１２３！ｚ～ ＡＢＣ");
+        FullWidthToHalfWidth.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "ABC 123!z~ ABC");
+
+        // Everything else is left as is, unlike with NFKC
+        let mut n = NormalizedString::from("ｶﾀｶﾅ \u{fb01} ①");
+        FullWidthToHalfWidth.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "ｶﾀｶﾅ \u{fb01} ①");
+
+        let mut n = NormalizedString::from("a１２b");
+        FullWidthToHalfWidth.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "a12b");
+        assert_eq!(
+            n.alignments_original(),
+            vec![
+                (0, 1),
+                (1, 2),
+                (1, 2),
+                (1, 2),
+                (2, 3),
+                (2, 3),
+                (2, 3),
+                (3, 4)
+            ]
+        );
+        assert_eq!(n.convert_offsets(Range::Normalized(1..3)), Some(1..7));
+        assert_eq!(
+            serde_json::to_string(&FullWidthToHalfWidth).unwrap(),
+            r#"{"type":"FullWidthToHalfWidth"}"#
+        );
+    }
+
+    #[test]
+    fn unicode_normalizer_serialization() {
+        let normalizer = UnicodeNormalizer::new("nfkc".parse().unwrap());
+        let serialized = serde_json::to_string(&normalizer).unwrap();
+        assert_eq!(serialized, r#"{"type":"Unicode","form":"NFKC"}"#);
+        assert_eq!(
+            serde_json::from_str::<UnicodeNormalizer>(&serialized).unwrap(),
+            normalizer
+        );
+        assert!(matches!(
+            serde_json::from_str::<crate::normalizers::NormalizerWrapper>(&serialized).unwrap(),
+            crate::normalizers::NormalizerWrapper::Unicode(n) if n == normalizer
+        ));
+
+        assert!(
+            serde_json::from_str::<UnicodeNormalizer>(r#"{"type":"NFKC","form":"NFKC"}"#).is_err()
+        );
+        assert!("NFX".parse::<NormalizationForm>().is_err());
+    }
 }
@@ -17,6 +17,11 @@ impl Sequence {
         Self { normalizers }
     }
 
+    /// Build a `Sequence` step by step, with a [`SequenceBuilder`]
+    pub fn builder() -> SequenceBuilder {
+        SequenceBuilder::default()
+    }
+
     pub fn get_normalizers(&self) -> &[NormalizerWrapper] {
         &self.normalizers
     }
@@ -24,6 +29,21 @@ impl Sequence {
     pub fn get_normalizers_mut(&mut self) -> &mut [NormalizerWrapper] {
         &mut self.normalizers
     }
+
+    /// Add a normalizer at the end of the sequence
+    pub fn push(&mut self, normalizer: NormalizerWrapper) {
+        self.normalizers.push(normalizer);
+    }
+
+    /// Insert a normalizer at the given position, shifting the ones after it
+    pub fn insert(&mut self, index: usize, normalizer: NormalizerWrapper) {
+        self.normalizers.insert(index, normalizer);
+    }
+
+    /// Remove the normalizer at the given position, and return it
+    pub fn remove(&mut self, index: usize) -> NormalizerWrapper {
+        self.normalizers.remove(index)
+    }
 }
 
 impl Normalizer for Sequence {
@@ -35,6 +55,47 @@ impl Normalizer for Sequence {
     }
 }
 
+/// A builder for a [`Sequence`] of normalizers, which flattens the nested sequences:
+/// ```
+/// use tokenizers::normalizers::{Lowercase, Sequence, NFKC};
+///
+/// let normalizer = Sequence::builder().then(NFKC).then(Lowercase).build().unwrap();
+/// assert_eq!(normalizer.get_normalizers().len(), 2);
+/// ```
+#[derive(Default)]
+pub struct SequenceBuilder {
+    normalizers: Vec<NormalizerWrapper>,
+}
+
+impl SequenceBuilder {
+    /// Add a normalizer at the end of the sequence. If it is itself a `Sequence`, its
+    /// normalizers get added instead.
+    #[must_use]
+    pub fn then<N: Into<NormalizerWrapper>>(mut self, normalizer: N) -> Self {
+        self.extend(normalizer.into());
+        self
+    }
+
+    fn extend(&mut self, normalizer: NormalizerWrapper) {
+        match normalizer {
+            NormalizerWrapper::Sequence(sequence) => {
+                for normalizer in sequence.normalizers {
+                    self.extend(normalizer);
+                }
+            }
+            normalizer => self.normalizers.push(normalizer),
+        }
+    }
+
+    /// Build the `Sequence`. This fails if no normalizer was added.
+    pub fn build(self) -> Result<Sequence> {
+        if self.normalizers.is_empty() {
+            return Err("A Sequence needs at least one normalizer".into());
+        }
+        Ok(Sequence::new(self.normalizers))
+    }
+}
+
 /// Lowercases the input
 #[derive(Copy, Clone, Debug)]
 #[macro_rules_attribute(impl_serde_type!)]
@@ -45,3 +106,93 @@ impl Normalizer for Lowercase {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normalizers::NFKC;
+    use crate::Tokenizer;
+
+    #[test]
+    fn sequence_remove_lowercase() {
+        let tokenizer_s = r#"{
+            "version": "1.0",
+            "truncation": null,
+            "padding": null,
+            "added_tokens": [],
+            "normalizer": {
+                "type": "Sequence",
+                "normalizers": [{"type": "NFKC"}, {"type": "Lowercase"}]
+            },
+            "pre_tokenizer": {"type": "WhitespaceSplit"},
+            "post_processor": null,
+            "decoder": null,
+            "model": {
+                "type": "WordLevel",
+                "vocab": {"<unk>": 0, "hello": 1, "Hello": 2},
+                "unk_token": "<unk>"
+            }
+        }"#;
+        let mut tokenizer: Tokenizer = tokenizer_s.parse().unwrap();
+        assert_eq!(tokenizer.encode("Hello", false).unwrap().get_ids(), [1]);
+
+        let mut normalizer = match tokenizer.get_normalizer() {
+            Some(NormalizerWrapper::Sequence(sequence)) => sequence.clone(),
+            _ => panic!("Expected a Sequence"),
+        };
+        let position = normalizer
+            .get_normalizers()
+            .iter()
+            .position(|n| matches!(n, NormalizerWrapper::Lowercase(_)))
+            .unwrap();
+        assert!(matches!(
+            normalizer.remove(position),
+            NormalizerWrapper::Lowercase(_)
+        ));
+        assert_eq!(normalizer.get_normalizers().len(), 1);
+        tokenizer.with_normalizer(normalizer);
+        assert_eq!(tokenizer.encode("Hello", false).unwrap().get_ids(), [2]);
+
+        let mut normalizer = Sequence::new(vec![]);
+        normalizer.push(Lowercase.into());
+        normalizer.insert(0, NFKC.into());
+        assert!(matches!(
+            normalizer.get_normalizers(),
+            [NormalizerWrapper::NFKC(_), NormalizerWrapper::Lowercase(_)]
+        ));
+    }
+
+    #[test]
+    fn sequence_builder() {
+        use crate::normalizers::{Strip, NFC};
+
+        let nested = Sequence::new(vec![
+            Lowercase.into(),
+            Sequence::new(vec![Strip::new(true, true).into()]).into(),
+        ]);
+        let normalizer = Sequence::builder()
+            .then(NFC)
+            .then(nested)
+            .then(NFKC)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            normalizer.get_normalizers(),
+            [
+                NormalizerWrapper::NFC(_),
+                NormalizerWrapper::Lowercase(_),
+                NormalizerWrapper::StripNormalizer(_),
+                NormalizerWrapper::NFKC(_),
+            ]
+        ));
+        let mut normalized = NormalizedString::from("  Hé ");
+        normalizer.normalize(&mut normalized).unwrap();
+        assert_eq!(normalized.get(), "hé");
+
+        assert!(Sequence::builder().build().is_err());
+        assert!(Sequence::builder()
+            .then(Sequence::new(vec![]))
+            .build()
+            .is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use crate::tokenizer::{NormalizedString, Normalizer, Result};
+use crate::utils::macro_rules_attribute;
+
+/// Replaces each run of consecutive whitespaces (spaces, tabs, newlines, ...) by a single
+/// space. The resulting space is aligned with the whole run in the original string.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[macro_rules_attribute(impl_serde_type!)]
+pub struct CollapseWhitespace;
+
+impl Normalizer for CollapseWhitespace {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        normalized.collapse_whitespace();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normalizer::Range;
+
+    fn collapse(s: &str) -> NormalizedString {
+        let mut n = NormalizedString::from(s);
+        CollapseWhitespace.normalize(&mut n).unwrap();
+        n
+    }
+
+    #[test]
+    fn tabs() {
+        let n = collapse("Hello\t\tthere\tfriend");
+        assert_eq!(n.get(), "Hello there friend");
+        assert_eq!(n.get_range_original(Range::Normalized(5..6)), Some("\t\t"));
+        assert_eq!(
+            n.get_range_original(Range::Normalized(6..11)),
+            Some("there")
+        );
+    }
+
+    #[test]
+    fn crlf() {
+        let n = collapse("line one\r\nline two\r\n");
+        assert_eq!(n.get(), "line one line two ");
+        assert_eq!(n.get_range_original(Range::Normalized(8..9)), Some("\r\n"));
+        assert_eq!(
+            n.get_range_original(Range::Normalized(17..18)),
+            Some("\r\n")
+        );
+    }
+
+    #[test]
+    fn mixed_runs() {
+        let n = collapse(" \t a \n\u{a0}\u{2003} b  ");
+        assert_eq!(n.get(), " a b ");
+        assert_eq!(n.get_range_original(Range::Normalized(0..1)), Some(" \t "));
+        assert_eq!(
+            n.get_range_original(Range::Normalized(2..3)),
+            Some(" \n\u{a0}\u{2003} ")
+        );
+        assert_eq!(n.get_range_original(Range::Normalized(3..5)), Some("b  "));
+
+        // Nothing to do
+        let n = collapse("a b");
+        assert_eq!(n.get(), "a b");
+        assert_eq!(n.get_range_original(Range::Normalized(1..2)), Some(" "));
+    }
+
+    #[test]
+    fn serialization() {
+        let serialized = serde_json::to_string(&CollapseWhitespace).unwrap();
+        assert_eq!(serialized, r#"{"type":"CollapseWhitespace"}"#);
+        assert_eq!(
+            serde_json::from_str::<CollapseWhitespace>(&serialized).unwrap(),
+            CollapseWhitespace
+        );
+    }
+}
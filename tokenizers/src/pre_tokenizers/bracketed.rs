@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+
+use crate::pre_tokenizers::PreTokenizerWrapper;
+use crate::tokenizer::normalizer::Range;
+use crate::tokenizer::{NormalizedString, Offsets, PreTokenizedString, PreTokenizer, Result};
+use crate::utils::macro_rules_attribute;
+
+/// Keeps the spans marked between the `open` and `close` delimiters, like `[[New York]]`,
+/// as single pre-tokens, without their delimiters. The rest of the text goes through the
+/// inner `pretokenizer`.
+///
+/// An `open` delimiter without any `close` one after it is kept as part of the text. The
+/// spans are only kept whole by this pre-tokenizer, so it should come last in a `Sequence`.
+#[derive(Clone, Debug, PartialEq)]
+#[macro_rules_attribute(impl_serde_type!)]
+pub struct Bracketed {
+    open: String,
+    close: String,
+    pretokenizer: Box<PreTokenizerWrapper>,
+}
+
+impl Bracketed {
+    pub fn new<P: Into<PreTokenizerWrapper>>(open: String, close: String, pretokenizer: P) -> Self {
+        Self {
+            open,
+            close,
+            pretokenizer: Box::new(pretokenizer.into()),
+        }
+    }
+
+    /// The ranges of the text in `inside`, with whether each of them is a bracketed span.
+    /// The delimiters are not part of any range.
+    fn ranges(&self, inside: &str) -> Vec<(Offsets, bool)> {
+        let mut ranges = vec![];
+        let mut prev = 0;
+        if !self.open.is_empty() && !self.close.is_empty() {
+            while let Some(open) = inside[prev..].find(&self.open) {
+                let start = prev + open + self.open.len();
+                let end = match inside[start..].find(&self.close) {
+                    Some(close) => start + close,
+                    None => break,
+                };
+                ranges.push(((prev, prev + open), false));
+                ranges.push(((start, end), true));
+                prev = end + self.close.len();
+            }
+        }
+        ranges.push(((prev, inside.len()), false));
+        ranges.retain(|((start, end), _)| start < end);
+        ranges
+    }
+
+    fn split(&self, normalized: NormalizedString) -> Result<Vec<NormalizedString>> {
+        let mut splits = vec![];
+        for ((start, end), bracketed) in self.ranges(normalized.get()) {
+            let slice = match normalized.slice(Range::Normalized(start..end)) {
+                Some(slice) => slice,
+                None => continue,
+            };
+            if bracketed {
+                splits.push(slice);
+            } else {
+                let mut pretokenized = PreTokenizedString::from(slice);
+                self.pretokenizer.pre_tokenize(&mut pretokenized)?;
+                splits.extend(pretokenized.into_normalized_splits());
+            }
+        }
+        Ok(splits)
+    }
+}
+
+impl PreTokenizer for Bracketed {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
+        pretokenized.split(|_, normalized| self.split(normalized))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pre_tokenizers::whitespace::Whitespace;
+    use crate::{OffsetReferential, OffsetType};
+
+    fn splits(pretok: &Bracketed, s: &str) -> Vec<(String, Offsets)> {
+        let mut pretokenized = PreTokenizedString::from(s);
+        pretok.pre_tokenize(&mut pretokenized).unwrap();
+        pretokenized
+            .get_splits(OffsetReferential::Original, OffsetType::Byte)
+            .into_iter()
+            .map(|(s, o, _)| (s.to_owned(), o))
+            .collect()
+    }
+
+    #[test]
+    fn bracketed_span() {
+        let pretok = Bracketed::new("[[".into(), "]]".into(), Whitespace::default());
+        assert_eq!(
+            splits(&pretok, "visit [[New York]] today"),
+            vec![
+                ("visit".into(), (0, 5)),
+                ("New York".into(), (8, 16)),
+                ("today".into(), (19, 24)),
+            ]
+        );
+        assert_eq!(
+            splits(&pretok, "[[São Paulo]][[Rio]]!"),
+            vec![
+                ("São Paulo".into(), (2, 12)),
+                ("Rio".into(), (16, 19)),
+                ("!".into(), (21, 22)),
+            ]
+        );
+        // Without a closing delimiter, everything goes through the inner pre-tokenizer
+        assert_eq!(
+            splits(&pretok, "a [[b c"),
+            vec![
+                ("a".into(), (0, 1)),
+                ("[[".into(), (2, 4)),
+                ("b".into(), (4, 5)),
+                ("c".into(), (6, 7)),
+            ]
+        );
+        assert_eq!(splits(&pretok, "[[]] x"), vec![("x".into(), (5, 6))]);
+    }
+
+    #[test]
+    fn serialization() {
+        let pretok = Bracketed::new("<".into(), ">".into(), Whitespace::default());
+        let serialized = serde_json::to_string(&pretok).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"Bracketed","open":"<","close":">","pretokenizer":{"type":"Whitespace"}}"#
+        );
+        let wrapper: PreTokenizerWrapper = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(wrapper, PreTokenizerWrapper::Bracketed(pretok));
+    }
+}
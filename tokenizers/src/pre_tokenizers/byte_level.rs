@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 
+use crate::decoders::wordpiece;
 use crate::utils::SysRegex;
 use serde::{Deserialize, Serialize};
 
@@ -59,6 +60,10 @@ pub struct ByteLevel {
     /// Set it to False if you want to use your own splitting.
     #[serde(default = "default_true")]
     pub use_regex: bool,
+    /// Whether the decoder should cleanup some tokenization artifacts (spaces before
+    /// punctuation, ...), which is lossy and only fits English text
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cleanup: bool,
 }
 
 fn default_true() -> bool {
@@ -71,6 +76,7 @@ impl Default for ByteLevel {
             add_prefix_space: true,
             trim_offsets: true,
             use_regex: true,
+            cleanup: false,
         }
     }
 }
@@ -81,6 +87,7 @@ impl ByteLevel {
             add_prefix_space,
             trim_offsets,
             use_regex,
+            cleanup: false,
         }
     }
 
@@ -105,6 +112,12 @@ impl ByteLevel {
         self.use_regex = v;
         self
     }
+
+    #[must_use]
+    pub fn cleanup(mut self, v: bool) -> Self {
+        self.cleanup = v;
+        self
+    }
 }
 
 /// As a `PreTokenizer`, `ByteLevel` is in charge of transforming all the unicode characters into
@@ -164,7 +177,12 @@ impl Decoder for ByteLevel {
                     .unwrap_or_else(|| t.as_bytes().to_vec())
             })
             .collect::<Vec<u8>>();
-        Ok(vec![String::from_utf8_lossy(&toks).to_string()])
+        let decoded = String::from_utf8_lossy(&toks);
+        if self.cleanup {
+            Ok(vec![wordpiece::cleanup(&decoded)])
+        } else {
+            Ok(vec![decoded.to_string()])
+        }
     }
 }
 
@@ -181,11 +199,7 @@ impl PostProcessor for ByteLevel {
     ) -> Result<Vec<Encoding>> {
         if self.trim_offsets {
             for encoding in encodings.iter_mut() {
-                process_offsets(encoding, self.add_prefix_space);
-                encoding
-                    .get_overflowing_mut()
-                    .iter_mut()
-                    .for_each(|encoding| process_offsets(encoding, self.add_prefix_space));
+                process_all_offsets(encoding, self.add_prefix_space);
             }
         }
         for (i, encoding) in encodings.iter_mut().enumerate() {
@@ -197,7 +211,24 @@ impl PostProcessor for ByteLevel {
 }
 
 pub fn process_offsets(encoding: &mut Encoding, add_prefix_space: bool) {
-    encoding.process_tokens_with_offsets_mut(|(i, (token, mut offsets))| {
+    trim_offsets(encoding, add_prefix_space, false)
+}
+
+/// Trim the offsets of the given encoding and of all its overflowing encodings
+pub(crate) fn process_all_offsets(encoding: &mut Encoding, add_prefix_space: bool) {
+    trim_offsets(encoding, add_prefix_space, false);
+    encoding
+        .get_overflowing_mut()
+        .iter_mut()
+        .for_each(|encoding| trim_offsets(encoding, add_prefix_space, true));
+}
+
+/// Remove the leading and trailing spaces from the offsets of each token. When the first
+/// token might start with a space added by `add_prefix_space`, this single space is kept since
+/// it is aligned with the first character of the word. An overflowing part starts in the
+/// middle of the sequence though, so its first token is trimmed like any other.
+fn trim_offsets(encoding: &mut Encoding, add_prefix_space: bool, overflowing: bool) {
+    encoding.process_tokens_with_offsets_mut(|(i, (token, offsets))| {
         let mut leading_spaces = token
             .chars()
             .take_while(|c| *c == BYTES_CHAR[&b' '] || c.is_whitespace())
@@ -213,7 +244,7 @@ pub fn process_offsets(encoding: &mut Encoding, add_prefix_space: bool) {
                 // If user uses `is_pretokenized=True` we might have
                 // offsets that might begin at the start of the string but are
                 // NOT the first token.
-                let is_first = i == 0 || offsets.0 == 0;
+                let is_first = (i == 0 && !overflowing) || offsets.0 == 0;
                 if is_first && add_prefix_space && leading_spaces == 1 {
                     // If we are processing the first pair of offsets, with `add_prefix_space`,
                     // then we shouldn't remove anything we added. If there are more than one
@@ -586,5 +617,104 @@ mod tests {
         )
         .unwrap();
         assert!(!byte_level.use_regex);
+        assert!(!byte_level.cleanup);
+    }
+
+    #[test]
+    fn decode_cleanup() {
+        let tokens: Vec<String> = ["Hello", "Ġ.", "Ġworld", "Ġ,", "Ġdon", "Ġ't", "Ġit", "Ġ's"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        let byte_level = ByteLevel::default();
+        assert_eq!(
+            byte_level.decode(tokens.clone()).unwrap(),
+            "Hello . world , don 't it 's"
+        );
+        let byte_level = byte_level.cleanup(true);
+        assert_eq!(
+            byte_level.decode(tokens).unwrap(),
+            "Hello. world, don 't it's"
+        );
+
+        let serialized = serde_json::to_string(&byte_level).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"ByteLevel","add_prefix_space":true,"trim_offsets":true,"use_regex":true,"cleanup":true}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<ByteLevel>(&serialized).unwrap(),
+            byte_level
+        );
+    }
+
+    #[test]
+    fn tokenizer_trims_offsets() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::tokenizer::{AddedToken, Tokenizer, TruncationParams};
+        use std::str::FromStr;
+
+        let vocab = ["[UNK]", "hello", "Ġhello", "Ġworld", "Ġ", "Ġfoo"]
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (t.to_string(), i as u32))
+            .collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let tokenizer = |byte_level: ByteLevel| {
+            let mut tokenizer = Tokenizer::new(model.clone());
+            tokenizer.with_pre_tokenizer(byte_level);
+            tokenizer.with_post_processor(byte_level);
+            tokenizer
+                .add_special_tokens(&[AddedToken::from("<s>", true)])
+                .unwrap();
+            // Round-trip through JSON
+            Tokenizer::from_str(&tokenizer.to_string(false).unwrap()).unwrap()
+        };
+        let offsets = |tokenizer: &Tokenizer, text: &str| {
+            let encoding = tokenizer.encode(text, false).unwrap();
+            (
+                encoding.get_tokens().to_vec(),
+                encoding.get_offsets().to_vec(),
+            )
+        };
+
+        let trimmed = tokenizer(ByteLevel::default().add_prefix_space(false));
+        assert_eq!(
+            offsets(&trimmed, "hello world  foo"),
+            (
+                vec!["hello".into(), "Ġworld".into(), "Ġ".into(), "Ġfoo".into()],
+                vec![(0, 5), (6, 11), (12, 12), (13, 16)]
+            )
+        );
+        let untrimmed = tokenizer(ByteLevel::new(false, false, true));
+        assert_eq!(
+            offsets(&untrimmed, "hello world  foo").1,
+            vec![(0, 5), (5, 11), (11, 12), (12, 16)]
+        );
+
+        // The added prefix space is aligned with the first word, so it stays
+        let mut prefixed = tokenizer(ByteLevel::default());
+        assert_eq!(
+            offsets(&prefixed, "hello world"),
+            (
+                vec!["Ġhello".into(), "Ġworld".into()],
+                vec![(0, 5), (6, 11)]
+            )
+        );
+        assert_eq!(
+            offsets(&prefixed, "<s> hello world").1,
+            vec![(0, 3), (4, 9), (10, 15)]
+        );
+        // The first token of an overflowing part gets trimmed too
+        prefixed.with_truncation(Some(TruncationParams {
+            max_length: 1,
+            ..Default::default()
+        }));
+        let encoding = prefixed.encode("hello world", false).unwrap();
+        assert_eq!(encoding.get_overflowing()[0].get_offsets(), &[(6, 11)]);
     }
 }
@@ -0,0 +1,275 @@
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::tokenizer::{
+    pattern::Pattern, Encoding, Offsets, PreTokenizedString, PreTokenizer, Result,
+    SplitDelimiterBehavior,
+};
+
+/// Splits on the capture groups of a regex: each group of each match becomes a pre-token,
+/// and the gaps between them (including the parts of a match outside of any group) are
+/// either kept as they are, to be split by the following pre-tokenizers, or dropped.
+///
+/// The groups don't overlap: a group starting inside a previous one is ignored, so only
+/// the outermost of some nested groups is used. The name of the group of each token can be
+/// recovered with [`CaptureSplit::token_groups`].
+#[derive(Debug, Serialize)]
+#[serde(tag = "type")]
+pub struct CaptureSplit {
+    pattern: String,
+    #[serde(skip)]
+    regex: Regex,
+    keep_gaps: bool,
+}
+
+impl<'de> Deserialize<'de> for CaptureSplit {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        enum Type {
+            CaptureSplit,
+        }
+
+        #[derive(Deserialize)]
+        pub struct CaptureSplitHelper {
+            #[serde(rename = "type")]
+            _type: Type,
+            pattern: String,
+            keep_gaps: bool,
+        }
+
+        let helper = CaptureSplitHelper::deserialize(deserializer)?;
+        Self::new(&helper.pattern, helper.keep_gaps).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Clone for CaptureSplit {
+    fn clone(&self) -> Self {
+        Self {
+            pattern: self.pattern.clone(),
+            regex: self.regex.clone(),
+            keep_gaps: self.keep_gaps,
+        }
+    }
+}
+
+impl PartialEq for CaptureSplit {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern && self.keep_gaps == other.keep_gaps
+    }
+}
+
+impl CaptureSplit {
+    pub fn new(pattern: &str, keep_gaps: bool) -> Result<Self> {
+        Ok(Self {
+            pattern: pattern.to_owned(),
+            regex: Regex::new(pattern)?,
+            keep_gaps,
+        })
+    }
+
+    /// The offsets of the groups matched in `inside`, with their name if they have one
+    fn groups<'s>(&'s self, inside: &str) -> Vec<(Offsets, Option<&'s str>)> {
+        let names = self.regex.capture_names().collect::<Vec<_>>();
+        let mut groups = vec![];
+        let mut end = 0;
+        for captures in self.regex.captures_iter(inside) {
+            for (group, name) in captures.iter().zip(&names).skip(1) {
+                if let Some(m) = group {
+                    if m.start() >= end && !m.as_str().is_empty() {
+                        groups.push(((m.start(), m.end()), *name));
+                        end = m.end();
+                    }
+                }
+            }
+        }
+        groups
+    }
+
+    /// Find the name of the group each token of the given encoding comes from, or `None`
+    /// for the tokens coming from a gap, an unnamed group or a special token.
+    ///
+    /// The groups are matched against `sequence`, which must be the original input of this
+    /// encoding, so this is reliable only for an encoding of a single sequence, whose
+    /// normalization doesn't change what this regex matches.
+    pub fn token_groups(&self, sequence: &str, encoding: &Encoding) -> Vec<Option<String>> {
+        let groups = self.groups(sequence);
+        encoding
+            .get_offsets()
+            .iter()
+            .zip(encoding.get_special_tokens_mask())
+            .map(|(&(start, end), &special)| {
+                if special == 1 || start == end {
+                    return None;
+                }
+                groups
+                    .iter()
+                    .find(|((g_start, g_end), _)| *g_start <= start && end <= *g_end)
+                    .and_then(|(_, name)| name.map(str::to_owned))
+            })
+            .collect()
+    }
+}
+
+/// Matches the gaps between the groups of a `CaptureSplit`
+struct Gaps<'a>(&'a CaptureSplit);
+
+impl Pattern for Gaps<'_> {
+    fn find_matches(&self, inside: &str) -> Result<Vec<(Offsets, bool)>> {
+        let mut splits = vec![];
+        let mut prev = 0;
+        for ((start, end), _) in self.0.groups(inside) {
+            if prev != start {
+                splits.push(((prev, start), true));
+            }
+            splits.push(((start, end), false));
+            prev = end;
+        }
+        if prev != inside.len() || splits.is_empty() {
+            splits.push(((prev, inside.len()), true));
+        }
+        Ok(splits)
+    }
+}
+
+impl PreTokenizer for CaptureSplit {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
+        let behavior = if self.keep_gaps {
+            SplitDelimiterBehavior::Isolated
+        } else {
+            SplitDelimiterBehavior::Removed
+        };
+        pretokenized.split(|_, normalized| normalized.split(Gaps(self), behavior))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::wordlevel::WordLevel;
+    use crate::pre_tokenizers::sequence::Sequence;
+    use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+    use crate::{OffsetReferential, OffsetType, Tokenizer};
+    use std::collections::HashMap;
+
+    const PATTERN: &str = r"(?P<date>\d{4}-\d{2}-\d{2}) \[(?P<level>[A-Z]+)\] (\w+):";
+
+    fn splits(pretok: &CaptureSplit, s: &str) -> Vec<(String, Offsets)> {
+        let mut pretokenized = PreTokenizedString::from(s);
+        pretok.pre_tokenize(&mut pretokenized).unwrap();
+        pretokenized
+            .get_splits(OffsetReferential::Original, OffsetType::Byte)
+            .into_iter()
+            .map(|(s, o, _)| (s.to_owned(), o))
+            .collect()
+    }
+
+    #[test]
+    fn drop_gaps() {
+        let pretok = CaptureSplit::new(PATTERN, false).unwrap();
+        assert_eq!(
+            splits(&pretok, "2023-01-02 [WARN] disk: almost full"),
+            vec![
+                ("2023-01-02".into(), (0, 10)),
+                ("WARN".into(), (12, 16)),
+                ("disk".into(), (18, 22)),
+            ]
+        );
+        assert!(splits(&pretok, "no match here").is_empty());
+    }
+
+    #[test]
+    fn keep_gaps() {
+        let pretok = CaptureSplit::new(PATTERN, true).unwrap();
+        assert_eq!(
+            splits(&pretok, "> 2023-01-02 [INFO] db: ok"),
+            vec![
+                ("> ".into(), (0, 2)),
+                ("2023-01-02".into(), (2, 12)),
+                (" [".into(), (12, 14)),
+                ("INFO".into(), (14, 18)),
+                ("] ".into(), (18, 20)),
+                ("db".into(), (20, 22)),
+                (": ok".into(), (22, 26)),
+            ]
+        );
+        assert_eq!(
+            splits(&pretok, "no match here"),
+            vec![("no match here".into(), (0, 13))]
+        );
+
+        // Nested groups only give the outermost one
+        let pretok = CaptureSplit::new(r"(a(b))c", true).unwrap();
+        assert_eq!(
+            splits(&pretok, "abcab"),
+            vec![("ab".into(), (0, 2)), ("cab".into(), (2, 5))]
+        );
+    }
+
+    #[test]
+    fn token_groups() {
+        let vocab = ["[UNK]", "2023-01-02", "WARN", "disk", "almost", "full"]
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (t.to_string(), i as u32))
+            .collect::<HashMap<_, _>>();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let pretok = CaptureSplit::new(PATTERN, true).unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(Sequence::new(vec![
+            pretok.clone().into(),
+            WhitespaceSplit::default().into(),
+        ]));
+
+        let input = "2023-01-02 [WARN] disk: almost full";
+        let encoding = tokenizer.encode(input, false).unwrap();
+        assert_eq!(
+            encoding.get_tokens(),
+            [
+                "2023-01-02",
+                "[UNK]",
+                "WARN",
+                "[UNK]",
+                "disk",
+                "[UNK]",
+                "almost",
+                "full"
+            ]
+        );
+        // The unnamed group of "disk" has no name to give
+        let mut expected = vec![None; 8];
+        expected[0] = Some("date".to_owned());
+        expected[2] = Some("level".to_owned());
+        assert_eq!(pretok.token_groups(input, &encoding), expected);
+    }
+
+    #[test]
+    fn serialization() {
+        let pretok = CaptureSplit::new(PATTERN, true).unwrap();
+        let serialized = serde_json::to_string(&pretok).unwrap();
+        assert_eq!(
+            serialized,
+            format!(
+                r#"{{"type":"CaptureSplit","pattern":{},"keep_gaps":true}}"#,
+                serde_json::to_string(PATTERN).unwrap()
+            )
+        );
+        let deserialized: CaptureSplit = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, pretok);
+        assert_eq!(
+            splits(&deserialized, "2023-01-02 [WARN] disk:"),
+            splits(&pretok, "2023-01-02 [WARN] disk:")
+        );
+
+        assert!(serde_json::from_str::<CaptureSplit>(
+            r#"{"type":"CaptureSplit","pattern":"(","keep_gaps":true}"#
+        )
+        .is_err());
+    }
+}
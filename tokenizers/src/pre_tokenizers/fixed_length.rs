@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+
+use crate::tokenizer::{normalizer::Range, PreTokenizedString, PreTokenizer, Result};
+use crate::utils::macro_rules_attribute;
+
+/// Splits the input in chunks of `length` bytes, the last one holding what remains. This is
+/// meant for byte-level models, and should be followed by `ByteLevel` (without its regex) to
+/// map each chunk to its bytes.
+///
+/// A char can't be split in the middle of the normalized string, so a chunk ending in the
+/// middle of a multi-byte char goes on until the end of it. A `length` of 0 keeps the input
+/// as it is.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[macro_rules_attribute(impl_serde_type!)]
+pub struct FixedLength {
+    pub length: usize,
+}
+
+impl FixedLength {
+    pub fn new(length: usize) -> Self {
+        Self { length }
+    }
+}
+
+impl PreTokenizer for FixedLength {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
+        if self.length == 0 {
+            return Ok(());
+        }
+
+        pretokenized.split(|_, normalized| {
+            let s = normalized.get();
+            let mut chunks = vec![];
+            let mut start = 0;
+            while start < s.len() {
+                let mut end = (start + self.length).min(s.len());
+                while !s.is_char_boundary(end) {
+                    end += 1;
+                }
+                chunks.push(
+                    normalized
+                        .slice(Range::Normalized(start..end))
+                        .expect("NormalizedString bad split"),
+                );
+                start = end;
+            }
+            Ok(chunks)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pre_tokenizers::byte_level::ByteLevel;
+    use crate::pre_tokenizers::sequence::Sequence;
+    use crate::pre_tokenizers::PreTokenizerWrapper;
+    use crate::{OffsetReferential, OffsetType};
+
+    fn splits(pretok: &dyn PreTokenizer, s: &str) -> Vec<(String, (usize, usize))> {
+        let mut pretokenized = PreTokenizedString::from(s);
+        pretok.pre_tokenize(&mut pretokenized).unwrap();
+        pretokenized
+            .get_splits(OffsetReferential::Original, OffsetType::Byte)
+            .into_iter()
+            .map(|(s, o, _)| (s.to_owned(), o))
+            .collect()
+    }
+
+    #[test]
+    fn chunks() {
+        let pretok = FixedLength::new(3);
+        assert_eq!(
+            splits(&pretok, "Hello there"),
+            vec![
+                ("Hel".into(), (0, 3)),
+                ("lo ".into(), (3, 6)),
+                ("the".into(), (6, 9)),
+                ("re".into(), (9, 11)),
+            ]
+        );
+        assert!(splits(&pretok, "").is_empty());
+        assert_eq!(
+            splits(&FixedLength::new(0), "Hello"),
+            vec![("Hello".into(), (0, 5))]
+        );
+    }
+
+    #[test]
+    fn multi_byte_chars() {
+        // "é" and "ü" are 2 bytes, "€" is 3 bytes
+        let pretok = FixedLength::new(2);
+        assert_eq!(
+            splits(&pretok, "aébü€c"),
+            vec![
+                ("aé".into(), (0, 3)),
+                ("bü".into(), (3, 6)),
+                ("€".into(), (6, 9)),
+                ("c".into(), (9, 10)),
+            ]
+        );
+        assert_eq!(
+            splits(&FixedLength::new(4), "aébü€c"),
+            vec![
+                ("aéb".into(), (0, 4)),
+                ("ü€".into(), (4, 9)),
+                ("c".into(), (9, 10))
+            ]
+        );
+    }
+
+    #[test]
+    fn with_byte_level() {
+        let pretok = Sequence::new(vec![
+            FixedLength::new(4).into(),
+            ByteLevel::default()
+                .add_prefix_space(false)
+                .use_regex(false)
+                .into(),
+        ]);
+        assert_eq!(
+            splits(&pretok, "Hé wörld"),
+            vec![
+                ("HÃ©Ġ".into(), (0, 4)),
+                ("wÃ¶r".into(), (4, 8)),
+                ("ld".into(), (8, 10)),
+            ]
+        );
+    }
+
+    #[test]
+    fn serialization() {
+        let pretok = FixedLength::new(8);
+        let pretok_s = r#"{"type":"FixedLength","length":8}"#;
+        assert_eq!(serde_json::to_string(&pretok).unwrap(), pretok_s);
+        let wrapper: PreTokenizerWrapper = serde_json::from_str(pretok_s).unwrap();
+        assert_eq!(wrapper, PreTokenizerWrapper::FixedLength(pretok));
+    }
+}
@@ -1,5 +1,8 @@
 use serde::{Deserialize, Deserializer, Serialize};
+use std::borrow::Cow;
 
+use crate::decoders::wordpiece;
+use crate::pre_tokenizers::whitespace::WhitespaceDefinition;
 use crate::tokenizer::{Decoder, PreTokenizedString, PreTokenizer, Result, SplitDelimiterBehavior};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Eq)]
@@ -9,6 +12,21 @@ use crate::tokenizer::{Decoder, PreTokenizedString, PreTokenizer, Result, SplitD
 pub struct Metaspace {
     replacement: char,
     pub add_prefix_space: bool,
+    /// Whether the decoder should cleanup some tokenization artifacts (spaces before
+    /// punctuation, ...)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub cleanup: bool,
+    /// Whether to replace each run of consecutive spaces by a single meta character,
+    /// instead of one per space
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub collapse_whitespace: bool,
+    /// Which characters get replaced by the meta character. By default, only the spaces
+    /// do.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub whitespace: Option<WhitespaceDefinition>,
+    /// The string the decoder puts in place of each meta character, `" "` by default
+    #[serde(skip_serializing_if = "wordpiece::is_default_word_separator")]
+    pub word_separator: String,
     #[serde(skip)]
     str_rep: String,
 }
@@ -29,12 +47,25 @@ impl<'de> Deserialize<'de> for Metaspace {
             _type: Type,
             replacement: char,
             pub add_prefix_space: bool,
+            #[serde(default)]
+            cleanup: bool,
+            #[serde(default)]
+            collapse_whitespace: bool,
+            #[serde(default)]
+            whitespace: Option<WhitespaceDefinition>,
+            #[serde(default = "wordpiece::default_word_separator")]
+            word_separator: String,
             #[serde(skip, rename = "str_rep")]
             _str_rep: String,
         }
 
         let helper = MetaspaceHelper::deserialize(deserializer)?;
-        Ok(Self::new(helper.replacement, helper.add_prefix_space))
+        let mut metaspace = Self::new(helper.replacement, helper.add_prefix_space);
+        metaspace.cleanup = helper.cleanup;
+        metaspace.collapse_whitespace = helper.collapse_whitespace;
+        metaspace.whitespace = helper.whitespace;
+        metaspace.word_separator = helper.word_separator;
+        Ok(metaspace)
     }
 }
 
@@ -44,6 +75,10 @@ impl Metaspace {
             replacement,
             str_rep: replacement.to_string(),
             add_prefix_space,
+            cleanup: false,
+            collapse_whitespace: false,
+            whitespace: None,
+            word_separator: wordpiece::default_word_separator(),
         }
     }
 
@@ -55,6 +90,14 @@ impl Metaspace {
         self.replacement = replacement;
         self.str_rep = replacement.to_string();
     }
+
+    /// Whether this char gets replaced by the meta character
+    fn is_space(&self, c: char) -> bool {
+        match self.whitespace {
+            Some(whitespace) => whitespace.is_whitespace(c),
+            None => c == ' ',
+        }
+    }
 }
 
 impl Default for Metaspace {
@@ -66,7 +109,26 @@ impl Default for Metaspace {
 impl PreTokenizer for Metaspace {
     fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
         pretokenized.split(|_, mut normalized| {
-            normalized.replace(' ', &self.str_rep)?;
+            if self.collapse_whitespace {
+                // Each run of spaces becomes a single meta character, followed by the removed
+                // spaces, so that its offsets start with the run
+                let mut in_run = false;
+                let mut transformations: Vec<(char, isize)> = vec![];
+                for c in normalized.get().chars() {
+                    let is_space = self.is_space(c);
+                    if !is_space {
+                        transformations.push((c, 0));
+                    } else if !in_run {
+                        transformations.push((self.replacement, 0));
+                    } else if let Some((_, change)) = transformations.last_mut() {
+                        *change -= 1;
+                    }
+                    in_run = is_space;
+                }
+                normalized.transform(transformations, 0);
+            } else {
+                normalized.replace(|c| self.is_space(c), &self.str_rep)?;
+            }
             if self.add_prefix_space && !normalized.get().starts_with(self.replacement) {
                 normalized.prepend(&self.str_rep);
             }
@@ -78,26 +140,54 @@ impl PreTokenizer for Metaspace {
 
 impl Decoder for Metaspace {
     fn decode_chain(&self, tokens: Vec<String>) -> Result<Vec<String>> {
-        Ok(tokens
+        let tokens = tokens
             .iter()
             .enumerate()
             .map(|(i, token)| {
-                token
-                    .chars()
-                    .flat_map(|c| {
-                        if c == self.replacement {
-                            if i == 0 && self.add_prefix_space {
-                                None
-                            } else {
-                                Some(' ')
-                            }
-                        } else {
-                            Some(c)
-                        }
-                    })
-                    .collect::<String>()
+                let mut decoded = String::with_capacity(token.len());
+                for c in token.chars() {
+                    if c != self.replacement {
+                        decoded.push(c);
+                    } else if i > 0 || !self.add_prefix_space {
+                        decoded.push_str(&self.word_separator);
+                    }
+                }
+                decoded
             })
-            .collect())
+            .collect::<Vec<_>>();
+
+        if self.cleanup {
+            // A space and the punctuation after it can be in different tokens, so we need
+            // to see them all at once
+            Ok(vec![wordpiece::cleanup(&tokens.concat())])
+        } else {
+            Ok(tokens)
+        }
+    }
+
+    fn decode_into<'v>(
+        &self,
+        ids: &[u32],
+        vocab_lookup: &dyn Fn(u32) -> Option<Cow<'v, str>>,
+        out: &mut String,
+    ) -> Result<()> {
+        let start = out.len();
+        let tokens = ids.iter().filter_map(|id| vocab_lookup(*id));
+        for (i, token) in tokens.enumerate() {
+            for c in token.chars() {
+                if c != self.replacement {
+                    out.push(c);
+                } else if i > 0 || !self.add_prefix_space {
+                    out.push_str(&self.word_separator);
+                }
+            }
+        }
+        if self.cleanup {
+            let cleaned = wordpiece::cleanup(&out[start..]);
+            out.truncate(start);
+            out.push_str(&cleaned);
+        }
+        Ok(())
     }
 }
 
@@ -108,6 +198,15 @@ mod tests {
 
     #[test]
     fn serialization() {
+        let mut metaspace = Metaspace::new('_', true);
+        metaspace.collapse_whitespace = true;
+        let metaspace_s = r#"{"type":"Metaspace","replacement":"_","add_prefix_space":true,"collapse_whitespace":true}"#;
+        assert_eq!(serde_json::to_string(&metaspace).unwrap(), metaspace_s);
+        assert_eq!(
+            serde_json::from_str::<Metaspace>(metaspace_s).unwrap(),
+            metaspace
+        );
+
         let metaspace = Metaspace::new('_', true);
         let metaspace_s = r#"{"type":"Metaspace","replacement":"_","add_prefix_space":true}"#;
         assert_eq!(serde_json::to_string(&metaspace).unwrap(), metaspace_s);
@@ -188,6 +287,99 @@ mod tests {
         );
     }
 
+    #[test]
+    fn collapse_whitespace() {
+        let get_splits = |pretok: &Metaspace, s: &str| {
+            let mut pretokenized = PreTokenizedString::from(s);
+            pretok.pre_tokenize(&mut pretokenized).unwrap();
+            pretokenized
+                .get_splits(OffsetReferential::Original, OffsetType::Byte)
+                .into_iter()
+                .map(|(s, o, _)| (s.to_owned(), o))
+                .collect::<Vec<_>>()
+        };
+
+        let mut pretok = Metaspace::new('▁', true);
+        pretok.collapse_whitespace = true;
+        // The offsets cover the whole run of spaces
+        assert_eq!(
+            get_splits(&pretok, "   Hey    friend"),
+            vec![("▁Hey".into(), (0, 6)), ("▁friend".into(), (6, 16))]
+        );
+        // The prefix space is only added when missing
+        assert_eq!(
+            get_splits(&pretok, "Hey  friend"),
+            vec![("▁Hey".into(), (0, 3)), ("▁friend".into(), (3, 11))]
+        );
+
+        pretok.add_prefix_space = false;
+        assert_eq!(
+            get_splits(&pretok, "Hey    friend"),
+            vec![("Hey".into(), (0, 3)), ("▁friend".into(), (3, 13))]
+        );
+
+        // Without it, each space gets its own meta character
+        pretok.collapse_whitespace = false;
+        assert_eq!(
+            get_splits(&pretok, "Hey  friend"),
+            vec![
+                ("Hey".into(), (0, 3)),
+                ("▁".into(), (3, 4)),
+                ("▁friend".into(), (4, 11))
+            ]
+        );
+    }
+
+    #[test]
+    fn whitespace_definition() {
+        let get_splits = |pretok: &Metaspace, s: &str| {
+            let mut pretokenized = PreTokenizedString::from(s);
+            pretok.pre_tokenize(&mut pretokenized).unwrap();
+            pretokenized
+                .get_splits(OffsetReferential::Original, OffsetType::Byte)
+                .into_iter()
+                .map(|(s, o, _)| (s.to_owned(), o))
+                .collect::<Vec<_>>()
+        };
+        // The no-break space takes 2 bytes
+        let input = "Hey\u{a0}friend\tok";
+
+        let mut pretok = Metaspace::new('▁', false);
+        assert_eq!(
+            get_splits(&pretok, input),
+            vec![("Hey\u{a0}friend\tok".into(), (0, 14))]
+        );
+        pretok.whitespace = Some(WhitespaceDefinition::Ascii);
+        assert_eq!(
+            get_splits(&pretok, input),
+            vec![
+                ("Hey\u{a0}friend".into(), (0, 11)),
+                ("▁ok".into(), (11, 14))
+            ]
+        );
+        pretok.whitespace = Some(WhitespaceDefinition::Unicode);
+        assert_eq!(
+            get_splits(&pretok, input),
+            vec![
+                ("Hey".into(), (0, 3)),
+                ("▁friend".into(), (3, 11)),
+                ("▁ok".into(), (11, 14))
+            ]
+        );
+        pretok.collapse_whitespace = true;
+        assert_eq!(
+            get_splits(&pretok, "Hey\u{a0} friend"),
+            vec![("Hey".into(), (0, 3)), ("▁friend".into(), (3, 12))]
+        );
+
+        let metaspace_s = r#"{"type":"Metaspace","replacement":"▁","add_prefix_space":false,"collapse_whitespace":true,"whitespace":"Unicode"}"#;
+        assert_eq!(serde_json::to_string(&pretok).unwrap(), metaspace_s);
+        assert_eq!(
+            serde_json::from_str::<Metaspace>(metaspace_s).unwrap(),
+            pretok
+        );
+    }
+
     #[test]
     fn decode() {
         let decoder = Metaspace::new('▁', true);
@@ -196,4 +388,77 @@ mod tests {
             .unwrap();
         assert_eq!(res, vec!["Hey", " friend!"])
     }
+
+    #[test]
+    fn decode_cleanup() {
+        let tokens = vec!["▁obj".to_string(), "▁.".to_string(), "▁field".to_string()];
+
+        let mut decoder = Metaspace::new('▁', true);
+        assert_eq!(decoder.decode(tokens.clone()).unwrap(), "obj . field");
+
+        decoder.cleanup = true;
+        assert_eq!(decoder.decode(tokens).unwrap(), "obj. field");
+        // Even when the space and the punctuation are in different tokens
+        let tokens = ["▁it", "▁", "'s", "▁here", "▁", "!"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(decoder.decode(tokens).unwrap(), "it's here!");
+
+        let metaspace_s =
+            r#"{"type":"Metaspace","replacement":"▁","add_prefix_space":true,"cleanup":true}"#;
+        assert_eq!(serde_json::to_string(&decoder).unwrap(), metaspace_s);
+        assert_eq!(
+            serde_json::from_str::<Metaspace>(metaspace_s).unwrap(),
+            decoder
+        );
+    }
+
+    #[test]
+    fn decode_into() {
+        let vocab = ["▁obj", "▁.", "▁field", "a▁b", "▁", "!"];
+        let vocab_lookup = |id: u32| vocab.get(id as usize).map(|t| Cow::Borrowed(*t));
+        // The unknown ids are skipped
+        let ids = [9, 0, 1, 9, 2, 3, 4, 5];
+        let tokens = ids
+            .iter()
+            .filter_map(|id| vocab.get(*id as usize).map(|t| t.to_string()))
+            .collect::<Vec<_>>();
+
+        for (add_prefix_space, cleanup) in [(true, false), (false, false), (true, true)] {
+            let mut decoder = Metaspace::new('▁', add_prefix_space);
+            decoder.cleanup = cleanup;
+            let mut out = String::from(">");
+            decoder.decode_into(&ids, &vocab_lookup, &mut out).unwrap();
+            assert_eq!(out, format!(">{}", decoder.decode(tokens.clone()).unwrap()));
+        }
+    }
+
+    #[test]
+    fn word_separator() {
+        let tokens = ["▁Hey", "▁fri", "end", "▁!"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>();
+        let mut decoder = Metaspace::new('▁', true);
+        decoder.word_separator = "".into();
+        assert_eq!(decoder.decode(tokens.clone()).unwrap(), "Heyfriend!");
+        decoder.word_separator = " | ".into();
+        assert_eq!(decoder.decode(tokens).unwrap(), "Hey | friend | !");
+
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"Metaspace","replacement":"▁","add_prefix_space":true,"word_separator":" | "}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<Metaspace>(&serialized).unwrap(),
+            decoder
+        );
+        let deserialized: Metaspace = serde_json::from_str(
+            r#"{"type":"Metaspace","replacement":"▁","add_prefix_space":true}"#,
+        )
+        .unwrap();
+        assert_eq!(deserialized.word_separator, " ");
+    }
 }
@@ -1,7 +1,10 @@
 pub mod bert;
+pub mod bracketed;
 pub mod byte_level;
+pub mod capture_split;
 pub mod delimiter;
 pub mod digits;
+pub mod fixed_length;
 pub mod metaspace;
 pub mod punctuation;
 pub mod sequence;
@@ -12,9 +15,12 @@ pub mod whitespace;
 use serde::{Deserialize, Serialize};
 
 use crate::pre_tokenizers::bert::BertPreTokenizer;
+use crate::pre_tokenizers::bracketed::Bracketed;
 use crate::pre_tokenizers::byte_level::ByteLevel;
+use crate::pre_tokenizers::capture_split::CaptureSplit;
 use crate::pre_tokenizers::delimiter::CharDelimiterSplit;
 use crate::pre_tokenizers::digits::Digits;
+use crate::pre_tokenizers::fixed_length::FixedLength;
 use crate::pre_tokenizers::metaspace::Metaspace;
 use crate::pre_tokenizers::punctuation::Punctuation;
 use crate::pre_tokenizers::sequence::Sequence;
@@ -37,6 +43,9 @@ pub enum PreTokenizerWrapper {
     WhitespaceSplit(WhitespaceSplit),
     Digits(Digits),
     UnicodeScripts(UnicodeScripts),
+    FixedLength(FixedLength),
+    CaptureSplit(CaptureSplit),
+    Bracketed(Bracketed),
 }
 
 impl PreTokenizer for PreTokenizerWrapper {
@@ -53,6 +62,9 @@ impl PreTokenizer for PreTokenizerWrapper {
             Self::WhitespaceSplit(wspt) => wspt.pre_tokenize(normalized),
             Self::Digits(wspt) => wspt.pre_tokenize(normalized),
             Self::UnicodeScripts(us) => us.pre_tokenize(normalized),
+            Self::FixedLength(fl) => fl.pre_tokenize(normalized),
+            Self::CaptureSplit(cs) => cs.pre_tokenize(normalized),
+            Self::Bracketed(b) => b.pre_tokenize(normalized),
         }
     }
 }
@@ -68,6 +80,9 @@ impl_enum_from!(Metaspace, PreTokenizerWrapper, Metaspace);
 impl_enum_from!(WhitespaceSplit, PreTokenizerWrapper, WhitespaceSplit);
 impl_enum_from!(Digits, PreTokenizerWrapper, Digits);
 impl_enum_from!(UnicodeScripts, PreTokenizerWrapper, UnicodeScripts);
+impl_enum_from!(FixedLength, PreTokenizerWrapper, FixedLength);
+impl_enum_from!(CaptureSplit, PreTokenizerWrapper, CaptureSplit);
+impl_enum_from!(Bracketed, PreTokenizerWrapper, Bracketed);
 
 #[cfg(test)]
 mod tests {
@@ -80,7 +95,7 @@ mod tests {
         assert_eq!(
             pre_tokenizer,
             PreTokenizerWrapper::Sequence(Sequence::new(vec![
-                PreTokenizerWrapper::WhitespaceSplit(WhitespaceSplit {}),
+                PreTokenizerWrapper::WhitespaceSplit(WhitespaceSplit::default()),
                 PreTokenizerWrapper::Metaspace(Metaspace::new('▁', true))
             ]))
         );
@@ -100,7 +115,7 @@ mod tests {
         assert_eq!(
             pre_tokenizer,
             PreTokenizerWrapper::Sequence(Sequence::new(vec![
-                PreTokenizerWrapper::WhitespaceSplit(WhitespaceSplit {}),
+                PreTokenizerWrapper::WhitespaceSplit(WhitespaceSplit::default()),
                 PreTokenizerWrapper::Metaspace(Metaspace::new('▁', true))
             ]))
         );
@@ -112,7 +127,7 @@ mod tests {
             serde_json::from_str(r#"{"type":"WhitespaceSplit"}"#).unwrap();
         assert_eq!(
             pre_tokenizer,
-            PreTokenizerWrapper::WhitespaceSplit(WhitespaceSplit {})
+            PreTokenizerWrapper::WhitespaceSplit(WhitespaceSplit::default())
         );
     }
 }
@@ -1,18 +1,63 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 
-use crate::tokenizer::{PreTokenizedString, PreTokenizer, Result, SplitDelimiterBehavior};
+use crate::tokenizer::pattern::Pattern;
+use crate::tokenizer::{Offsets, PreTokenizedString, PreTokenizer, Result, SplitDelimiterBehavior};
 use crate::utils::macro_rules_attribute;
 use unicode_categories::UnicodeCategories;
 
+/// Checks whether a character is punctuation: any char in the Unicode `P*` categories,
+/// along with the ASCII symbols (like `$` or `+`) that BERT also considers punctuation
 fn is_punc(x: char) -> bool {
     char::is_ascii_punctuation(&x) || x.is_punctuation()
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Matches the punctuation, except the chars in `exceptions` when they are surrounded by
+/// letters, like the apostrophe in `don't`
+struct PunctuationPattern<'a> {
+    exceptions: &'a BTreeSet<char>,
+}
+
+impl Pattern for PunctuationPattern<'_> {
+    fn find_matches(&self, inside: &str) -> Result<Vec<(Offsets, bool)>> {
+        if self.exceptions.is_empty() {
+            return is_punc.find_matches(inside);
+        }
+        if inside.is_empty() {
+            return Ok(vec![((0, 0), false)]);
+        }
+
+        let chars = inside.char_indices().collect::<Vec<_>>();
+        let is_letter = |i: usize| chars.get(i).is_some_and(|(_, c)| c.is_alphabetic());
+        let mut matches = vec![];
+        let mut last_offset = 0;
+        for (i, (b, c)) in chars.iter().enumerate() {
+            let attached =
+                self.exceptions.contains(c) && i > 0 && is_letter(i - 1) && is_letter(i + 1);
+            if is_punc(*c) && !attached {
+                if last_offset < *b {
+                    matches.push(((last_offset, *b), false));
+                }
+                last_offset = b + c.len_utf8();
+                matches.push(((*b, last_offset), true));
+            }
+        }
+        if last_offset < inside.len() {
+            matches.push(((last_offset, inside.len()), false));
+        }
+        Ok(matches)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 #[macro_rules_attribute(impl_serde_type!)]
 pub struct Punctuation {
     #[serde(default = "default_split")]
     behavior: SplitDelimiterBehavior,
+    /// The punctuation that stays attached to the letters around it, sorted so that it
+    /// always gets serialized the same way
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    exceptions: BTreeSet<char>,
 }
 
 fn default_split() -> SplitDelimiterBehavior {
@@ -21,7 +66,18 @@ fn default_split() -> SplitDelimiterBehavior {
 
 impl Punctuation {
     pub fn new(behavior: SplitDelimiterBehavior) -> Self {
-        Self { behavior }
+        Self {
+            behavior,
+            exceptions: BTreeSet::new(),
+        }
+    }
+
+    /// Keep the given punctuation chars attached when they are between two letters, like
+    /// the apostrophe of English contractions. They still get split everywhere else.
+    #[must_use]
+    pub fn with_exceptions(mut self, exceptions: BTreeSet<char>) -> Self {
+        self.exceptions = exceptions;
+        self
     }
 }
 
@@ -33,7 +89,14 @@ impl Default for Punctuation {
 
 impl PreTokenizer for Punctuation {
     fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
-        pretokenized.split(|_, s| s.split(is_punc, self.behavior))
+        pretokenized.split(|_, s| {
+            s.split(
+                PunctuationPattern {
+                    exceptions: &self.exceptions,
+                },
+                self.behavior,
+            )
+        })
     }
 }
 
@@ -64,6 +127,89 @@ mod tests {
         );
     }
 
+    #[test]
+    fn punctuation_unicode() {
+        let mut pretokenized: PreTokenizedString = "Wait\u{2014}«what?»「はい」".into();
+        Punctuation::default()
+            .pre_tokenize(&mut pretokenized)
+            .unwrap();
+        assert_eq!(
+            pretokenized
+                .get_splits(OffsetReferential::Original, OffsetType::Char)
+                .into_iter()
+                .map(|(s, o, _)| (s, o))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Wait", (0, 4)),
+                ("\u{2014}", (4, 5)),
+                ("«", (5, 6)),
+                ("what", (6, 10)),
+                ("?", (10, 11)),
+                ("»", (11, 12)),
+                ("「", (12, 13)),
+                ("はい", (13, 15)),
+                ("」", (15, 16)),
+            ]
+        );
+
+        let mut pretokenized: PreTokenizedString = "Wait\u{2014}«what?»「はい」".into();
+        Punctuation::new(SplitDelimiterBehavior::Removed)
+            .pre_tokenize(&mut pretokenized)
+            .unwrap();
+        assert_eq!(
+            pretokenized
+                .get_splits(OffsetReferential::Original, OffsetType::Char)
+                .into_iter()
+                .map(|(s, o, _)| (s, o))
+                .collect::<Vec<_>>(),
+            vec![("Wait", (0, 4)), ("what", (6, 10)), ("はい", (13, 15))]
+        );
+    }
+
+    #[test]
+    fn punctuation_exceptions() {
+        let pretok = Punctuation::default().with_exceptions(['\''].iter().copied().collect());
+        let splits = |s: &str| {
+            let mut pretokenized: PreTokenizedString = s.into();
+            pretok.pre_tokenize(&mut pretokenized).unwrap();
+            pretokenized
+                .get_splits(OffsetReferential::Original, OffsetType::Byte)
+                .into_iter()
+                .map(|(s, o, _)| (s.to_owned(), o))
+                .collect::<Vec<_>>()
+        };
+        let owned = |splits: &[(&str, Offsets)]| {
+            splits
+                .iter()
+                .map(|(s, o)| (s.to_string(), *o))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(splits("don't"), owned(&[("don't", (0, 5))]));
+        assert_eq!(
+            splits("'quote'"),
+            owned(&[("'", (0, 1)), ("quote", (1, 6)), ("'", (6, 7))])
+        );
+        assert_eq!(
+            splits("I can't, 'ok' rock'n'roll!"),
+            owned(&[
+                ("I can't", (0, 7)),
+                (",", (7, 8)),
+                (" ", (8, 9)),
+                ("'", (9, 10)),
+                ("ok", (10, 12)),
+                ("'", (12, 13)),
+                (" rock'n'roll", (13, 25)),
+                ("!", (25, 26)),
+            ])
+        );
+        // Only the exceptions stay attached
+        assert_eq!(
+            splits("l'été-là"),
+            owned(&[("l'été", (0, 7)), ("-", (7, 8)), ("là", (8, 11))])
+        );
+    }
+
     #[test]
     fn deserialization() {
         let punctuation: Punctuation = serde_json::from_str(r#"{"type": "Punctuation"}"#).unwrap();
@@ -74,6 +220,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn serialization() {
+        let punctuation = Punctuation::new(SplitDelimiterBehavior::Removed);
+        let serialized = serde_json::to_string(&punctuation).unwrap();
+        assert_eq!(serialized, r#"{"type":"Punctuation","behavior":"Removed"}"#);
+        assert_eq!(
+            serde_json::from_str::<Punctuation>(&serialized).unwrap(),
+            punctuation
+        );
+    }
+
+    #[test]
+    fn serialization_exceptions() {
+        let punctuation =
+            Punctuation::default().with_exceptions(['.', '\'', '-', '’'].iter().copied().collect());
+        let serialized = serde_json::to_string(&punctuation).unwrap();
+        // Always in the same order
+        assert_eq!(
+            serialized,
+            r#"{"type":"Punctuation","behavior":"Isolated","exceptions":["'","-",".","’"]}"#
+        );
+        let reversed =
+            Punctuation::default().with_exceptions(['’', '-', '\'', '.'].iter().copied().collect());
+        assert_eq!(serde_json::to_string(&reversed).unwrap(), serialized);
+        assert_eq!(
+            serde_json::from_str::<Punctuation>(&serialized).unwrap(),
+            punctuation
+        );
+    }
+
     #[test]
     #[should_panic]
     fn deserialization_erroneous() {
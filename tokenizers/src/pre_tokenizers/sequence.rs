@@ -13,11 +13,85 @@ impl Sequence {
     pub fn new(pretokenizers: Vec<PreTokenizerWrapper>) -> Self {
         Self { pretokenizers }
     }
+
+    /// Build a `Sequence` step by step, with a [`SequenceBuilder`]
+    pub fn builder() -> SequenceBuilder {
+        SequenceBuilder::default()
+    }
+
+    pub fn get_pretokenizers(&self) -> &[PreTokenizerWrapper] {
+        &self.pretokenizers
+    }
+
+    /// Add a pretokenizer at the end of the sequence
+    pub fn push(&mut self, pretokenizer: PreTokenizerWrapper) {
+        self.pretokenizers.push(pretokenizer);
+    }
+
+    /// Insert a pretokenizer at the given position, shifting the ones after it
+    pub fn insert(&mut self, index: usize, pretokenizer: PreTokenizerWrapper) {
+        self.pretokenizers.insert(index, pretokenizer);
+    }
+
+    /// Remove the pretokenizer at the given position, and return it
+    pub fn remove(&mut self, index: usize) -> PreTokenizerWrapper {
+        self.pretokenizers.remove(index)
+    }
+}
+
+/// A builder for a [`Sequence`] of pre-tokenizers, which flattens the nested sequences:
+/// ```
+/// use tokenizers::pre_tokenizers::punctuation::Punctuation;
+/// use tokenizers::pre_tokenizers::sequence::Sequence;
+/// use tokenizers::pre_tokenizers::whitespace::WhitespaceSplit;
+///
+/// let pre_tokenizer = Sequence::builder()
+///     .then(WhitespaceSplit::default())
+///     .then(Punctuation::default())
+///     .build()
+///     .unwrap();
+/// assert_eq!(pre_tokenizer.get_pretokenizers().len(), 2);
+/// ```
+#[derive(Default)]
+pub struct SequenceBuilder {
+    pretokenizers: Vec<PreTokenizerWrapper>,
+}
+
+impl SequenceBuilder {
+    /// Add a pretokenizer at the end of the sequence. If it is itself a `Sequence`, its
+    /// pretokenizers get added instead.
+    #[must_use]
+    pub fn then<P: Into<PreTokenizerWrapper>>(mut self, pretokenizer: P) -> Self {
+        self.extend(pretokenizer.into());
+        self
+    }
+
+    fn extend(&mut self, pretokenizer: PreTokenizerWrapper) {
+        match pretokenizer {
+            PreTokenizerWrapper::Sequence(sequence) => {
+                for pretokenizer in sequence.pretokenizers {
+                    self.extend(pretokenizer);
+                }
+            }
+            pretokenizer => self.pretokenizers.push(pretokenizer),
+        }
+    }
+
+    /// Build the `Sequence`. This fails if no pretokenizer was added.
+    pub fn build(self) -> Result<Sequence> {
+        if self.pretokenizers.is_empty() {
+            return Err("A Sequence needs at least one pretokenizer".into());
+        }
+        Ok(Sequence::new(self.pretokenizers))
+    }
 }
 
 impl PreTokenizer for Sequence {
     fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
         for pretokenizer in &self.pretokenizers {
+            if pretokenized.is_empty() {
+                break;
+            }
             pretokenizer.pre_tokenize(pretokenized)?;
         }
         Ok(())
@@ -33,7 +107,7 @@ mod tests {
     #[test]
     fn sequence_basic() {
         let pretokenizers = vec![
-            PreTokenizerWrapper::WhitespaceSplit(WhitespaceSplit),
+            PreTokenizerWrapper::WhitespaceSplit(WhitespaceSplit::default()),
             PreTokenizerWrapper::Punctuation(Punctuation::default()),
         ];
         let pretok = Sequence::new(pretokenizers);
@@ -58,4 +132,56 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn sequence_empty() {
+        use crate::pre_tokenizers::byte_level::ByteLevel;
+        use crate::pre_tokenizers::metaspace::Metaspace;
+
+        let pretok = Sequence::new(vec![
+            PreTokenizerWrapper::Metaspace(Metaspace::default()),
+            PreTokenizerWrapper::ByteLevel(ByteLevel::default()),
+        ]);
+        let mut pretokenized: PreTokenizedString = "".into();
+        pretok.pre_tokenize(&mut pretokenized).unwrap();
+        assert!(pretokenized.is_empty());
+        assert!(pretokenized
+            .get_splits(OffsetReferential::Original, OffsetType::Byte)
+            .is_empty());
+    }
+
+    #[test]
+    fn sequence_builder() {
+        use crate::pre_tokenizers::digits::Digits;
+
+        let nested = Sequence::new(vec![
+            Punctuation::default().into(),
+            Sequence::new(vec![Digits::new(true).into()]).into(),
+        ]);
+        let pretok = Sequence::builder()
+            .then(WhitespaceSplit::default())
+            .then(nested)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            pretok.get_pretokenizers(),
+            [
+                PreTokenizerWrapper::WhitespaceSplit(_),
+                PreTokenizerWrapper::Punctuation(_),
+                PreTokenizerWrapper::Digits(_),
+            ]
+        ));
+        let mut pretokenized: PreTokenizedString = "Hey 42!".into();
+        pretok.pre_tokenize(&mut pretokenized).unwrap();
+        assert_eq!(
+            pretokenized
+                .get_splits(OffsetReferential::Original, OffsetType::Byte)
+                .into_iter()
+                .map(|(s, _, _)| s)
+                .collect::<Vec<_>>(),
+            vec!["Hey", "4", "2", "!"]
+        );
+
+        assert!(Sequence::builder().build().is_err());
+    }
 }
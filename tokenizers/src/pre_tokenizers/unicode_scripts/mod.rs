@@ -3,3 +3,6 @@ mod scripts;
 
 // Re-export the PreTokenizer
 pub use pre_tokenizer::UnicodeScripts;
+pub use scripts::Script;
+
+pub(crate) use scripts::get_script;
@@ -2,7 +2,9 @@
 // Unicode scripts : https://gist.github.com/Narsil/07556f26dc84a6baeff4d499e68d3cd2
 // Rust adaptation : https://gist.github.com/Narsil/1df9fbbf5296a8d4d62de55dcb2fe700
 
-#[derive(PartialEq, Debug, Clone, Copy, Eq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Debug, Clone, Copy, Eq, Serialize, Deserialize)]
 pub enum Script {
     Any,
     Adlam,
@@ -1,17 +1,214 @@
 use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
 
+use crate::pre_tokenizers::unicode_scripts::{get_script, Script};
 use crate::tokenizer::{
-    pattern::Invert, PreTokenizedString, PreTokenizer, Result, SplitDelimiterBehavior,
+    normalizer::Range, pattern::Invert, NormalizedString, PreTokenizedString, PreTokenizer, Result,
+    SplitDelimiterBehavior,
 };
 use crate::utils::macro_rules_attribute;
+use unicode_categories::UnicodeCategories;
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-#[macro_rules_attribute(impl_serde_type!)]
-pub struct Whitespace;
+/// Which characters count as whitespace when splitting
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WhitespaceDefinition {
+    /// Only the ASCII whitespaces: the space, `\t`, `\n`, `\x0C` and `\r`
+    Ascii,
+    /// All the Unicode whitespaces, including the no-break space U+00A0
+    #[default]
+    Unicode,
+}
+
+impl WhitespaceDefinition {
+    pub fn is_whitespace(self, c: char) -> bool {
+        match self {
+            Self::Ascii => c.is_ascii_whitespace(),
+            Self::Unicode => c.is_whitespace(),
+        }
+    }
+
+    pub(crate) fn is_unicode(&self) -> bool {
+        *self == Self::Unicode
+    }
+}
+
+/// Splits on whitespaces, keeping the words and punctuation, which are found with the
+/// `\w+|[^\w\s]+` pattern by default. A custom pattern can be provided with `with_pattern`.
+///
+/// The scripts written without spaces, like Thai, can be given to `with_script_fallback`
+/// so that their words get split further.
+///
+/// The default pattern treats all the Unicode whitespaces as separators. With
+/// [`WhitespaceDefinition::Ascii`], the other ones are kept as punctuation instead.
+///
+/// The separators are removed, unless `keep_delimiters` is set, in which case each run of
+/// them becomes a pre-token of its own.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(tag = "type")]
+pub struct Whitespace {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pattern: Option<String>,
+    #[serde(skip)]
+    regex: Option<Regex>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fallback_scripts: Vec<Script>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    split_fallback_chars: bool,
+    #[serde(skip_serializing_if = "WhitespaceDefinition::is_unicode")]
+    whitespace: WhitespaceDefinition,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    keep_delimiters: bool,
+}
+
+impl<'de> Deserialize<'de> for Whitespace {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        enum Type {
+            Whitespace,
+        }
+
+        #[derive(Deserialize)]
+        pub struct WhitespaceHelper {
+            #[serde(rename = "type")]
+            _type: Type,
+            #[serde(default)]
+            pattern: Option<String>,
+            #[serde(default)]
+            fallback_scripts: Vec<Script>,
+            #[serde(default)]
+            split_fallback_chars: bool,
+            #[serde(default)]
+            whitespace: WhitespaceDefinition,
+            #[serde(default)]
+            keep_delimiters: bool,
+        }
+
+        let helper = WhitespaceHelper::deserialize(deserializer)?;
+        let whitespace = match helper.pattern {
+            Some(pattern) => Self::with_pattern(&pattern).map_err(serde::de::Error::custom)?,
+            None => Self::default(),
+        };
+        Ok(whitespace
+            .with_script_fallback(helper.fallback_scripts)
+            .split_fallback_chars(helper.split_fallback_chars)
+            .with_whitespace(helper.whitespace)
+            .keep_delimiters(helper.keep_delimiters))
+    }
+}
+
+impl PartialEq for Whitespace {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+            && self.fallback_scripts == other.fallback_scripts
+            && self.split_fallback_chars == other.split_fallback_chars
+            && self.whitespace == other.whitespace
+            && self.keep_delimiters == other.keep_delimiters
+    }
+}
+impl Eq for Whitespace {}
+
+impl Whitespace {
+    /// Use the given regex to find the tokens, instead of `\w+|[^\w\s]+`. Everything it
+    /// doesn't match is removed.
+    pub fn with_pattern(pattern: &str) -> Result<Self> {
+        let regex = Regex::new(pattern)
+            .map_err(|e| format!("Invalid Whitespace pattern `{}`: {}", pattern, e))?;
+        Ok(Self {
+            pattern: Some(pattern.to_owned()),
+            regex: Some(regex),
+            ..Default::default()
+        })
+    }
+
+    pub fn get_pattern(&self) -> Option<&str> {
+        self.pattern.as_deref()
+    }
+
+    /// Also split the words containing characters of the given scripts, on the boundaries
+    /// between these scripts and any other one. This is meant for the scripts written
+    /// without spaces between the words, like Thai, Lao or Khmer.
+    #[must_use]
+    pub fn with_script_fallback(mut self, scripts: Vec<Script>) -> Self {
+        self.fallback_scripts = scripts;
+        self
+    }
+
+    /// Whether to split the characters of the fallback scripts from each other too. The
+    /// nonspacing marks stay with the character they modify.
+    #[must_use]
+    pub fn split_fallback_chars(mut self, split: bool) -> Self {
+        self.split_fallback_chars = split;
+        self
+    }
+
+    pub fn get_fallback_scripts(&self) -> &[Script] {
+        &self.fallback_scripts
+    }
+
+    /// Choose which characters are whitespaces for the default pattern. A custom pattern
+    /// decides this by itself.
+    #[must_use]
+    pub fn with_whitespace(mut self, whitespace: WhitespaceDefinition) -> Self {
+        self.whitespace = whitespace;
+        self
+    }
 
-impl Default for Whitespace {
-    fn default() -> Self {
-        Self
+    pub fn get_whitespace(&self) -> WhitespaceDefinition {
+        self.whitespace
+    }
+
+    /// Whether to keep each run of the text between the words, usually whitespaces, as a
+    /// pre-token of its own instead of removing it, so that the exact spacing is preserved
+    #[must_use]
+    pub fn keep_delimiters(mut self, keep: bool) -> Self {
+        self.keep_delimiters = keep;
+        self
+    }
+
+    pub fn get_keep_delimiters(&self) -> bool {
+        self.keep_delimiters
+    }
+
+    /// Split the given word according to the fallback scripts
+    fn split_fallback(&self, normalized: NormalizedString) -> Vec<NormalizedString> {
+        let mut boundaries = vec![];
+        let mut last_script = None;
+        for (offset, c) in normalized.get().char_indices() {
+            // The marks belong to the character they modify
+            if c.is_mark_nonspacing() {
+                continue;
+            }
+            let script = get_script(c);
+            let in_fallback = self.fallback_scripts.contains(&script);
+            let is_boundary = match last_script {
+                None => false,
+                Some(last) if last != script => {
+                    in_fallback || self.fallback_scripts.contains(&last)
+                }
+                Some(_) => in_fallback && self.split_fallback_chars,
+            };
+            if is_boundary {
+                boundaries.push(offset);
+            }
+            last_script = Some(script);
+        }
+        if boundaries.is_empty() {
+            return vec![normalized];
+        }
+
+        boundaries.insert(0, 0);
+        boundaries.push(normalized.len());
+        boundaries
+            .windows(2)
+            .map(|w| {
+                normalized
+                    .slice(Range::Normalized(w[0]..w[1]))
+                    .expect("NormalizedString bad split")
+            })
+            .collect()
     }
 }
 
@@ -19,23 +216,49 @@ impl PreTokenizer for Whitespace {
     fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
         lazy_static! {
             static ref RE: Regex = Regex::new(r"\w+|[^\w\s]+").unwrap();
+            static ref ASCII_RE: Regex = Regex::new(r"\w+|[^\w\t\n\x0C\r ]+").unwrap();
         }
-        let re_ref: &Regex = &RE;
+        let re_ref: &Regex = match (&self.regex, self.whitespace) {
+            (Some(regex), _) => regex,
+            (None, WhitespaceDefinition::Unicode) => &RE,
+            (None, WhitespaceDefinition::Ascii) => &ASCII_RE,
+        };
 
-        pretokenized.split(|_, normalized| {
-            normalized.split(Invert(re_ref), SplitDelimiterBehavior::Removed)
-        })
+        let behavior = if self.keep_delimiters {
+            SplitDelimiterBehavior::Isolated
+        } else {
+            SplitDelimiterBehavior::Removed
+        };
+        pretokenized.split(|_, normalized| normalized.split(Invert(re_ref), behavior))?;
+        if self.fallback_scripts.is_empty() {
+            return Ok(());
+        }
+        pretokenized.split(|_, normalized| Ok(self.split_fallback(normalized)))
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Splits on the whitespaces, as given by its `whitespace` definition
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 #[macro_rules_attribute(impl_serde_type!)]
-pub struct WhitespaceSplit;
+pub struct WhitespaceSplit {
+    #[serde(default, skip_serializing_if = "WhitespaceDefinition::is_unicode")]
+    pub whitespace: WhitespaceDefinition,
+}
+
+impl WhitespaceSplit {
+    pub fn new(whitespace: WhitespaceDefinition) -> Self {
+        Self { whitespace }
+    }
+}
 
 impl PreTokenizer for WhitespaceSplit {
     fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
+        let whitespace = self.whitespace;
         pretokenized.split(|_, normalized| {
-            normalized.split(char::is_whitespace, SplitDelimiterBehavior::Removed)
+            normalized.split(
+                |c| whitespace.is_whitespace(c),
+                SplitDelimiterBehavior::Removed,
+            )
         })
     }
 }
@@ -79,6 +302,163 @@ mod tests {
         }
     }
 
+    #[test]
+    fn keep_delimiters() {
+        let pretok = Whitespace::default().keep_delimiters(true);
+        let splits = |s: &str| {
+            let mut pretokenized = PreTokenizedString::from(s);
+            pretok.pre_tokenize(&mut pretokenized).unwrap();
+            pretokenized
+                .get_splits(OffsetReferential::Original, OffsetType::Byte)
+                .into_iter()
+                .map(|(s, o, _)| (s.to_owned(), o))
+                .collect::<Vec<_>>()
+        };
+        let expected = |e: &[(&str, (usize, usize))]| {
+            e.iter()
+                .map(|(s, o)| (s.to_string(), *o))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            splits("a  b"),
+            expected(&[("a", (0, 1)), ("  ", (1, 3)), ("b", (3, 4))])
+        );
+        assert_eq!(
+            splits(" Hey\tman! "),
+            expected(&[
+                (" ", (0, 1)),
+                ("Hey", (1, 4)),
+                ("\t", (4, 5)),
+                ("man", (5, 8)),
+                ("!", (8, 9)),
+                (" ", (9, 10))
+            ])
+        );
+
+        let serialized = serde_json::to_string(&pretok).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"Whitespace","keep_delimiters":true}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<Whitespace>(&serialized).unwrap(),
+            pretok
+        );
+        assert_eq!(
+            serde_json::to_string(&Whitespace::default()).unwrap(),
+            r#"{"type":"Whitespace"}"#
+        );
+    }
+
+    #[test]
+    fn custom_pattern() {
+        let pretok = Whitespace::with_pattern(r"[#@]\w+|\w+|[^\w\s]+").unwrap();
+        let mut pretokenized = PreTokenizedString::from("Hey @friend, #rust!");
+        pretok.pre_tokenize(&mut pretokenized).unwrap();
+        assert_eq!(
+            pretokenized
+                .get_splits(OffsetReferential::Original, OffsetType::Byte)
+                .into_iter()
+                .map(|(s, o, _)| (s, o))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Hey", (0, 3)),
+                ("@friend", (4, 11)),
+                (",", (11, 12)),
+                ("#rust", (13, 18)),
+                ("!", (18, 19))
+            ]
+        );
+
+        let err = Whitespace::with_pattern(r"\w+(").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with(r"Invalid Whitespace pattern `\w+(`"));
+    }
+
+    #[test]
+    fn serialization() {
+        let pretok = Whitespace::with_pattern(r"#\w+|\w+").unwrap();
+        let pretok_s = r##"{"type":"Whitespace","pattern":"#\\w+|\\w+"}"##;
+        assert_eq!(serde_json::to_string(&pretok).unwrap(), pretok_s);
+        let deserialized: Whitespace = serde_json::from_str(pretok_s).unwrap();
+        assert_eq!(deserialized, pretok);
+        assert_eq!(deserialized.get_pattern(), Some(r"#\w+|\w+"));
+
+        // The default pattern isn't serialized
+        let pretok_s = r#"{"type":"Whitespace"}"#;
+        assert_eq!(
+            serde_json::to_string(&Whitespace::default()).unwrap(),
+            pretok_s
+        );
+        assert_eq!(
+            serde_json::from_str::<Whitespace>(pretok_s).unwrap(),
+            Whitespace::default()
+        );
+
+        // An invalid pattern fails the deserialization
+        assert!(
+            serde_json::from_str::<Whitespace>(r#"{"type":"Whitespace","pattern":"("}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn script_fallback() {
+        let get_splits = |pretok: &Whitespace, s: &str| {
+            let mut pretokenized = PreTokenizedString::from(s);
+            pretok.pre_tokenize(&mut pretokenized).unwrap();
+            pretokenized
+                .get_splits(OffsetReferential::Original, OffsetType::Byte)
+                .into_iter()
+                .map(|(s, o, _)| (s.to_owned(), o))
+                .collect::<Vec<_>>()
+        };
+        let input = "ภาษาไทยABC123 ok";
+
+        // Without fallback, the whole Thai run stays in the same word
+        assert_eq!(
+            get_splits(&Whitespace::default(), input),
+            vec![("ภาษาไทยABC123".into(), (0, 27)), ("ok".into(), (28, 30))]
+        );
+
+        // The Thai chars take 3 bytes each
+        let pretok = Whitespace::default().with_script_fallback(vec![Script::Thai]);
+        assert_eq!(
+            get_splits(&pretok, input),
+            vec![
+                ("ภาษาไทย".into(), (0, 21)),
+                ("ABC123".into(), (21, 27)),
+                ("ok".into(), (28, 30))
+            ]
+        );
+
+        // Each char gets its own split, keeping the marks
+        let pretok = pretok.split_fallback_chars(true);
+        let splits = get_splits(&pretok, "ภาษาไทย ที่");
+        assert_eq!(
+            splits.iter().map(|(s, _)| s.as_str()).collect::<Vec<_>>(),
+            vec!["ภ", "า", "ษ", "า", "ไ", "ท", "ย", "ที่"]
+        );
+        assert_eq!(splits[1].1, (3, 6));
+        assert_eq!(splits[7].1, (22, 31));
+    }
+
+    #[test]
+    fn script_fallback_serialization() {
+        let pretok = Whitespace::default()
+            .with_script_fallback(vec![Script::Thai, Script::Khmer])
+            .split_fallback_chars(true);
+        let pretok_s = r#"{"type":"Whitespace","fallback_scripts":["Thai","Khmer"],"split_fallback_chars":true}"#;
+        assert_eq!(serde_json::to_string(&pretok).unwrap(), pretok_s);
+        let deserialized: Whitespace = serde_json::from_str(pretok_s).unwrap();
+        assert_eq!(deserialized, pretok);
+        assert_eq!(
+            deserialized.get_fallback_scripts(),
+            [Script::Thai, Script::Khmer]
+        );
+    }
+
     #[test]
     fn whitespace_split() {
         let tests = vec![
@@ -88,7 +468,7 @@ mod tests {
                 vec![("Hey,", (0, 4)), ("man,", (5, 9)), ("Good?", (10, 15))],
             ),
         ];
-        let pretok = WhitespaceSplit;
+        let pretok = WhitespaceSplit::default();
         for (s, res) in tests {
             let mut pretokenized = PreTokenizedString::from(s);
             pretok.pre_tokenize(&mut pretokenized).unwrap();
@@ -102,4 +482,75 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn whitespace_definition() {
+        let get_splits = |pretok: &dyn PreTokenizer, s: &str| {
+            let mut pretokenized = PreTokenizedString::from(s);
+            pretok.pre_tokenize(&mut pretokenized).unwrap();
+            pretokenized
+                .get_splits(OffsetReferential::Original, OffsetType::Byte)
+                .into_iter()
+                .map(|(s, o, _)| (s.to_owned(), o))
+                .collect::<Vec<_>>()
+        };
+        // The no-break space takes 2 bytes
+        let input = "New\u{a0}York, ok";
+
+        let unicode = vec![
+            ("New".into(), (0, 3)),
+            ("York".into(), (5, 9)),
+            (",".into(), (9, 10)),
+            ("ok".into(), (11, 13)),
+        ];
+        assert_eq!(get_splits(&Whitespace::default(), input), unicode);
+        let pretok = Whitespace::default().with_whitespace(WhitespaceDefinition::Ascii);
+        assert_eq!(
+            get_splits(&pretok, input),
+            vec![
+                ("New".into(), (0, 3)),
+                ("\u{a0}".into(), (3, 5)),
+                ("York".into(), (5, 9)),
+                (",".into(), (9, 10)),
+                ("ok".into(), (11, 13)),
+            ]
+        );
+
+        assert_eq!(
+            get_splits(&WhitespaceSplit::default(), input),
+            vec![
+                ("New".into(), (0, 3)),
+                ("York,".into(), (5, 10)),
+                ("ok".into(), (11, 13))
+            ]
+        );
+        let pretok = WhitespaceSplit::new(WhitespaceDefinition::Ascii);
+        assert_eq!(
+            get_splits(&pretok, input),
+            vec![("New\u{a0}York,".into(), (0, 10)), ("ok".into(), (11, 13))]
+        );
+    }
+
+    #[test]
+    fn whitespace_definition_serialization() {
+        assert_eq!(
+            serde_json::to_string(&WhitespaceSplit::default()).unwrap(),
+            r#"{"type":"WhitespaceSplit"}"#
+        );
+        let pretok = WhitespaceSplit::new(WhitespaceDefinition::Ascii);
+        let pretok_s = r#"{"type":"WhitespaceSplit","whitespace":"Ascii"}"#;
+        assert_eq!(serde_json::to_string(&pretok).unwrap(), pretok_s);
+        assert_eq!(
+            serde_json::from_str::<WhitespaceSplit>(pretok_s).unwrap(),
+            pretok
+        );
+
+        let pretok = Whitespace::default().with_whitespace(WhitespaceDefinition::Ascii);
+        let pretok_s = r#"{"type":"Whitespace","whitespace":"Ascii"}"#;
+        assert_eq!(serde_json::to_string(&pretok).unwrap(), pretok_s);
+        assert_eq!(
+            serde_json::from_str::<Whitespace>(pretok_s).unwrap(),
+            pretok
+        );
+    }
 }
@@ -0,0 +1,194 @@
+use crate::tokenizer::{Encoding, PostProcessor, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Adds the `bos` token at the start of the encoding and the `eos` one at its end, only when
+/// they are not already there. This lets some already processed input go through the
+/// processing again without getting its special tokens twice.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "type")]
+pub struct EnsureBosEos {
+    bos: (String, u32),
+    eos: (String, u32),
+}
+
+impl Default for EnsureBosEos {
+    fn default() -> Self {
+        Self {
+            bos: ("<s>".into(), 0),
+            eos: ("</s>".into(), 2),
+        }
+    }
+}
+
+impl EnsureBosEos {
+    pub fn new(bos: (String, u32), eos: (String, u32)) -> Self {
+        Self { bos, eos }
+    }
+
+    /// An Encoding of the given special token
+    fn special_token((token, id): &(String, u32), type_id: u32) -> Encoding {
+        Encoding::new(
+            vec![*id],
+            vec![type_id],
+            vec![token.clone()],
+            vec![None],
+            vec![(0, 0)],
+            vec![1],
+            vec![1],
+            vec![],
+            HashMap::new(),
+        )
+    }
+
+    fn ensure(&self, mut encoding: Encoding) -> Encoding {
+        let overflowing = encoding
+            .take_overflowing()
+            .into_iter()
+            .map(|o| self.ensure(o))
+            .collect();
+
+        let type_ids = encoding.get_type_ids();
+        let bos = (encoding.get_ids().first() != Some(&self.bos.1))
+            .then(|| Self::special_token(&self.bos, type_ids.first().copied().unwrap_or(0)));
+        let eos = (encoding.get_ids().last() != Some(&self.eos.1))
+            .then(|| Self::special_token(&self.eos, type_ids.last().copied().unwrap_or(0)));
+
+        let mut encoding = Encoding::merge(
+            bos.into_iter().chain(std::iter::once(encoding)).chain(eos),
+            false,
+        );
+        encoding.set_overflowing(overflowing);
+        encoding
+    }
+}
+
+impl PostProcessor for EnsureBosEos {
+    /// At most 2, as the `bos` and `eos` tokens are added only when they are missing
+    fn added_tokens(&self, _is_pair: bool) -> usize {
+        2
+    }
+
+    fn has_exact_added_tokens(&self) -> bool {
+        false
+    }
+
+    fn process_encodings(
+        &self,
+        encodings: Vec<Encoding>,
+        add_special_tokens: bool,
+    ) -> Result<Vec<Encoding>> {
+        if !add_special_tokens {
+            return Ok(encodings);
+        }
+
+        Ok(vec![self.ensure(Encoding::merge(encodings, false))])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Token;
+
+    fn encoding(tokens: &[(u32, &str)]) -> Encoding {
+        let mut offset = 0;
+        let tokens = tokens
+            .iter()
+            .map(|(id, token)| {
+                let start = offset;
+                offset += token.len();
+                Token::new(*id, token.to_string(), (start, offset))
+            })
+            .collect();
+        Encoding::from_tokens(tokens, 0)
+    }
+
+    #[test]
+    fn serde() {
+        let processor = EnsureBosEos::default();
+        let processor_r = r#"{"type":"EnsureBosEos","bos":["<s>",0],"eos":["</s>",2]}"#;
+        assert_eq!(serde_json::to_string(&processor).unwrap(), processor_r);
+        assert_eq!(
+            serde_json::from_str::<EnsureBosEos>(processor_r).unwrap(),
+            processor
+        );
+    }
+
+    #[test]
+    fn neither() {
+        let processor = EnsureBosEos::default();
+        let output = processor
+            .process(encoding(&[(5, "Hello"), (6, "there")]), None, true)
+            .unwrap();
+        assert_eq!(output.get_ids(), [0, 5, 6, 2]);
+        assert_eq!(output.get_tokens(), ["<s>", "Hello", "there", "</s>"]);
+        assert_eq!(output.get_offsets(), [(0, 0), (0, 5), (5, 10), (0, 0)]);
+        assert_eq!(output.get_type_ids(), [0, 0, 0, 0]);
+        assert_eq!(output.get_special_tokens_mask(), [1, 0, 0, 1]);
+        assert_eq!(output.get_attention_mask(), [1, 1, 1, 1]);
+        assert_eq!(output.get_sequence_ids(), [None, Some(0), Some(0), None]);
+
+        // Nothing is added without the special tokens
+        let output = processor
+            .process(encoding(&[(5, "Hello")]), None, false)
+            .unwrap();
+        assert_eq!(output.get_ids(), [5]);
+    }
+
+    #[test]
+    fn already_bos() {
+        let processor = EnsureBosEos::default();
+        let output = processor
+            .process(encoding(&[(0, "<s>"), (5, "Hello")]), None, true)
+            .unwrap();
+        assert_eq!(output.get_ids(), [0, 5, 2]);
+        assert_eq!(output.get_offsets(), [(0, 3), (3, 8), (0, 0)]);
+        assert_eq!(output.get_special_tokens_mask(), [0, 0, 1]);
+    }
+
+    #[test]
+    fn already_eos() {
+        let processor = EnsureBosEos::default();
+        let output = processor
+            .process(encoding(&[(5, "Hello"), (2, "</s>")]), None, true)
+            .unwrap();
+        assert_eq!(output.get_ids(), [0, 5, 2]);
+        assert_eq!(output.get_offsets(), [(0, 0), (0, 5), (5, 9)]);
+        assert_eq!(output.get_special_tokens_mask(), [1, 0, 0]);
+    }
+
+    #[test]
+    fn both() {
+        let processor = EnsureBosEos::default();
+        let input = encoding(&[(0, "<s>"), (5, "Hello"), (2, "</s>")]);
+        let output = processor.process(input.clone(), None, true).unwrap();
+        assert_eq!(output.get_ids(), input.get_ids());
+        assert_eq!(output.get_offsets(), input.get_offsets());
+
+        // Processing the output again doesn't change it
+        let once = processor
+            .process(encoding(&[(5, "Hello")]), None, true)
+            .unwrap();
+        let twice = processor
+            .process_encodings(vec![once.clone()], true)
+            .unwrap();
+        assert_eq!(twice, vec![once]);
+    }
+
+    #[test]
+    fn pair_and_overflowing() {
+        let processor = EnsureBosEos::default();
+        let mut first = encoding(&[(5, "Hello"), (6, "there")]);
+        first.truncate(1, 0, Default::default());
+        let output = processor
+            .process(first, Some(encoding(&[(7, "pair")])), true)
+            .unwrap();
+        assert_eq!(output.get_ids(), [0, 5, 7, 2]);
+        assert_eq!(output.get_type_ids(), [0, 0, 1, 1]);
+        assert_eq!(output.get_sequence_ids(), [None, Some(0), Some(1), None]);
+        let overflowing = &output.get_overflowing()[0];
+        assert_eq!(overflowing.get_ids(), [0, 6, 7, 2]);
+        assert_eq!(overflowing.get_special_tokens_mask(), [1, 0, 0, 1]);
+    }
+}
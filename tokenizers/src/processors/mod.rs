@@ -1,7 +1,9 @@
 pub mod bert;
+pub mod ensure;
 pub mod roberta;
 pub mod sequence;
 pub mod template;
+pub mod truncate;
 
 // Re-export these as processors
 pub use super::pre_tokenizers::byte_level;
@@ -10,9 +12,11 @@ use serde::{Deserialize, Serialize};
 
 use crate::pre_tokenizers::byte_level::ByteLevel;
 use crate::processors::bert::BertProcessing;
+use crate::processors::ensure::EnsureBosEos;
 use crate::processors::roberta::RobertaProcessing;
 use crate::processors::sequence::Sequence;
 use crate::processors::template::TemplateProcessing;
+use crate::processors::truncate::TruncateProcessing;
 use crate::{Encoding, PostProcessor, Result};
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Eq)]
@@ -24,6 +28,8 @@ pub enum PostProcessorWrapper {
     ByteLevel(ByteLevel),
     Template(TemplateProcessing),
     Sequence(Sequence),
+    Truncate(TruncateProcessing),
+    EnsureBosEos(EnsureBosEos),
 }
 
 impl PostProcessor for PostProcessorWrapper {
@@ -34,6 +40,20 @@ impl PostProcessor for PostProcessorWrapper {
             Self::Roberta(roberta) => roberta.added_tokens(is_pair),
             Self::Template(template) => template.added_tokens(is_pair),
             Self::Sequence(bl) => bl.added_tokens(is_pair),
+            Self::Truncate(truncate) => truncate.added_tokens(is_pair),
+            Self::EnsureBosEos(ensure) => ensure.added_tokens(is_pair),
+        }
+    }
+
+    fn has_exact_added_tokens(&self) -> bool {
+        match self {
+            Self::Bert(bert) => bert.has_exact_added_tokens(),
+            Self::ByteLevel(bl) => bl.has_exact_added_tokens(),
+            Self::Roberta(roberta) => roberta.has_exact_added_tokens(),
+            Self::Template(template) => template.has_exact_added_tokens(),
+            Self::Sequence(bl) => bl.has_exact_added_tokens(),
+            Self::Truncate(truncate) => truncate.has_exact_added_tokens(),
+            Self::EnsureBosEos(ensure) => ensure.has_exact_added_tokens(),
         }
     }
 
@@ -48,6 +68,8 @@ impl PostProcessor for PostProcessorWrapper {
             Self::Roberta(roberta) => roberta.process_encodings(encodings, add_special_tokens),
             Self::Template(template) => template.process_encodings(encodings, add_special_tokens),
             Self::Sequence(bl) => bl.process_encodings(encodings, add_special_tokens),
+            Self::Truncate(truncate) => truncate.process_encodings(encodings, add_special_tokens),
+            Self::EnsureBosEos(ensure) => ensure.process_encodings(encodings, add_special_tokens),
         }
     }
 }
@@ -57,6 +79,8 @@ impl_enum_from!(ByteLevel, PostProcessorWrapper, ByteLevel);
 impl_enum_from!(RobertaProcessing, PostProcessorWrapper, Roberta);
 impl_enum_from!(TemplateProcessing, PostProcessorWrapper, Template);
 impl_enum_from!(Sequence, PostProcessorWrapper, Sequence);
+impl_enum_from!(TruncateProcessing, PostProcessorWrapper, Truncate);
+impl_enum_from!(EnsureBosEos, PostProcessorWrapper, EnsureBosEos);
 
 #[cfg(test)]
 mod tests {
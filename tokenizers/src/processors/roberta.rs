@@ -1,4 +1,4 @@
-use crate::processors::byte_level::process_offsets;
+use crate::processors::byte_level::process_all_offsets;
 use crate::tokenizer::{Encoding, PostProcessor, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -62,11 +62,7 @@ impl PostProcessor for RobertaProcessing {
     ) -> Result<Vec<Encoding>> {
         if self.trim_offsets {
             for encoding in encodings.iter_mut() {
-                process_offsets(encoding, self.add_prefix_space);
-                encoding
-                    .get_overflowing_mut()
-                    .iter_mut()
-                    .for_each(|encoding| process_offsets(encoding, self.add_prefix_space));
+                process_all_offsets(encoding, self.add_prefix_space);
             }
         }
 
@@ -23,6 +23,10 @@ impl PostProcessor for Sequence {
             .sum::<usize>()
     }
 
+    fn has_exact_added_tokens(&self) -> bool {
+        self.processors.iter().all(|p| p.has_exact_added_tokens())
+    }
+
     fn process_encodings(
         &self,
         mut encodings: Vec<Encoding>,
@@ -48,6 +48,9 @@
 //!
 //! The same construct is used for special tokens: `<identifier>(:<type_id>)?`.
 //!
+//! When the model only has a few type ids, like `2` for BERT, the builder can be given a
+//! `type_vocab_size` to check that all the type ids of the templates are in range.
+//!
 //! **Warning**: You must ensure that you are giving the correct tokens/ids as these will
 //! be added to the `Encoding` without any further check. If the given ids correspond to
 //! something totally different in a `Tokenizer` using this `PostProcessor`, it might lead
@@ -71,6 +74,47 @@ pub enum Sequence {
     B,
 }
 
+/// Which sequence the special tokens added by a [`TemplateProcessing`] are part of, as
+/// given by [`Encoding::get_sequence_ids`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SpecialTokenSequenceAttribution {
+    /// The special tokens are not part of any sequence
+    #[default]
+    None,
+    /// The special tokens are part of the sequence right before them, or of the one right
+    /// after them when they come first. With `[CLS] $A [SEP] $B [SEP]`, both `[CLS]` and the
+    /// first `[SEP]` are part of the sequence 0, and the last `[SEP]` of the sequence 1.
+    Adjacent,
+}
+
+impl SpecialTokenSequenceAttribution {
+    fn is_none(&self) -> bool {
+        *self == Self::None
+    }
+
+    /// The sequence of each piece of the template, if any
+    fn attribute(&self, template: &[Piece]) -> Vec<Option<usize>> {
+        let sequence = |piece: &Piece| match piece {
+            Piece::Sequence { id, .. } => Some(usize::from(*id != Sequence::A)),
+            Piece::SpecialToken { .. } => None,
+        };
+        match self {
+            Self::None => template.iter().map(sequence).collect(),
+            Self::Adjacent => {
+                let first = template.iter().find_map(sequence);
+                let mut previous = None;
+                template
+                    .iter()
+                    .map(|piece| {
+                        previous = sequence(piece).or(previous);
+                        previous.or(first)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
 /// Represents the different kind of pieces that constitute a template.
 /// It can be either the input sequence or a [`SpecialToken`]:
 ///
@@ -333,7 +377,7 @@ impl From<HashMap<String, SpecialToken>> for Tokens {
 /// ```
 ///
 #[derive(Debug, Clone, PartialEq, Builder, Serialize, Deserialize, Eq)]
-#[serde(tag = "type", from = "TemplateProcessingDeserializer")]
+#[serde(tag = "type", try_from = "TemplateProcessingDeserializer")]
 #[builder(build_fn(validate = "Self::validate"))]
 pub struct TemplateProcessing {
     #[builder(try_setter, default = "\"$0\".try_into().unwrap()")]
@@ -348,6 +392,18 @@ pub struct TemplateProcessing {
     added_pair: usize,
     #[builder(setter(into), default)]
     special_tokens: Tokens,
+    /// The number of type ids the model knows of, if limited. All the type ids used in the
+    /// templates must be lower than this.
+    #[builder(setter(strip_option), default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    type_vocab_size: Option<u32>,
+    /// Which sequence the added special tokens are part of
+    #[builder(default)]
+    #[serde(
+        default,
+        skip_serializing_if = "SpecialTokenSequenceAttribution::is_none"
+    )]
+    sequence_attribution: SpecialTokenSequenceAttribution,
 }
 
 impl From<&str> for TemplateProcessingBuilderError {
@@ -371,18 +427,49 @@ struct TemplateProcessingDeserializer {
     single: Template,
     pair: Template,
     special_tokens: Tokens,
+    #[serde(default)]
+    type_vocab_size: Option<u32>,
+    #[serde(default)]
+    sequence_attribution: SpecialTokenSequenceAttribution,
 }
-impl From<TemplateProcessingDeserializer> for TemplateProcessing {
-    fn from(t: TemplateProcessingDeserializer) -> Self {
+impl TryFrom<TemplateProcessingDeserializer> for TemplateProcessing {
+    type Error = String;
+
+    fn try_from(t: TemplateProcessingDeserializer) -> StdResult<Self, Self::Error> {
+        check_type_ids(&[&t.single, &t.pair], t.type_vocab_size)?;
         let added_single = count_added(&t.single, Some(&t.special_tokens));
         let added_pair = count_added(&t.pair, Some(&t.special_tokens));
-        Self {
+        Ok(Self {
             single: t.single,
             pair: t.pair,
             added_single,
             added_pair,
             special_tokens: t.special_tokens,
-        }
+            type_vocab_size: t.type_vocab_size,
+            sequence_attribution: t.sequence_attribution,
+        })
+    }
+}
+
+/// Check that all the type ids of the given templates are lower than `type_vocab_size`
+fn check_type_ids(templates: &[&Template], type_vocab_size: Option<u32>) -> StdResult<(), String> {
+    let type_vocab_size = match type_vocab_size {
+        Some(size) => size,
+        None => return Ok(()),
+    };
+    let out_of_range = templates
+        .iter()
+        .flat_map(|template| template.0.iter())
+        .map(|piece| match piece {
+            Piece::Sequence { type_id, .. } | Piece::SpecialToken { type_id, .. } => *type_id,
+        })
+        .find(|type_id| *type_id >= type_vocab_size);
+    match out_of_range {
+        Some(type_id) => Err(format!(
+            "Type id {} is out of range, the model only has {} of them",
+            type_id, type_vocab_size
+        )),
+        None => Ok(()),
     }
 }
 
@@ -436,6 +523,16 @@ impl TemplateProcessingBuilder {
             return Err("Template for `pair` must use both sequences".into());
         }
 
+        let single = self
+            .single
+            .clone()
+            .unwrap_or_else(|| "$0".try_into().unwrap());
+        let pair = self
+            .pair
+            .clone()
+            .unwrap_or_else(|| "$A:0 $B:1".try_into().unwrap());
+        check_type_ids(&[&single, &pair], self.type_vocab_size.flatten())?;
+
         let check = |sp| {
             let exist = self
                 .special_tokens
@@ -479,6 +576,8 @@ impl Default for TemplateProcessing {
             added_single: 0,
             added_pair: 0,
             special_tokens: Tokens::default(),
+            type_vocab_size: None,
+            sequence_attribution: SpecialTokenSequenceAttribution::None,
         }
     }
 }
@@ -494,9 +593,11 @@ impl TemplateProcessing {
         mut encodings: Vec<Encoding>,
         add_special_tokens: bool,
     ) -> Result<Vec<Encoding>> {
+        let sequences = self.sequence_attribution.attribute(template);
         let final_encodings: Vec<Encoding> = template
             .iter()
-            .flat_map(|piece| {
+            .zip(sequences)
+            .flat_map(|(piece, sequence)| {
                 match piece {
                     Piece::Sequence { id, type_id } => {
                         let i = usize::from(*id != Sequence::A);
@@ -510,7 +611,7 @@ impl TemplateProcessing {
                             let tok = &self.special_tokens.0[id]; // We already checked existance above
                             let len = tok.ids.len();
 
-                            let encoding = Encoding::new(
+                            let mut encoding = Encoding::new(
                                 tok.ids.clone(),
                                 std::iter::repeat(*type_id).take(len).collect(),
                                 tok.tokens.clone(),
@@ -527,6 +628,9 @@ impl TemplateProcessing {
                                 // sequence_range
                                 HashMap::new(),
                             );
+                            if let Some(sequence) = sequence {
+                                encoding.set_sequence_id(sequence);
+                            }
                             Some(encoding)
                         } else {
                             None
@@ -1087,6 +1191,90 @@ mod tests {
         );
     }
 
+    #[test]
+    fn custom_type_ids() {
+        let processor = TemplateProcessing::builder()
+            .try_pair("[CLS]:0 $A:0 [SEP]:0 $B:2 [SEP]:2")
+            .unwrap()
+            .special_tokens(vec![("[CLS]", 1), ("[SEP]", 0)])
+            .type_vocab_size(3)
+            .build()
+            .unwrap();
+
+        use crate::Token;
+        let encoding = Encoding::from_tokens(vec![Token::new(12, "Hello".into(), (0, 5))], 0);
+        let pair = Encoding::from_tokens(vec![Token::new(15, "pair".into(), (0, 4))], 0);
+        let pair_encoding = processor.process(encoding, Some(pair), true).unwrap();
+        assert_eq!(pair_encoding.get_type_ids(), [0, 0, 0, 2, 2]);
+
+        let processor_s = serde_json::to_string(&processor).unwrap();
+        assert!(processor_s.ends_with(r#","type_vocab_size":3}"#));
+        assert_eq!(
+            serde_json::from_str::<TemplateProcessing>(&processor_s).unwrap(),
+            processor
+        );
+
+        // The type ids must be in range, even the default ones
+        let err = Err("Type id 2 is out of range, the model only has 2 of them".into());
+        let processor = TemplateProcessing::builder()
+            .try_pair("$A:0 $B:2")
+            .unwrap()
+            .type_vocab_size(2)
+            .build();
+        assert_eq!(processor, err);
+        let processor = TemplateProcessing::builder().type_vocab_size(1).build();
+        assert_eq!(
+            processor,
+            Err("Type id 1 is out of range, the model only has 1 of them".into())
+        );
+        let out_of_range = processor_s.replace(r#""type_vocab_size":3"#, r#""type_vocab_size":2"#);
+        assert!(serde_json::from_str::<TemplateProcessing>(&out_of_range)
+            .unwrap_err()
+            .to_string()
+            .starts_with("Type id 2 is out of range"));
+    }
+
+    #[test]
+    fn sequence_attribution() {
+        let processor = TemplateProcessing::builder()
+            .try_single("[CLS] $0 [SEP]")
+            .unwrap()
+            .try_pair("[CLS] $A:0 [SEP] $B:1 [SEP]:1")
+            .unwrap()
+            .special_tokens(vec![("[CLS]", 1), ("[SEP]", 0)])
+            .sequence_attribution(SpecialTokenSequenceAttribution::Adjacent)
+            .build()
+            .unwrap();
+
+        use crate::Token;
+        let encoding = Encoding::from_tokens(
+            vec![
+                Token::new(12, "Hello".into(), (0, 5)),
+                Token::new(14, "there".into(), (6, 11)),
+            ],
+            0,
+        );
+        let pair = Encoding::from_tokens(vec![Token::new(15, "pair".into(), (0, 4))], 0);
+        let single_encoding = processor.process(encoding.clone(), None, true).unwrap();
+        assert_eq!(single_encoding.get_sequence_ids(), [Some(0); 4]);
+        let pair_encoding = processor.process(encoding, Some(pair), true).unwrap();
+        assert_eq!(
+            pair_encoding.get_sequence_ids(),
+            [Some(0), Some(0), Some(0), Some(0), Some(1), Some(1)]
+        );
+        assert_eq!(pair_encoding.get_type_ids(), [0, 0, 0, 0, 1, 1]);
+        assert_eq!(pair_encoding.get_special_tokens_mask(), [1, 0, 0, 1, 0, 1]);
+        assert_eq!(single_encoding.sequence_boundary(), None);
+        assert_eq!(pair_encoding.sequence_boundary(), Some(4));
+
+        let processor_s = serde_json::to_string(&processor).unwrap();
+        assert!(processor_s.ends_with(r#","sequence_attribution":"Adjacent"}"#));
+        assert_eq!(
+            serde_json::from_str::<TemplateProcessing>(&processor_s).unwrap(),
+            processor
+        );
+    }
+
     #[test]
     fn expect_wrong_error_message() {
         let processor = TemplateProcessing::builder()
@@ -0,0 +1,295 @@
+use crate::tokenizer::{Encoding, PostProcessor, Result};
+use crate::utils::truncation::{
+    truncate_encodings, TruncationDirection, TruncationParams, TruncationStrategy,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Truncates the final encoding so that it fits in `max_length`, exactly like the truncation
+/// of the `Tokenizer` does, but as a processing step. It is meant to be used at the end of a
+/// `Sequence`, once the special tokens have been added: these special tokens are always kept,
+/// and only the content of the sequences gets truncated, the removed parts going to the
+/// overflowing encodings.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "type")]
+pub struct TruncateProcessing {
+    pub max_length: usize,
+    pub strategy: TruncationStrategy,
+    pub stride: usize,
+    #[serde(default)]
+    pub direction: TruncationDirection,
+}
+
+impl Default for TruncateProcessing {
+    fn default() -> Self {
+        Self {
+            max_length: 512,
+            strategy: TruncationStrategy::default(),
+            stride: 0,
+            direction: TruncationDirection::default(),
+        }
+    }
+}
+
+impl TruncateProcessing {
+    pub fn new(max_length: usize, strategy: TruncationStrategy, stride: usize) -> Self {
+        Self {
+            max_length,
+            strategy,
+            stride,
+            direction: TruncationDirection::default(),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum TruncateProcessorError {
+    #[error("max_length ({0}) must be greater than the {1} special tokens of the encoding")]
+    TooManySpecialTokens(usize, usize),
+    #[error(
+        "stride ({0}) must be smaller than the {1} tokens left once the special tokens are kept"
+    )]
+    StrideTooLarge(usize, usize),
+}
+
+/// Either some protected tokens, or the content of the given sequence
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Special(std::ops::Range<usize>),
+    Content(usize, std::ops::Range<usize>),
+}
+
+fn slice(encoding: &Encoding, range: std::ops::Range<usize>) -> Encoding {
+    Encoding::new(
+        encoding.get_ids()[range.clone()].to_vec(),
+        encoding.get_type_ids()[range.clone()].to_vec(),
+        encoding.get_tokens()[range.clone()].to_vec(),
+        encoding.get_word_ids()[range.clone()].to_vec(),
+        encoding.get_offsets()[range.clone()].to_vec(),
+        encoding.get_special_tokens_mask()[range.clone()].to_vec(),
+        encoding.get_attention_mask()[range].to_vec(),
+        vec![],
+        HashMap::new(),
+    )
+}
+
+/// Split the encoding into consecutive segments of special tokens, or content of a sequence.
+/// Only the first two sequences are considered content, anything else is protected.
+fn segments(encoding: &Encoding) -> Vec<Segment> {
+    let sequence_ids = encoding.get_sequence_ids();
+    let special_tokens_mask = encoding.get_special_tokens_mask();
+
+    let mut segments: Vec<Segment> = vec![];
+    for i in 0..encoding.len() {
+        let content = sequence_ids[i].filter(|s| *s < 2 && special_tokens_mask[i] == 0);
+        match (content, segments.last_mut()) {
+            (None, Some(Segment::Special(range))) => range.end = i + 1,
+            (Some(s), Some(Segment::Content(seq, range))) if *seq == s => range.end = i + 1,
+            (None, _) => segments.push(Segment::Special(i..i + 1)),
+            (Some(s), _) => segments.push(Segment::Content(s, i..i + 1)),
+        }
+    }
+    segments
+}
+
+impl PostProcessor for TruncateProcessing {
+    fn added_tokens(&self, _is_pair: bool) -> usize {
+        0
+    }
+
+    fn process_encodings(
+        &self,
+        encodings: Vec<Encoding>,
+        _add_special_tokens: bool,
+    ) -> Result<Vec<Encoding>> {
+        let mut encoding = Encoding::merge(encodings, false);
+        if encoding.len() <= self.max_length {
+            return Ok(vec![encoding]);
+        }
+
+        let segments = segments(&encoding);
+        let mut contents: Vec<Vec<Encoding>> = vec![vec![], vec![]];
+        for segment in &segments {
+            if let Segment::Content(seq, range) = segment {
+                contents[*seq].push(slice(&encoding, range.clone()));
+            }
+        }
+        let mut contents = contents
+            .into_iter()
+            .map(|parts| (!parts.is_empty()).then(|| Encoding::merge(parts, false)))
+            .collect::<Vec<_>>();
+
+        // The special tokens are never truncated, only the content around them
+        let n_content: usize = contents.iter().flatten().map(|c| c.len()).sum();
+        let n_special = encoding.len() - n_content;
+        if self.max_length < n_special {
+            return Err(Box::new(TruncateProcessorError::TooManySpecialTokens(
+                self.max_length,
+                n_special,
+            )));
+        }
+        let max_length = self.max_length - n_special;
+        if max_length > 0 && self.stride >= max_length {
+            return Err(Box::new(TruncateProcessorError::StrideTooLarge(
+                self.stride,
+                max_length,
+            )));
+        }
+        let params = TruncationParams {
+            direction: self.direction,
+            max_length,
+            strategy: self.strategy,
+            stride: self.stride,
+            ..Default::default()
+        };
+        let (first, second) = match (contents[0].take(), contents[1].take()) {
+            (Some(first), second) => truncate_encodings(first, second, &params)?,
+            (None, Some(second)) => {
+                let (second, _) = truncate_encodings(second, None, &params)?;
+                (Encoding::default(), Some(second))
+            }
+            (None, None) => return Ok(vec![encoding]),
+        };
+
+        // Put the truncated content back in place, between the special tokens. Merging the
+        // parts also gives the special tokens to each of the overflowing encodings.
+        let mut truncated = [Some(first), second];
+        for (seq, content) in truncated.iter_mut().enumerate() {
+            if let Some(content) = content {
+                content.set_sequence_id(seq);
+                content
+                    .get_overflowing_mut()
+                    .iter_mut()
+                    .for_each(|o| o.set_sequence_id(seq));
+            }
+        }
+        let overflowing = encoding.take_overflowing();
+        let mut result = Encoding::merge(
+            segments.into_iter().filter_map(|segment| match segment {
+                Segment::Special(range) => Some(slice(&encoding, range)),
+                Segment::Content(seq, _) => truncated[seq].take(),
+            }),
+            false,
+        );
+        result.get_overflowing_mut().extend(overflowing);
+        Ok(vec![result])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processors::bert::BertProcessing;
+    use crate::processors::sequence::Sequence;
+    use crate::processors::PostProcessorWrapper;
+    use crate::tokenizer::PostProcessor;
+
+    fn encoding(ids: &[u32]) -> Encoding {
+        ids.iter()
+            .enumerate()
+            .map(|(i, id)| (*id, id.to_string(), (i, i + 1), Some(i as u32), 0))
+            .collect()
+    }
+
+    fn bert_then_truncate(truncate: TruncateProcessing) -> Sequence {
+        Sequence::new(vec![
+            PostProcessorWrapper::Bert(BertProcessing::new(
+                ("[SEP]".into(), 102),
+                ("[CLS]".into(), 101),
+            )),
+            PostProcessorWrapper::Truncate(truncate),
+        ])
+    }
+
+    #[test]
+    fn keeps_special_tokens() {
+        let processor = bert_then_truncate(TruncateProcessing::new(
+            6,
+            TruncationStrategy::LongestFirst,
+            0,
+        ));
+
+        let single = processor
+            .process(encoding(&[1, 2, 3, 4, 5, 6]), None, true)
+            .unwrap();
+        assert_eq!(single.get_ids(), &[101, 1, 2, 3, 4, 102]);
+        assert_eq!(single.get_special_tokens_mask(), &[1, 0, 0, 0, 0, 1]);
+        assert_eq!(
+            single.get_sequence_ids(),
+            vec![None, Some(0), Some(0), Some(0), Some(0), None]
+        );
+        assert_eq!(single.get_overflowing().len(), 1);
+        assert_eq!(single.get_overflowing()[0].get_ids(), &[101, 5, 6, 102]);
+
+        // Short enough, nothing to do
+        let short = processor.process(encoding(&[1, 2]), None, true).unwrap();
+        assert_eq!(short.get_ids(), &[101, 1, 2, 102]);
+        assert!(short.get_overflowing().is_empty());
+    }
+
+    #[test]
+    fn pair_strategies() {
+        let processor = bert_then_truncate(TruncateProcessing::new(
+            7,
+            TruncationStrategy::LongestFirst,
+            0,
+        ));
+        let pair = processor
+            .process(encoding(&[1, 2, 3]), Some(encoding(&[4, 5, 6, 7])), true)
+            .unwrap();
+        assert_eq!(pair.get_ids(), &[101, 1, 2, 102, 4, 5, 102]);
+        assert_eq!(pair.get_type_ids(), &[0, 0, 0, 0, 1, 1, 1]);
+        assert_eq!(pair.token_to_sequence(4), Some(1));
+
+        let processor =
+            bert_then_truncate(TruncateProcessing::new(7, TruncationStrategy::OnlyFirst, 0));
+        let pair = processor
+            .process(encoding(&[1, 2, 3]), Some(encoding(&[4, 5])), true)
+            .unwrap();
+        assert_eq!(pair.get_ids(), &[101, 1, 2, 102, 4, 5, 102]);
+
+        let processor = bert_then_truncate(TruncateProcessing::new(
+            4,
+            TruncationStrategy::OnlySecond,
+            0,
+        ));
+        assert!(processor.process(encoding(&[1, 2, 3]), None, true).is_err());
+        // Not even enough room for the special tokens
+        let processor = bert_then_truncate(TruncateProcessing::new(
+            1,
+            TruncationStrategy::LongestFirst,
+            0,
+        ));
+        assert!(processor.process(encoding(&[1, 2, 3]), None, true).is_err());
+    }
+
+    #[test]
+    fn stride() {
+        let processor = bert_then_truncate(TruncateProcessing::new(
+            5,
+            TruncationStrategy::LongestFirst,
+            1,
+        ));
+        let single = processor
+            .process(encoding(&[1, 2, 3, 4, 5]), None, true)
+            .unwrap();
+        assert_eq!(single.get_ids(), &[101, 1, 2, 3, 102]);
+        let overflowing = single
+            .get_overflowing()
+            .iter()
+            .map(|o| o.get_ids().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(overflowing, vec![vec![101, 3, 4, 5, 102]]);
+    }
+
+    #[test]
+    fn serialization() {
+        let truncate = TruncateProcessing::new(128, TruncationStrategy::OnlyFirst, 16);
+        let truncate_s = r#"{"type":"TruncateProcessing","max_length":128,"strategy":"OnlyFirst","stride":16,"direction":"Right"}"#;
+        assert_eq!(serde_json::to_string(&truncate).unwrap(), truncate_s);
+        assert_eq!(
+            serde_json::from_str::<PostProcessorWrapper>(truncate_s).unwrap(),
+            PostProcessorWrapper::Truncate(truncate)
+        );
+    }
+}
@@ -1,10 +1,23 @@
 use super::{
-    normalizer::Range, Model, NormalizedString, Normalizer, Offsets, PreTokenizedString, Token,
+    normalizer::Range, Model, NormalizedString, Normalizer, Offsets, PreTokenizedString, Result,
+    Token,
 };
 use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
 use regex::Regex;
 use serde::{ser::SerializeSeq, Deserialize, Serialize, Serializer};
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Error returned when adding a token in strict mode, if the id it would get is already used
+/// by another token, either from the model or from the added vocabulary
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[error("The added token `{token}` can't get the id {id}, already used by `{existing}`")]
+pub struct AddedTokenIdCollision {
+    pub token: String,
+    pub id: u32,
+    pub existing: String,
+}
 
 /// Represent a token added by the user on top of the existing Model vocabulary.
 /// AddedToken can be configured to specify the behavior they should have in various situations
@@ -25,6 +38,57 @@ pub struct AddedToken {
     pub normalized: bool,
     /// Whether this token is special
     pub special: bool,
+    /// Another form of this token to match in the input, like `[ mask ]` for `[MASK]`. It is
+    /// matched just like the `content`, but the token keeps its `content` in the `Encoding`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalized_match: Option<String>,
+    /// The regex used to match this token, in which case `content` is its pattern
+    #[serde(skip)]
+    regex: Option<TokenRegex>,
+}
+
+/// Gives the id of a regex `AddedToken` match from its content
+pub type RegexTokenIdFn = Arc<dyn Fn(&str) -> Option<u32> + Send + Sync>;
+
+/// How the matches of a regex `AddedToken` get their ids
+#[derive(Clone)]
+pub enum RegexTokenIds {
+    /// The first number found in the match is an offset in this range of ids
+    Range(std::ops::Range<u32>),
+    /// The ids are given by this function, from the matched content
+    Fn(RegexTokenIdFn),
+}
+
+impl RegexTokenIds {
+    fn id(&self, content: &str) -> Option<u32> {
+        match self {
+            Self::Range(range) => {
+                let digits = content
+                    .split(|c: char| !c.is_ascii_digit())
+                    .find(|d| !d.is_empty())?;
+                let id = range.start.checked_add(digits.parse().ok()?)?;
+                range.contains(&id).then_some(id)
+            }
+            Self::Fn(f) => f(content),
+        }
+    }
+}
+
+impl std::fmt::Debug for RegexTokenIds {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Range(range) => f.debug_tuple("Range").field(range).finish(),
+            Self::Fn(_) => f.debug_tuple("Fn").finish(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct TokenRegex {
+    regex: Regex,
+    /// The same regex, but only matching whole tokens
+    anchored: Regex,
+    ids: RegexTokenIds,
 }
 
 impl AddedToken {
@@ -66,6 +130,50 @@ impl AddedToken {
         self.normalized = normalized;
         self
     }
+    /// Specify another form of this token to match in the input text, in addition to its
+    /// content. Both are matched in the same way, according to the other options.
+    #[must_use]
+    pub fn normalized_match<S: Into<String>>(mut self, normalized_match: S) -> Self {
+        self.normalized_match = Some(normalized_match.into());
+        self
+    }
+    /// All the forms of this token to match in the input text
+    fn match_forms(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.content.as_str()).chain(self.normalized_match.as_deref())
+    }
+    /// Build a special token matching all the occurences of the given regex `pattern`, like
+    /// a family of sentinel tokens. The id of each match is given by `id_fn`, and the matches
+    /// for which it returns `None` are ignored.
+    ///
+    /// These tokens are matched against the raw input, before any other added token. They
+    /// don't take any room in the added vocabulary, and are not saved with the `Tokenizer`, so
+    /// their ids should usually be part of the `Model` vocabulary for the decoding to work.
+    pub fn regex<F>(pattern: &str, id_fn: F) -> Result<Self>
+    where
+        F: Fn(&str) -> Option<u32> + Send + Sync + 'static,
+    {
+        Self::with_regex(pattern, RegexTokenIds::Fn(Arc::new(id_fn)))
+    }
+    /// Build a special token matching all the occurences of the given regex `pattern`, like
+    /// `regex`, but using the first number found in each match as an offset in the given
+    /// range of ids. With `<extra_id_\d+>` and `100..200`, `<extra_id_3>` gets the id `103`.
+    pub fn regex_range(pattern: &str, ids: std::ops::Range<u32>) -> Result<Self> {
+        Self::with_regex(pattern, RegexTokenIds::Range(ids))
+    }
+    fn with_regex(pattern: &str, ids: RegexTokenIds) -> Result<Self> {
+        Ok(Self {
+            regex: Some(TokenRegex {
+                regex: Regex::new(pattern)?,
+                anchored: Regex::new(&format!("^(?:{})$", pattern))?,
+                ids,
+            }),
+            ..Self::from(pattern, true)
+        })
+    }
+    /// Whether this token matches a regex pattern instead of its exact content
+    pub fn is_regex(&self) -> bool {
+        self.regex.is_some()
+    }
 }
 impl Default for AddedToken {
     fn default() -> Self {
@@ -76,6 +184,8 @@ impl Default for AddedToken {
             rstrip: false,
             normalized: true,
             special: false,
+            normalized_match: None,
+            regex: None,
         }
     }
 }
@@ -142,11 +252,12 @@ fn space_rightmost_at_start(sentence: &str) -> usize {
 #[derive(Clone, Debug)]
 pub(super) struct AddedVocabulary {
     /// Contains the mapping from String (token content) to ID. This map contains both special
-    /// tokens and classic added tokens that were added to the this vocabulary.
-    added_tokens_map: HashMap<String, u32>,
+    /// tokens and classic added tokens that were added to the this vocabulary. It is shared
+    /// between clones, like the other maps and the tries.
+    added_tokens_map: Arc<HashMap<String, u32>>,
     /// Contains the mapping from ID to AddedToken for all the added tokens, both special
     /// and classic.
-    added_tokens_map_r: HashMap<u32, AddedToken>,
+    added_tokens_map_r: Arc<HashMap<u32, AddedToken>>,
 
     /// Contains only the classic AddedToken, in the specific order the user gave them.
     added_tokens: Vec<AddedToken>,
@@ -157,10 +268,14 @@ pub(super) struct AddedVocabulary {
     /// us remove them easily with an O(1) complexity.
     special_tokens_set: HashSet<String>,
 
+    /// Contains the AddedToken matching a regex pattern, in the specific order the user gave
+    /// them. These are matched before any other AddedToken.
+    regex_tokens: Vec<AddedToken>,
+
     /// A RegexSet containing all the non-normalized patterns used to split on AddedTokens
-    split_trie: MatchingSet,
+    split_trie: Arc<MatchingSet>,
     /// A RegexSet containing all the normalized patterns used to split on AddedTokens
-    split_normalized_trie: MatchingSet,
+    split_normalized_trie: Arc<MatchingSet>,
 }
 
 impl AddedVocabulary {
@@ -172,13 +287,14 @@ impl AddedVocabulary {
             .match_kind(MatchKind::LeftmostLongest)
             .build::<_, &&[u8]>([]);
         Self {
-            added_tokens_map: HashMap::new(),
-            added_tokens_map_r: HashMap::new(),
+            added_tokens_map: Arc::default(),
+            added_tokens_map_r: Arc::default(),
             added_tokens: vec![],
             special_tokens: vec![],
             special_tokens_set: HashSet::new(),
-            split_trie: (trie, vec![]),
-            split_normalized_trie: (normalized_trie, vec![]),
+            regex_tokens: vec![],
+            split_trie: Arc::new((trie, vec![])),
+            split_normalized_trie: Arc::new((normalized_trie, vec![])),
         }
     }
 
@@ -192,7 +308,8 @@ impl AddedVocabulary {
         &self.added_tokens_map
     }
 
-    /// Get the id matching one of our token if it exists
+    /// Get the id matching one of our token if it exists. The added tokens take precedence
+    /// over the vocabulary of the model.
     pub fn token_to_id(&self, token: &str, model: &impl Model) -> Option<u32> {
         self.added_tokens_map
             .get(token)
@@ -200,7 +317,8 @@ impl AddedVocabulary {
             .or_else(|| model.token_to_id(token))
     }
 
-    /// Get the token matching the given id if it exists
+    /// Get the token matching the given id if it exists. The added tokens take precedence
+    /// over the vocabulary of the model.
     pub fn id_to_token(&self, id: u32, model: &impl Model) -> Option<String> {
         self.added_tokens_map_r
             .get(&id)
@@ -208,9 +326,27 @@ impl AddedVocabulary {
             .or_else(|| model.id_to_token(id))
     }
 
+    /// Get the token matching the given id like `id_to_token`, borrowing it when possible
+    pub(crate) fn id_to_token_cow<'a>(
+        &'a self,
+        id: u32,
+        model: &'a impl Model,
+    ) -> Option<Cow<'a, str>> {
+        self.added_tokens_map_r
+            .get(&id)
+            .map(|t| Cow::Borrowed(t.content.as_str()))
+            .or_else(|| model.id_to_token_cow(id))
+    }
+
     /// Check if a token is a special token
     pub fn is_special_token(&self, token: &str) -> bool {
         self.special_tokens_set.contains(token)
+            || self
+                .regex_tokens
+                .iter()
+                .filter(|t| t.special)
+                .filter_map(|t| t.regex.as_ref())
+                .any(|r| r.anchored.is_match(token))
     }
 
     /// Add some special tokens to the vocabulary
@@ -223,15 +359,45 @@ impl AddedVocabulary {
         self.add_tokens(tokens, model, normalizer)
     }
 
-    /// Add some tokens to the vocabulary
+    /// Add some tokens to the vocabulary. A new token whose id would already be used by another
+    /// one, like when the model vocabulary has some holes, gets the next free id instead.
     pub fn add_tokens<N: Normalizer>(
         &mut self,
         tokens: &[AddedToken],
         model: &impl Model,
         normalizer: Option<&N>,
     ) -> usize {
+        self.try_add_tokens(tokens, model, normalizer, false)
+            .expect("Adding tokens can only fail when strict")
+    }
+
+    /// Add some tokens to the vocabulary like `add_tokens`. When `strict`, a new token whose id
+    /// would already be used by another one makes it fail with an [`AddedTokenIdCollision`],
+    /// leaving the vocabulary untouched.
+    pub fn try_add_tokens<N: Normalizer>(
+        &mut self,
+        tokens: &[AddedToken],
+        model: &impl Model,
+        normalizer: Option<&N>,
+        strict: bool,
+    ) -> Result<usize> {
+        if strict {
+            self.check_id_collisions(tokens, model)?;
+        }
+
+        // Regex tokens don't have any id of their own, we just keep them aside
+        let (regex_tokens, tokens): (Vec<&AddedToken>, Vec<&AddedToken>) =
+            tokens.iter().partition(|token| token.is_regex());
+        let mut added = 0;
+        for token in regex_tokens {
+            if !self.regex_tokens.contains(token) {
+                self.regex_tokens.push(token.clone());
+                added += 1;
+            }
+        }
+
         // Handle special tokens (if any)
-        for token in tokens {
+        for &token in &tokens {
             if token.special
                 && !token.content.is_empty()
                 && !self.special_tokens_set.contains(&token.content)
@@ -243,7 +409,7 @@ impl AddedVocabulary {
 
         // Then we delegate to `add_tokens`, that will take care of refreshing added tokens too.
         let mut ignored = 0;
-        for token in tokens {
+        for &token in &tokens {
             if token.content.is_empty() {
                 ignored += 1;
                 continue;
@@ -253,8 +419,9 @@ impl AddedVocabulary {
                 ignored += 1;
                 id
             } else {
-                let new_id = (model.get_vocab_size() + self.added_tokens_map.len()) as u32;
-                self.added_tokens_map.insert(token.content.clone(), new_id);
+                let first_id = (model.get_vocab_size() + self.added_tokens_map.len()) as u32;
+                let new_id = self.free_id(first_id, &token.content, model, strict)?;
+                Arc::make_mut(&mut self.added_tokens_map).insert(token.content.clone(), new_id);
 
                 if !self.special_tokens_set.contains(&token.content) {
                     self.added_tokens.push(token.clone());
@@ -264,7 +431,7 @@ impl AddedVocabulary {
             };
 
             // Update the current revert operation
-            self.added_tokens_map_r
+            Arc::make_mut(&mut self.added_tokens_map_r)
                 .entry(id)
                 .and_modify(|t| *t = token.clone())
                 .or_insert_with(|| token.clone());
@@ -273,14 +440,57 @@ impl AddedVocabulary {
         self.refresh_added_tokens(model, normalizer);
 
         // Return the number of added tokens
-        tokens.len() - ignored
+        Ok(added + tokens.len() - ignored)
+    }
+
+    /// The first id from `id` used neither by an added token nor by the model, for the given
+    /// new `token`. An id used by the model makes it fail instead when `strict`.
+    fn free_id(&self, mut id: u32, token: &str, model: &impl Model, strict: bool) -> Result<u32> {
+        loop {
+            if !self.added_tokens_map_r.contains_key(&id) {
+                match model.id_to_token(id) {
+                    None => return Ok(id),
+                    Some(existing) if strict => {
+                        return Err(Box::new(AddedTokenIdCollision {
+                            token: token.to_owned(),
+                            id,
+                            existing,
+                        }))
+                    }
+                    Some(_) => {}
+                }
+            }
+            id += 1;
+        }
+    }
+
+    /// Check that none of the new tokens among the given ones would get an id used by the model,
+    /// before adding any of them
+    fn check_id_collisions(&self, tokens: &[AddedToken], model: &impl Model) -> Result<()> {
+        let mut next_id = (model.get_vocab_size() + self.added_tokens_map.len()) as u32;
+        let mut seen = HashSet::new();
+        for token in tokens {
+            if token.is_regex()
+                || token.content.is_empty()
+                || self.token_to_id(&token.content, model).is_some()
+                || !seen.insert(token.content.as_str())
+            {
+                continue;
+            }
+            next_id = self.free_id(next_id, &token.content, model, true)? + 1;
+        }
+        Ok(())
     }
 
     /// Reconstruct our internal RegexSet when new tokens are added to the vocabulary.
     ///
     /// We keep two different RegexSet, one that will take care of matching against the
     /// non-normalized string, and one matching against the normalized one.
-    fn refresh_added_tokens<N: Normalizer>(&mut self, model: &impl Model, normalizer: Option<&N>) {
+    pub(super) fn refresh_added_tokens<N: Normalizer>(
+        &mut self,
+        model: &impl Model,
+        normalizer: Option<&N>,
+    ) {
         type TupleTokenId<'a> = (&'a AddedToken, u32);
         let (normalized, non_normalized): (Vec<TupleTokenId>, Vec<TupleTokenId>) = self
             .special_tokens
@@ -295,27 +505,30 @@ impl AddedVocabulary {
             })
             .partition(|(token, _)| token.normalized);
 
-        let (tokens, ids): (Vec<&AddedToken>, Vec<u32>) = non_normalized.into_iter().unzip();
+        let (patterns, ids): (Vec<&str>, Vec<u32>) = non_normalized
+            .into_iter()
+            .flat_map(|(token, id)| token.match_forms().map(move |form| (form, id)))
+            .unzip();
         let trie = AhoCorasickBuilder::new()
             .match_kind(MatchKind::LeftmostLongest)
-            .build(tokens.iter().map(|token| &token.content));
-        self.split_trie = (trie, ids);
+            .build(patterns);
+        self.split_trie = Arc::new((trie, ids));
 
-        let (ntokens, nids): (Vec<&AddedToken>, Vec<u32>) = normalized.into_iter().unzip();
-        let patterns: Vec<_> = ntokens
-            .iter()
-            .map(|token| {
-                let mut content = NormalizedString::from(token.content.as_ref());
+        let (patterns, nids): (Vec<NormalizedString>, Vec<u32>) = normalized
+            .into_iter()
+            .flat_map(|(token, id)| token.match_forms().map(move |form| (form, id)))
+            .map(|(form, id)| {
+                let mut content = NormalizedString::from(form);
                 if let Some(n) = normalizer {
                     n.normalize(&mut content).unwrap();
                 }
-                content
+                (content, id)
             })
-            .collect();
+            .unzip();
         let normalized_trie = AhoCorasickBuilder::new()
             .match_kind(MatchKind::LeftmostLongest)
             .build(patterns.iter().map(|content| content.get()));
-        self.split_normalized_trie = (normalized_trie, nids);
+        self.split_normalized_trie = Arc::new((normalized_trie, nids));
     }
 
     /// Find any AddedToken in the given sentence, using the provided MatchingSet.
@@ -326,6 +539,46 @@ impl AddedVocabulary {
         &self,
         sentence: &str,
         split_re: &'a MatchingSet,
+    ) -> Vec<(Option<u32>, Offsets)> {
+        let matches = split_re.0.find_iter(sentence).map(|mat| {
+            let id = split_re.1[mat.pattern()];
+            (id, mat.start(), mat.end(), &self.added_tokens_map_r[&id])
+        });
+        Self::split_on_matches(sentence, matches)
+    }
+
+    /// Find any regex AddedToken in the given sentence, like `find_matches` does. When
+    /// several of them overlap, the leftmost and then longest one is kept.
+    fn find_regex_matches(&self, sentence: &str) -> Vec<(Option<u32>, Offsets)> {
+        let mut matches = self
+            .regex_tokens
+            .iter()
+            .filter_map(|token| Some((token, token.regex.as_ref()?)))
+            .flat_map(|(token, regex)| {
+                regex.regex.find_iter(sentence).filter_map(move |mat| {
+                    let id = regex.ids.id(mat.as_str())?;
+                    Some((id, mat.start(), mat.end(), token))
+                })
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by_key(|(_, start, stop, _)| (*start, std::cmp::Reverse(*stop)));
+
+        let mut last_stop = 0;
+        let matches = matches.into_iter().filter(move |(_, start, stop, _)| {
+            let keep = *start >= last_stop && start != stop;
+            if keep {
+                last_stop = *stop;
+            }
+            keep
+        });
+        Self::split_on_matches(sentence, matches)
+    }
+
+    /// Build the splits covering the entire sentence out of the given ordered matches, while
+    /// applying the options of their AddedToken.
+    fn split_on_matches<'a>(
+        sentence: &str,
+        matches: impl Iterator<Item = (u32, usize, usize, &'a AddedToken)>,
     ) -> Vec<(Option<u32>, Offsets)> {
         if sentence.is_empty() {
             return vec![(None, (0, 0))];
@@ -334,12 +587,7 @@ impl AddedVocabulary {
         let mut start_offset = 0;
         let mut splits = vec![];
 
-        for mat in split_re.0.find_iter(sentence) {
-            let mut start = mat.start();
-            let mut stop = mat.end();
-            let aho_id = mat.pattern();
-            let id = split_re.1[aho_id];
-            let added_token = &self.added_tokens_map_r.get(&id).unwrap();
+        for (id, mut start, mut stop, added_token) in matches {
             if added_token.single_word {
                 let start_space = start == 0 || !ends_with_word(&sentence[..start]);
                 let stop_space = stop == sentence.len() || !starts_with_word(&sentence[stop..]);
@@ -385,15 +633,38 @@ impl AddedVocabulary {
         sentence: NormalizedString,
         split_re: &MatchingSet,
     ) -> Vec<(NormalizedString, Option<Vec<Token>>)> {
-        self.find_matches(sentence.get(), split_re)
+        let splits = self.find_matches(sentence.get(), split_re);
+        Self::split_with_matches(sentence, splits, Some(&self.added_tokens_map_r))
+    }
+
+    /// Split the input sentence on the given splits, covering all of it. The tokens keep the
+    /// original content of their match, even once it got normalized, unless they are found
+    /// in `tokens` with a `normalized_match`, in which case they get their own content.
+    fn split_with_matches(
+        sentence: NormalizedString,
+        splits: Vec<(Option<u32>, Offsets)>,
+        tokens: Option<&HashMap<u32, AddedToken>>,
+    ) -> Vec<(NormalizedString, Option<Vec<Token>>)> {
+        // When nothing matched, the slice covering all of it would just be a copy
+        if let [(None, (0, end))] = splits[..] {
+            let original_range = 0..sentence.len_original();
+            if sentence.convert_offsets(Range::Normalized(0..end)) == Some(original_range) {
+                return vec![(sentence, None)];
+            }
+        }
+
+        splits
             .into_iter()
             .map(|(id, byte_offsets)| {
                 let slice = sentence
                     .slice(Range::Normalized(byte_offsets.0..byte_offsets.1))
                     .expect("AddedVocabulary bad split");
                 if let Some(id) = id {
-                    let value = slice.get().to_owned();
-                    let len = value.len();
+                    let value = match tokens.and_then(|tokens| tokens.get(&id)) {
+                        Some(token) if token.normalized_match.is_some() => token.content.clone(),
+                        _ => slice.get_original().to_owned(),
+                    };
+                    let len = slice.len();
                     (slice, Some(vec![Token::new(id, value, (0, len))]))
                 } else {
                     (slice, None)
@@ -408,12 +679,41 @@ impl AddedVocabulary {
     /// non-normalized one. For example, when we expect to extract the token `yesterday` in the
     /// input sentence `I read a book Yesterday`, if the normalizer is supposed to lowercase
     /// everything, we expect a match.
+    ///
+    /// The tokens that are not `normalized` are matched against the raw input first, and the
+    /// `normalized` ones are then matched against what remains, once normalized. This means
+    /// that when both could match the same span, the non-`normalized` token wins. In both
+    /// cases, the offsets still refer to the original input. The regex tokens are extracted
+    /// before all of them.
     pub fn extract_and_normalize<N: Normalizer>(
         &self,
         normalizer: Option<&N>,
         sequence: &str,
     ) -> PreTokenizedString {
-        let mut pretokenized: PreTokenizedString = sequence.into();
+        let mut pretokenized = PreTokenizedString::default();
+        self.extract_and_normalize_into(normalizer, sequence, &mut pretokenized);
+        pretokenized
+    }
+
+    /// Like `extract_and_normalize`, but reusing the buffers of the given `pretokenized`,
+    /// whose previous content gets discarded.
+    pub(crate) fn extract_and_normalize_into<N: Normalizer>(
+        &self,
+        normalizer: Option<&N>,
+        sequence: &str,
+        pretokenized: &mut PreTokenizedString,
+    ) {
+        pretokenized.reset(sequence);
+
+        // 0. We extract the regex tokens, that always match against the non-normalized string
+        if !self.regex_tokens.is_empty() {
+            pretokenized
+                .split(|_, sequence| {
+                    let splits = self.find_regex_matches(sequence.get());
+                    Ok(Self::split_with_matches(sequence, splits, None))
+                })
+                .expect("AddedVocabulary bad split");
+        }
 
         // 1. We extract all the non-normalized tokens from the non-normalized string
         pretokenized
@@ -427,8 +727,6 @@ impl AddedVocabulary {
                 Ok(self.split_with_indices(sequence, &self.split_normalized_trie))
             })
             .expect("AddedVocabulary bad split");
-
-        pretokenized
     }
 }
 
@@ -442,7 +740,7 @@ pub(super) struct AddedTokenWithId {
 }
 
 impl Serialize for AddedVocabulary {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
@@ -557,6 +855,9 @@ mod tests {
         fn save(&self, _folder: &Path, _name: Option<&str>) -> Result<Vec<PathBuf>> {
             unimplemented!()
         }
+        fn serialize_parts(&self) -> Result<Vec<(String, Vec<u8>)>> {
+            unimplemented!()
+        }
         fn get_trainer(&self) -> Self::Trainer {
             TrainerMock
         }
@@ -601,6 +902,58 @@ mod tests {
         assert_eq!(vocab.len(), 2);
     }
 
+    #[test]
+    fn added_ids_collision() {
+        // There is a hole at 2, and the model size makes the next id the one of `[UNK]`
+        let model = ModelMock::new(&[("a", 0), ("b", 1), ("[UNK]", 3)]);
+        let normalizer: Option<&NormalizerWrapper> = None;
+        let tokens = [
+            AddedToken::from("[CLS]", true),
+            AddedToken::from("[SEP]", true),
+        ];
+
+        let mut vocab = AddedVocabulary::new();
+        let err = vocab
+            .try_add_tokens(&tokens, &model, normalizer, true)
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<AddedTokenIdCollision>(),
+            Some(&AddedTokenIdCollision {
+                token: "[CLS]".into(),
+                id: 3,
+                existing: "[UNK]".into(),
+            })
+        );
+        assert_eq!(vocab.len(), 0);
+        assert!(!vocab.is_special_token("[CLS]"));
+
+        let mut vocab = AddedVocabulary::new();
+        assert_eq!(vocab.add_special_tokens(&tokens, &model, normalizer), 2);
+        assert_eq!(vocab.token_to_id("[CLS]", &model), Some(4));
+        assert_eq!(vocab.token_to_id("[SEP]", &model), Some(5));
+        assert_eq!(vocab.token_to_id("[UNK]", &model), Some(3));
+        assert_eq!(vocab.id_to_token(3, &model), Some("[UNK]".into()));
+        assert_eq!(vocab.id_to_token(4, &model), Some("[CLS]".into()));
+        let result = vocab.extract_and_normalize(normalizer, "[CLS] a [SEP]");
+        assert_eq!(
+            simplify_output(&result),
+            vec![
+                ("[CLS]", Some(vec![4])),
+                (" a ", None),
+                ("[SEP]", Some(vec![5]))
+            ]
+        );
+
+        // The ids of the other added tokens get skipped in strict mode too
+        assert_eq!(
+            vocab
+                .try_add_tokens(&[AddedToken::from("c", false)], &model, normalizer, true)
+                .unwrap(),
+            1
+        );
+        assert_eq!(vocab.token_to_id("c", &model), Some(6));
+    }
+
     #[test]
     fn can_add_special_tokens() {
         let model = ModelMock::new(&[("test", 0), ("tost", 1)]);
@@ -641,6 +994,30 @@ mod tests {
         assert!(!vocab.added_tokens_map.contains_key("test"));
     }
 
+    #[test]
+    fn clone_shares_vocab() {
+        let model = ModelMock::new(&[("test", 0)]);
+        let normalizer: Option<&NormalizerWrapper> = None;
+        let mut vocab = AddedVocabulary::new();
+        vocab.add_tokens(&[AddedToken::from("added", false)], &model, normalizer);
+
+        let mut clone = vocab.clone();
+        assert!(Arc::ptr_eq(
+            &vocab.added_tokens_map,
+            &clone.added_tokens_map
+        ));
+        assert!(Arc::ptr_eq(&vocab.split_trie, &clone.split_trie));
+
+        // Modifying the clone doesn't change the original
+        clone.add_tokens(&[AddedToken::from("other", false)], &model, normalizer);
+        assert!(!Arc::ptr_eq(
+            &vocab.added_tokens_map,
+            &clone.added_tokens_map
+        ));
+        assert_eq!(vocab.len(), 1);
+        assert_eq!(clone.len(), 2);
+    }
+
     #[test]
     fn can_extract_added_tokens() {
         // Is able to extract both normal and special tokens
@@ -731,6 +1108,63 @@ mod tests {
         );
     }
 
+    #[test]
+    fn normalized_matching() {
+        use crate::normalizers::{StripAccents, NFD};
+
+        let model = ModelMock::new(&[]);
+        let normalizer: NormalizerWrapper =
+            crate::normalizers::Sequence::new(vec![NFD.into(), StripAccents.into()]).into();
+        let mut vocab = AddedVocabulary::new();
+
+        vocab.add_tokens(
+            &[
+                AddedToken::from("café", false),
+                AddedToken::from("thé", false).normalized(false),
+            ],
+            &model,
+            Some(&normalizer),
+        );
+
+        // `café` is matched after normalization, `thé` only on the raw input
+        let result = vocab.extract_and_normalize(Some(&normalizer), "cafe café the thé");
+        assert_eq!(
+            simplify_output(&result),
+            vec![
+                ("cafe", Some(vec![0])),
+                (" ", None),
+                ("cafe", Some(vec![0])),
+                (" the ", None),
+                ("thé", Some(vec![1])),
+            ]
+        );
+        assert_eq!(
+            result
+                .get_splits(OffsetReferential::Original, OffsetType::Byte)
+                .into_iter()
+                .filter(|(_, _, tokens)| tokens.is_some())
+                .map(|(_, offsets, _)| offsets)
+                .collect::<Vec<_>>(),
+            vec![(0, 4), (5, 10), (15, 19)]
+        );
+
+        // When both could match the same span, the non-normalized one wins
+        vocab.add_tokens(
+            &[AddedToken::from("cafe", false).normalized(false)],
+            &model,
+            Some(&normalizer),
+        );
+        let result = vocab.extract_and_normalize(Some(&normalizer), "cafe café");
+        assert_eq!(
+            simplify_output(&result),
+            vec![
+                ("cafe", Some(vec![2])),
+                (" ", None),
+                ("cafe", Some(vec![0]))
+            ]
+        );
+    }
+
     #[test]
     fn empty_matches() {
         let vocab = AddedVocabulary::new();
@@ -825,4 +1259,131 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn normalized_match() {
+        let model = ModelMock::new(&[]);
+        let mut vocab = AddedVocabulary::new();
+        let normalizer = Lowercase;
+
+        vocab.add_special_tokens(
+            &[AddedToken::from("[MASK]", true)
+                .normalized_match("[ mask ]")
+                .lstrip(true)],
+            &model,
+            Some(&normalizer),
+        );
+        vocab.add_tokens(
+            &[AddedToken::from("yesterday", false)
+                .normalized_match("YDay")
+                .single_word(true)],
+            &model,
+            Some(&normalizer),
+        );
+        let result = vocab.extract_and_normalize(
+            Some(&normalizer),
+            "I read a [ mask ] yday, [MASK] Yesterday, not ydays",
+        );
+        assert_eq!(
+            simplify_output(&result),
+            vec![
+                ("i read a", None),
+                (" [ mask ]", Some(vec![0])),
+                (" ", None),
+                ("yday", Some(vec![1])),
+                (",", None),
+                (" [MASK]", Some(vec![0])),
+                (" ", None),
+                ("yesterday", Some(vec![1])),
+                (", not ydays", None),
+            ]
+        );
+        // The tokens keep their own content, whatever form matched
+        let values = result
+            .get_splits(OffsetReferential::Original, OffsetType::Byte)
+            .into_iter()
+            .filter_map(|(_, _, tokens)| Some(tokens.as_ref()?[0].value.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(values, ["[MASK]", "yesterday", "[MASK]", "yesterday"]);
+
+        let token = AddedToken::from("[MASK]", true).normalized_match("[ mask ]");
+        assert_eq!(
+            serde_json::to_string(&token).unwrap(),
+            r#"{"content":"[MASK]","single_word":false,"lstrip":false,"rstrip":false,"normalized":false,"special":true,"normalized_match":"[ mask ]"}"#
+        );
+    }
+
+    #[test]
+    fn regex_tokens() {
+        let model = ModelMock::new(&[]);
+        let mut vocab = AddedVocabulary::new();
+        let normalizer = Lowercase;
+
+        assert_eq!(
+            vocab.add_tokens(
+                &[
+                    AddedToken::regex_range(r"<extra_id_\d+>", 100..200).unwrap(),
+                    AddedToken::regex_range(r"<extra_id_\d+>", 100..200).unwrap(),
+                    AddedToken::from("<mask>", false),
+                ],
+                &model,
+                Some(&normalizer),
+            ),
+            2
+        );
+        // Regex tokens don't take any room in the vocabulary
+        assert_eq!(vocab.len(), 1);
+
+        let result = vocab.extract_and_normalize(
+            Some(&normalizer),
+            "Hey <extra_id_0> you<extra_id_12><MASK> <extra_id_3><extra_id_100>",
+        );
+        assert_eq!(
+            simplify_output(&result),
+            vec![
+                ("hey ", None),
+                ("<extra_id_0>", Some(vec![100])),
+                (" you", None),
+                ("<extra_id_12>", Some(vec![112])),
+                ("<mask>", Some(vec![0])),
+                (" ", None),
+                ("<extra_id_3>", Some(vec![103])),
+                // Out of the range of ids
+                ("<extra_id_100>", None),
+            ]
+        );
+        assert!(vocab.is_special_token("<extra_id_42>"));
+        assert!(!vocab.is_special_token("Hey <extra_id_42>"));
+    }
+
+    #[test]
+    fn regex_tokens_options() {
+        let model = ModelMock::new(&[]);
+        let mut vocab = AddedVocabulary::new();
+        let normalizer: Option<&NormalizerWrapper> = None;
+
+        let token = AddedToken::regex(r"\[[A-Z]+\]", |content| match content {
+            "[A]" => Some(1),
+            "[BB]" => Some(2),
+            _ => None,
+        })
+        .unwrap()
+        .lstrip(true)
+        .rstrip(true);
+        vocab.add_tokens(&[token], &model, normalizer);
+
+        let result = vocab.extract_and_normalize(normalizer, "a [A] b  [BB] c [C] d");
+        assert_eq!(
+            simplify_output(&result),
+            vec![
+                ("a", None),
+                (" [A] ", Some(vec![1])),
+                ("b", None),
+                ("  [BB] ", Some(vec![2])),
+                ("c [C] d", None),
+            ]
+        );
+
+        assert!(AddedToken::regex_range("<extra(", 0..1).is_err());
+    }
 }
@@ -0,0 +1,607 @@
+use super::{
+    Decoder, Model, Normalizer, PostProcessor, PreTokenizer, Result, Tokenizer, TokenizerImpl,
+};
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use serde::{forward_to_deserialize_any, Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// The first bytes of any file in the binary format, the last one being its version
+const MAGIC: &[u8] = b"TKBIN\x01";
+
+const NULL: u8 = 0;
+const FALSE: u8 = 1;
+const TRUE: u8 = 2;
+const UNSIGNED: u8 = 3;
+const NEGATIVE: u8 = 4;
+const FLOAT: u8 = 5;
+const STRING: u8 = 6;
+const ARRAY: u8 = 7;
+const OBJECT: u8 = 8;
+
+#[derive(thiserror::Error, Debug)]
+pub enum BinaryFormatError {
+    #[error("Not a tokenizer in the binary format, or saved with an unsupported version")]
+    InvalidHeader,
+    #[error("Unexpected end of the binary tokenizer")]
+    UnexpectedEnd,
+    #[error("Invalid value tag {0} at byte {1} of the binary tokenizer")]
+    InvalidTag(u8, usize),
+    #[error("Invalid UTF-8 string at byte {0} of the binary tokenizer")]
+    InvalidString(usize),
+    #[error("Unsupported number {0} in the binary tokenizer")]
+    InvalidNumber(String),
+    #[error("Unexpected bytes after the end of the binary tokenizer, at byte {0}")]
+    TrailingBytes(usize),
+    #[error("{0}")]
+    Custom(String),
+}
+
+impl de::Error for BinaryFormatError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self::Custom(msg.to_string())
+    }
+}
+
+fn write_len(buffer: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        buffer.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    buffer.push(n as u8);
+}
+
+fn write_str(buffer: &mut Vec<u8>, s: &str) {
+    write_len(buffer, s.len() as u64);
+    buffer.extend_from_slice(s.as_bytes());
+}
+
+/// Write the given value, each one being a tag followed by its content. All the lengths
+/// and integers are written as LEB128 varints.
+fn write_value(buffer: &mut Vec<u8>, value: &Value) -> Result<()> {
+    match value {
+        Value::Null => buffer.push(NULL),
+        Value::Bool(false) => buffer.push(FALSE),
+        Value::Bool(true) => buffer.push(TRUE),
+        Value::Number(n) => {
+            if let Some(n) = n.as_u64() {
+                buffer.push(UNSIGNED);
+                write_len(buffer, n);
+            } else if let Some(n) = n.as_i64() {
+                buffer.push(NEGATIVE);
+                write_len(buffer, !(n as u64));
+            } else if let Some(n) = n.as_f64() {
+                buffer.push(FLOAT);
+                buffer.extend_from_slice(&n.to_le_bytes());
+            } else {
+                return Err(Box::new(BinaryFormatError::InvalidNumber(n.to_string())));
+            }
+        }
+        Value::String(s) => {
+            buffer.push(STRING);
+            write_str(buffer, s);
+        }
+        Value::Array(values) => {
+            buffer.push(ARRAY);
+            write_len(buffer, values.len() as u64);
+            for value in values {
+                write_value(buffer, value)?;
+            }
+        }
+        Value::Object(map) => {
+            buffer.push(OBJECT);
+            write_len(buffer, map.len() as u64);
+            for (key, value) in map {
+                write_str(buffer, key);
+                write_value(buffer, value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+type ReadResult<T> = std::result::Result<T, BinaryFormatError>;
+
+/// Deserializes the values written by `write_value` straight from the bytes, borrowing the
+/// strings, like `serde_json` does for the JSON
+struct Deserializer<'de> {
+    bytes: &'de [u8],
+    pos: usize,
+}
+
+impl<'de> Deserializer<'de> {
+    fn peek(&self) -> ReadResult<u8> {
+        self.bytes
+            .get(self.pos)
+            .copied()
+            .ok_or(BinaryFormatError::UnexpectedEnd)
+    }
+
+    fn byte(&mut self) -> ReadResult<u8> {
+        let byte = self.peek()?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn take(&mut self, n: usize) -> ReadResult<&'de [u8]> {
+        if self.bytes.len() - self.pos < n {
+            return Err(BinaryFormatError::UnexpectedEnd);
+        }
+        let taken = &self.bytes[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(taken)
+    }
+
+    fn len(&mut self) -> ReadResult<u64> {
+        let mut n = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            if shift >= 64 {
+                return Err(BinaryFormatError::InvalidTag(byte, self.pos - 1));
+            }
+            n |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+            shift += 7;
+        }
+    }
+
+    fn str(&mut self) -> ReadResult<&'de str> {
+        let len = self.len()? as usize;
+        let start = self.pos;
+        let bytes = self.take(len)?;
+        std::str::from_utf8(bytes).map_err(|_| BinaryFormatError::InvalidString(start))
+    }
+
+    fn invalid_tag(&self, tag: u8) -> BinaryFormatError {
+        BinaryFormatError::InvalidTag(tag, self.pos - 1)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = BinaryFormatError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> ReadResult<V::Value> {
+        match self.byte()? {
+            NULL => visitor.visit_unit(),
+            FALSE => visitor.visit_bool(false),
+            TRUE => visitor.visit_bool(true),
+            UNSIGNED => visitor.visit_u64(self.len()?),
+            NEGATIVE => visitor.visit_i64(!self.len()? as i64),
+            FLOAT => {
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(self.take(8)?);
+                visitor.visit_f64(f64::from_le_bytes(bytes))
+            }
+            STRING => visitor.visit_borrowed_str(self.str()?),
+            ARRAY => {
+                let remaining = self.len()?;
+                visitor.visit_seq(Compound {
+                    de: self,
+                    remaining,
+                })
+            }
+            OBJECT => {
+                let remaining = self.len()?;
+                visitor.visit_map(Compound {
+                    de: self,
+                    remaining,
+                })
+            }
+            tag => Err(self.invalid_tag(tag)),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> ReadResult<V::Value> {
+        if self.peek()? == NULL {
+            self.pos += 1;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> ReadResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// The enums are written like in JSON, as a string for the unit variants, and as a map
+    /// with a single entry for the other ones
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> ReadResult<V::Value> {
+        match self.byte()? {
+            STRING => visitor.visit_enum(self.str()?.into_deserializer()),
+            OBJECT if self.len()? == 1 => visitor.visit_enum(self),
+            tag => Err(self.invalid_tag(tag)),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// The content of an array or an object, with the number of values left to read
+struct Compound<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    remaining: u64,
+}
+
+impl<'de, 'a> SeqAccess<'de> for Compound<'a, 'de> {
+    type Error = BinaryFormatError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> ReadResult<Option<T::Value>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        // The length can't be trusted before reading the values
+        Some((self.remaining as usize).min(self.de.bytes.len() - self.de.pos))
+    }
+}
+
+impl<'de, 'a> MapAccess<'de> for Compound<'a, 'de> {
+    type Error = BinaryFormatError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> ReadResult<Option<K::Value>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(MapKey(self.de.str()?)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> ReadResult<V::Value> {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some((self.remaining as usize).min(self.de.bytes.len() - self.de.pos))
+    }
+}
+
+impl<'de> EnumAccess<'de> for &mut Deserializer<'de> {
+    type Error = BinaryFormatError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> ReadResult<(V::Value, Self)> {
+        let variant = seed.deserialize(self.str()?.into_deserializer())?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for &mut Deserializer<'de> {
+    type Error = BinaryFormatError;
+
+    fn unit_variant(self) -> ReadResult<()> {
+        de::IgnoredAny::deserialize(self).map(|_| ())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> ReadResult<T::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> ReadResult<V::Value> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> ReadResult<V::Value> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
+
+/// The key of an object, always written as a string like in JSON. The keys of the maps
+/// using numbers get parsed back.
+struct MapKey<'de>(&'de str);
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> ReadResult<V::Value> {
+                match self.0.parse() {
+                    Ok(n) => visitor.$visit(n),
+                    Err(_) => visitor.visit_borrowed_str(self.0),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for MapKey<'de> {
+    type Error = BinaryFormatError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> ReadResult<V::Value> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    deserialize_parsed_key! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> ReadResult<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> ReadResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> ReadResult<V::Value> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        bool i128 u128 f32 f64 char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+fn read_binary<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    if !bytes.starts_with(MAGIC) {
+        return Err(Box::new(BinaryFormatError::InvalidHeader));
+    }
+    let mut deserializer = Deserializer {
+        bytes,
+        pos: MAGIC.len(),
+    };
+    let value = T::deserialize(&mut deserializer)?;
+    if deserializer.pos != bytes.len() {
+        return Err(Box::new(BinaryFormatError::TrailingBytes(deserializer.pos)));
+    }
+    Ok(value)
+}
+
+impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
+where
+    M: Serialize,
+    N: Serialize,
+    PT: Serialize,
+    PP: Serialize,
+    D: Serialize,
+{
+    /// Serialize the current tokenizer in the binary format. It holds exactly what the JSON
+    /// serialization does, but is smaller, and gets deserialized without parsing any text.
+    /// Building the vocabulary of the model still takes most of the loading time though, so
+    /// loading is only a bit faster than with the JSON (see `serialization_benchmark`). The
+    /// JSON format should still be used to share a tokenizer, since the binary one might
+    /// change across versions.
+    pub fn to_binary(&self) -> Result<Vec<u8>> {
+        let value = serde_json::to_value(self)?;
+        let mut buffer = MAGIC.to_vec();
+        write_value(&mut buffer, &value)?;
+        Ok(buffer)
+    }
+
+    /// Save the current tokenizer at the given path, in the binary format
+    pub fn save_binary<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let serialized = self.to_binary()?;
+
+        let mut file = File::create(path)?;
+        file.write_all(&serialized)?;
+
+        Ok(())
+    }
+}
+
+impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
+where
+    M: DeserializeOwned + Model,
+    N: DeserializeOwned + Normalizer,
+    PT: DeserializeOwned + PreTokenizer,
+    PP: DeserializeOwned + PostProcessor,
+    D: DeserializeOwned + Decoder,
+{
+    /// Instantiate a new Tokenizer from bytes in the binary format
+    pub fn from_binary<B: AsRef<[u8]>>(bytes: B) -> Result<Self> {
+        read_binary(bytes.as_ref())
+    }
+
+    /// Instantiate a new Tokenizer from the given file, in the binary format
+    pub fn from_binary_file<P: AsRef<Path>>(file: P) -> Result<Self> {
+        let mut content = vec![];
+        File::open(file)?.read_to_end(&mut content)?;
+        Self::from_binary(content)
+    }
+}
+
+impl Tokenizer {
+    /// Instantiate a new Tokenizer from bytes in the binary format
+    pub fn from_binary<B: AsRef<[u8]>>(bytes: B) -> Result<Self> {
+        read_binary(bytes.as_ref())
+    }
+
+    /// Instantiate a new Tokenizer from the given file, in the binary format
+    pub fn from_binary_file<P: AsRef<Path>>(file: P) -> Result<Self> {
+        let mut content = vec![];
+        File::open(file)?.read_to_end(&mut content)?;
+        read_binary(&content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoders::wordpiece::WordPiece as WordPieceDecoder;
+    use crate::models::unigram::Unigram;
+    use crate::normalizers::bert::BertNormalizer;
+    use crate::pre_tokenizers::bert::BertPreTokenizer;
+    use crate::processors::bert::BertProcessing;
+    use crate::tokenizer::{AddedToken, PaddingParams, TruncationParams};
+
+    fn tokenizer() -> Tokenizer {
+        let pieces = vec![
+            ("<unk>".to_string(), 0.0),
+            ("hello".to_string(), -1.5),
+            ("world".to_string(), -2.25),
+            ("h".to_string(), -3.0),
+            ("e".to_string(), -3.1),
+        ];
+        let mut tokenizer = Tokenizer::new(Unigram::from(pieces, Some(0), None).unwrap());
+        tokenizer
+            .with_normalizer(BertNormalizer::default())
+            .with_pre_tokenizer(BertPreTokenizer)
+            .with_post_processor(BertProcessing::new(
+                ("[SEP]".into(), 101),
+                ("[CLS]".into(), 102),
+            ))
+            .with_decoder(WordPieceDecoder::default())
+            .with_padding(Some(PaddingParams::default()))
+            .with_truncation(Some(TruncationParams {
+                max_length: 6,
+                ..Default::default()
+            }));
+        tokenizer
+            .add_special_tokens(&[
+                AddedToken::from("[CLS]", true),
+                AddedToken::from("[SEP]", true),
+            ])
+            .unwrap();
+        tokenizer
+            .add_tokens(&[AddedToken::from("héllo wörld", false)])
+            .unwrap();
+        tokenizer
+    }
+
+    #[test]
+    fn roundtrip() {
+        let tokenizer = tokenizer();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tokenizer.bin");
+        tokenizer.save_binary(&path).unwrap();
+        let loaded = Tokenizer::from_binary_file(&path).unwrap();
+
+        assert_eq!(
+            loaded.to_string(false).unwrap(),
+            tokenizer.to_string(false).unwrap()
+        );
+        for input in [
+            "Hello world",
+            "héllo wörld hello",
+            "Hello world hello world hello",
+        ] {
+            assert_eq!(
+                loaded.encode(input, true).unwrap(),
+                tokenizer.encode(input, true).unwrap()
+            );
+        }
+        // Smaller than the JSON
+        assert!(
+            std::fs::metadata(&path).unwrap().len()
+                < tokenizer.to_string(false).unwrap().len() as u64
+        );
+    }
+
+    #[test]
+    fn roundtrip_models() {
+        use crate::models::bpe::BPE;
+        use crate::models::wordlevel::WordLevel;
+        use crate::models::wordpiece::WordPiece;
+        use crate::models::{ModelWrapper, UnkPolicy};
+        use std::collections::HashMap;
+
+        let vocab: HashMap<String, u32> = [("<unk>", 0), ("a", 1), ("b", 2), ("ab", 3)]
+            .iter()
+            .map(|(token, id)| (token.to_string(), *id))
+            .collect();
+        let models: Vec<ModelWrapper> = vec![
+            BPE::builder()
+                .vocab_and_merges(vocab.clone(), vec![("a".into(), "b".into())])
+                .unk_policy(UnkPolicy::Token("<unk>".into()))
+                .dropout(0.5)
+                .build()
+                .unwrap()
+                .into(),
+            BPE::builder()
+                .vocab_and_merges(vocab.clone(), vec![])
+                .unk_policy(UnkPolicy::Error)
+                .build()
+                .unwrap()
+                .into(),
+            WordPiece::builder()
+                .vocab(vocab.clone())
+                .build()
+                .unwrap()
+                .into(),
+            WordLevel::builder().vocab(vocab).build().unwrap().into(),
+        ];
+        for model in models {
+            let tokenizer = Tokenizer::new(model);
+            let loaded = Tokenizer::from_binary(tokenizer.to_binary().unwrap()).unwrap();
+            assert_eq!(
+                loaded.to_string(false).unwrap(),
+                tokenizer.to_string(false).unwrap()
+            );
+            assert_eq!(
+                std::mem::discriminant(loaded.get_model()),
+                std::mem::discriminant(tokenizer.get_model())
+            );
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let binary = tokenizer().to_binary().unwrap();
+        assert!(Tokenizer::from_binary(&binary[..binary.len() - 1]).is_err());
+        assert!(Tokenizer::from_binary(&binary[1..]).is_err());
+        assert!(Tokenizer::from_binary(tokenizer().to_string(false).unwrap()).is_err());
+
+        let mut numbers = vec![];
+        let value = serde_json::json!([0, 1, u64::MAX, -1, i64::MIN, 0.5, -1e300, "", {}]);
+        write_value(&mut numbers, &value).unwrap();
+        let mut deserializer = Deserializer {
+            bytes: &numbers,
+            pos: 0,
+        };
+        assert_eq!(Value::deserialize(&mut deserializer).unwrap(), value);
+        assert_eq!(deserializer.pos, numbers.len());
+
+        // Some bytes left after the tokenizer
+        let mut binary = binary;
+        binary.push(NULL);
+        assert!(Tokenizer::from_binary(&binary).is_err());
+    }
+}
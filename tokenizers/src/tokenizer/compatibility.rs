@@ -0,0 +1,128 @@
+use std::any::Any;
+
+use crate::decoders::bpe::BPEDecoder;
+use crate::decoders::sequence::Sequence as DecoderSequence;
+use crate::decoders::wordpiece::WordPiece as WordPieceDecoder;
+use crate::decoders::DecoderWrapper;
+use crate::models::bpe::BPE;
+use crate::models::unigram::Unigram;
+use crate::models::wordlevel::WordLevel;
+use crate::models::wordpiece::WordPiece;
+use crate::models::ModelWrapper;
+use crate::pre_tokenizers::byte_level::ByteLevel;
+use crate::pre_tokenizers::sequence::Sequence;
+use crate::pre_tokenizers::PreTokenizerWrapper;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ModelKind {
+    Bpe,
+    WordPiece,
+    Other,
+}
+
+/// What a decoder needs to make sense of the tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Requirement {
+    ByteLevelPreTokenizer,
+    Model(ModelKind),
+}
+
+fn model_kind(model: &dyn Any) -> Option<ModelKind> {
+    match model.downcast_ref::<ModelWrapper>() {
+        Some(ModelWrapper::BPE(_)) => Some(ModelKind::Bpe),
+        Some(ModelWrapper::WordPiece(_)) => Some(ModelKind::WordPiece),
+        Some(_) => Some(ModelKind::Other),
+        None if model.is::<BPE>() => Some(ModelKind::Bpe),
+        None if model.is::<WordPiece>() => Some(ModelKind::WordPiece),
+        None if model.is::<WordLevel>() || model.is::<Unigram>() => Some(ModelKind::Other),
+        // We know nothing about custom models
+        None => None,
+    }
+}
+
+fn wrapper_has_byte_level(pre_tokenizer: &PreTokenizerWrapper) -> bool {
+    match pre_tokenizer {
+        PreTokenizerWrapper::ByteLevel(_) => true,
+        PreTokenizerWrapper::Sequence(seq) => {
+            seq.get_pretokenizers().iter().any(wrapper_has_byte_level)
+        }
+        _ => false,
+    }
+}
+
+fn has_byte_level(pre_tokenizer: &dyn Any) -> Option<bool> {
+    if let Some(pre_tokenizer) = pre_tokenizer.downcast_ref::<PreTokenizerWrapper>() {
+        Some(wrapper_has_byte_level(pre_tokenizer))
+    } else if let Some(seq) = pre_tokenizer.downcast_ref::<Sequence>() {
+        Some(seq.get_pretokenizers().iter().any(wrapper_has_byte_level))
+    } else if pre_tokenizer.is::<ByteLevel>() {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+fn wrapper_requirements(decoder: &DecoderWrapper, requirements: &mut Vec<Requirement>) {
+    match decoder {
+        DecoderWrapper::ByteLevel(_) => requirements.push(Requirement::ByteLevelPreTokenizer),
+        DecoderWrapper::WordPiece(_) => requirements.push(Requirement::Model(ModelKind::WordPiece)),
+        DecoderWrapper::BPE(_) => requirements.push(Requirement::Model(ModelKind::Bpe)),
+        DecoderWrapper::Sequence(seq) => {
+            for decoder in seq.get_decoders() {
+                wrapper_requirements(decoder, requirements);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn requirements(decoder: &dyn Any) -> Vec<Requirement> {
+    let mut requirements = vec![];
+    if let Some(decoder) = decoder.downcast_ref::<DecoderWrapper>() {
+        wrapper_requirements(decoder, &mut requirements);
+    } else if let Some(seq) = decoder.downcast_ref::<DecoderSequence>() {
+        for decoder in seq.get_decoders() {
+            wrapper_requirements(decoder, &mut requirements);
+        }
+    } else if decoder.is::<ByteLevel>() {
+        requirements.push(Requirement::ByteLevelPreTokenizer);
+    } else if decoder.is::<WordPieceDecoder>() {
+        requirements.push(Requirement::Model(ModelKind::WordPiece));
+    } else if decoder.is::<BPEDecoder>() {
+        requirements.push(Requirement::Model(ModelKind::Bpe));
+    }
+    requirements
+}
+
+/// Describe why the decoder obviously doesn't fit with the model or the pre-tokenizer, if it
+/// doesn't. Only the components of this crate are known, the custom ones are always accepted.
+pub(crate) fn decoder_mismatch<M: 'static, PT: 'static, D: 'static>(
+    model: &M,
+    pre_tokenizer: Option<&PT>,
+    decoder: &D,
+) -> Option<String> {
+    requirements(decoder)
+        .into_iter()
+        .find_map(|requirement| match requirement {
+            Requirement::ByteLevelPreTokenizer => {
+                let has_byte_level = match pre_tokenizer {
+                    Some(pre_tokenizer) => has_byte_level(pre_tokenizer)?,
+                    None => false,
+                };
+                if has_byte_level {
+                    None
+                } else {
+                    Some("The ByteLevel decoder needs a ByteLevel pre-tokenizer".to_string())
+                }
+            }
+            Requirement::Model(kind) if model_kind(model)? != kind => {
+                let name = match kind {
+                    ModelKind::Bpe => "BPE",
+                    ModelKind::WordPiece => "WordPiece",
+                    ModelKind::Other => unreachable!(),
+                };
+                Some(format!("The {} decoder needs a {} model", name, name))
+            }
+            Requirement::Model(_) => None,
+        })
+}
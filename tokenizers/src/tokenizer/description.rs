@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::TokenizerImpl;
+use crate::{Model, Result};
+
+/// A summary of each stage of the pipeline of a `Tokenizer`, as given by
+/// [`TokenizerImpl::describe`]. Its `Display` gives one line per stage, like
+/// `Normalizer: Sequence[NFKC, Lowercase]`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineDescription {
+    pub normalizer: Option<ComponentDescription>,
+    pub pre_tokenizer: Option<ComponentDescription>,
+    pub model: ModelDescription,
+    pub post_processor: Option<ComponentDescription>,
+    pub decoder: Option<ComponentDescription>,
+}
+
+/// The type and parameters of a normalizer, pre-tokenizer, post-processor or decoder, as
+/// they get saved with the `Tokenizer`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComponentDescription {
+    /// The type of the component, like `NFKC` or `ByteLevel`
+    pub kind: String,
+    /// Its parameters, except for the components it contains
+    pub params: BTreeMap<String, Value>,
+    /// The components it contains, like the ones of a `Sequence`, in order
+    pub children: Vec<ComponentDescription>,
+}
+
+/// The type of a model, and the size of its vocabulary
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelDescription {
+    pub kind: String,
+    /// The size of the vocabulary of the model, without the added tokens
+    pub vocab_size: usize,
+}
+
+/// Whether this value is itself a serialized component
+fn is_component(value: &Value) -> bool {
+    matches!(value, Value::Object(map) if map.get("type").is_some_and(Value::is_string))
+}
+
+/// The type of a serialized component, or `Unknown` if it doesn't say
+fn kind(map: &mut serde_json::Map<String, Value>) -> String {
+    match map.remove("type") {
+        Some(Value::String(kind)) => kind,
+        _ => "Unknown".into(),
+    }
+}
+
+impl ComponentDescription {
+    fn from_value(value: Value) -> Self {
+        let mut map = match value {
+            Value::Object(map) => map,
+            _ => serde_json::Map::new(),
+        };
+        let kind = kind(&mut map);
+
+        let mut params = BTreeMap::new();
+        let mut children = vec![];
+        for (key, value) in map {
+            match value {
+                Value::Array(values) if !values.is_empty() && values.iter().all(is_component) => {
+                    children.extend(values.into_iter().map(Self::from_value))
+                }
+                value if is_component(&value) => children.push(Self::from_value(value)),
+                value => {
+                    params.insert(key, value);
+                }
+            }
+        }
+
+        Self {
+            kind,
+            params,
+            children,
+        }
+    }
+
+    fn describe<C: Serialize>(component: &Option<C>) -> Result<Option<Self>> {
+        Ok(match component {
+            Some(component) => Some(Self::from_value(serde_json::to_value(component)?)),
+            None => None,
+        })
+    }
+}
+
+impl fmt::Display for ComponentDescription {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if !self.params.is_empty() {
+            let params = self
+                .params
+                .iter()
+                .map(|(key, value)| format!("{}:{}", key, value))
+                .collect::<Vec<_>>();
+            write!(f, "{{{}}}", params.join(", "))?;
+        }
+        if !self.children.is_empty() {
+            let children = self
+                .children
+                .iter()
+                .map(|child| child.to_string())
+                .collect::<Vec<_>>();
+            write!(f, "[{}]", children.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for PipelineDescription {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let stage = |component: &Option<ComponentDescription>| {
+            component
+                .as_ref()
+                .map_or_else(|| "None".to_string(), |c| c.to_string())
+        };
+        writeln!(f, "Normalizer: {}", stage(&self.normalizer))?;
+        writeln!(f, "PreTokenizer: {}", stage(&self.pre_tokenizer))?;
+        writeln!(
+            f,
+            "Model: {} ({} tokens)",
+            self.model.kind, self.model.vocab_size
+        )?;
+        writeln!(f, "PostProcessor: {}", stage(&self.post_processor))?;
+        write!(f, "Decoder: {}", stage(&self.decoder))
+    }
+}
+
+impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
+where
+    M: Model + Serialize,
+    N: Serialize,
+    PT: Serialize,
+    PP: Serialize,
+    D: Serialize,
+{
+    /// Describe each stage of the pipeline, with what gets saved of them, so that it can be
+    /// inspected without going through the JSON of the saved `Tokenizer`.
+    pub fn describe(&self) -> Result<PipelineDescription> {
+        let model = match serde_json::to_value(&self.model)? {
+            Value::Object(mut map) => kind(&mut map),
+            _ => "Unknown".into(),
+        };
+
+        Ok(PipelineDescription {
+            normalizer: ComponentDescription::describe(&self.normalizer)?,
+            pre_tokenizer: ComponentDescription::describe(&self.pre_tokenizer)?,
+            model: ModelDescription {
+                kind: model,
+                vocab_size: self.model.get_vocab_size(),
+            },
+            post_processor: ComponentDescription::describe(&self.post_processor)?,
+            decoder: ComponentDescription::describe(&self.decoder)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decoders::byte_level::ByteLevel;
+    use crate::models::bpe::BPE;
+    use crate::normalizers::{Lowercase, Sequence, NFKC};
+    use crate::Tokenizer;
+
+    #[test]
+    fn describe() {
+        let mut tokenizer = Tokenizer::new(BPE::default());
+        tokenizer
+            .with_normalizer(Sequence::new(vec![NFKC.into(), Lowercase.into()]))
+            .with_pre_tokenizer(ByteLevel::default());
+
+        let description = tokenizer.describe().unwrap();
+        assert_eq!(
+            description.to_string(),
+            "Normalizer: Sequence[NFKC, Lowercase]\n\
+             PreTokenizer: ByteLevel{add_prefix_space:true, trim_offsets:true, use_regex:true}\n\
+             Model: BPE (0 tokens)\n\
+             PostProcessor: None\n\
+             Decoder: None"
+        );
+
+        let normalizer = description.normalizer.as_ref().unwrap();
+        assert_eq!(normalizer.kind, "Sequence");
+        assert!(normalizer.params.is_empty());
+        assert_eq!(normalizer.children.len(), 2);
+        let pre_tokenizer = description.pre_tokenizer.as_ref().unwrap();
+        assert_eq!(pre_tokenizer.params["add_prefix_space"], true);
+        assert!(pre_tokenizer.children.is_empty());
+
+        let serialized = serde_json::to_string(&description).unwrap();
+        assert_eq!(
+            serde_json::from_str::<super::PipelineDescription>(&serialized).unwrap(),
+            description
+        );
+    }
+}
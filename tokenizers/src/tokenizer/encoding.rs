@@ -1,5 +1,5 @@
 use crate::parallelism::*;
-use crate::tokenizer::{Offsets, Token};
+use crate::tokenizer::{Offsets, Result, Token};
 use crate::utils::padding::PaddingDirection;
 use crate::utils::truncation::TruncationDirection;
 use serde::{Deserialize, Serialize};
@@ -28,7 +28,24 @@ pub struct Encoding {
     /// Ranges of tokens covered by each sequence. If this is empty we consider
     /// there is only one sequence in this Encoding, and that it covers the entire range.
     sequence_ranges: HashMap<usize, Range<usize>>,
+    /// The number of tokens each overflowing Encoding shares with the previous one
+    #[serde(default)]
+    overflow_stride: usize,
+    /// The number of tokens removed by the truncation
+    #[serde(default)]
+    num_truncated_tokens: usize,
 }
+
+/// The owned parts of an [`Encoding`], as returned by [`Encoding::into_parts`]
+#[derive(Default, PartialEq, Eq, Debug, Clone)]
+pub struct EncodingParts {
+    pub ids: Vec<u32>,
+    pub type_ids: Vec<u32>,
+    pub attention_mask: Vec<u32>,
+    pub special_tokens_mask: Vec<u32>,
+    pub offsets: Vec<Offsets>,
+}
+
 impl Encoding {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
@@ -52,6 +69,8 @@ impl Encoding {
             attention_mask,
             overflowing,
             sequence_ranges,
+            overflow_stride: 0,
+            num_truncated_tokens: 0,
         }
     }
 
@@ -66,6 +85,8 @@ impl Encoding {
             attention_mask: Vec::with_capacity(len),
             overflowing: vec![],
             sequence_ranges: HashMap::new(),
+            overflow_stride: 0,
+            num_truncated_tokens: 0,
         }
     }
 
@@ -95,6 +116,8 @@ impl Encoding {
             special_tokens_mask: vec![0; length],
             overflowing: vec![],
             sequence_ranges: HashMap::new(),
+            overflow_stride: 0,
+            num_truncated_tokens: 0,
         }
     }
 
@@ -117,11 +140,68 @@ impl Encoding {
         }
     }
 
+    /// The index of the first token of the second sequence, separating the tokens of the
+    /// first sequence from the ones of the second, like to build a prefix attention mask.
+    /// It follows the sequence ids, so the special tokens between both sequences come before
+    /// it unless they are attributed to the second sequence. This is `None` when there is no
+    /// second sequence, or when it has no token left after truncation.
+    pub fn sequence_boundary(&self) -> Option<usize> {
+        self.sequence_ranges
+            .get(&1)
+            .filter(|range| !range.is_empty())
+            .map(|range| range.start)
+    }
+
     /// Set the given sequence id for the whole range of tokens contained in this Encoding
     pub fn set_sequence_id(&mut self, sequence_id: usize) {
         self.sequence_ranges.insert(sequence_id, 0..self.len());
     }
 
+    /// Relabel the tokens in `range` as being part of the given sequence id, also updating
+    /// their type ids. The tokens of this range are removed from any other sequence, which must
+    /// then still cover a contiguous range of tokens. A sequence left without any token is
+    /// removed.
+    pub fn set_sequence_id_range(&mut self, range: Range<usize>, sequence_id: usize) -> Result<()> {
+        if range.start > range.end || range.end > self.len() {
+            return Err(format!(
+                "Invalid range {:?} for an Encoding of length {}",
+                range,
+                self.len()
+            )
+            .into());
+        }
+        if self.sequence_ranges.is_empty() {
+            self.sequence_ranges.insert(0, 0..self.len());
+        }
+
+        let mut ranges = self.sequence_ranges.clone();
+        ranges.remove(&sequence_id);
+        for (seq_id, seq_range) in ranges.iter_mut() {
+            if seq_range.start < range.start && seq_range.end > range.end {
+                return Err(format!(
+                    "Relabeling {:?} would split the range {:?} of sequence {}",
+                    range, seq_range, seq_id
+                )
+                .into());
+            }
+            if seq_range.start >= range.start {
+                seq_range.start = seq_range.start.max(range.end).min(seq_range.end);
+            }
+            if seq_range.end <= range.end {
+                seq_range.end = seq_range.end.min(range.start).max(seq_range.start);
+            }
+        }
+        ranges.insert(sequence_id, range.clone());
+        // The sequences left without any token don't count anymore
+        ranges.retain(|_, seq_range| seq_range.start < seq_range.end);
+        self.sequence_ranges = ranges;
+
+        self.type_ids[range]
+            .iter_mut()
+            .for_each(|t| *t = sequence_id as u32);
+        Ok(())
+    }
+
     pub fn get_tokens(&self) -> &[String] {
         &self.tokens[..]
     }
@@ -135,11 +215,16 @@ impl Encoding {
     }
 
     pub fn get_sequence_ids(&self) -> Vec<Option<usize>> {
+        if self.sequence_ranges.is_empty() {
+            return vec![Some(0); self.len()];
+        }
         let mut sequences = vec![None; self.len()];
-        for seq_id in 0..self.n_sequences() {
-            let range = self.sequence_range(seq_id);
+        for (seq_id, range) in &self.sequence_ranges {
             let seq_len = range.len();
-            sequences.splice(range, std::iter::repeat(Some(seq_id)).take(seq_len));
+            sequences.splice(
+                range.clone(),
+                std::iter::repeat(Some(*seq_id)).take(seq_len),
+            );
         }
         sequences
     }
@@ -176,6 +261,38 @@ impl Encoding {
         &self.overflowing
     }
 
+    /// The number of tokens that each overflowing Encoding shares with the previous one,
+    /// including this Encoding for the first one. It is the `stride` used for the truncation,
+    /// and is 0 when there was no truncation.
+    pub fn overflow_stride(&self) -> usize {
+        self.overflow_stride
+    }
+
+    pub(crate) fn set_overflow_stride(&mut self, stride: usize) {
+        self.overflow_stride = stride;
+    }
+
+    /// The number of tokens that were removed from this Encoding by the truncation, whether
+    /// or not they are kept in the overflowing Encodings. It is 0 when there was no truncation.
+    pub fn num_truncated_tokens(&self) -> usize {
+        self.num_truncated_tokens
+    }
+
+    /// Whether some tokens were removed from this Encoding by the truncation
+    pub fn was_truncated(&self) -> bool {
+        self.num_truncated_tokens > 0
+    }
+
+    /// The length this Encoding had before being truncated. Any padding added after the
+    /// truncation is counted too.
+    pub fn original_length(&self) -> usize {
+        self.len() + self.num_truncated_tokens
+    }
+
+    pub(crate) fn set_num_truncated_tokens(&mut self, num_truncated_tokens: usize) {
+        self.num_truncated_tokens = num_truncated_tokens;
+    }
+
     pub fn set_overflowing(&mut self, overflowing: Vec<Encoding>) {
         self.overflowing = overflowing;
     }
@@ -188,6 +305,39 @@ impl Encoding {
         std::mem::take(&mut self.overflowing)
     }
 
+    /// Consume this Encoding to move out its numeric parts without copying them.
+    /// Use the `get_*` accessors to borrow them instead.
+    pub fn into_parts(self) -> EncodingParts {
+        EncodingParts {
+            ids: self.ids,
+            type_ids: self.type_ids,
+            attention_mask: self.attention_mask,
+            special_tokens_mask: self.special_tokens_mask,
+            offsets: self.offsets,
+        }
+    }
+
+    /// Make this Encoding a copy of `other`, while reusing its buffers (including the ones of
+    /// its tokens) as long as their capacity allows it.
+    pub fn refill_from(&mut self, other: &Encoding) {
+        self.ids.clone_from(&other.ids);
+        self.type_ids.clone_from(&other.type_ids);
+        self.tokens.clone_from(&other.tokens);
+        self.words.clone_from(&other.words);
+        self.offsets.clone_from(&other.offsets);
+        self.special_tokens_mask
+            .clone_from(&other.special_tokens_mask);
+        self.attention_mask.clone_from(&other.attention_mask);
+        self.overflowing
+            .resize_with(other.overflowing.len(), Default::default);
+        for (overflowing, other) in self.overflowing.iter_mut().zip(&other.overflowing) {
+            overflowing.refill_from(other);
+        }
+        self.sequence_ranges.clone_from(&other.sequence_ranges);
+        self.overflow_stride = other.overflow_stride;
+        self.num_truncated_tokens = other.num_truncated_tokens;
+    }
+
     pub(crate) fn process_tokens_with_offsets_mut<F>(&mut self, func: F)
     where
         F: FnMut((usize, (&String, &mut Offsets))),
@@ -253,16 +403,27 @@ impl Encoding {
         }
     }
 
-    /// Get the offsets of the word at the given index in the input sequence.
+    /// Get the offsets of the word at the given index in the input sequence, covering all of
+    /// its tokens: from the smallest start to the largest end of their offsets. The special
+    /// tokens are never part of a word, and `None` is returned if the word can't be found in
+    /// this sequence.
     pub fn word_to_chars(&self, word: u32, sequence_id: usize) -> Option<Offsets> {
-        self.word_to_tokens(word, sequence_id)
-            .and_then(|(start, end)| {
-                if end == 0 {
-                    None
-                } else {
-                    Some((self.offsets[start].0, self.offsets[end - 1].1))
-                }
+        let has_sequence = if self.sequence_ranges.is_empty() {
+            sequence_id == 0
+        } else {
+            self.sequence_ranges.contains_key(&sequence_id)
+        };
+        if !has_sequence {
+            return None;
+        }
+
+        self.sequence_range(sequence_id)
+            .filter(|i| {
+                self.words.get(*i) == Some(&Some(word))
+                    && self.special_tokens_mask.get(*i) != Some(&1)
             })
+            .filter_map(|i| self.offsets.get(i).copied())
+            .reduce(|(start, end), (s, e)| (start.min(s), end.max(e)))
     }
 
     /// Get the offsets of the token at the given index.
@@ -273,6 +434,19 @@ impl Encoding {
         ))
     }
 
+    /// Get the text of the token at the given index in `original`, the sequence it was
+    /// encoded from, as it was before any normalization. This gives back the case of a
+    /// token lowercased by the normalizer, for example. The offsets must be in bytes, and
+    /// for a pair, `original` is the sequence of the token.
+    pub fn token_surface_from_original<'a>(
+        &self,
+        token: usize,
+        original: &'a str,
+    ) -> Option<&'a str> {
+        let (start, end) = self.offsets.get(token).copied()?;
+        original.get(start..end)
+    }
+
     /// Get the word that contains the token at the given index.
     pub fn token_to_word(&self, token: usize) -> Option<(usize, u32)> {
         Some((
@@ -301,7 +475,30 @@ impl Encoding {
         )
     }
 
-    /// Truncate the current `Encoding`.
+    /// Remove the token at the given index, keeping the sequence ranges in sync
+    pub(crate) fn remove_token(&mut self, index: usize) {
+        self.ids.remove(index);
+        self.type_ids.remove(index);
+        self.tokens.remove(index);
+        self.words.remove(index);
+        self.offsets.remove(index);
+        self.special_tokens_mask.remove(index);
+        self.attention_mask.remove(index);
+        for range in self.sequence_ranges.values_mut() {
+            if range.start > index {
+                range.start -= 1;
+            }
+            if range.end > index {
+                range.end -= 1;
+            }
+        }
+    }
+
+    /// Truncate the current `Encoding` to `max_len` tokens, independently of any
+    /// `TruncationParams`. The tokens removed from the `direction` side are split into
+    /// overflowing encodings of at most `max_len` tokens, each one repeating the last `stride`
+    /// tokens of the previous one. Every window keeps the offsets, word ids, type ids and
+    /// special tokens mask of its tokens, and the part of each sequence range it covers.
     ///
     /// Panics if `stride >= max_len`
     pub fn truncate(&mut self, max_len: usize, stride: usize, direction: TruncationDirection) {
@@ -312,15 +509,13 @@ impl Encoding {
 
         if max_len == 0 {
             let o = std::mem::replace(self, Encoding::with_capacity(0));
+            self.num_truncated_tokens = o.num_truncated_tokens + encoding_len;
             self.overflowing.push(o);
             return;
         }
 
         assert!(stride < max_len);
 
-        // When truncating, we lose the `sequence_ranges` information.
-        self.sequence_ranges.clear();
-
         let offset = max_len - stride;
         let mut end = false;
         let parts_ranges: Vec<(usize, usize)> = match direction {
@@ -363,7 +558,9 @@ impl Encoding {
             special_tokens_mask: self.special_tokens_mask[start..stop].to_vec(),
             attention_mask: self.attention_mask[start..stop].to_vec(),
             overflowing: vec![],
-            sequence_ranges: HashMap::new(),
+            sequence_ranges: self.window_sequence_ranges(start, stop),
+            overflow_stride: stride,
+            num_truncated_tokens: self.num_truncated_tokens + encoding_len - max_len,
         };
 
         loop {
@@ -381,12 +578,26 @@ impl Encoding {
                 special_tokens_mask: self.special_tokens_mask[start..stop].to_vec(),
                 attention_mask: self.attention_mask[start..stop].to_vec(),
                 overflowing: vec![],
-                sequence_ranges: HashMap::new(),
+                sequence_ranges: self.window_sequence_ranges(start, stop),
+                overflow_stride: 0,
+                num_truncated_tokens: 0,
             });
         }
         *self = new_encoding;
     }
 
+    /// The sequence ranges of the tokens between `start` and `stop`, relative to `start`. A
+    /// sequence without any token in there gets an empty range.
+    fn window_sequence_ranges(&self, start: usize, stop: usize) -> HashMap<usize, Range<usize>> {
+        self.sequence_ranges
+            .iter()
+            .map(|(seq_id, range)| {
+                let clip = |i: usize| i.clamp(start, stop) - start;
+                (*seq_id, clip(range.start)..clip(range.end))
+            })
+            .collect()
+    }
+
     /// Merge all Encodings together
     pub fn merge<I: IntoIterator<Item = Encoding>>(encodings: I, growing_offsets: bool) -> Self {
         let mut encoding = Encoding::default();
@@ -434,13 +645,14 @@ impl Encoding {
         // Finish by merging ourself with the other encoding
         let original_self_len = self.len(); // Must be before any modification to self.ids
 
-        self.sequence_ranges
-            .extend(pair.sequence_ranges.into_iter().map(|(seq_id, range)| {
-                (
-                    seq_id,
-                    original_self_len + range.start..original_self_len + range.end,
-                )
-            }));
+        // A sequence that continues in `pair` keeps a single range
+        for (seq_id, range) in pair.sequence_ranges {
+            let range = original_self_len + range.start..original_self_len + range.end;
+            self.sequence_ranges
+                .entry(seq_id)
+                .and_modify(|r| r.end = range.end)
+                .or_insert(range);
+        }
         self.ids.extend(pair.ids);
         self.type_ids.extend(pair.type_ids);
         self.tokens.extend(pair.tokens);
@@ -460,6 +672,31 @@ impl Encoding {
         self.special_tokens_mask.extend(pair.special_tokens_mask);
         self.attention_mask.extend(pair.attention_mask);
         self.overflowing = overflowings;
+        self.overflow_stride = self.overflow_stride.max(pair.overflow_stride);
+        self.num_truncated_tokens += pair.num_truncated_tokens;
+    }
+
+    /// Pad this encoding to the given length, in the given direction. Unlike
+    /// [`Encoding::pad`], this fails instead of leaving the encoding as is when it is
+    /// longer than `target_length`.
+    pub fn pad_to(
+        &mut self,
+        target_length: usize,
+        pad_id: u32,
+        pad_type_id: u32,
+        pad_token: &str,
+        direction: PaddingDirection,
+    ) -> Result<()> {
+        if self.len() > target_length {
+            return Err(format!(
+                "Cannot pad an Encoding of length {} to a length of {}",
+                self.len(),
+                target_length
+            )
+            .into());
+        }
+        self.pad(target_length, pad_id, pad_type_id, pad_token, direction);
+        Ok(())
     }
 
     pub fn pad(
@@ -469,10 +706,32 @@ impl Encoding {
         pad_type_id: u32,
         pad_token: &str,
         direction: PaddingDirection,
+    ) {
+        self.pad_with_attention_value(target_length, pad_id, pad_type_id, pad_token, 0, direction)
+    }
+
+    /// Pad like `pad`, but with the given value in the attention mask of the padding tokens,
+    /// instead of 0. With a value of 1, the padding can't be told apart from the content by
+    /// the attention mask anymore, only by the `special_tokens_mask` (and the ids).
+    pub fn pad_with_attention_value(
+        &mut self,
+        target_length: usize,
+        pad_id: u32,
+        pad_type_id: u32,
+        pad_token: &str,
+        pad_attention_value: u32,
+        direction: PaddingDirection,
     ) {
         // Dispatch call to all the overflowings first
         self.overflowing.maybe_par_iter_mut().for_each(|encoding| {
-            encoding.pad(target_length, pad_id, pad_type_id, pad_token, direction)
+            encoding.pad_with_attention_value(
+                target_length,
+                pad_id,
+                pad_type_id,
+                pad_token,
+                pad_attention_value,
+                direction,
+            )
         });
 
         // Then check if we should pad ourself
@@ -501,7 +760,7 @@ impl Encoding {
                     .chain(self.words.drain(..))
                     .collect();
                 self.attention_mask = (0..pad_length)
-                    .map(|_| 0)
+                    .map(|_| pad_attention_value)
                     .chain(self.attention_mask.drain(..))
                     .collect();
                 self.special_tokens_mask = (0..pad_length)
@@ -519,7 +778,8 @@ impl Encoding {
                 self.tokens
                     .extend((0..pad_length).map(|_| pad_token.to_owned()));
                 self.words.extend((0..pad_length).map(|_| None));
-                self.attention_mask.extend((0..pad_length).map(|_| 0));
+                self.attention_mask
+                    .extend((0..pad_length).map(|_| pad_attention_value));
                 self.special_tokens_mask.extend((0..pad_length).map(|_| 1));
                 self.offsets.extend((0..pad_length).map(|_| (0, 0)));
             }
@@ -600,6 +860,102 @@ mod tests {
         );
     }
 
+    #[test]
+    fn truncate_with_stride() {
+        let windows = |encoding: &Encoding| {
+            std::iter::once(encoding)
+                .chain(encoding.get_overflowing())
+                .map(|e| e.get_ids().to_vec())
+                .collect::<Vec<_>>()
+        };
+        let twenty = (0..20).map(|i| (i, i.to_string(), (i as usize, i as usize + 1), None, 0));
+
+        let mut right: Encoding = twenty.clone().collect();
+        right.truncate(8, 2, TruncationDirection::Right);
+        assert_eq!(right.overflow_stride(), 2);
+        let right = windows(&right);
+        assert_eq!(right.len(), 3);
+        for (previous, window) in right.iter().zip(&right[1..]) {
+            assert_eq!(previous[previous.len() - 2..], window[..2]);
+        }
+        assert_eq!(right[2], (12..20).collect::<Vec<_>>());
+
+        let mut left: Encoding = twenty.collect();
+        left.truncate(8, 2, TruncationDirection::Left);
+        assert_eq!(left.overflow_stride(), 2);
+        let left = windows(&left);
+        for (previous, window) in left.iter().zip(&left[1..]) {
+            assert_eq!(previous[..2], window[window.len() - 2..]);
+        }
+
+        // Without truncation, nothing overlaps
+        let mut short: Encoding = (0..4)
+            .map(|i| (i, i.to_string(), (0, 1), None, 0))
+            .collect();
+        short.truncate(8, 2, TruncationDirection::Right);
+        assert_eq!(short.overflow_stride(), 0);
+    }
+
+    #[test]
+    fn truncate_keeps_sequence_ranges() {
+        let sequence = |ids: std::ops::Range<u32>, type_id: u32, seq_id: usize| {
+            let mut encoding: Encoding = ids
+                .map(|i| (i, i.to_string(), (0, 1), Some(i), type_id))
+                .collect();
+            encoding.set_sequence_id(seq_id);
+            encoding
+        };
+        let pair = Encoding::merge(vec![sequence(0..4, 0, 0), sequence(4..7, 1, 1)], false);
+        let windows = |encoding: Encoding| {
+            std::iter::once(&encoding)
+                .chain(encoding.get_overflowing())
+                .map(|e| {
+                    (
+                        e.get_ids().to_vec(),
+                        e.get_type_ids().to_vec(),
+                        e.get_sequence_ids(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(pair.sequence_boundary(), Some(4));
+        let mut right = pair.clone();
+        right.truncate(4, 1, TruncationDirection::Right);
+        assert_eq!(right.n_sequences(), 2);
+        assert_eq!(right.sequence_boundary(), None);
+        assert_eq!(right.get_overflowing()[0].sequence_boundary(), Some(1));
+        assert_eq!(right.get_word_ids(), [Some(0), Some(1), Some(2), Some(3)]);
+        assert_eq!(right.token_to_sequence(3), Some(0));
+        assert_eq!(right.sequence_range(1), 4..4);
+        assert_eq!(
+            windows(right),
+            vec![
+                (vec![0, 1, 2, 3], vec![0, 0, 0, 0], vec![Some(0); 4]),
+                (
+                    vec![3, 4, 5, 6],
+                    vec![0, 1, 1, 1],
+                    vec![Some(0), Some(1), Some(1), Some(1)]
+                ),
+            ]
+        );
+
+        let mut left = pair;
+        left.truncate(5, 0, TruncationDirection::Left);
+        assert_eq!(left.sequence_range(1), 2..5);
+        assert_eq!(
+            windows(left),
+            vec![
+                (
+                    vec![2, 3, 4, 5, 6],
+                    vec![0, 0, 1, 1, 1],
+                    vec![Some(0), Some(0), Some(1), Some(1), Some(1)]
+                ),
+                (vec![0, 1], vec![0, 0], vec![Some(0); 2]),
+            ]
+        );
+    }
+
     #[test]
     fn truncate() {
         let mut a = Encoding {
@@ -638,6 +994,7 @@ mod tests {
                     attention_mask: vec![1],
                     ..Default::default()
                 }],
+                num_truncated_tokens: 1,
                 ..Default::default()
             }
         );
@@ -679,6 +1036,7 @@ mod tests {
                     overflowing: vec![],
                     ..Default::default()
                 }],
+                num_truncated_tokens: 3,
                 ..Default::default()
             }
         );
@@ -735,6 +1093,8 @@ mod tests {
                     overflowing: vec![],
                     ..Default::default()
                 }],
+                overflow_stride: 2,
+                num_truncated_tokens: 1,
                 ..Default::default()
             }
         );
@@ -778,6 +1138,7 @@ mod tests {
                     attention_mask: vec![1],
                     ..Default::default()
                 }],
+                num_truncated_tokens: 1,
                 ..Default::default()
             }
         );
@@ -849,6 +1210,9 @@ mod tests {
         assert_eq!(encoding.word_to_chars(0, 1), Some((0, 3)));
         assert_eq!(encoding.word_to_chars(1, 1), Some((4, 7)));
 
+        assert_eq!(encoding.word_to_chars(4, 0), None);
+        assert_eq!(encoding.word_to_chars(0, 2), None);
+
         assert_eq!(encoding.token_to_chars(0), Some((0, (0, 2))));
         assert_eq!(encoding.token_to_chars(1), Some((0, (2, 5))));
         assert_eq!(encoding.token_to_chars(7), Some((1, (0, 3))));
@@ -874,4 +1238,105 @@ mod tests {
         assert_eq!(encoding.char_to_word(2, 1), Some(0));
         assert_eq!(encoding.char_to_word(9, 1), Some(2));
     }
+
+    #[test]
+    fn set_sequence_id_range() {
+        let mut encoding = Encoding {
+            ids: vec![1, 2, 3, 4, 5, 6],
+            type_ids: vec![0; 6],
+            ..Default::default()
+        };
+        assert_eq!(encoding.token_to_sequence(4), Some(0));
+
+        encoding.set_sequence_id_range(3..6, 1).unwrap();
+        assert_eq!(encoding.n_sequences(), 2);
+        assert_eq!(encoding.token_to_sequence(2), Some(0));
+        assert_eq!(encoding.token_to_sequence(3), Some(1));
+        assert_eq!(encoding.token_to_sequence(5), Some(1));
+        assert_eq!(encoding.get_type_ids(), &[0, 0, 0, 1, 1, 1]);
+        assert_eq!(
+            encoding.get_sequence_ids(),
+            vec![Some(0), Some(0), Some(0), Some(1), Some(1), Some(1)]
+        );
+
+        // A third segment, taken from the end of the second one
+        encoding.set_sequence_id_range(5..6, 2).unwrap();
+        assert_eq!(encoding.token_to_sequence(4), Some(1));
+        assert_eq!(encoding.token_to_sequence(5), Some(2));
+        assert_eq!(encoding.get_type_ids(), &[0, 0, 0, 1, 1, 2]);
+
+        // Out of bounds, or splitting an existing sequence in two
+        assert!(encoding.set_sequence_id_range(4..7, 1).is_err());
+        assert!(encoding.set_sequence_id_range(1..2, 3).is_err());
+        assert_eq!(encoding.get_type_ids(), &[0, 0, 0, 1, 1, 2]);
+
+        // An empty range doesn't make a sequence, and an emptied sequence doesn't count
+        encoding.set_sequence_id_range(3..3, 3).unwrap();
+        assert_eq!(encoding.n_sequences(), 3);
+        encoding.set_sequence_id_range(3..6, 1).unwrap();
+        assert_eq!(encoding.n_sequences(), 2);
+        assert_eq!(encoding.token_to_sequence(5), Some(1));
+        encoding.set_sequence_id_range(0..6, 0).unwrap();
+        assert_eq!(encoding.n_sequences(), 1);
+        assert_eq!(encoding.get_sequence_ids(), vec![Some(0); 6]);
+    }
+
+    #[test]
+    fn word_to_chars_with_special_tokens() {
+        let encoding = Encoding {
+            ids: vec![101, 1, 2, 3, 4, 102],
+            type_ids: vec![0; 6],
+            // The second word got its tokens out of order, and the last special token has a
+            // word id, like an added token would
+            words: vec![None, Some(0), Some(1), Some(1), Some(1), Some(2)],
+            offsets: vec![(0, 0), (0, 5), (9, 12), (6, 9), (12, 13), (13, 18)],
+            special_tokens_mask: vec![1, 0, 0, 0, 0, 1],
+            ..Default::default()
+        };
+        assert_eq!(encoding.word_to_chars(0, 0), Some((0, 5)));
+        assert_eq!(encoding.word_to_chars(1, 0), Some((6, 13)));
+        assert_eq!(encoding.word_to_chars(2, 0), None);
+        assert_eq!(encoding.word_to_chars(3, 0), None);
+        // There is only one sequence
+        assert_eq!(encoding.word_to_chars(0, 1), None);
+    }
+
+    #[test]
+    fn pad_to() {
+        let encoding = Encoding::from_tokens(
+            vec![
+                Token::new(1, "a".into(), (0, 1)),
+                Token::new(2, "b".into(), (2, 3)),
+            ],
+            0,
+        );
+
+        let mut left = encoding.clone();
+        left.pad_to(4, 0, 1, "[PAD]", PaddingDirection::Left)
+            .unwrap();
+        assert_eq!(left.get_ids(), [0, 0, 1, 2]);
+        assert_eq!(left.get_type_ids(), [1, 1, 0, 0]);
+        assert_eq!(left.get_tokens(), ["[PAD]", "[PAD]", "a", "b"]);
+        assert_eq!(left.get_offsets(), [(0, 0), (0, 0), (0, 1), (2, 3)]);
+        assert_eq!(left.get_attention_mask(), [0, 0, 1, 1]);
+        assert_eq!(left.get_special_tokens_mask(), [1, 1, 0, 0]);
+
+        let mut right = encoding.clone();
+        right
+            .pad_to(4, 0, 1, "[PAD]", PaddingDirection::Right)
+            .unwrap();
+        assert_eq!(right.get_ids(), [1, 2, 0, 0]);
+        assert_eq!(right.get_offsets(), [(0, 1), (2, 3), (0, 0), (0, 0)]);
+        assert_eq!(right.get_attention_mask(), [1, 1, 0, 0]);
+
+        // Padding to the current length is a no-op, shrinking is an error
+        let mut same = encoding.clone();
+        same.pad_to(2, 0, 0, "[PAD]", PaddingDirection::Right)
+            .unwrap();
+        assert_eq!(same.get_ids(), encoding.get_ids());
+        assert!(right
+            .pad_to(3, 0, 0, "[PAD]", PaddingDirection::Left)
+            .is_err());
+        assert_eq!(right.len(), 4);
+    }
 }
@@ -0,0 +1,203 @@
+use super::{AddedToken, PaddingDirection, Result, Tokenizer, TruncationDirection};
+use serde_json::{Map, Value};
+use std::fs::read_to_string;
+use std::path::Path;
+
+/// The keys of `special_tokens_map.json` that name a single special token
+const SPECIAL_TOKEN_KEYS: &[&str] = &[
+    "bos_token",
+    "eos_token",
+    "unk_token",
+    "sep_token",
+    "pad_token",
+    "cls_token",
+    "mask_token",
+];
+
+/// Read the JSON object in the given file, if it exists
+fn read_object(path: &Path) -> Result<Option<Map<String, Value>>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    match serde_json::from_str(&read_to_string(path)?)? {
+        Value::Object(map) => Ok(Some(map)),
+        _ => Err(format!("{} should contain a JSON object", path.display()).into()),
+    }
+}
+
+/// A special token, given either as its content or as the fields of an `AddedToken`
+fn special_token(value: &Value) -> Option<AddedToken> {
+    match value {
+        Value::String(content) => Some(AddedToken::from(content.as_str(), true)),
+        Value::Object(fields) => {
+            let flag = |name: &str, default: bool| {
+                fields.get(name).and_then(Value::as_bool).unwrap_or(default)
+            };
+            let content = fields.get("content")?.as_str()?;
+            Some(
+                AddedToken::from(content, true)
+                    .single_word(flag("single_word", false))
+                    .lstrip(flag("lstrip", false))
+                    .rstrip(flag("rstrip", false))
+                    .normalized(flag("normalized", false)),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// The content of the special token with the given key, if any
+fn content<'a>(map: &'a Map<String, Value>, key: &str) -> Option<&'a str> {
+    match map.get(key)? {
+        Value::String(content) => Some(content),
+        Value::Object(fields) => fields.get("content")?.as_str(),
+        _ => None,
+    }
+}
+
+impl Tokenizer {
+    /// Load a `Tokenizer` from a directory laid out like the ones of the Hugging Face Hub.
+    /// Its `tokenizer.json` gets loaded first. Then, when they exist, the special tokens of
+    /// `special_tokens_map.json` get added, and the `model_max_length`, `padding_side`,
+    /// `truncation_side` and `pad_token` of `tokenizer_config.json` set the truncation and
+    /// padding.
+    pub fn from_hf_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut tokenizer = Tokenizer::from_file(dir.join("tokenizer.json"))?;
+        let special_tokens_map = read_object(&dir.join("special_tokens_map.json"))?;
+        let config = read_object(&dir.join("tokenizer_config.json"))?;
+
+        if let Some(map) = &special_tokens_map {
+            let additional = map
+                .get("additional_special_tokens")
+                .and_then(Value::as_array)
+                .map_or(&[][..], |tokens| tokens.as_slice());
+            let tokens = SPECIAL_TOKEN_KEYS
+                .iter()
+                .filter_map(|key| map.get(*key))
+                .chain(additional)
+                .filter_map(special_token)
+                .collect::<Vec<_>>();
+            tokenizer.add_special_tokens(&tokens)?;
+        }
+
+        let config = match config {
+            Some(config) => config,
+            None => return Ok(tokenizer),
+        };
+        // A huge `model_max_length`, like the `1e30` that `transformers` uses when there is no
+        // limit, doesn't fit in a `u64` and leaves the truncation as it is
+        let max_length = config.get("model_max_length").and_then(Value::as_u64);
+        let truncation_direction = match config.get("truncation_side").and_then(Value::as_str) {
+            Some("left") => Some(TruncationDirection::Left),
+            Some("right") => Some(TruncationDirection::Right),
+            _ => None,
+        };
+        if max_length.is_some() || truncation_direction.is_some() {
+            let mut truncation = tokenizer.get_truncation().cloned().unwrap_or_default();
+            if let Some(max_length) = max_length {
+                truncation.max_length = max_length as usize;
+            }
+            if let Some(direction) = truncation_direction {
+                truncation.direction = direction;
+            }
+            tokenizer.with_truncation(Some(truncation));
+        }
+
+        let pad_token = content(&config, "pad_token")
+            .or_else(|| content(special_tokens_map.as_ref()?, "pad_token"));
+        let pad_id = pad_token.and_then(|token| tokenizer.token_to_id(token));
+        let padding_direction = match config.get("padding_side").and_then(Value::as_str) {
+            Some("left") => Some(PaddingDirection::Left),
+            Some("right") => Some(PaddingDirection::Right),
+            _ => None,
+        };
+        if let (Some(pad_token), Some(pad_id)) = (pad_token, pad_id) {
+            let mut padding = tokenizer.get_padding().cloned().unwrap_or_default();
+            padding.pad_token = pad_token.to_owned();
+            padding.pad_id = pad_id;
+            if let Some(direction) = padding_direction {
+                padding.direction = direction;
+            }
+            tokenizer.with_padding(Some(padding));
+        } else if let (Some(padding), Some(direction)) =
+            (tokenizer.get_padding_mut(), padding_direction)
+        {
+            padding.direction = direction;
+        }
+
+        Ok(tokenizer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PaddingDirection, Tokenizer, TruncationDirection};
+
+    #[test]
+    fn from_hf_dir() {
+        let tokenizer = Tokenizer::from_hf_dir("tests/fixtures/hf_dir").unwrap();
+
+        // The special tokens are added, even the ones already in the vocabulary
+        assert_eq!(tokenizer.token_to_id("<mask>"), Some(4));
+        let encoding = tokenizer.encode("[UNK] hello <mask>", false).unwrap();
+        assert_eq!(encoding.get_ids(), [0, 2, 4]);
+        assert_eq!(
+            tokenizer.decode(encoding.get_ids().to_vec(), true).unwrap(),
+            "hello"
+        );
+
+        let truncation = tokenizer.get_truncation().unwrap();
+        assert_eq!(truncation.max_length, 3);
+        assert_eq!(truncation.direction, TruncationDirection::Right);
+        let padding = tokenizer.get_padding().unwrap();
+        assert_eq!(padding.pad_token, "[PAD]");
+        assert_eq!(padding.pad_id, 1);
+        assert!(matches!(padding.direction, PaddingDirection::Left));
+
+        let encodings = tokenizer
+            .encode_batch(vec!["hello world <mask> world", "world"], true)
+            .unwrap();
+        assert_eq!(encodings[0].get_ids(), [2, 3, 4]);
+        assert_eq!(encodings[1].get_ids(), [1, 1, 3]);
+        assert_eq!(encodings[1].get_attention_mask(), [0, 0, 1]);
+    }
+
+    #[test]
+    fn from_hf_dir_without_optional_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::copy(
+            "tests/fixtures/hf_dir/tokenizer.json",
+            dir.path().join("tokenizer.json"),
+        )
+        .unwrap();
+
+        let tokenizer = Tokenizer::from_hf_dir(dir.path()).unwrap();
+        assert_eq!(tokenizer.get_vocab_size(true), 4);
+        assert!(tokenizer.get_truncation().is_none());
+        assert!(tokenizer.get_padding().is_none());
+
+        assert!(Tokenizer::from_hf_dir(dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn from_hf_dir_without_max_length() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::copy(
+            "tests/fixtures/hf_dir/tokenizer.json",
+            dir.path().join("tokenizer.json"),
+        )
+        .unwrap();
+
+        // The ways `transformers` saves its "no limit" length
+        for max_length in &["1e30", "1000000000000000019884624838656"] {
+            std::fs::write(
+                dir.path().join("tokenizer_config.json"),
+                format!(r#"{{"model_max_length": {}}}"#, max_length),
+            )
+            .unwrap();
+            let tokenizer = Tokenizer::from_hf_dir(dir.path()).unwrap();
+            assert!(tokenizer.get_truncation().is_none());
+        }
+    }
+}
@@ -0,0 +1,332 @@
+use super::{
+    Encoding, Model, Normalizer, OffsetReferential, OffsetType, Offsets, PostProcessor,
+    PreTokenizer, Result, Token, TokenizerImpl,
+};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::ops::Range;
+
+#[derive(thiserror::Error, Debug)]
+pub enum IncrementalEncoderError {
+    #[error("Invalid edit range {0:?} for a text of length {1}")]
+    InvalidRange(Range<usize>, usize),
+}
+
+/// Keeps the `Encoding` of some text up-to-date while this text gets edited, like the input
+/// of an interactive application.
+///
+/// The normalization and pre-tokenization always run on the whole text, since they are cheap
+/// and can depend on the context. The model, which does the heavy lifting, only runs on the
+/// words around the edit though: the edited region gets widened to the surrounding word
+/// boundaries, plus one word on each side, so that any merge happening at the edge of the
+/// edit is recomputed. All the other words reuse the tokens computed for them previously.
+///
+/// Any edit touching an added token leads to a full re-encode.
+pub struct IncrementalEncoder<'t, M, N, PT, PP, D> {
+    tokenizer: &'t TokenizerImpl<M, N, PT, PP, D>,
+    add_special_tokens: bool,
+    text: String,
+    /// The normalized content and model tokens of each word, by byte offsets in `text`
+    words: HashMap<Offsets, (String, Vec<Token>)>,
+    /// The byte offsets of each added token in `text`
+    added: Vec<Offsets>,
+    encoding: Encoding,
+    retokenized: usize,
+}
+
+/// Move `pos` to the start of the word containing it, and then to the start of `margin`
+/// words before it.
+fn word_start(text: &str, pos: usize, margin: usize) -> usize {
+    let mut chars = text[..pos].char_indices().rev().peekable();
+    let mut start = pos;
+    for _ in 0..=margin {
+        while let Some((i, _)) = chars.next_if(|(_, c)| !c.is_whitespace()) {
+            start = i;
+        }
+        while let Some((i, _)) = chars.next_if(|(_, c)| c.is_whitespace()) {
+            start = i;
+        }
+    }
+    start
+}
+
+/// Move `pos` to the end of the word containing it, and then to the end of `margin` words
+/// after it.
+fn word_end(text: &str, pos: usize, margin: usize) -> usize {
+    let mut chars = text[pos..].char_indices().peekable();
+    let mut end = pos;
+    for _ in 0..=margin {
+        while let Some((i, c)) = chars.next_if(|(_, c)| !c.is_whitespace()) {
+            end = pos + i + c.len_utf8();
+        }
+        while let Some((i, c)) = chars.next_if(|(_, c)| c.is_whitespace()) {
+            end = pos + i + c.len_utf8();
+        }
+    }
+    end
+}
+
+impl<'t, M, N, PT, PP, D> IncrementalEncoder<'t, M, N, PT, PP, D>
+where
+    M: Model,
+    N: Normalizer,
+    PT: PreTokenizer,
+    PP: PostProcessor,
+{
+    /// Encode the given text, and keep what's needed to update its `Encoding` later on.
+    pub fn new(
+        tokenizer: &'t TokenizerImpl<M, N, PT, PP, D>,
+        text: &str,
+        add_special_tokens: bool,
+    ) -> Result<Self> {
+        let mut encoder = Self {
+            tokenizer,
+            add_special_tokens,
+            text: text.to_owned(),
+            words: HashMap::new(),
+            added: vec![],
+            encoding: Encoding::default(),
+            retokenized: 0,
+        };
+        encoder.encode(0..0, HashMap::new())?;
+        Ok(encoder)
+    }
+
+    /// Replace the given byte range of the text by `replacement`, and return the updated
+    /// `Encoding`. It is the same `Encoding` that encoding the whole new text would give.
+    ///
+    /// If the edit fails, for example when the new text goes over the input limits of the
+    /// tokenizer, the text and its `Encoding` are left as they were.
+    pub fn edit(&mut self, range: Range<usize>, replacement: &str) -> Result<&Encoding> {
+        if range.start > range.end
+            || range.end > self.text.len()
+            || !self.text.is_char_boundary(range.start)
+            || !self.text.is_char_boundary(range.end)
+        {
+            return Err(Box::new(IncrementalEncoderError::InvalidRange(
+                range,
+                self.text.len(),
+            )));
+        }
+
+        let touches_added = self
+            .added
+            .iter()
+            .any(|(start, end)| range.start <= *end && range.end >= *start);
+        let old_words = std::mem::take(&mut self.words);
+        let replaced = self.text[range.clone()].to_owned();
+        self.text.replace_range(range.clone(), replacement);
+
+        let edit_end = range.start + replacement.len();
+        let (reusable, widened) = if touches_added {
+            (HashMap::new(), 0..self.text.len())
+        } else {
+            // The words after the edit keep their tokens, but got shifted
+            let reusable = old_words
+                .into_iter()
+                .filter_map(|((start, end), word)| {
+                    if end <= range.start {
+                        Some(((start, end), word))
+                    } else if start >= range.end {
+                        let shift = |o: usize| o - range.end + edit_end;
+                        Some(((shift(start), shift(end)), word))
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+            let widened = word_start(&self.text, range.start, 1)..word_end(&self.text, edit_end, 1);
+            (reusable, widened)
+        };
+
+        if let Err(e) = self.encode(widened, reusable) {
+            // The words of the previous text just get tokenized again on the next edit
+            self.text.replace_range(range.start..edit_end, &replaced);
+            return Err(e);
+        }
+        Ok(&self.encoding)
+    }
+
+    /// Encode the current text, running the model only on the words overlapping `widened`,
+    /// or those that can't be found in `reusable`.
+    fn encode(
+        &mut self,
+        widened: Range<usize>,
+        reusable: HashMap<Offsets, (String, Vec<Token>)>,
+    ) -> Result<()> {
+        let limits = &self.tokenizer.input_limits;
+        limits.check_bytes(self.text.len())?;
+        let normalized = self
+            .tokenizer
+            .added_vocabulary
+            .extract_and_normalize(self.tokenizer.cached_normalizer().as_ref(), &self.text);
+        limits.check_bytes(normalized.normalized_len())?;
+        let mut pre_tokenized = self.tokenizer.do_pre_tokenize(normalized)?;
+        limits.check_pretokens(pre_tokenized.len())?;
+        self.added = pre_tokenized
+            .get_splits(OffsetReferential::Original, OffsetType::Byte)
+            .into_iter()
+            .filter(|(_, _, tokens)| tokens.is_some())
+            .map(|(_, offsets, _)| offsets)
+            .collect();
+
+        let retokenized = Cell::new(0);
+        pre_tokenized.tokenize(|normalized| {
+            let (start, end) = normalized.offsets_original();
+            let edited = start < widened.end && end > widened.start;
+            match reusable.get(&(start, end)) {
+                Some((content, tokens)) if !edited && content == normalized.get() => {
+                    Ok(tokens.clone())
+                }
+                _ => {
+                    retokenized.set(retokenized.get() + 1);
+                    self.tokenizer.model.tokenize(normalized.get())
+                }
+            }
+        })?;
+        self.retokenized = retokenized.get();
+
+        let added = &self.added;
+        self.words = pre_tokenized
+            .get_splits(OffsetReferential::Original, OffsetType::Byte)
+            .into_iter()
+            .filter(|(_, offsets, _)| !added.contains(offsets))
+            .filter_map(|(content, offsets, tokens)| {
+                Some((offsets, (content.to_owned(), tokens.clone()?)))
+            })
+            .collect();
+
+        let encoding = pre_tokenized.into_encoding(None, 0, OffsetType::Byte)?;
+        self.encoding = self
+            .tokenizer
+            .post_process(encoding, None, self.add_special_tokens)?;
+        Ok(())
+    }
+
+    /// The `Encoding` of the current text
+    pub fn get_encoding(&self) -> &Encoding {
+        &self.encoding
+    }
+
+    /// The current text, with all the edits applied
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    /// The number of words that went through the model during the last update
+    pub fn get_retokenized(&self) -> usize {
+        self.retokenized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::bpe::BPE;
+    use crate::pre_tokenizers::whitespace::Whitespace;
+    use crate::tokenizer::{AddedToken, Tokenizer};
+
+    fn tokenizer() -> Tokenizer {
+        let vocab = [
+            ("a", 0),
+            ("b", 1),
+            ("c", 2),
+            ("d", 3),
+            ("ab", 4),
+            ("abc", 5),
+            ("cd", 6),
+            ("[UNK]", 7),
+        ]
+        .iter()
+        .map(|(t, i)| (t.to_string(), *i))
+        .collect();
+        let merges = vec![
+            ("a".to_string(), "b".to_string()),
+            ("ab".to_string(), "c".to_string()),
+            ("c".to_string(), "d".to_string()),
+        ];
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab, merges)
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(bpe);
+        tokenizer.with_pre_tokenizer(Whitespace::default());
+        tokenizer
+            .add_special_tokens(&[AddedToken::from("<sep>", true)])
+            .unwrap();
+        tokenizer
+    }
+
+    fn assert_matches_full_encode(tokenizer: &Tokenizer, encoding: &Encoding, text: &str) {
+        let expected = tokenizer.encode(text, true).unwrap();
+        assert_eq!(encoding, &expected);
+    }
+
+    #[test]
+    fn edits() {
+        let tokenizer = tokenizer();
+        let mut encoder = IncrementalEncoder::new(&tokenizer, "ab cd ab cd abc", true).unwrap();
+        assert_matches_full_encode(&tokenizer, encoder.get_encoding(), "ab cd ab cd abc");
+
+        // Inserting a `c` merges with the `ab` before it
+        let encoding = encoder.edit(8..8, "c").unwrap().clone();
+        assert_eq!(encoder.get_text(), "ab cd abc cd abc");
+        assert_eq!(encoding.get_tokens()[2], "abc");
+        assert_matches_full_encode(&tokenizer, &encoding, "ab cd abc cd abc");
+        assert!(encoder.get_retokenized() < 5);
+
+        // Removing a space joins two words
+        let encoding = encoder.edit(2..3, "").unwrap().clone();
+        assert_eq!(encoder.get_text(), "abcd abc cd abc");
+        assert_matches_full_encode(&tokenizer, &encoding, "abcd abc cd abc");
+
+        // Replacing at the very end, and at the very start
+        let encoding = encoder.edit(12..15, "dd").unwrap().clone();
+        assert_matches_full_encode(&tokenizer, &encoding, "abcd abc cd dd");
+        let encoding = encoder.edit(0..0, "c").unwrap().clone();
+        assert_matches_full_encode(&tokenizer, &encoding, "cabcd abc cd dd");
+
+        assert!(encoder.edit(3..100, "").is_err());
+    }
+
+    #[test]
+    fn edits_with_added_tokens() {
+        let tokenizer = tokenizer();
+        let mut encoder = IncrementalEncoder::new(&tokenizer, "ab <sep> cd", true).unwrap();
+
+        // Touching the added token falls back to a full encode
+        let encoding = encoder.edit(8..8, "ab").unwrap().clone();
+        assert_matches_full_encode(&tokenizer, &encoding, "ab <sep>ab cd");
+        assert_eq!(encoder.get_retokenized(), 3);
+
+        // Breaking it, and creating it back
+        let encoding = encoder.edit(4..5, "").unwrap().clone();
+        assert_matches_full_encode(&tokenizer, &encoding, "ab <ep>ab cd");
+        let encoding = encoder.edit(4..4, "s").unwrap().clone();
+        assert_matches_full_encode(&tokenizer, &encoding, "ab <sep>ab cd");
+
+        let encoding = encoder.edit(11..11, "c").unwrap().clone();
+        assert_matches_full_encode(&tokenizer, &encoding, "ab <sep>ab ccd");
+    }
+
+    #[test]
+    fn input_limits() {
+        let mut tokenizer = tokenizer();
+        tokenizer.with_input_limits(Some(8), None);
+        assert!(IncrementalEncoder::new(&tokenizer, "ab cd ab c", true).is_err());
+
+        // A failed edit leaves the encoder as it was
+        let mut encoder = IncrementalEncoder::new(&tokenizer, "ab cd", true).unwrap();
+        assert!(encoder.edit(5..5, " ab c").is_err());
+        assert_eq!(encoder.get_text(), "ab cd");
+        assert_matches_full_encode(&tokenizer, encoder.get_encoding(), "ab cd");
+        let encoding = encoder.edit(5..5, " ab").unwrap().clone();
+        assert_matches_full_encode(&tokenizer, &encoding, "ab cd ab");
+
+        tokenizer.with_input_limits(None, Some(2));
+        let mut encoder = IncrementalEncoder::new(&tokenizer, "ab cd", true).unwrap();
+        assert!(encoder.edit(0..0, "c ").is_err());
+        assert_eq!(encoder.get_text(), "ab cd");
+    }
+}
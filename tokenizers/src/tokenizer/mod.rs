@@ -16,21 +16,32 @@ use std::{
     io::BufReader,
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
+use log::warn;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
+use crate::normalizers::cached::{NormalizationCache, WithCache};
 use crate::utils::iter::ResultShunt;
+use crate::utils::padding::padding_length_for;
 use crate::utils::parallelism::*;
 use crate::utils::progress::{ProgressBar, ProgressStyle};
+use crate::utils::truncation::truncated_lengths;
 
 mod added_vocabulary;
+mod binary;
+mod compatibility;
+mod description;
 mod encoding;
+mod hf_dir;
+mod incremental;
 pub mod normalizer;
 pub mod pattern;
 pub mod pre_tokenizer;
 mod serialization;
+mod spec;
 
 // Re-export wrappers
 pub use crate::decoders::DecoderWrapper;
@@ -40,14 +51,20 @@ pub use crate::pre_tokenizers::PreTokenizerWrapper;
 pub use crate::processors::PostProcessorWrapper;
 // And some other types
 pub use crate::utils::iter::LinesWithEnding;
-pub use crate::utils::padding::{pad_encodings, PaddingDirection, PaddingParams, PaddingStrategy};
+pub use crate::utils::padding::{
+    pad_and_stack, pad_encodings, PaddingDirection, PaddingParams, PaddingStrategy,
+};
 pub use crate::utils::truncation::{
-    truncate_encodings, TruncationDirection, TruncationParams, TruncationStrategy,
+    truncate_batch, truncate_encodings, TruncationDirection, TruncationParams, TruncationStrategy,
 };
 pub use added_vocabulary::*;
+pub use binary::BinaryFormatError;
+pub use description::{ComponentDescription, ModelDescription, PipelineDescription};
 pub use encoding::*;
+pub use incremental::*;
 pub use normalizer::{NormalizedString, OffsetReferential, SplitDelimiterBehavior};
 pub use pre_tokenizer::*;
+pub use spec::*;
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Result<T> = std::result::Result<T, Error>;
@@ -77,6 +94,11 @@ pub trait Model {
     fn token_to_id(&self, token: &str) -> Option<u32>;
     /// Find the string token associated to an ID
     fn id_to_token(&self, id: u32) -> Option<String>;
+    /// Find the string token associated to an ID like `id_to_token`, but borrowing it from
+    /// the vocabulary when the model can, instead of allocating it
+    fn id_to_token_cow(&self, id: u32) -> Option<Cow<'_, str>> {
+        self.id_to_token(id).map(Cow::Owned)
+    }
     /// Retrieve the entire vocabulary mapping (token -> ID)
     fn get_vocab(&self) -> HashMap<String, u32>;
     /// Retrieve the size of the vocabulary
@@ -84,15 +106,29 @@ pub trait Model {
     /// Save the current `Model` in the given folder, using the given `prefix` for the various
     /// files that need to be saved.
     fn save(&self, folder: &Path, prefix: Option<&str>) -> Result<Vec<PathBuf>>;
+    /// Serialize the files that `save` would write, as pairs of file name (without any
+    /// prefix) and content, to store them somewhere else than on disk.
+    fn serialize_parts(&self) -> Result<Vec<(String, Vec<u8>)>>;
     /// Get an instance of a Trainer capable of training this Model
     fn get_trainer(&self) -> <Self as Model>::Trainer;
+    /// Retrieve the token used for the unknown parts of the input, if any
+    fn unk_token(&self) -> Option<String> {
+        None
+    }
 }
 
 /// A `PostProcessor` has the responsibility to post process an encoded output of the `Tokenizer`.
 /// It adds any special tokens that a language model would require.
 pub trait PostProcessor {
-    /// Returns the number of tokens that will be added during the processing step
+    /// Returns the number of tokens that will be added during the processing step. When this
+    /// number depends on the encodings, it is only an upper bound, and
+    /// `has_exact_added_tokens` returns `false`.
     fn added_tokens(&self, is_pair: bool) -> usize;
+    /// Whether `added_tokens` is exactly the number of tokens that the processing adds,
+    /// whatever the encodings
+    fn has_exact_added_tokens(&self) -> bool {
+        true
+    }
     /// Process both encodings and returns a new merged one
     fn process(
         &self,
@@ -157,6 +193,33 @@ pub trait Decoder {
         Ok(results.join(""))
     }
     fn decode_chain(&self, tokens: Vec<String>) -> Result<Vec<String>>;
+    /// Decode the tokens of the given `ids` like `decode` does, appending the result to `out`.
+    /// The `vocab_lookup` gives the token of each id, or `None` for the ones to skip.
+    ///
+    /// This collects the tokens to go through `decode`, but the decoders that only concatenate
+    /// them can override it to write them straight into `out` instead.
+    fn decode_into<'v>(
+        &self,
+        ids: &[u32],
+        vocab_lookup: &dyn Fn(u32) -> Option<Cow<'v, str>>,
+        out: &mut String,
+    ) -> Result<()> {
+        let tokens = ids
+            .iter()
+            .filter_map(|id| vocab_lookup(*id))
+            .map(Cow::into_owned)
+            .collect();
+        out.push_str(&self.decode(tokens)?);
+        Ok(())
+    }
+    /// Decode each of the given sequences, like `decode` does. The decoders can override it
+    /// to share some state, like their buffers, between the sequences.
+    fn decode_batch(&self, sequences: Vec<Vec<String>>) -> Result<Vec<String>> {
+        sequences
+            .into_iter()
+            .map(|tokens| self.decode(tokens))
+            .collect()
+    }
 }
 
 /// A `Trainer` has the responsibility to train a model. We feed it with lines/sentences
@@ -179,6 +242,17 @@ pub trait Trainer {
     fn feed_with_counter(&mut self, counter: HashMap<String, u32>) -> ();
 }
 
+/// Receives the progress of a `Trainer`, as a series of phases (e.g. computing the alphabet,
+/// counting the pairs, ...), each made of a known number of steps
+pub trait TrainerProgress: Send + Sync {
+    /// A new phase that will take `total` steps is starting
+    fn on_phase_start(&self, phase: &str, total: usize);
+    /// The given phase advanced, and is now at step `current` out of `total`
+    fn on_advance(&self, phase: &str, current: usize, total: usize);
+    /// The given phase is over, after `total` steps
+    fn on_phase_end(&self, phase: &str, total: usize);
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Token {
     pub id: u32,
@@ -200,6 +274,18 @@ pub enum InputSequence<'s> {
     PreTokenizedCow(Cow<'s, [Cow<'s, str>]>),
 }
 
+impl InputSequence<'_> {
+    /// The total length in bytes of the sequence
+    fn byte_len(&self) -> usize {
+        match self {
+            Self::Raw(seq) => seq.len(),
+            Self::PreTokenized(seq) => seq.iter().map(|s| s.len()).sum(),
+            Self::PreTokenizedOwned(seq) => seq.iter().map(|s| s.len()).sum(),
+            Self::PreTokenizedCow(seq) => seq.iter().map(|s| s.len()).sum(),
+        }
+    }
+}
+
 impl<'s> From<Cow<'s, str>> for InputSequence<'s> {
     fn from(input: Cow<'s, str>) -> Self {
         Self::Raw(input)
@@ -280,6 +366,16 @@ where
 #[error("{0}")]
 pub struct BuilderError(String);
 
+/// Error returned by the operations that would modify the vocabulary of a frozen tokenizer
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum TokenizerError {
+    #[error("The tokenizer is frozen, its vocabulary can't be modified")]
+    Frozen,
+}
+
+/// Checks that a decoder fits with the model and the pre-tokenizer
+type DecoderCheck<M, PT, D> = fn(&M, Option<&PT>, &D) -> Option<String>;
+
 /// Builder for Tokenizer structs.
 ///
 /// `build()` fails if the `model` is missing.
@@ -294,6 +390,9 @@ pub struct TokenizerBuilder<M, N, PT, PP, D> {
 
     truncation: Option<TruncationParams>,
     padding: Option<PaddingParams>,
+
+    decoder_check: Option<DecoderCheck<M, PT, D>>,
+    strict_decoder_check: bool,
 }
 
 impl<M, N, PT, PP, D> Default for TokenizerBuilder<M, N, PT, PP, D>
@@ -328,16 +427,31 @@ where
             added_vocabulary: AddedVocabulary::new(),
             truncation: None,
             padding: None,
+            decoder_check: None,
+            strict_decoder_check: false,
         }
     }
 
     /// Convert the TokenizerBuilder to a Tokenizer.
     ///
-    /// Conversion fails if the `model` is missing.
+    /// Conversion fails if the `model` is missing, if the truncation parameters
+    /// can't be respected, or if the decoder doesn't fit with the rest under a strict
+    /// decoder check.
     pub fn build(self) -> Result<TokenizerImpl<M, N, PT, PP, D>> {
+        if let Some(trunc) = &self.truncation {
+            self.validate_truncation(trunc)?;
+        }
         let model = self
             .model
             .ok_or_else(|| Box::new(BuilderError("Model missing.".into())))?;
+        if let (Some(check), Some(decoder)) = (self.decoder_check, &self.decoder) {
+            if let Some(mismatch) = check(&model, self.pre_tokenizer.as_ref(), decoder) {
+                if self.strict_decoder_check {
+                    return Err(Box::new(BuilderError(format!("{}.", mismatch))));
+                }
+                warn!("{}, the decoded strings will probably be wrong.", mismatch);
+            }
+        }
         Ok(TokenizerImpl {
             normalizer: self.normalizer,
             pre_tokenizer: self.pre_tokenizer,
@@ -348,9 +462,51 @@ where
             added_vocabulary: self.added_vocabulary,
             truncation: self.truncation,
             padding: self.padding,
+            input_limits: InputLimits::default(),
+            thread_pool: None,
+            frozen: false,
+            normalization_cache: None,
         })
     }
 
+    /// Check that the truncation parameters are compatible with the rest of
+    /// the configuration.
+    fn validate_truncation(&self, trunc: &TruncationParams) -> Result<()> {
+        let n_added_tokens = match &self.post_processor {
+            Some(processor) if trunc.reserve_special_tokens => processor.added_tokens(false),
+            _ => 0,
+        };
+        if trunc.max_length <= n_added_tokens {
+            return Err(Box::new(BuilderError(format!(
+                "Truncation max_length ({}) must be greater than the {} special tokens \
+                 added by the post-processor.",
+                trunc.max_length, n_added_tokens
+            ))));
+        }
+        let effective_max_length = trunc.max_length - n_added_tokens;
+        if trunc.stride >= effective_max_length {
+            return Err(Box::new(BuilderError(format!(
+                "Truncation stride ({}) must be smaller than the effective max length of {} \
+                 (= {} max_length - {} added special tokens).",
+                trunc.stride, effective_max_length, trunc.max_length, n_added_tokens
+            ))));
+        }
+        if let Some(PaddingParams {
+            strategy: PaddingStrategy::Fixed(length),
+            ..
+        }) = &self.padding
+        {
+            if *length < trunc.max_length {
+                return Err(Box::new(BuilderError(format!(
+                    "Fixed padding length ({}) must not be smaller than the truncation \
+                     max_length ({}).",
+                    length, trunc.max_length
+                ))));
+            }
+        }
+        Ok(())
+    }
+
     /// Set the model.
     #[must_use]
     pub fn with_model(mut self, model: M) -> Self {
@@ -401,6 +557,24 @@ where
     }
 }
 
+impl<M, N, PT, PP, D> TokenizerBuilder<M, N, PT, PP, D>
+where
+    M: Model + 'static,
+    PT: PreTokenizer + 'static,
+    D: Decoder + 'static,
+{
+    /// Check that the decoder fits with the model and the pre-tokenizer when building, like
+    /// a `ByteLevel` decoder needing a `ByteLevel` pre-tokenizer, or a `WordPiece` decoder
+    /// needing a `WordPiece` model. A decoder that obviously doesn't fit fails the build
+    /// when `strict`, and only logs a warning otherwise.
+    #[must_use]
+    pub fn with_decoder_check(mut self, strict: bool) -> Self {
+        self.decoder_check = Some(compatibility::decoder_mismatch::<M, PT, D>);
+        self.strict_decoder_check = strict;
+        self
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Tokenizer(
     TokenizerImpl<
@@ -447,6 +621,34 @@ impl Tokenizer {
         let tokenizer_file = crate::utils::from_pretrained::from_pretrained(identifier, params)?;
         Tokenizer::from_file(tokenizer_file)
     }
+
+    /// Export only what the model needs to tokenize, for the tools that don't need the rest
+    /// of the pipeline: `{"vocab": {...}, "merges": [...]}` with a `BPE`, or
+    /// `{"vocab": {...}, "scores": [...]}` with a `Unigram`, the scores being in the order
+    /// of the ids. The other models only give their `vocab`. The normalizer, pre-tokenizer,
+    /// post-processor, decoder and added tokens are all left out.
+    pub fn export_minimal(&self) -> Result<serde_json::Value> {
+        let mut minimal = serde_json::Map::new();
+        if let ModelWrapper::Unigram(unigram) = self.get_model() {
+            let pieces = unigram.get_vocab_with_scores();
+            let vocab = pieces
+                .iter()
+                .enumerate()
+                .map(|(id, (token, _))| (token.clone(), id.into()))
+                .collect::<serde_json::Map<_, _>>();
+            let scores = pieces.iter().map(|(_, score)| *score).collect::<Vec<_>>();
+            minimal.insert("vocab".into(), vocab.into());
+            minimal.insert("scores".into(), scores.into());
+        } else {
+            let mut model = serde_json::to_value(self.get_model())?;
+            for key in &["vocab", "merges"] {
+                if let Some(value) = model.get_mut(key) {
+                    minimal.insert(key.to_string(), value.take());
+                }
+            }
+        }
+        Ok(minimal.into())
+    }
 }
 
 impl std::str::FromStr for Tokenizer {
@@ -475,6 +677,10 @@ where
             added_vocabulary: t.added_vocabulary,
             padding: t.padding,
             truncation: t.truncation,
+            input_limits: t.input_limits,
+            thread_pool: t.thread_pool,
+            frozen: t.frozen,
+            normalization_cache: t.normalization_cache,
         })
     }
 }
@@ -515,6 +721,38 @@ pub struct TokenizerImpl<M, N, PT, PP, D> {
     // General processing parameters
     truncation: Option<TruncationParams>,
     padding: Option<PaddingParams>,
+    input_limits: InputLimits,
+    thread_pool: Option<Arc<rayon::ThreadPool>>,
+    frozen: bool,
+    normalization_cache: Option<NormalizationCache>,
+}
+
+thread_local! {
+    /// The `PreTokenizedString` in which each thread encodes its sequences, so that encoding
+    /// many of them, like with `encode_batch`, reuses its buffers instead of reallocating them
+    static PRE_TOKENIZED: std::cell::RefCell<PreTokenizedString> = Default::default();
+}
+
+/// Where the encoding of a sequence adds the time spent in each stage, with `encode_traced`
+#[cfg(feature = "trace")]
+type TraceCell = std::cell::Cell<EncodeTrace>;
+#[cfg(not(feature = "trace"))]
+type TraceCell = std::cell::Cell<()>;
+
+/// Evaluate `$stage`, adding the time it took to the `$field` of the given trace, if any
+macro_rules! traced {
+    ($trace:expr, $field:ident, $stage:expr) => {{
+        #[cfg(feature = "trace")]
+        let start = $trace.is_some().then(std::time::Instant::now);
+        let result = $stage;
+        #[cfg(feature = "trace")]
+        if let (Some(trace), Some(start)) = ($trace, start) {
+            let mut elapsed = trace.get();
+            elapsed.$field += start.elapsed();
+            trace.set(elapsed);
+        }
+        result
+    }};
 }
 
 impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
@@ -538,12 +776,20 @@ where
 
             truncation: None,
             padding: None,
+            input_limits: InputLimits::default(),
+            thread_pool: None,
+            frozen: false,
+            normalization_cache: None,
         }
     }
 
     /// Set the normalizer
     pub fn with_normalizer(&mut self, normalizer: impl Into<N>) -> &mut Self {
         self.normalizer = Some(normalizer.into());
+        self.clear_normalization_cache();
+        // The normalized added tokens must match with this new normalizer
+        self.added_vocabulary
+            .refresh_added_tokens(&self.model, self.normalizer.as_ref());
         self
     }
 
@@ -552,6 +798,40 @@ where
         self.normalizer.as_ref()
     }
 
+    /// Get a mutable reference to the normalizer, to edit it in place.
+    /// The added tokens are normalized when the normalizer gets set, so they keep matching
+    /// the normalizer as it was until it is set again with `with_normalizer`. The
+    /// normalization cache gets emptied though.
+    pub fn get_normalizer_mut(&mut self) -> Option<&mut N> {
+        self.clear_normalization_cache();
+        self.normalizer.as_mut()
+    }
+
+    /// Cache the results of the normalizer, keeping the `capacity` most recently normalized
+    /// inputs (see [`NormalizationCache`]). This helps when the same inputs get encoded over
+    /// and over again, but only slows things down with mostly unique ones. The normalizer
+    /// **must be deterministic** for the outputs to stay the same. A `capacity` of 0 removes
+    /// the cache.
+    ///
+    /// The cache is not serialized along with the tokenizer, and a clone starts with an empty
+    /// one.
+    pub fn with_normalization_cache(&mut self, capacity: usize) -> &mut Self {
+        self.normalization_cache = (capacity > 0).then(|| NormalizationCache::new(capacity));
+        self
+    }
+
+    /// Get the cache set with `with_normalization_cache`, if any
+    pub fn get_normalization_cache(&self) -> Option<&NormalizationCache> {
+        self.normalization_cache.as_ref()
+    }
+
+    /// Empty the cache set with `with_normalization_cache`, if any
+    pub fn clear_normalization_cache(&self) {
+        if let Some(cache) = &self.normalization_cache {
+            cache.clear();
+        }
+    }
+
     /// Set the pre tokenizer
     pub fn with_pre_tokenizer(&mut self, pre_tokenizer: impl Into<PT>) -> &mut Self {
         self.pre_tokenizer = Some(pre_tokenizer.into());
@@ -563,6 +843,11 @@ where
         self.pre_tokenizer.as_ref()
     }
 
+    /// Get a mutable reference to the pre tokenizer, to edit it in place
+    pub fn get_pre_tokenizer_mut(&mut self) -> Option<&mut PT> {
+        self.pre_tokenizer.as_mut()
+    }
+
     /// Set the post processor
     pub fn with_post_processor(&mut self, post_processor: impl Into<PP>) -> &mut Self {
         self.post_processor = Some(post_processor.into());
@@ -574,6 +859,11 @@ where
         self.post_processor.as_ref()
     }
 
+    /// Get a mutable reference to the post processor, to edit it in place
+    pub fn get_post_processor_mut(&mut self) -> Option<&mut PP> {
+        self.post_processor.as_mut()
+    }
+
     /// Set the decoder
     pub fn with_decoder(&mut self, decoder: impl Into<D>) -> &mut Self {
         self.decoder = Some(decoder.into());
@@ -585,10 +875,16 @@ where
         self.decoder.as_ref()
     }
 
-    /// Set the model
-    pub fn with_model(&mut self, model: impl Into<M>) -> &mut Self {
+    /// Get a mutable reference to the decoder, to edit it in place
+    pub fn get_decoder_mut(&mut self) -> Option<&mut D> {
+        self.decoder.as_mut()
+    }
+
+    /// Set the model. It fails with [`TokenizerError::Frozen`] if the tokenizer is frozen.
+    pub fn with_model(&mut self, model: impl Into<M>) -> Result<&mut Self> {
+        self.check_not_frozen()?;
         self.model = model.into();
-        self
+        Ok(self)
     }
 
     /// Get the model
@@ -628,6 +924,89 @@ where
         self.padding.as_mut()
     }
 
+    /// Limit the size of the sequences to encode, failing with an [`InputTooLarge`] error, before
+    /// any tokenization, for the ones that are larger. A sequence can have `max_input_bytes`
+    /// both before and after its normalization, and `max_pretokens` once pre-tokenized. Both
+    /// limits are disabled with `None`, which is the default.
+    ///
+    /// These limits are not serialized along with the tokenizer.
+    pub fn with_input_limits(
+        &mut self,
+        max_input_bytes: Option<usize>,
+        max_pretokens: Option<usize>,
+    ) -> &mut Self {
+        self.input_limits = InputLimits {
+            max_input_bytes,
+            max_pretokens,
+        };
+        self
+    }
+
+    /// Get the currently set input limits
+    pub fn get_input_limits(&self) -> &InputLimits {
+        &self.input_limits
+    }
+
+    /// Run the batch operations and the training in the given thread pool, instead of the
+    /// global one, to bound the number of threads this tokenizer uses. `None` goes back to
+    /// the global pool, which is the default.
+    ///
+    /// The thread pool is not serialized along with the tokenizer.
+    pub fn set_thread_pool(&mut self, pool: Option<Arc<rayon::ThreadPool>>) -> &mut Self {
+        self.thread_pool = pool;
+        self
+    }
+
+    /// Get the thread pool set with `set_thread_pool`, if any
+    pub fn get_thread_pool(&self) -> Option<&Arc<rayon::ThreadPool>> {
+        self.thread_pool.as_ref()
+    }
+
+    /// Check that the `pad_id` of the padding is part of the vocabulary, if it asks for it.
+    /// The padding of a batch uses the same parameters, so checking its encodings is enough.
+    fn check_padding(&self) -> Result<()> {
+        match &self.padding {
+            Some(params) if params.validate_pad_id && self.id_to_token(params.pad_id).is_none() => {
+                Err(format!("Padding id {} is not part of the vocabulary", params.pad_id).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Build the default padding parameters, using the given token of the vocabulary
+    /// to pad. This fails if the token is not part of the vocabulary.
+    pub fn padding_params_for(&self, token: &str) -> Result<PaddingParams> {
+        let pad_id = self
+            .token_to_id(token)
+            .ok_or_else(|| format!("Padding token `{}` is not part of the vocabulary", token))?;
+        Ok(PaddingParams {
+            pad_id,
+            pad_token: token.to_owned(),
+            ..Default::default()
+        })
+    }
+
+    /// Use the given token of the vocabulary to pad, keeping the other padding parameters.
+    /// If the padding is not enabled yet, it gets enabled with the default parameters.
+    /// This fails if the token is not part of the vocabulary.
+    pub fn set_padding_token(&mut self, token: &str) -> Result<()> {
+        self.check_not_frozen()?;
+        let params = self.padding_params_for(token)?;
+        match &mut self.padding {
+            Some(padding) => {
+                padding.pad_id = params.pad_id;
+                padding.pad_token = params.pad_token;
+            }
+            None => self.padding = Some(params),
+        }
+        Ok(())
+    }
+
+    /// Get the id of the token used to pad, if the padding is enabled
+    pub fn pad_token_id(&self) -> Option<u32> {
+        self.padding.as_ref().map(|p| p.pad_id)
+    }
+
     /// Get the vocabulary
     pub fn get_vocab(&self, with_added_tokens: bool) -> HashMap<String, u32> {
         let mut final_vocab = self.model.get_vocab();
@@ -655,6 +1034,48 @@ where
             }
     }
 
+    /// Get the size of the vocabulary, split between the tokens of the model and the added
+    /// ones. An added token that the model already knows is counted only once, in `base`.
+    pub fn vocab_breakdown(&self) -> VocabBreakdown {
+        let mut breakdown = VocabBreakdown {
+            base: self.model.get_vocab_size(),
+            ..Default::default()
+        };
+        for token in self.added_vocabulary.get_vocab().keys() {
+            if self.model.token_to_id(token).is_some() {
+                continue;
+            }
+            if self.added_vocabulary.is_special_token(token) {
+                breakdown.added_special += 1;
+            } else {
+                breakdown.added_regular += 1;
+            }
+        }
+        breakdown
+    }
+
+    /// Find all the tokens of the vocabulary, including the added ones, that contain the
+    /// given substring, sorted by id
+    pub fn find_tokens(&self, substr: &str) -> Vec<(String, u32)> {
+        let mut found = self
+            .model
+            .get_vocab()
+            .into_iter()
+            .filter(|(token, _)| token.contains(substr))
+            .collect::<Vec<_>>();
+        found.extend(
+            self.added_vocabulary
+                .get_vocab()
+                .iter()
+                .filter(|(token, _)| token.contains(substr))
+                .map(|(token, id)| (token.clone(), *id)),
+        );
+        // The added tokens can also be part of the model vocab
+        found.sort_unstable_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        found.dedup();
+        found
+    }
+
     /// Converts a token in the corresponding id.
     pub fn token_to_id(&self, token: &str) -> Option<u32> {
         self.added_vocabulary.token_to_id(token, &self.model)
@@ -670,44 +1091,126 @@ where
         &self,
         sequence: InputSequence,
         type_id: u32,
+        offsets_referential: OffsetReferential,
         offsets_type: OffsetType,
     ) -> Result<Encoding> {
-        let encode = |is_pre_tokenized, subseq_idx, subseq| -> Result<Encoding> {
-            let normalized = self
-                .added_vocabulary
-                .extract_and_normalize(self.normalizer.as_ref(), subseq);
-            let pre_tokenized = self.do_pre_tokenize(normalized)?;
-            let subseq_encoding = self.do_tokenize(
-                pre_tokenized,
-                type_id,
-                if is_pre_tokenized {
-                    Some(subseq_idx as u32)
-                } else {
-                    None
-                },
-                offsets_type,
-            )?;
+        self.encode_single_sequence_until(
+            sequence,
+            type_id,
+            offsets_referential,
+            offsets_type,
+            None,
+            None,
+        )
+    }
+
+    /// Encode a single sequence like `encode_single_sequence`, stopping the tokenization
+    /// as soon as there are at least `max_tokens` tokens, if given. The time spent in each
+    /// stage gets added to `trace`, if given.
+    fn encode_single_sequence_until(
+        &self,
+        sequence: InputSequence,
+        type_id: u32,
+        offsets_referential: OffsetReferential,
+        offsets_type: OffsetType,
+        max_tokens: Option<usize>,
+        trace: Option<&TraceCell>,
+    ) -> Result<Encoding> {
+        #[cfg(not(feature = "trace"))]
+        let _ = trace;
+        self.input_limits.check_bytes(sequence.byte_len())?;
+        // What the previous subsequences used of the limits
+        let used = std::cell::Cell::new((0, 0));
+        let encode = |is_pre_tokenized,
+                      subseq_idx,
+                      subseq: &str,
+                      max_tokens: Option<usize>|
+         -> Result<Encoding> {
+            let word_idx = if is_pre_tokenized {
+                Some(subseq_idx as u32)
+            } else {
+                None
+            };
+            let encode_in = |pre_tokenized: &mut PreTokenizedString| {
+                traced!(
+                    trace,
+                    normalize,
+                    self.added_vocabulary.extract_and_normalize_into(
+                        self.cached_normalizer().as_ref(),
+                        subseq,
+                        pre_tokenized,
+                    )
+                );
+                let (bytes, pretokens) = used.get();
+                let bytes = bytes + pre_tokenized.normalized_len();
+                self.input_limits.check_bytes(bytes)?;
+                // The pre-tokenization can split or remove some parts of the normalized
+                // sequence, so we keep track of it beforehand if we need its referential
+                let referential = match offsets_referential {
+                    OffsetReferential::Original => None,
+                    OffsetReferential::Normalized => {
+                        Some(NormalizedReferential::new(pre_tokenized))
+                    }
+                };
+                if let Some(ref pretok) = self.pre_tokenizer {
+                    traced!(trace, pre_tokenize, pretok.pre_tokenize(pre_tokenized))?;
+                }
+                let pretokens = pretokens + pre_tokenized.len();
+                self.input_limits.check_pretokens(pretokens)?;
+                used.set((bytes, pretokens));
+                let tokenize =
+                    |normalized: &NormalizedString| self.model.tokenize(normalized.get());
+                traced!(trace, model, {
+                    match max_tokens {
+                        Some(max_tokens) => pre_tokenized.tokenize_until(tokenize, max_tokens)?,
+                        None => pre_tokenized.tokenize(tokenize)?,
+                    }
+                    pre_tokenized.take_encoding(
+                        word_idx,
+                        type_id,
+                        offsets_type,
+                        referential.as_ref(),
+                    )
+                })
+            };
+
+            PRE_TOKENIZED.with(|reused| match reused.try_borrow_mut() {
+                Ok(mut pre_tokenized) => encode_in(&mut pre_tokenized),
+                // Some encoding is already using it higher in the stack
+                Err(_) => encode_in(&mut PreTokenizedString::default()),
+            })
+        };
 
-            Ok(subseq_encoding)
+        // With pre-tokenized input, the subsequences after the `max_tokens` are skipped
+        let mut remaining = max_tokens;
+        let mut encode_subseq = |i, sequence: &str| {
+            if remaining == Some(0) {
+                return None;
+            }
+            let encoding = encode(true, i, sequence, remaining);
+            if let (Some(remaining), Ok(encoding)) = (remaining.as_mut(), &encoding) {
+                *remaining -= encoding.len().min(*remaining);
+            }
+            Some(encoding)
         };
 
         match sequence {
             InputSequence::PreTokenized(seq) => seq
                 .iter()
                 .enumerate()
-                .map(|(i, sequence)| encode(true, i, sequence))
+                .map_while(|(i, sequence)| encode_subseq(i, sequence))
                 .collect(),
             InputSequence::PreTokenizedOwned(seq) => seq
                 .iter()
                 .enumerate()
-                .map(|(i, sequence)| encode(true, i, sequence))
+                .map_while(|(i, sequence)| encode_subseq(i, sequence))
                 .collect(),
             InputSequence::PreTokenizedCow(seq) => seq
                 .iter()
                 .enumerate()
-                .map(|(i, sequence)| encode(true, i, sequence))
+                .map_while(|(i, sequence)| encode_subseq(i, sequence))
                 .collect(),
-            InputSequence::Raw(seq) => encode(false, 0, seq.as_ref()),
+            InputSequence::Raw(seq) => encode(false, 0, seq.as_ref(), max_tokens),
         }
     }
 
@@ -737,20 +1240,12 @@ where
     where
         E: Into<EncodeInput<'s>>,
     {
-        // Extract sequences from the EncodeInput
-        let (sequence, pair) = match input.into() {
-            EncodeInput::Single(s1) => (s1, None),
-            EncodeInput::Dual(s1, s2) => (s1, Some(s2)),
-        };
-
-        // Encode each sequence
-        let encoding = self.encode_single_sequence(sequence, 0, OffsetType::Byte)?;
-        let pair_encoding = pair
-            .map(|sequence| self.encode_single_sequence(sequence, 1, OffsetType::Byte))
-            .transpose()?;
-
-        // And finally post process
-        self.post_process(encoding, pair_encoding, add_special_tokens)
+        self.encode_with_referential(
+            input,
+            add_special_tokens,
+            OffsetReferential::Original,
+            OffsetType::Byte,
+        )
     }
 
     /// Encode the given input, using offsets relative to chars instead of bytes.
@@ -777,6 +1272,42 @@ where
     /// tokenizer.encode(("A complete sequence", &["And", "a", "tokenized"][..]), false);
     /// ```
     pub fn encode_char_offsets<'s, E>(&self, input: E, add_special_tokens: bool) -> Result<Encoding>
+    where
+        E: Into<EncodeInput<'s>>,
+    {
+        self.encode_with_referential(
+            input,
+            add_special_tokens,
+            OffsetReferential::Original,
+            OffsetType::Char,
+        )
+    }
+
+    /// Encode the given input, with offsets in the requested referential and type.
+    ///
+    /// Using [`OffsetReferential::Normalized`], the offsets of each sequence point into
+    /// its normalized form instead of the original input, which is useful to work with
+    /// the normalized sequence directly:
+    ///
+    /// ```
+    /// # use tokenizers::{OffsetReferential, OffsetType, Tokenizer};
+    /// # use tokenizers::models::bpe::BPE;
+    /// # let mut tokenizer = Tokenizer::new(BPE::default());
+    /// #
+    /// tokenizer.encode_with_referential(
+    ///     "Single sequence",
+    ///     false,
+    ///     OffsetReferential::Normalized,
+    ///     OffsetType::Byte,
+    /// );
+    /// ```
+    pub fn encode_with_referential<'s, E>(
+        &self,
+        input: E,
+        add_special_tokens: bool,
+        offsets_referential: OffsetReferential,
+        offsets_type: OffsetType,
+    ) -> Result<Encoding>
     where
         E: Into<EncodeInput<'s>>,
     {
@@ -787,106 +1318,764 @@ where
         };
 
         // Encode each sequence
-        let encoding = self.encode_single_sequence(sequence, 0, OffsetType::Char)?;
+        let encoding =
+            self.encode_single_sequence(sequence, 0, offsets_referential, offsets_type)?;
         let pair_encoding = pair
-            .map(|sequence| self.encode_single_sequence(sequence, 1, OffsetType::Char))
+            .map(|sequence| {
+                self.encode_single_sequence(sequence, 1, offsets_referential, offsets_type)
+            })
             .transpose()?;
 
         // And finally post process
+        self.check_padding()?;
         self.post_process(encoding, pair_encoding, add_special_tokens)
     }
 
-    /// Decode the given ids, back to a String
-    pub fn decode(&self, ids: Vec<u32>, skip_special_tokens: bool) -> Result<String> {
-        let tokens = ids
-            .into_iter()
-            .filter_map(|id| {
-                self.added_vocabulary
-                    .id_to_token(id, &self.model)
-                    .filter(|token| {
-                        !skip_special_tokens || !self.added_vocabulary.is_special_token(token)
-                    })
-            })
-            .collect::<Vec<_>>();
+    /// Encode the given sequence like `encode` with the special tokens, but keeping only its
+    /// first `max_tokens` tokens, as if truncated before the post-processing. The model stops
+    /// as soon as it produced enough tokens, so the tail of a long input never gets tokenized.
+    /// The extra tokens of the last tokenized split are dropped, so there are never any
+    /// overflowing encodings.
+    pub fn encode_truncated<'s, S>(&self, input: S, max_tokens: usize) -> Result<Encoding>
+    where
+        S: Into<InputSequence<'s>>,
+    {
+        let mut encoding = self.encode_single_sequence_until(
+            input.into(),
+            0,
+            OffsetReferential::Original,
+            OffsetType::Byte,
+            Some(max_tokens),
+            None,
+        )?;
+        // The last tokenized split can give more tokens than needed
+        encoding.truncate(max_tokens, 0, TruncationDirection::Right);
+        encoding.take_overflowing();
 
-        if let Some(decoder) = &self.decoder {
-            decoder.decode(tokens)
-        } else {
-            Ok(tokens.join(" "))
-        }
+        self.check_padding()?;
+        self.post_process(encoding, None, true)
     }
-}
 
-impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
-where
-    M: Model,
-{
-    /// Tokenization logic, makes the bridge between the pre-tokenization phase and the real
-    /// tokenization phase, and converting offsets back to the original referential.
-    fn do_tokenize<P: Into<PreTokenizedString>>(
+    /// Encode the given input like `encode`, while measuring the time spent in each stage of
+    /// the pipeline. For a pair, or some pre-tokenized input, the durations of all the
+    /// sequences are summed up.
+    #[cfg(feature = "trace")]
+    pub fn encode_traced<'s, E>(
         &self,
-        pretokenized: P,
-        type_id: u32,
-        word_idx: Option<u32>,
-        offsets_type: OffsetType,
-    ) -> Result<Encoding> {
-        let mut pretokenized: PreTokenizedString = pretokenized.into();
-        pretokenized.tokenize(|normalized| self.model.tokenize(normalized.get()))?;
-        pretokenized.into_encoding(word_idx, type_id, offsets_type)
-    }
-}
+        input: E,
+        add_special_tokens: bool,
+    ) -> Result<(Encoding, EncodeTrace)>
+    where
+        E: Into<EncodeInput<'s>>,
+    {
+        let (sequence, pair) = match input.into() {
+            EncodeInput::Single(s1) => (s1, None),
+            EncodeInput::Dual(s1, s2) => (s1, Some(s2)),
+        };
 
-impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
-where
-    N: Normalizer,
-{
-    /// Normalization logic, go through all normalizers
-    fn do_normalize<V: Into<NormalizedString>>(&self, normalized: V) -> Result<NormalizedString> {
-        let mut normalized: NormalizedString = normalized.into();
+        let trace = TraceCell::default();
+        let encode = |sequence, type_id| {
+            self.encode_single_sequence_until(
+                sequence,
+                type_id,
+                OffsetReferential::Original,
+                OffsetType::Byte,
+                None,
+                Some(&trace),
+            )
+        };
+        let encoding = encode(sequence, 0)?;
+        let pair_encoding = pair.map(|sequence| encode(sequence, 1)).transpose()?;
 
-        if let Some(ref normalizer) = self.normalizer {
-            normalizer.normalize(&mut normalized)?;
-        }
+        self.check_padding()?;
+        let start = std::time::Instant::now();
+        let encoding = self.post_process(encoding, pair_encoding, add_special_tokens)?;
+        let mut trace = trace.get();
+        trace.post_process = start.elapsed();
 
-        Ok(normalized)
+        Ok((encoding, trace))
     }
-}
 
-impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
-where
-    N: Normalizer,
-    M: Model,
-{
-    /// Register the given tokens as special tokens. This is especially useful for removing
-    /// these special tokens while decoding
-    pub fn add_special_tokens(&mut self, tokens: &[AddedToken]) -> usize {
-        self.added_vocabulary
-            .add_special_tokens(tokens, &self.model, self.normalizer.as_ref())
-    }
+    /// Encode some words, like the ones of a dataset that comes already split. Each word is
+    /// used as a single pre-token: it goes through the normalizer before the model tokenizes
+    /// it, but neither the pre-tokenizer nor the splitting on the added tokens runs. The word
+    /// ids of the resulting `Encoding` are the indices of the given words, and the offsets are
+    /// byte offsets in the words joined with single spaces, like `words.join(" ")`.
+    pub fn encode_pretokenized(
+        &self,
+        words: &[&str],
+        add_special_tokens: bool,
+    ) -> Result<Encoding> {
+        // The words are joined with spaces, which count too
+        let bytes = words.iter().map(|word| word.len()).sum::<usize>();
+        self.input_limits
+            .check_bytes(bytes + words.len().saturating_sub(1))?;
+        self.input_limits.check_pretokens(words.len())?;
+        let mut word_start = 0;
+        let encoding = words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| {
+                let normalized = self.do_normalize(*word)?;
+                let mut encoding =
+                    self.do_tokenize(normalized, 0, Some(i as u32), OffsetType::Byte, None)?;
+                encoding
+                    .get_offsets_mut()
+                    .iter_mut()
+                    .for_each(|(start, end)| {
+                        *start += word_start;
+                        *end += word_start;
+                    });
+                word_start += word.len() + 1;
+                Ok(encoding)
+            })
+            .collect::<Result<Encoding>>()?;
 
-    /// Add the given tokens to the added vocabulary
-    pub fn add_tokens(&mut self, tokens: &[AddedToken]) -> usize {
-        self.added_vocabulary
-            .add_tokens(tokens, &self.model, self.normalizer.as_ref())
+        self.check_padding()?;
+        self.post_process(encoding, None, add_special_tokens)
     }
-}
-
-impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
-where
-    PT: PreTokenizer,
-{
-    /// PreTokenization logic, handling the case where there is no PreTokenizer set
-    fn do_pre_tokenize<P: Into<PreTokenizedString>>(
-        &self,
-        pretokenized: P,
-    ) -> Result<PreTokenizedString> {
-        let mut pretokenized: PreTokenizedString = pretokenized.into();
 
-        if let Some(ref pretok) = self.pre_tokenizer {
-            pretok.pre_tokenize(&mut pretokenized)?;
+    /// Count the tokens that `encode` would give for the given input, without building any
+    /// `Encoding`. The truncation and padding are taken into account, as well as the special
+    /// tokens when `add_special_tokens` is set, but the post-processor is expected to only add
+    /// these special tokens. When the post-processor can't tell how many of them it adds, the
+    /// input is encoded to count them.
+    pub fn count_tokens<'s, E>(&self, input: E, add_special_tokens: bool) -> Result<usize>
+    where
+        E: Into<EncodeInput<'s>>,
+    {
+        if add_special_tokens
+            && matches!(&self.post_processor, Some(processor) if !processor.has_exact_added_tokens())
+        {
+            return Ok(self.encode(input, true)?.len());
         }
 
-        Ok(pretokenized)
+        let (sequence, pair) = match input.into() {
+            EncodeInput::Single(s1) => (s1, None),
+            EncodeInput::Dual(s1, s2) => (s1, Some(s2)),
+        };
+        let is_pair = pair.is_some();
+        let length = self.count_sequence_tokens(sequence)?;
+        let pair_length = pair
+            .map(|pair| self.count_sequence_tokens(pair))
+            .transpose()?;
+
+        // Same as `post_process`, without the encodings
+        let n_added_tokens = self
+            .post_processor
+            .as_ref()
+            .map_or(0, |processor| processor.added_tokens(is_pair));
+        let (length, pair_length) = if let Some(trunc) = &self.truncation {
+            if add_special_tokens && n_added_tokens > 0 && trunc.reserve_special_tokens {
+                let params = TruncationParams {
+                    max_length: trunc.max_length.saturating_sub(n_added_tokens),
+                    ..*trunc
+                };
+                truncated_lengths(length, pair_length, &params)?
+            } else {
+                truncated_lengths(length, pair_length, trunc)?
+            }
+        } else {
+            (length, pair_length)
+        };
+        let mut count = length + pair_length.unwrap_or(0);
+        if add_special_tokens {
+            count += n_added_tokens;
+        }
+        if let Some(params) = &self.padding {
+            count = count.max(padding_length_for(count, params));
+        }
+        Ok(count)
+    }
+
+    /// Count the tokens of a single sequence, like `encode_single_sequence` would produce
+    fn count_sequence_tokens(&self, sequence: InputSequence) -> Result<usize> {
+        self.input_limits.check_bytes(sequence.byte_len())?;
+        let used = std::cell::Cell::new((0, 0));
+        let count = |subseq: &str| -> Result<usize> {
+            let normalized = self
+                .added_vocabulary
+                .extract_and_normalize(self.cached_normalizer().as_ref(), subseq);
+            let (bytes, pretokens) = used.get();
+            let bytes = bytes + normalized.normalized_len();
+            self.input_limits.check_bytes(bytes)?;
+            let pre_tokenized = self.do_pre_tokenize(normalized)?;
+            let pretokens = pretokens + pre_tokenized.len();
+            self.input_limits.check_pretokens(pretokens)?;
+            used.set((bytes, pretokens));
+            pre_tokenized
+                .get_splits(OffsetReferential::Normalized, OffsetType::Byte)
+                .into_iter()
+                .map(|(split, _, tokens)| match tokens {
+                    Some(tokens) => Ok(tokens.len()),
+                    None => Ok(self.model.tokenize(split)?.len()),
+                })
+                .sum()
+        };
+
+        match sequence {
+            InputSequence::PreTokenized(seq) => seq.iter().map(|s| count(s)).sum(),
+            InputSequence::PreTokenizedOwned(seq) => seq.iter().map(|s| count(s)).sum(),
+            InputSequence::PreTokenizedCow(seq) => seq.iter().map(|s| count(s)).sum(),
+            InputSequence::Raw(seq) => count(seq.as_ref()),
+        }
+    }
+
+    /// Encode the given sequence like `encode`, but also report which parts of it didn't make
+    /// it into the `Encoding` as is. This is meant to help debugging, and is slower than a
+    /// simple `encode` since the sequence gets normalized twice.
+    ///
+    /// The spans of the report are byte offsets in the given sequence. Only the unknown tokens
+    /// that are not overflowing are reported.
+    pub fn encode_with_report(
+        &self,
+        sequence: &str,
+        add_special_tokens: bool,
+    ) -> Result<(Encoding, EncodeReport)> {
+        let encoding = self.encode(sequence, add_special_tokens)?;
+
+        let unk_id = self
+            .model
+            .unk_token()
+            .and_then(|unk| self.token_to_id(&unk));
+        let unk_spans = encoding
+            .get_ids()
+            .iter()
+            .zip(encoding.get_offsets())
+            .zip(encoding.get_special_tokens_mask())
+            .filter(|((id, _), special)| Some(**id) == unk_id && **special == 0)
+            .map(|((_, offsets), _)| *offsets)
+            .collect();
+        let removed_spans = self
+            .added_vocabulary
+            .extract_and_normalize(self.cached_normalizer().as_ref(), sequence)
+            .removed_ranges();
+
+        Ok((
+            encoding,
+            EncodeReport {
+                unk_spans,
+                removed_spans,
+            },
+        ))
+    }
+
+    /// Encode the given text and decode it back, to check whether the tokenizer gives back
+    /// the exact same text. No special token gets added, and neither the truncation nor the
+    /// padding are applied, so this only depends on the normalizer, pre-tokenizer, model and
+    /// decoder.
+    ///
+    /// Any change made by the normalizer, like the lowercasing, counts as a loss, as well as
+    /// the unknown tokens or the spaces lost without a decoder. The byte-level tokenizers are
+    /// usually lossless, while WordPiece loses the spaces around the punctuation.
+    pub fn is_roundtrip_lossless(&self, text: &str) -> Result<RoundtripReport> {
+        let encoding = self.encode_single_sequence(
+            text.into(),
+            0,
+            OffsetReferential::Original,
+            OffsetType::Byte,
+        )?;
+        let decoded = self.decode(encoding.get_ids().to_vec(), false)?;
+        Ok(RoundtripReport::new(text, decoded))
+    }
+
+    /// Decode the given ids, back to a String
+    pub fn decode(&self, ids: Vec<u32>, skip_special_tokens: bool) -> Result<String> {
+        let mut decoded = String::new();
+        self.decode_into(&ids, skip_special_tokens, &mut decoded)?;
+        Ok(decoded)
+    }
+
+    /// Decode the given ids like [`decode`](Self::decode). When `cleanup_tokenization_spaces`,
+    /// a final pass then removes the spaces before the punctuation and in some contractions
+    /// (` .`, ` ,`, ` n't`, ` 's`, ...), whatever the decoder. This cleanup is lossy, and only
+    /// fits English text.
+    pub fn decode_with_cleanup(
+        &self,
+        ids: &[u32],
+        skip_special_tokens: bool,
+        cleanup_tokenization_spaces: bool,
+    ) -> Result<String> {
+        let mut decoded = String::new();
+        self.decode_into(ids, skip_special_tokens, &mut decoded)?;
+        if cleanup_tokenization_spaces {
+            decoded = crate::decoders::wordpiece::cleanup(&decoded);
+        }
+        Ok(decoded)
+    }
+
+    /// Decode the given ids like [`decode`](Self::decode), appending the result to `out`.
+    /// The tokens are borrowed from the vocabulary, so with a decoder that writes them
+    /// straight into `out`, like `Metaspace` or `Fuse`, no string gets allocated per token.
+    pub fn decode_into(
+        &self,
+        ids: &[u32],
+        skip_special_tokens: bool,
+        out: &mut String,
+    ) -> Result<()> {
+        let vocab_lookup = |id| {
+            self.added_vocabulary
+                .id_to_token_cow(id, &self.model)
+                .filter(|token| {
+                    !skip_special_tokens || !self.added_vocabulary.is_special_token(token)
+                })
+        };
+
+        if let Some(decoder) = &self.decoder {
+            decoder.decode_into(ids, &vocab_lookup, out)
+        } else {
+            let tokens = ids.iter().filter_map(|id| vocab_lookup(*id));
+            for (i, token) in tokens.enumerate() {
+                if i > 0 {
+                    out.push(' ');
+                }
+                out.push_str(&token);
+            }
+            Ok(())
+        }
+    }
+
+    /// Convert the given ids to their tokens, as given to the decoder by `decode`. The unknown
+    /// ids are skipped, as well as the special tokens when `skip_special_tokens` is set.
+    pub fn decode_tokens(&self, ids: &[u32], skip_special_tokens: bool) -> Result<Vec<String>> {
+        Ok(ids
+            .iter()
+            .filter_map(|id| {
+                self.added_vocabulary
+                    .id_to_token(*id, &self.model)
+                    .filter(|token| {
+                        !skip_special_tokens || !self.added_vocabulary.is_special_token(token)
+                    })
+            })
+            .collect())
+    }
+
+    /// Join the tokens of the given ids with ` | ` without using the decoder, to see where
+    /// the boundaries between the tokens are.
+    pub fn decode_debug(&self, ids: &[u32], skip_special_tokens: bool) -> Result<String> {
+        Ok(self.decode_tokens(ids, skip_special_tokens)?.join(" | "))
+    }
+
+    /// Decode the given ids like [`decode`](Self::decode), also returning the char range of
+    /// the output produced by each of the decoded ids. The ranges follow each other and cover
+    /// the whole output, skipped ids having no range at all.
+    pub fn decode_with_spans(
+        &self,
+        ids: &[u32],
+        skip_special_tokens: bool,
+    ) -> Result<(String, Vec<DecodedSpan>)> {
+        let (ids, tokens): (Vec<u32>, Vec<String>) = ids
+            .iter()
+            .filter_map(|id| {
+                self.added_vocabulary
+                    .id_to_token(*id, &self.model)
+                    .filter(|token| {
+                        !skip_special_tokens || !self.added_vocabulary.is_special_token(token)
+                    })
+                    .map(|token| (*id, token))
+            })
+            .unzip();
+
+        let (output, lengths) = match &self.decoder {
+            Some(decoder) => {
+                let output = decoder.decode(tokens.clone())?;
+                let pieces = decoder.decode_chain(tokens.clone())?;
+                let lengths = if pieces.len() == tokens.len() && pieces.concat() == output {
+                    pieces.iter().map(|p| p.chars().count()).collect()
+                } else {
+                    // The decoder merges or rewrites some of the tokens, so we decode them
+                    // incrementally like `DecodeStream` does, each token getting the text
+                    // that its decoding adds
+                    let mut lengths = Vec::with_capacity(tokens.len());
+                    let (mut context, mut read) = (0, 0);
+                    let mut prefix = String::new();
+                    for i in 0..tokens.len() {
+                        let decoded = decoder.decode(tokens[context..=i].to_vec())?;
+                        if decoded.len() <= prefix.len()
+                            || decoded.ends_with('\u{FFFD}')
+                            || !decoded.starts_with(&prefix)
+                        {
+                            lengths.push(0);
+                            continue;
+                        }
+                        lengths.push(decoded[prefix.len()..].chars().count());
+                        context = read;
+                        read = i + 1;
+                        prefix = decoder.decode(tokens[context..read].to_vec())?;
+                    }
+                    let decoded_len = lengths.iter().sum::<usize>();
+                    if let Some(last) = lengths.last_mut() {
+                        *last += output.chars().count().saturating_sub(decoded_len);
+                    }
+                    lengths
+                };
+                (output, lengths)
+            }
+            None => {
+                let lengths = tokens
+                    .iter()
+                    .enumerate()
+                    .map(|(i, token)| token.chars().count() + usize::from(i > 0))
+                    .collect();
+                (tokens.join(" "), lengths)
+            }
+        };
+
+        // The spans never go past the output, even if the incremental decoding gave more
+        let output_len = output.chars().count();
+        let mut start = 0;
+        let spans = ids
+            .into_iter()
+            .zip(lengths)
+            .map(|(id, len)| {
+                let end = (start + len).min(output_len);
+                let span = start..end;
+                start = end;
+                (id, span)
+            })
+            .collect();
+        Ok((output, spans))
+    }
+
+    /// Create a [`DecodeStream`] to decode ids one at a time, as they get generated
+    pub fn decode_stream(&self, skip_special_tokens: bool) -> DecodeStream<'_, M, N, PT, PP, D> {
+        DecodeStream::new(self, skip_special_tokens)
+    }
+}
+
+/// A decoded id, with the char range of the output it produced, as returned by
+/// [`TokenizerImpl::decode_with_spans`]
+pub type DecodedSpan = (u32, std::ops::Range<usize>);
+
+/// The size of the vocabulary of each kind of token, as returned by
+/// [`TokenizerImpl::vocab_breakdown`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VocabBreakdown {
+    /// The tokens of the model
+    pub base: usize,
+    /// The added special tokens
+    pub added_special: usize,
+    /// The added tokens that are not special
+    pub added_regular: usize,
+}
+
+/// The time spent in each stage of [`TokenizerImpl::encode_traced`]
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EncodeTrace {
+    /// The extraction of the added tokens, and the normalization
+    pub normalize: std::time::Duration,
+    pub pre_tokenize: std::time::Duration,
+    /// The tokenization by the model, and the creation of the `Encoding`
+    pub model: std::time::Duration,
+    /// The truncation, post-processing and padding
+    pub post_process: std::time::Duration,
+}
+
+/// What happened to the input of [`TokenizerImpl::encode_with_report`], as byte offsets in
+/// the original sequence.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EncodeReport {
+    /// The spans that became an unknown token
+    pub unk_spans: Vec<Offsets>,
+    /// The spans removed by the normalizer
+    pub removed_spans: Vec<Offsets>,
+}
+
+/// The limits on the size of each sequence to encode, set with
+/// [`TokenizerImpl::with_input_limits`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InputLimits {
+    /// The maximum number of bytes, both before and after the normalization
+    pub max_input_bytes: Option<usize>,
+    /// The maximum number of pre-tokens, after the pre-tokenization
+    pub max_pretokens: Option<usize>,
+}
+
+impl InputLimits {
+    fn check_bytes(&self, bytes: usize) -> std::result::Result<(), InputTooLarge> {
+        match self.max_input_bytes {
+            Some(limit) if bytes > limit => Err(InputTooLarge::Bytes { bytes, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_pretokens(&self, pretokens: usize) -> std::result::Result<(), InputTooLarge> {
+        match self.max_pretokens {
+            Some(limit) if pretokens > limit => Err(InputTooLarge::PreTokens { pretokens, limit }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The error returned when encoding a sequence larger than the [`InputLimits`]
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum InputTooLarge {
+    #[error("The sequence has {bytes} bytes, more than the limit of {limit}")]
+    Bytes { bytes: usize, limit: usize },
+    #[error("The sequence has {pretokens} pre-tokens, more than the limit of {limit}")]
+    PreTokens { pretokens: usize, limit: usize },
+}
+
+/// The result of [`TokenizerImpl::is_roundtrip_lossless`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundtripReport {
+    /// The text given back by the decoding
+    pub decoded: String,
+    /// The byte offset, in the original text, of the first char that the decoded text doesn't
+    /// give back, or `None` if both texts are the same
+    pub first_divergence: Option<usize>,
+}
+
+impl RoundtripReport {
+    fn new(text: &str, decoded: String) -> Self {
+        let mut decoded_chars = decoded.chars();
+        let first_divergence = text
+            .char_indices()
+            .find(|(_, c)| decoded_chars.next() != Some(*c))
+            .map(|(offset, _)| offset)
+            // The decoded text might have some more chars at the end
+            .or_else(|| decoded_chars.next().map(|_| text.len()));
+        Self {
+            decoded,
+            first_divergence,
+        }
+    }
+
+    /// Whether the decoded text is the same as the original one
+    pub fn is_lossless(&self) -> bool {
+        self.first_divergence.is_none()
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum DecodeStreamError {
+    #[error("the decoded text doesn't start with the previously decoded prefix")]
+    InvalidPrefix,
+}
+
+/// Decodes ids one at a time, only yielding text once it is complete.
+///
+/// Decoding each id on its own isn't enough: some decoders need the surrounding
+/// tokens (to handle spaces for example), and some tokens only represent a part
+/// of a character (byte-fallback, byte-level). So we keep the ids of the last
+/// yielded chunk as a prefix, decode the prefix with the new ids, and return
+/// whatever comes after the decoded prefix once it doesn't end with an incomplete
+/// character anymore.
+pub struct DecodeStream<'tok, M, N, PT, PP, D> {
+    tokenizer: &'tok TokenizerImpl<M, N, PT, PP, D>,
+    skip_special_tokens: bool,
+    /// The ids that have not been fully consumed yet, prefix included
+    ids: Vec<u32>,
+    /// The text decoded from `ids[..prefix_index]`
+    prefix: String,
+    /// The number of ids in `ids` that make the prefix
+    prefix_index: usize,
+}
+
+impl<'tok, M, N, PT, PP, D> DecodeStream<'tok, M, N, PT, PP, D>
+where
+    M: Model,
+    N: Normalizer,
+    PT: PreTokenizer,
+    PP: PostProcessor,
+    D: Decoder,
+{
+    fn new(tokenizer: &'tok TokenizerImpl<M, N, PT, PP, D>, skip_special_tokens: bool) -> Self {
+        Self {
+            tokenizer,
+            skip_special_tokens,
+            ids: vec![],
+            prefix: String::new(),
+            prefix_index: 0,
+        }
+    }
+
+    /// Feed the next id, returning the newly decoded text if any is ready
+    pub fn step(&mut self, id: u32) -> Result<Option<String>> {
+        self.ids.push(id);
+        let string = self
+            .tokenizer
+            .decode(self.ids.clone(), self.skip_special_tokens)?;
+        if string.len() <= self.prefix.len() || string.ends_with('\u{FFFD}') {
+            return Ok(None);
+        }
+        if !string.starts_with(&self.prefix) {
+            return Err(Box::new(DecodeStreamError::InvalidPrefix));
+        }
+
+        let new_text = string[self.prefix.len()..].to_string();
+        let new_prefix_index = self.ids.len() - self.prefix_index;
+        self.ids.drain(..self.prefix_index);
+        self.prefix = self
+            .tokenizer
+            .decode(self.ids.clone(), self.skip_special_tokens)?;
+        self.prefix_index = new_prefix_index;
+        Ok(Some(new_text))
+    }
+
+    /// Decode whatever hasn't been yielded yet, once the generation is over. An
+    /// incomplete character at the end gets decoded as a replacement character.
+    pub fn finish(self) -> Result<Option<String>> {
+        let string = self.tokenizer.decode(self.ids, self.skip_special_tokens)?;
+        if string.len() <= self.prefix.len() {
+            return Ok(None);
+        }
+        if !string.starts_with(&self.prefix) {
+            return Err(Box::new(DecodeStreamError::InvalidPrefix));
+        }
+        Ok(Some(string[self.prefix.len()..].to_string()))
+    }
+}
+
+impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D> {
+    /// Freeze the vocabulary of this tokenizer, for good. The operations that would modify
+    /// it afterwards, like `add_tokens`, `with_model`, `set_padding_token` or the training,
+    /// fail with [`TokenizerError::Frozen`]. The other settings, as well as encoding and
+    /// decoding, are not affected.
+    ///
+    /// A clone of a frozen tokenizer is frozen too, but the flag is not serialized.
+    pub fn freeze(&mut self) -> &mut Self {
+        self.frozen = true;
+        self
+    }
+
+    /// Whether the tokenizer has been frozen with `freeze`
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// Fail with [`TokenizerError::Frozen`] if the tokenizer is frozen
+    fn check_not_frozen(&self) -> Result<()> {
+        if self.frozen {
+            return Err(Box::new(TokenizerError::Frozen));
+        }
+        Ok(())
+    }
+}
+
+impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
+where
+    M: Model,
+{
+    /// Tokenization logic, makes the bridge between the pre-tokenization phase and the real
+    /// tokenization phase, and converting offsets back to the original referential.
+    fn do_tokenize<P: Into<PreTokenizedString>>(
+        &self,
+        pretokenized: P,
+        type_id: u32,
+        word_idx: Option<u32>,
+        offsets_type: OffsetType,
+        referential: Option<&NormalizedReferential>,
+    ) -> Result<Encoding> {
+        let mut pretokenized: PreTokenizedString = pretokenized.into();
+        pretokenized.tokenize(|normalized| self.model.tokenize(normalized.get()))?;
+        match referential {
+            Some(referential) => pretokenized.into_encoding_with_referential(
+                word_idx,
+                type_id,
+                offsets_type,
+                referential,
+            ),
+            None => pretokenized.into_encoding(word_idx, type_id, offsets_type),
+        }
+    }
+}
+
+impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
+where
+    N: Normalizer,
+{
+    /// Apply only the configured normalizer to the given sequence, and return the
+    /// resulting text. The sequence is returned unchanged if there is no normalizer.
+    pub fn normalize(&self, sequence: &str) -> Result<String> {
+        Ok(self.normalize_with_offsets(sequence)?.get().to_owned())
+    }
+
+    /// Apply only the configured normalizer to the given sequence, and return the
+    /// `NormalizedString`, which keeps the alignment with the original sequence.
+    pub fn normalize_with_offsets(&self, sequence: &str) -> Result<NormalizedString> {
+        self.do_normalize(sequence)
+    }
+
+    /// Normalization logic, go through all normalizers
+    fn do_normalize<V: Into<NormalizedString>>(&self, normalized: V) -> Result<NormalizedString> {
+        let mut normalized: NormalizedString = normalized.into();
+
+        if let Some(normalizer) = self.cached_normalizer() {
+            normalizer.normalize(&mut normalized)?;
+        }
+
+        Ok(normalized)
+    }
+
+    /// The normalizer, going through the normalization cache if there is one
+    fn cached_normalizer(&self) -> Option<WithCache<'_, N>> {
+        self.normalizer.as_ref().map(|inner| WithCache {
+            inner,
+            cache: self.normalization_cache.as_ref(),
+        })
+    }
+}
+
+impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
+where
+    N: Normalizer,
+    M: Model,
+{
+    /// Register the given tokens as special tokens. This is especially useful for removing
+    /// these special tokens while decoding. It returns the number of tokens that were created
+    /// in the vocabulary, and fails with [`TokenizerError::Frozen`] if the tokenizer is frozen.
+    pub fn add_special_tokens(&mut self, tokens: &[AddedToken]) -> Result<usize> {
+        self.check_not_frozen()?;
+        Ok(self
+            .added_vocabulary
+            .add_special_tokens(tokens, &self.model, self.normalizer.as_ref()))
+    }
+
+    /// Add the given tokens to the added vocabulary. It returns the number of tokens that were
+    /// created in the vocabulary, and fails with [`TokenizerError::Frozen`] if the tokenizer is
+    /// frozen.
+    pub fn add_tokens(&mut self, tokens: &[AddedToken]) -> Result<usize> {
+        self.check_not_frozen()?;
+        Ok(self
+            .added_vocabulary
+            .add_tokens(tokens, &self.model, self.normalizer.as_ref()))
+    }
+
+    /// Register the given tokens as special tokens like `add_special_tokens`. When `strict`, it
+    /// fails instead of giving the next free id to a token whose id would already be used by
+    /// another one. It fails with [`TokenizerError::Frozen`] if the tokenizer is frozen.
+    pub fn try_add_special_tokens(&mut self, tokens: &[AddedToken], strict: bool) -> Result<usize> {
+        self.try_add_tokens(tokens, strict)
+    }
+
+    /// Add the given tokens to the added vocabulary like `add_tokens`. When `strict`, it fails
+    /// instead of giving the next free id to a token whose id would already be used by another
+    /// one. It fails with [`TokenizerError::Frozen`] if the tokenizer is frozen.
+    pub fn try_add_tokens(&mut self, tokens: &[AddedToken], strict: bool) -> Result<usize> {
+        self.check_not_frozen()?;
+        self.added_vocabulary
+            .try_add_tokens(tokens, &self.model, self.normalizer.as_ref(), strict)
+    }
+}
+
+impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
+where
+    PT: PreTokenizer,
+{
+    /// PreTokenization logic, handling the case where there is no PreTokenizer set
+    fn do_pre_tokenize<P: Into<PreTokenizedString>>(
+        &self,
+        pretokenized: P,
+    ) -> Result<PreTokenizedString> {
+        let mut pretokenized: PreTokenizedString = pretokenized.into();
+
+        if let Some(ref pretok) = self.pre_tokenizer {
+            pretok.pre_tokenize(&mut pretokenized)?;
+        }
+
+        Ok(pretokenized)
     }
 }
 
@@ -910,9 +2099,9 @@ where
                     0
                 };
 
-                if add_special_tokens && n_added_tokens > 0 {
+                if add_special_tokens && n_added_tokens > 0 && trunc.reserve_special_tokens {
                     let params = TruncationParams {
-                        max_length: trunc.max_length - n_added_tokens,
+                        max_length: trunc.max_length.saturating_sub(n_added_tokens),
                         ..*trunc
                     };
                     truncate_encodings(encoding, pair_encoding, &params)?
@@ -924,8 +2113,18 @@ where
             }
         };
 
+        // The post processors build new encodings, so we keep the stride of the overflowing
+        let stride = pair_encoding
+            .as_ref()
+            .map_or(0, |pair| pair.overflow_stride())
+            .max(encoding.overflow_stride());
+        let num_truncated_tokens = encoding.num_truncated_tokens()
+            + pair_encoding
+                .as_ref()
+                .map_or(0, |pair| pair.num_truncated_tokens());
+
         // 2. Then We post process
-        let final_encoding = if let Some(processor) = &self.post_processor {
+        let mut final_encoding = if let Some(processor) = &self.post_processor {
             processor.process(encoding, pair_encoding, add_special_tokens)?
         } else {
             let encodings = if let Some(pair_encoding) = pair_encoding {
@@ -940,6 +2139,18 @@ where
             }
             encodings.pop().unwrap()
         };
+        final_encoding.set_overflow_stride(final_encoding.overflow_stride().max(stride));
+        final_encoding.set_num_truncated_tokens(num_truncated_tokens);
+        if num_truncated_tokens > 0
+            && matches!(&self.truncation, Some(trunc) if trunc.warn_on_truncation)
+        {
+            warn!(
+                "Input truncated to {} tokens, {} tokens were dropped out of {}",
+                final_encoding.len(),
+                num_truncated_tokens,
+                final_encoding.original_length()
+            );
+        }
 
         // 3. Then we pad if needed
         let [final_encoding] = if let Some(params) = &self.padding {
@@ -952,8 +2163,17 @@ where
 
         Ok(final_encoding)
     }
+
+    /// Truncate the given encodings so that their lengths sum to at most `total_budget`,
+    /// removing the tokens from the longest ones first. See [`truncate_batch`].
+    pub fn truncate_batch(&self, encodings: &mut [Encoding], total_budget: usize) {
+        truncate_batch(encodings, total_budget)
+    }
 }
 
+/// The number of inputs encoded at once by `encode_batch_streaming`
+pub const STREAMING_CHUNK_SIZE: usize = 1024;
+
 impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
 where
     M: Model + Send + Sync,
@@ -962,7 +2182,9 @@ where
     PP: PostProcessor + Send + Sync,
     D: Decoder + Send + Sync,
 {
-    /// Encode all the sentences in parallel, using multiple threads
+    /// Encode all the sentences in parallel, using multiple threads. The inputs can mix some
+    /// single sequences and some pairs, as `EncodeInput`s, each getting the same encoding as
+    /// with `encode`, until the padding of the whole batch.
     pub fn encode_batch<'s, E>(
         &self,
         inputs: Vec<E>,
@@ -971,17 +2193,61 @@ where
     where
         E: Into<EncodeInput<'s>> + Send,
     {
-        let mut encodings = inputs
-            .into_maybe_par_iter()
-            .map(|input| self.encode(input, add_special_tokens))
-            .collect::<Result<Vec<Encoding>>>()?;
+        // The padding runs in the pool too, being parallel as well
+        install_in(self.thread_pool.as_deref(), || {
+            let mut encodings = inputs
+                .into_maybe_par_iter()
+                .map(|input| self.encode(input, add_special_tokens))
+                .collect::<Result<Vec<Encoding>>>()?;
+
+            if let Some(params) = &self.padding {
+                // We do the padding here to make sure we handle the batch padding
+                pad_encodings(&mut encodings, params)?;
+            }
 
-        if let Some(params) = &self.padding {
-            // We do the padding here to make sure we handle the batch padding
-            pad_encodings(&mut encodings, params)?;
-        }
+            Ok(encodings)
+        })
+    }
+
+    /// Encode all the sentences in parallel like `encode_batch`, but into `out`, which is
+    /// resized to match `inputs.len()`. Each of its `Encoding`s is refilled with
+    /// [`Encoding::refill_from`] as soon as its input got encoded, rather than replaced, so
+    /// that the buffers they hold get reused when encoding some batches of similar shapes in
+    /// a loop. Only the inputs past the current length of `out` get new `Encoding`s.
+    ///
+    /// If an error occurs, the content of `out` is unspecified.
+    pub fn encode_batch_into<'s, E>(
+        &self,
+        mut inputs: Vec<E>,
+        add_special_tokens: bool,
+        out: &mut Vec<Encoding>,
+    ) -> Result<()>
+    where
+        E: Into<EncodeInput<'s>> + Send,
+    {
+        out.truncate(inputs.len());
+        let extra_inputs = inputs.split_off(out.len());
+
+        install_in(self.thread_pool.as_deref(), || {
+            out.maybe_par_iter_mut()
+                .zip(inputs)
+                .map(|(reused, input)| {
+                    reused.refill_from(&self.encode(input, add_special_tokens)?);
+                    Ok(())
+                })
+                .collect::<Result<()>>()?;
+            let extra = extra_inputs
+                .into_maybe_par_iter()
+                .map(|input| self.encode(input, add_special_tokens))
+                .collect::<Result<Vec<Encoding>>>()?;
+            out.extend(extra);
+
+            if let Some(params) = &self.padding {
+                pad_encodings(out, params)?;
+            }
 
-        Ok(encodings)
+            Ok(())
+        })
     }
 
     /// Encode all the sentences in parallel, using multiple threads.
@@ -994,17 +2260,90 @@ where
     where
         E: Into<EncodeInput<'s>> + Send,
     {
-        let mut encodings = inputs
-            .into_maybe_par_iter()
-            .map(|input| self.encode_char_offsets(input, add_special_tokens))
-            .collect::<Result<Vec<Encoding>>>()?;
+        // The padding runs in the pool too, being parallel as well
+        install_in(self.thread_pool.as_deref(), || {
+            let mut encodings = inputs
+                .into_maybe_par_iter()
+                .map(|input| self.encode_char_offsets(input, add_special_tokens))
+                .collect::<Result<Vec<Encoding>>>()?;
+
+            if let Some(params) = &self.padding {
+                // We do the padding here to make sure we handle the batch padding
+                pad_encodings(&mut encodings, params)?;
+            }
+
+            Ok(encodings)
+        })
+    }
+
+    /// Encode all the sentences in parallel, yielding each `Encoding` as soon as it is
+    /// ready instead of collecting them all. The inputs are consumed by chunks of
+    /// `STREAMING_CHUNK_SIZE`, so only one chunk is ever kept in memory, which makes it
+    /// possible to process inputs that wouldn't fit at once.
+    ///
+    /// The encodings are **not** guaranteed to be yielded in the order of the inputs.
+    /// When padding is enabled, it is applied to each chunk as if it was a batch.
+    pub fn encode_batch_streaming<'a, 's, I, E>(
+        &'a self,
+        inputs: I,
+        add_special_tokens: bool,
+    ) -> impl Iterator<Item = Result<Encoding>> + 'a
+    where
+        I: IntoIterator<Item = E>,
+        I::IntoIter: 'a,
+        E: Into<EncodeInput<'s>> + Send + 'a,
+    {
+        let mut inputs = inputs.into_iter();
+        let mut ready: std::vec::IntoIter<Result<Encoding>> = vec![].into_iter();
+        std::iter::from_fn(move || loop {
+            if let Some(encoding) = ready.next() {
+                return Some(encoding);
+            }
 
-        if let Some(params) = &self.padding {
-            // We do the padding here to make sure we handle the batch padding
-            pad_encodings(&mut encodings, params)?;
-        }
+            let chunk = inputs
+                .by_ref()
+                .take(STREAMING_CHUNK_SIZE)
+                .collect::<Vec<_>>();
+            if chunk.is_empty() {
+                return None;
+            }
+            ready = self.encode_chunk(chunk, add_special_tokens).into_iter();
+        })
+    }
 
-        Ok(encodings)
+    /// Encode a chunk of `encode_batch_streaming`, keeping the errors along with the
+    /// successful encodings
+    fn encode_chunk<'s, E>(&self, inputs: Vec<E>, add_special_tokens: bool) -> Vec<Result<Encoding>>
+    where
+        E: Into<EncodeInput<'s>> + Send,
+    {
+        install_in(self.thread_pool.as_deref(), || {
+            let (encodings, errors): (Vec<_>, Vec<_>) = inputs
+                .into_maybe_par_iter()
+                .map(|input| self.encode(input, add_special_tokens))
+                .collect::<Vec<_>>()
+                .into_iter()
+                .partition(|encoding| encoding.is_ok());
+            let mut encodings = encodings
+                .into_iter()
+                .filter_map(|encoding| encoding.ok())
+                .collect::<Vec<_>>();
+
+            if let Some(params) = &self.padding {
+                if let Err(e) = pad_encodings(&mut encodings, params) {
+                    // Each encoding that couldn't get padded gets the error, so that there
+                    // is still one result per input
+                    let message = e.to_string();
+                    return encodings
+                        .iter()
+                        .map(|_| Err(message.as_str().into()))
+                        .chain(errors)
+                        .collect();
+                }
+            }
+
+            encodings.into_iter().map(Ok).chain(errors).collect()
+        })
     }
 
     /// Decode all sentences in parallel
@@ -1016,17 +2355,31 @@ where
     where
         M: Send + Sync,
     {
-        sentences
-            .into_maybe_par_iter()
-            .map(|sentence| self.decode(sentence, skip_special_tokens))
-            .collect()
+        install_in(self.thread_pool.as_deref(), || {
+            sentences
+                .into_maybe_par_iter()
+                .map(|sentence| self.decode(sentence, skip_special_tokens))
+                .collect()
+        })
+    }
+
+    /// Check the roundtrip of all the given texts in parallel, like `is_roundtrip_lossless`
+    pub fn is_roundtrip_lossless_batch(&self, texts: &[&str]) -> Result<Vec<RoundtripReport>> {
+        install_in(self.thread_pool.as_deref(), || {
+            texts
+                .into_maybe_par_iter()
+                .map(|text| self.is_roundtrip_lossless(text))
+                .collect()
+        })
     }
 
     /// Train our Model from files
     pub fn train_from_files<T>(&mut self, trainer: &mut T, files: Vec<String>) -> Result<&mut Self>
     where
-        T: Trainer<Model = M> + Sync,
+        T: Trainer<Model = M> + Send + Sync,
     {
+        self.check_not_frozen()?;
+        let pool = self.thread_pool.clone();
         let mut len = 0;
         for file in files.iter() {
             len += File::open(file)
@@ -1064,29 +2417,32 @@ where
                     None
                 };
 
-                trainer.feed(
-                    sequences.map(|s| {
-                        if let Some(progress) = &progress {
-                            progress.inc(s.len() as u64)
-                        }
-                        s
-                    }),
-                    |seq| {
-                        let normalized = self.do_normalize(seq.as_ref())?;
-                        let pre_tokenized = self.do_pre_tokenize(normalized)?;
-                        Ok(pre_tokenized
-                            .get_splits(OffsetReferential::Original, OffsetType::Byte)
-                            .into_iter()
-                            .map(|(s, _, _)| s.to_owned())
-                            .collect())
-                    },
-                )?;
+                install_in(pool.as_deref(), || {
+                    trainer.feed(
+                        sequences.map(|s| {
+                            if let Some(progress) = &progress {
+                                progress.inc(s.len() as u64)
+                            }
+                            s
+                        }),
+                        |seq| {
+                            let normalized = self.do_normalize(seq.as_ref())?;
+                            let pre_tokenized = self.do_pre_tokenize(normalized)?;
+                            Ok(pre_tokenized
+                                .get_splits(OffsetReferential::Original, OffsetType::Byte)
+                                .into_iter()
+                                .map(|(s, _, _)| s.to_owned())
+                                .collect())
+                        },
+                    )
+                })?;
 
                 if let Some(pbar) = progress {
                     pbar.finish();
                 }
-                let special_tokens = trainer.train(&mut self.model)?;
-                self.add_special_tokens(&special_tokens);
+                let special_tokens =
+                    install_in(pool.as_deref(), || trainer.train(&mut self.model))?;
+                self.add_special_tokens(&special_tokens)?;
 
                 Ok(())
             },
@@ -1099,21 +2455,57 @@ where
     where
         T: Trainer<Model = M> + Sync,
     {
+        self.check_not_frozen()?;
         trainer.feed_with_counter(counter);
 
-        let special_tokens = trainer.train(&mut self.model)?;
-        self.add_special_tokens(&special_tokens);
+        let pool = self.thread_pool.clone();
+        let special_tokens = install_in(pool.as_deref(), || trainer.train(&mut self.model))?;
+        self.add_special_tokens(&special_tokens)?;
 
         Ok(self)
     }
 
-    /// Train our Model, using the given Trainer and iterator
-    pub fn train<T, I, S>(&mut self, trainer: &mut T, sequences: I) -> Result<&mut Self>
+    /// Train our Model from several counters, whose counts are multiplied by the given
+    /// weights before being summed up.
+    ///
+    /// The weighted counts are rounded to the nearest integer, and saturate at `u32::MAX`.
+    pub fn train_from_counters<T>(
+        &mut self,
+        trainer: &mut T,
+        counters: Vec<(HashMap<String, u32>, f64)>,
+    ) -> Result<&mut Self>
     where
         T: Trainer<Model = M> + Sync,
+    {
+        let mut counter = HashMap::new();
+        for (words, weight) in counters {
+            for (word, count) in words {
+                // Casting a float saturates, and gives 0 for a negative or NaN weight
+                let weighted = (count as f64 * weight).round() as u32;
+                if weighted > 0 {
+                    let total = counter.entry(word).or_insert(0u32);
+                    *total = total.saturating_add(weighted);
+                }
+            }
+        }
+
+        self.train_from_counter(trainer, counter)
+    }
+
+    /// Train our Model, using the given Trainer and iterator of sequences, which can be
+    /// generated on the fly.
+    ///
+    /// Each sequence is processed like a line read by `train_from_files`, so yielding
+    /// the lines of some files (with their line endings) gives the same result as
+    /// training from these files. Only the word counts are kept in memory, the
+    /// sequences are dropped as soon as they have been pre-processed.
+    pub fn train<T, I, S>(&mut self, trainer: &mut T, sequences: I) -> Result<&mut Self>
+    where
+        T: Trainer<Model = M> + Send + Sync,
         I: Iterator<Item = S> + Send,
         S: AsRef<str> + Send,
     {
+        self.check_not_frozen()?;
         let (lower, upper) = sequences.size_hint();
         let len = upper.unwrap_or(lower) as u64;
         let progress = if trainer.should_show_progress() {
@@ -1134,29 +2526,32 @@ where
             None
         };
 
-        trainer.feed(
-            sequences.map(|s| {
-                if let Some(progress) = &progress {
-                    progress.inc(1)
-                }
-                s
-            }),
-            |seq| {
-                let normalized = self.do_normalize(seq.as_ref())?;
-                let pre_tokenized = self.do_pre_tokenize(normalized)?;
-                Ok(pre_tokenized
-                    .get_splits(OffsetReferential::Original, OffsetType::Byte)
-                    .into_iter()
-                    .map(|(s, _, _)| s.to_owned())
-                    .collect())
-            },
-        )?;
+        let pool = self.thread_pool.clone();
+        install_in(pool.as_deref(), || {
+            trainer.feed(
+                sequences.map(|s| {
+                    if let Some(progress) = &progress {
+                        progress.inc(1)
+                    }
+                    s
+                }),
+                |seq| {
+                    let normalized = self.do_normalize(seq.as_ref())?;
+                    let pre_tokenized = self.do_pre_tokenize(normalized)?;
+                    Ok(pre_tokenized
+                        .get_splits(OffsetReferential::Original, OffsetType::Byte)
+                        .into_iter()
+                        .map(|(s, _, _)| s.to_owned())
+                        .collect())
+                },
+            )
+        })?;
         if let Some(pbar) = progress {
             pbar.finish();
         }
 
-        let special_tokens = trainer.train(&mut self.model)?;
-        self.add_special_tokens(&special_tokens);
+        let special_tokens = install_in(pool.as_deref(), || trainer.train(&mut self.model))?;
+        self.add_special_tokens(&special_tokens)?;
 
         Ok(self)
     }
@@ -1255,3 +2650,1918 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::bpe::BPE;
+    use crate::normalizers::{Lowercase, Sequence, NFKC};
+    use crate::tokenizer::normalizer::Range;
+
+    #[test]
+    fn normalize() {
+        let mut tokenizer = Tokenizer::new(BPE::default());
+        assert_eq!(
+            tokenizer.normalize("Ｈｅｌｌｏ ﬁne").unwrap(),
+            "Ｈｅｌｌｏ ﬁne"
+        );
+
+        tokenizer.with_normalizer(Sequence::new(vec![NFKC.into(), Lowercase.into()]));
+        assert_eq!(tokenizer.normalize("Ｈｅｌｌｏ ﬁne").unwrap(), "hello fine");
+
+        let normalized = tokenizer.normalize_with_offsets("Ｈｅｌｌｏ ﬁne").unwrap();
+        assert_eq!(normalized.get(), "hello fine");
+        assert_eq!(normalized.get_original(), "Ｈｅｌｌｏ ﬁne");
+        // `ﬁ` is both `f` and `i` once normalized
+        assert_eq!(
+            normalized.convert_offsets(Range::Normalized(0..5)),
+            Some(0..15)
+        );
+        assert_eq!(
+            normalized.convert_offsets(Range::Normalized(6..8)),
+            Some(16..19)
+        );
+    }
+
+    #[test]
+    fn added_tokens_follow_normalizer() {
+        use crate::normalizers::{StripAccents, NFD};
+
+        let mut tokenizer = Tokenizer::new(BPE::default());
+        tokenizer
+            .add_tokens(&[AddedToken::from("café", false)])
+            .unwrap();
+        // The normalizer is set after adding the token
+        tokenizer.with_normalizer(Sequence::new(vec![NFD.into(), StripAccents.into()]));
+
+        for input in ["cafe", "café"].iter() {
+            let encoding = tokenizer.encode(*input, false).unwrap();
+            assert_eq!(encoding.get_ids(), &[0]);
+            assert_eq!(encoding.get_offsets(), &[(0, input.len())]);
+        }
+    }
+
+    #[test]
+    fn added_tokens_keep_their_casing() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::normalizers::{StripAccents, NFD};
+        use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+
+        let vocab = ["[UNK]", "hello", "world"]
+            .iter()
+            .enumerate()
+            .map(|(i, token)| (token.to_string(), i as u32))
+            .collect::<HashMap<_, _>>();
+        let mut tokenizer = Tokenizer::new(
+            WordLevel::builder()
+                .vocab(vocab)
+                .unk_token("[UNK]".into())
+                .build()
+                .unwrap(),
+        );
+        tokenizer.with_normalizer(Sequence::new(vec![
+            NFD.into(),
+            StripAccents.into(),
+            Lowercase.into(),
+        ]));
+        tokenizer.with_pre_tokenizer(WhitespaceSplit::default());
+        tokenizer
+            .add_special_tokens(&[AddedToken::from("<System>", true)])
+            .unwrap();
+        tokenizer
+            .add_tokens(&[AddedToken::from("<Usér>", false)])
+            .unwrap();
+        let system = tokenizer.token_to_id("<System>");
+        let user = tokenizer.token_to_id("<Usér>");
+
+        let sequence = "<System> Hello Wörld <USER>";
+        let encoding = tokenizer.encode(sequence, false).unwrap();
+        assert_eq!(
+            encoding.get_tokens(),
+            &["<System>", "hello", "world", "<USER>"]
+        );
+        assert_eq!(encoding.get_ids(), &[system.unwrap(), 1, 2, user.unwrap()]);
+        assert_eq!(encoding.get_offsets()[3], (22, 28));
+        // A cased special token is not normalized, so it must match exactly
+        let encoding = tokenizer.encode("<system> hello", false).unwrap();
+        assert_eq!(encoding.get_ids(), &[0, 1]);
+    }
+
+    #[test]
+    fn overflow_stride_after_post_processing() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+        use crate::processors::bert::BertProcessing;
+
+        let words = (0..20).map(|i| format!("w{}", i)).collect::<Vec<_>>();
+        let vocab = words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| (word.clone(), i as u32))
+            .chain(vec![
+                ("[UNK]".into(), 20),
+                ("[CLS]".into(), 21),
+                ("[SEP]".into(), 22),
+            ])
+            .collect::<HashMap<_, _>>();
+        let mut tokenizer = Tokenizer::new(
+            WordLevel::builder()
+                .vocab(vocab)
+                .unk_token("[UNK]".into())
+                .build()
+                .unwrap(),
+        );
+        tokenizer
+            .with_pre_tokenizer(WhitespaceSplit::default())
+            .with_post_processor(BertProcessing::new(
+                ("[SEP]".into(), 22),
+                ("[CLS]".into(), 21),
+            ))
+            .with_truncation(Some(TruncationParams {
+                max_length: 10,
+                stride: 2,
+                ..Default::default()
+            }));
+
+        let encoding = tokenizer.encode(words.join(" "), true).unwrap();
+        assert_eq!(encoding.overflow_stride(), 2);
+        let windows = std::iter::once(&encoding)
+            .chain(encoding.get_overflowing())
+            .map(|e| e.get_ids()[1..e.len() - 1].to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(windows.len(), 3);
+        for (previous, window) in windows.iter().zip(&windows[1..]) {
+            assert_eq!(previous[previous.len() - 2..], window[..2]);
+        }
+    }
+
+    #[test]
+    fn num_truncated_tokens() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+        use crate::processors::bert::BertProcessing;
+
+        let vocab = [("[UNK]", 0), ("[CLS]", 1), ("[SEP]", 2), ("a", 3)]
+            .iter()
+            .map(|(t, i)| (t.to_string(), *i))
+            .collect::<HashMap<_, _>>();
+        let mut tokenizer = Tokenizer::new(
+            WordLevel::builder()
+                .vocab(vocab)
+                .unk_token("[UNK]".into())
+                .build()
+                .unwrap(),
+        );
+        tokenizer
+            .with_pre_tokenizer(WhitespaceSplit::default())
+            .with_post_processor(BertProcessing::new(
+                ("[SEP]".into(), 2),
+                ("[CLS]".into(), 1),
+            ));
+        let words = |n: usize| vec!["a"; n].join(" ");
+
+        // Without truncation, nothing is dropped
+        let encoding = tokenizer.encode(words(12), true).unwrap();
+        assert_eq!(encoding.num_truncated_tokens(), 0);
+
+        tokenizer.with_truncation(Some(TruncationParams {
+            max_length: 10,
+            ..Default::default()
+        }));
+        let encoding = tokenizer.encode(words(12), true).unwrap();
+        assert_eq!(encoding.len(), 10);
+        assert_eq!(encoding.num_truncated_tokens(), 4);
+        assert!(encoding.was_truncated());
+        assert_eq!(encoding.original_length(), 14);
+        assert_eq!(encoding.get_overflowing().len(), 1);
+        let encoding = tokenizer.encode(words(8), true).unwrap();
+        assert_eq!(encoding.num_truncated_tokens(), 0);
+        assert!(!encoding.was_truncated());
+        assert_eq!(encoding.original_length(), 10);
+
+        // Warning about it doesn't change the output
+        tokenizer.with_truncation(Some(TruncationParams {
+            max_length: 10,
+            warn_on_truncation: true,
+            ..Default::default()
+        }));
+        let encoding = tokenizer.encode(words(12), true).unwrap();
+        assert!(encoding.was_truncated());
+        assert_eq!(encoding.original_length(), 14);
+
+        // Both sequences of a pair are counted
+        let encoding = tokenizer.encode((words(10), words(6)), true).unwrap();
+        assert_eq!(encoding.len(), 10);
+        assert_eq!(encoding.num_truncated_tokens(), 9);
+    }
+
+    #[test]
+    fn export_minimal() {
+        use crate::models::bpe::BPE;
+        use crate::models::unigram::Unigram;
+        use crate::models::wordlevel::WordLevel;
+        use crate::models::wordpiece::WordPiece;
+        use crate::normalizers::Lowercase;
+
+        let keys = |tokenizer: &Tokenizer| {
+            let exported = tokenizer.export_minimal().unwrap();
+            let mut keys = exported
+                .as_object()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>();
+            keys.sort();
+            keys
+        };
+        let vocab = [("a", 0), ("b", 1), ("ab", 2)]
+            .iter()
+            .map(|(t, i)| (t.to_string(), *i))
+            .collect::<HashMap<_, _>>();
+
+        let mut tokenizer = Tokenizer::new(
+            BPE::builder()
+                .vocab_and_merges(vocab.clone(), vec![("a".into(), "b".into())])
+                .build()
+                .unwrap(),
+        );
+        tokenizer.with_normalizer(Lowercase);
+        assert_eq!(keys(&tokenizer), ["merges", "vocab"]);
+        let exported = tokenizer.export_minimal().unwrap();
+        assert_eq!(exported["merges"], serde_json::json!(["a b"]));
+        assert_eq!(exported["vocab"]["ab"], 2);
+
+        let tokenizer = Tokenizer::new(
+            Unigram::from(
+                vec![("<unk>".into(), 0.0), ("a".into(), -0.5)],
+                Some(0),
+                None,
+            )
+            .unwrap(),
+        );
+        assert_eq!(keys(&tokenizer), ["scores", "vocab"]);
+        let exported = tokenizer.export_minimal().unwrap();
+        assert_eq!(exported["vocab"]["a"], 1);
+        assert_eq!(exported["scores"], serde_json::json!([0.0, -0.5]));
+
+        let tokenizer = Tokenizer::new(WordPiece::builder().vocab(vocab.clone()).build().unwrap());
+        assert_eq!(keys(&tokenizer), ["vocab"]);
+        let tokenizer = Tokenizer::new(WordLevel::builder().vocab(vocab).build().unwrap());
+        assert_eq!(keys(&tokenizer), ["vocab"]);
+    }
+
+    #[test]
+    fn normalization_cache() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::normalizers::NFKC;
+        use crate::pre_tokenizers::whitespace::Whitespace;
+
+        let vocab = ["<unk>", "hello", "ﬁne", "fine", "!"]
+            .iter()
+            .enumerate()
+            .map(|(i, token)| (token.to_string(), i as u32))
+            .collect::<HashMap<_, _>>();
+        let mut tokenizer = Tokenizer::new(
+            WordLevel::builder()
+                .vocab(vocab)
+                .unk_token("<unk>".into())
+                .build()
+                .unwrap(),
+        );
+        tokenizer
+            .with_normalizer(NFKC)
+            .with_pre_tokenizer(Whitespace::default());
+        let mut cached = tokenizer.clone();
+        cached.with_normalization_cache(16);
+        let hits = |tokenizer: &Tokenizer| tokenizer.get_normalization_cache().unwrap().get_hits();
+
+        let inputs = [
+            "ＨＥＬＬＯ ﬁne!",
+            "hello",
+            "ＨＥＬＬＯ ﬁne!",
+            "ﬁne hello",
+            "hello",
+        ];
+        for input in inputs {
+            assert_eq!(
+                cached.encode(input, false).unwrap(),
+                tokenizer.encode(input, false).unwrap()
+            );
+        }
+        assert_eq!(hits(&cached), 2);
+
+        cached.clear_normalization_cache();
+        cached.encode("hello", false).unwrap();
+        assert_eq!(hits(&cached), 2);
+
+        // The normalizer and the serialization are left untouched
+        assert!(matches!(
+            cached.get_normalizer(),
+            Some(NormalizerWrapper::NFKC(_))
+        ));
+        assert_eq!(
+            cached.to_string(false).unwrap(),
+            tokenizer.to_string(false).unwrap()
+        );
+        let clone = cached.clone();
+        assert_eq!(clone.get_normalization_cache().unwrap().get_capacity(), 16);
+        assert_eq!(hits(&clone), 0);
+
+        // Changing the normalizer empties the cache, and a capacity of 0 removes it
+        assert_eq!(cached.encode("ﬁne", false).unwrap().get_ids(), [3]);
+        cached.with_normalizer(Lowercase);
+        assert_eq!(cached.encode("ﬁne", false).unwrap().get_ids(), [2]);
+        assert_eq!(hits(&cached), 2);
+        cached.with_normalization_cache(0);
+        assert!(cached.get_normalization_cache().is_none());
+    }
+
+    #[test]
+    fn decode_tokens() {
+        use crate::decoders::wordpiece::WordPiece as WordPieceDecoder;
+        use crate::models::wordpiece::WordPiece;
+
+        let vocab = ["[UNK]", "[CLS]", "[SEP]", "hello", "world", "##s"]
+            .iter()
+            .enumerate()
+            .map(|(i, token)| (token.to_string(), i as u32))
+            .collect::<HashMap<_, _>>();
+        let mut tokenizer = Tokenizer::new(WordPiece::builder().vocab(vocab).build().unwrap());
+        tokenizer.with_decoder(WordPieceDecoder::default());
+        tokenizer
+            .add_special_tokens(&[
+                AddedToken::from("[CLS]", true),
+                AddedToken::from("[SEP]", true),
+            ])
+            .unwrap();
+
+        let ids = [1, 3, 4, 5, 2];
+        assert_eq!(
+            tokenizer.decode(ids.to_vec(), true).unwrap(),
+            "hello worlds"
+        );
+        let tokens = tokenizer.decode_tokens(&ids, false).unwrap();
+        assert_eq!(tokens, ["[CLS]", "hello", "world", "##s", "[SEP]"]);
+        let tokens = tokenizer.decode_tokens(&ids, true).unwrap();
+        assert_eq!(tokens.len(), ids.len() - 2);
+        assert_eq!(
+            tokenizer.decode_debug(&ids, true).unwrap(),
+            "hello | world | ##s"
+        );
+    }
+
+    #[test]
+    fn count_tokens() {
+        use crate::models::wordpiece::WordPiece;
+        use crate::pre_tokenizers::whitespace::Whitespace;
+        use crate::processors::bert::BertProcessing;
+        use crate::processors::ensure::EnsureBosEos;
+
+        let vocab = ["[UNK]", "[CLS]", "[SEP]", "hello", "world", "##s", "!"]
+            .iter()
+            .enumerate()
+            .map(|(i, token)| (token.to_string(), i as u32))
+            .collect::<HashMap<_, _>>();
+        let mut tokenizer = Tokenizer::new(WordPiece::builder().vocab(vocab).build().unwrap());
+        tokenizer
+            .with_normalizer(Lowercase)
+            .with_pre_tokenizer(Whitespace::default())
+            .with_post_processor(BertProcessing::new(
+                ("[SEP]".into(), 2),
+                ("[CLS]".into(), 1),
+            ));
+        tokenizer
+            .add_tokens(&[AddedToken::from("<mask>", true)])
+            .unwrap();
+
+        let inputs: Vec<EncodeInput> = vec![
+            "Hello worlds!".into(),
+            "".into(),
+            "hello <mask> worlds worlds unknown !".into(),
+            ("Hello worlds", "hello hello hello hello").into(),
+            (&["Hello", "worlds!"][..]).into(),
+        ];
+        let check = |tokenizer: &Tokenizer| {
+            for input in &inputs {
+                for add_special_tokens in [true, false] {
+                    // When truncation fails, both fail
+                    assert_eq!(
+                        tokenizer
+                            .count_tokens(input.clone(), add_special_tokens)
+                            .ok(),
+                        tokenizer
+                            .encode(input.clone(), add_special_tokens)
+                            .ok()
+                            .map(|e| e.len())
+                    );
+                }
+            }
+        };
+        check(&tokenizer);
+
+        for strategy in [
+            TruncationStrategy::LongestFirst,
+            TruncationStrategy::OnlyFirst,
+        ] {
+            for reserve_special_tokens in [true, false] {
+                tokenizer.with_truncation(Some(TruncationParams {
+                    max_length: 5,
+                    strategy,
+                    reserve_special_tokens,
+                    ..Default::default()
+                }));
+                check(&tokenizer);
+            }
+        }
+        tokenizer.with_padding(Some(PaddingParams {
+            strategy: PaddingStrategy::Fixed(3),
+            pad_to_multiple_of: Some(4),
+            ..Default::default()
+        }));
+        check(&tokenizer);
+
+        // The special tokens already in the input are not added again
+        tokenizer.with_padding(None);
+        tokenizer.with_post_processor(EnsureBosEos::new(("[CLS]".into(), 1), ("[SEP]".into(), 2)));
+        tokenizer
+            .add_special_tokens(&[AddedToken::from("[CLS]", true)])
+            .unwrap();
+        assert_eq!(tokenizer.count_tokens("[CLS] hello", true).unwrap(), 3);
+        check(&tokenizer);
+    }
+
+    #[test]
+    fn unk_offsets_in_original() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::models::wordpiece::WordPiece;
+        use crate::normalizers::{StripAccents, NFD};
+        use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+
+        let vocab = ["hello", "[UNK]"]
+            .iter()
+            .enumerate()
+            .map(|(i, token)| (token.to_string(), i as u32))
+            .collect::<HashMap<_, _>>();
+        let models: Vec<ModelWrapper> = vec![
+            WordLevel::builder()
+                .vocab(vocab.clone())
+                .unk_token("[UNK]".into())
+                .build()
+                .unwrap()
+                .into(),
+            WordPiece::builder()
+                .vocab(vocab.clone())
+                .build()
+                .unwrap()
+                .into(),
+            BPE::builder()
+                .vocab_and_merges(vocab, vec![])
+                .unk_token("[UNK]".into())
+                .fuse_unk(true)
+                .build()
+                .unwrap()
+                .into(),
+        ];
+        for model in models {
+            let mut tokenizer = Tokenizer::new(model);
+            tokenizer.with_normalizer(Sequence::new(vec![
+                NFD.into(),
+                StripAccents.into(),
+                Lowercase.into(),
+            ]));
+            tokenizer.with_pre_tokenizer(WhitespaceSplit::default());
+
+            // Without merges, the BPE only knows [UNK], but its offsets are the same
+            let encoding = tokenizer.encode("hello Wörld hello", false).unwrap();
+            assert_eq!(encoding.get_ids()[1], 1);
+            assert_eq!(encoding.get_offsets(), &[(0, 5), (6, 12), (13, 18)]);
+        }
+    }
+
+    #[test]
+    fn encode_pretokenized() {
+        use crate::models::wordpiece::WordPiece;
+        use crate::pre_tokenizers::whitespace::Whitespace;
+
+        let vocab = ["[UNK]", "new", "york", "##ers", "!", "-"]
+            .iter()
+            .enumerate()
+            .map(|(i, token)| (token.to_string(), i as u32))
+            .collect::<HashMap<_, _>>();
+        let mut tokenizer = Tokenizer::new(WordPiece::builder().vocab(vocab).build().unwrap());
+        tokenizer.with_normalizer(Lowercase);
+        tokenizer.with_pre_tokenizer(Whitespace::default());
+        tokenizer
+            .add_tokens(&[AddedToken::from("-", false)])
+            .unwrap();
+
+        let words = ["New", "Yorkers", "!"];
+        let encoding = tokenizer.encode_pretokenized(&words, false).unwrap();
+        assert_eq!(encoding.get_tokens(), &["new", "york", "##ers", "!"]);
+        assert_eq!(
+            encoding.get_word_ids(),
+            &[Some(0), Some(1), Some(1), Some(2)]
+        );
+        // The offsets are in the words joined with spaces
+        assert_eq!(encoding.get_offsets(), &[(0, 3), (4, 8), (8, 11), (12, 13)]);
+        let joined = words.join(" ");
+        assert_eq!(
+            encoding
+                .get_offsets()
+                .iter()
+                .map(|(start, end)| &joined[*start..*end])
+                .collect::<Vec<_>>(),
+            ["New", "York", "ers", "!"]
+        );
+
+        // The words are never split
+        let encoding = tokenizer
+            .encode_pretokenized(&["new-york", "new york"], false)
+            .unwrap();
+        assert_eq!(encoding.get_tokens(), &["[UNK]", "[UNK]"]);
+        assert_eq!(encoding.get_word_ids(), &[Some(0), Some(1)]);
+
+        // The byte limit counts the spaces between the words
+        tokenizer.with_input_limits(Some(12), None);
+        assert!(tokenizer.encode_pretokenized(&words, false).is_err());
+        tokenizer.with_input_limits(Some(13), None);
+        assert!(tokenizer.encode_pretokenized(&words, false).is_ok());
+
+        // The pad id is checked like with `encode`
+        tokenizer.with_padding(Some(PaddingParams {
+            pad_id: 42,
+            validate_pad_id: true,
+            ..Default::default()
+        }));
+        assert!(tokenizer.encode_pretokenized(&words, false).is_err());
+    }
+
+    #[test]
+    fn encode_with_report() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::normalizers::bert::BertNormalizer;
+        use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+
+        let vocab = [
+            ("[UNK]".to_string(), 0),
+            ("hello".to_string(), 1),
+            ("world".to_string(), 2),
+        ]
+        .iter()
+        .cloned()
+        .collect::<HashMap<_, _>>();
+        let mut tokenizer = Tokenizer::new(
+            WordLevel::builder()
+                .vocab(vocab)
+                .unk_token("[UNK]".into())
+                .build()
+                .unwrap(),
+        );
+        tokenizer.with_pre_tokenizer(WhitespaceSplit::default());
+
+        let sequence = "hello wor\u{0}ld\u{7} there";
+        let (encoding, report) = tokenizer.encode_with_report(sequence, false).unwrap();
+        assert_eq!(encoding.get_ids(), &[1, 0, 0]);
+        assert_eq!(
+            report,
+            EncodeReport {
+                unk_spans: vec![(6, 13), (14, 19)],
+                removed_spans: vec![],
+            }
+        );
+
+        // The normalizer removes the control chars
+        tokenizer.with_normalizer(BertNormalizer::default());
+        let (encoding, report) = tokenizer.encode_with_report(sequence, false).unwrap();
+        assert_eq!(encoding.get_ids(), &[1, 2, 0]);
+        assert_eq!(
+            report,
+            EncodeReport {
+                unk_spans: vec![(14, 19)],
+                removed_spans: vec![(9, 10), (12, 13)],
+            }
+        );
+        assert_eq!(encoding, tokenizer.encode(sequence, false).unwrap());
+    }
+
+    #[test]
+    fn decode_with_spans() {
+        use crate::decoders::byte_fallback::ByteFallback;
+        use crate::decoders::wordpiece::WordPiece as WordPieceDecoder;
+        use crate::models::wordlevel::WordLevel;
+
+        let vocab = [
+            "<unk>", "Hello", "world", "##s", "!", "<0xE2>", "<0x9C>", "<0x85>",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (t.to_string(), i as u32))
+        .collect::<HashMap<_, _>>();
+        let mut tokenizer = Tokenizer::new(WordLevel::builder().vocab(vocab).build().unwrap());
+        tokenizer
+            .add_special_tokens(&[AddedToken::from("[SEP]", true)])
+            .unwrap();
+
+        let spans_text = |output: &str, spans: &[DecodedSpan]| {
+            let chars = output.chars().collect::<Vec<_>>();
+            spans
+                .iter()
+                .map(|(_, range)| chars[range.clone()].iter().collect::<String>())
+                .collect::<Vec<_>>()
+        };
+
+        // Without decoder, the tokens are joined with spaces
+        let (output, spans) = tokenizer.decode_with_spans(&[1, 2], false).unwrap();
+        assert_eq!(output, "Hello world");
+        assert_eq!(spans, vec![(1, 0..5), (2, 5..11)]);
+
+        // The spans tile the output, the cleanup included
+        tokenizer.with_decoder(WordPieceDecoder::default());
+        let ids = [1, 2, 3, 4, 8, 1];
+        let (output, spans) = tokenizer.decode_with_spans(&ids, true).unwrap();
+        assert_eq!(output, tokenizer.decode(ids.to_vec(), true).unwrap());
+        assert_eq!(output, "Hello worlds! Hello");
+        assert_eq!(
+            spans_text(&output, &spans),
+            vec!["Hello", " world", "s", "!", " Hello"]
+        );
+        assert_eq!(
+            spans.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 1]
+        );
+        assert_eq!(spans.last().unwrap().1.end, output.chars().count());
+
+        // A decoder merging the tokens: the character goes to the token that completes it
+        tokenizer.with_decoder(ByteFallback);
+        let (output, spans) = tokenizer
+            .decode_with_spans(&[1, 5, 6, 7, 4], false)
+            .unwrap();
+        assert_eq!(output, "Hello✅!");
+        assert_eq!(
+            spans,
+            vec![(1, 0..5), (5, 5..5), (6, 5..5), (7, 5..6), (4, 6..7)]
+        );
+    }
+
+    #[test]
+    fn encode_batch_streaming() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::pre_tokenizers::whitespace::Whitespace;
+
+        let vocab = [
+            ("[UNK]".to_string(), 0),
+            ("a".to_string(), 1),
+            ("b".to_string(), 2),
+        ]
+        .iter()
+        .cloned()
+        .collect::<HashMap<_, _>>();
+        let mut tokenizer = Tokenizer::new(
+            WordLevel::builder()
+                .vocab(vocab)
+                .unk_token("[UNK]".into())
+                .build()
+                .unwrap(),
+        );
+        tokenizer.with_pre_tokenizer(Whitespace::default());
+
+        // Enough inputs to span several chunks, input `i` having `i % 7 + 1` words
+        let inputs = (0..STREAMING_CHUNK_SIZE * 2 + 10)
+            .map(|i| (0..=i % 7).map(|_| "a").collect::<Vec<_>>().join(" "))
+            .collect::<Vec<_>>();
+
+        let mut lengths = tokenizer
+            .encode_batch_streaming(inputs.iter().map(|s| s.as_str()), false)
+            .map(|encoding| encoding.unwrap().len())
+            .collect::<Vec<_>>();
+        let mut expected = (0..inputs.len()).map(|i| i % 7 + 1).collect::<Vec<_>>();
+        lengths.sort_unstable();
+        expected.sort_unstable();
+        assert_eq!(lengths, expected);
+
+        // When the padding fails, each input still gets its own result
+        tokenizer.with_padding(Some(PaddingParams {
+            pad_id: 7,
+            validate_pad_id: true,
+            ..Default::default()
+        }));
+        let results = tokenizer
+            .encode_batch_streaming(inputs.iter().map(|s| s.as_str()), false)
+            .collect::<Vec<_>>();
+        assert_eq!(results.len(), inputs.len());
+        assert!(results.iter().all(|result| result.is_err()));
+    }
+
+    #[test]
+    fn train_like_files() {
+        use crate::models::bpe::BpeTrainerBuilder;
+        use crate::models::TrainerWrapper;
+        use crate::pre_tokenizers::byte_level::ByteLevel;
+        use std::io::Write;
+
+        let lines = vec![
+            "Hello there, my friend\n".to_string(),
+            "Hello again, friends\r\n".to_string(),
+            "there there".to_string(),
+        ];
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(lines.concat().as_bytes()).unwrap();
+
+        let get_tokenizer = || {
+            let mut tokenizer = Tokenizer::new(BPE::default());
+            tokenizer.with_pre_tokenizer(ByteLevel::default());
+            tokenizer
+        };
+        let get_trainer = || -> TrainerWrapper {
+            BpeTrainerBuilder::new()
+                .show_progress(false)
+                .vocab_size(300)
+                .build()
+                .into()
+        };
+
+        let mut from_files = get_tokenizer();
+        from_files
+            .train_from_files(
+                &mut get_trainer(),
+                vec![file.path().to_str().unwrap().to_string()],
+            )
+            .unwrap();
+
+        let mut from_iterator = get_tokenizer();
+        from_iterator
+            .train(&mut get_trainer(), lines.into_iter())
+            .unwrap();
+
+        assert_eq!(from_files.get_vocab(true), from_iterator.get_vocab(true));
+        assert_eq!(from_files.get_model(), from_iterator.get_model());
+    }
+
+    #[test]
+    fn find_tokens() {
+        use crate::models::bpe::BpeTrainerBuilder;
+        use crate::models::TrainerWrapper;
+        use crate::pre_tokenizers::whitespace::Whitespace;
+
+        let mut tokenizer = Tokenizer::new(BPE::default());
+        tokenizer.with_pre_tokenizer(Whitespace::default());
+        let mut trainer: TrainerWrapper = BpeTrainerBuilder::new()
+            .show_progress(false)
+            .vocab_size(100)
+            .special_tokens(vec![AddedToken::from("<th>", true)])
+            .build()
+            .into();
+        let lines = [
+            "the other one is there",
+            "that is the thing",
+            "this or that",
+        ];
+        tokenizer.train(&mut trainer, lines.iter()).unwrap();
+        tokenizer
+            .add_tokens(&[
+                AddedToken::from("<|the_end|>", false),
+                AddedToken::from("[SEP]", true),
+            ])
+            .unwrap();
+
+        let found = tokenizer.find_tokens("th");
+        assert!(found.iter().all(|(token, _)| token.contains("th")));
+        assert!(found.windows(2).all(|w| w[0].1 < w[1].1));
+        assert!(found.contains(&("<th>".into(), 0)));
+        assert!(found.iter().any(|(token, _)| token == "the"));
+        let end_id = tokenizer.token_to_id("<|the_end|>").unwrap();
+        assert_eq!(found.last(), Some(&("<|the_end|>".into(), end_id)));
+
+        let mut expected = tokenizer
+            .get_vocab(true)
+            .into_iter()
+            .filter(|(token, _)| token.contains("th"))
+            .collect::<Vec<_>>();
+        expected.sort_unstable_by_key(|(_, id)| *id);
+        assert_eq!(found, expected);
+        assert!(tokenizer.find_tokens("xyz").is_empty());
+    }
+
+    #[test]
+    fn regex_added_tokens() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+
+        let vocab = [
+            ("<unk>", 0),
+            ("Fill", 1),
+            ("in", 2),
+            ("<extra_id_0>", 10),
+            ("<extra_id_1>", 11),
+            ("<extra_id_2>", 12),
+        ]
+        .iter()
+        .map(|(t, i)| (t.to_string(), *i))
+        .collect::<HashMap<_, _>>();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("<unk>".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(WhitespaceSplit::default());
+        tokenizer
+            .add_special_tokens(&[AddedToken::regex_range(r"<extra_id_\d+>", 10..13)
+                .unwrap()
+                .lstrip(true)])
+            .unwrap();
+
+        let encoding = tokenizer
+            .encode("Fill <extra_id_0> in <extra_id_2><extra_id_1>", false)
+            .unwrap();
+        assert_eq!(encoding.get_ids(), &[1, 10, 2, 12, 11]);
+        assert_eq!(
+            encoding.get_tokens(),
+            &[
+                "Fill",
+                " <extra_id_0>",
+                "in",
+                " <extra_id_2>",
+                "<extra_id_1>"
+            ]
+        );
+        assert_eq!(encoding.get_offsets()[3], (20, 33));
+        assert_eq!(
+            tokenizer.decode(encoding.get_ids().to_vec(), true).unwrap(),
+            "Fill in"
+        );
+    }
+
+    #[test]
+    fn encode_empty_input() {
+        use crate::pre_tokenizers::byte_level::ByteLevel;
+        use crate::pre_tokenizers::metaspace::Metaspace;
+        use crate::pre_tokenizers::sequence::Sequence as PreTokenizerSequence;
+        use crate::pre_tokenizers::whitespace::Whitespace;
+
+        let pre_tokenizers: Vec<PreTokenizerWrapper> = vec![
+            ByteLevel::default().add_prefix_space(true).into(),
+            Whitespace::default().into(),
+            Metaspace::default().into(),
+            PreTokenizerSequence::new(vec![
+                Metaspace::default().into(),
+                ByteLevel::default().into(),
+            ])
+            .into(),
+        ];
+        for pre_tokenizer in pre_tokenizers {
+            let mut tokenizer = Tokenizer::new(BPE::default());
+            tokenizer.with_pre_tokenizer(pre_tokenizer);
+            let encoding = tokenizer.encode("", false).unwrap();
+            assert!(encoding.is_empty());
+            assert!(encoding.get_offsets().is_empty());
+            assert!(encoding.get_tokens().is_empty());
+        }
+    }
+
+    #[test]
+    fn set_padding_token() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+
+        let vocab = [("<unk>", 0), ("<eos>", 1), ("a", 2), ("b", 3)]
+            .iter()
+            .map(|(t, i)| (t.to_string(), *i))
+            .collect::<HashMap<_, _>>();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("<unk>".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(WhitespaceSplit::default());
+        assert_eq!(tokenizer.pad_token_id(), None);
+
+        assert!(tokenizer.set_padding_token("<pad>").is_err());
+        assert!(tokenizer.get_padding().is_none());
+
+        tokenizer.with_padding(Some(PaddingParams {
+            strategy: PaddingStrategy::Fixed(4),
+            ..Default::default()
+        }));
+        tokenizer.set_padding_token("<eos>").unwrap();
+        assert_eq!(tokenizer.pad_token_id(), Some(1));
+        let padding = tokenizer.get_padding().unwrap();
+        assert_eq!(padding.pad_token, "<eos>");
+        assert!(matches!(padding.strategy, PaddingStrategy::Fixed(4)));
+
+        let encoding = tokenizer.encode("a b", false).unwrap();
+        assert_eq!(encoding.get_ids(), [2, 3, 1, 1]);
+        assert_eq!(encoding.get_tokens(), ["a", "b", "<eos>", "<eos>"]);
+
+        // The added tokens can be used too
+        tokenizer.with_padding(None);
+        tokenizer
+            .add_special_tokens(&[AddedToken::from("<pad>", true)])
+            .unwrap();
+        tokenizer.set_padding_token("<pad>").unwrap();
+        assert_eq!(tokenizer.pad_token_id(), Some(4));
+        let params = tokenizer.padding_params_for("<pad>").unwrap();
+        assert_eq!(params.pad_id, 4);
+        assert!(matches!(params.strategy, PaddingStrategy::BatchLongest));
+
+        // The pad id can be checked against the vocabulary
+        tokenizer.with_padding(Some(PaddingParams {
+            pad_id: 7,
+            validate_pad_id: true,
+            ..Default::default()
+        }));
+        assert!(tokenizer.encode("a b", false).is_err());
+        assert!(tokenizer.encode_batch(vec!["a", "a b"], false).is_err());
+        tokenizer.get_padding_mut().unwrap().pad_id = 4;
+        let encodings = tokenizer.encode_batch(vec!["a", "a b"], false).unwrap();
+        assert_eq!(encodings[0].get_ids(), [2, 4]);
+    }
+
+    #[test]
+    fn is_roundtrip_lossless() {
+        use crate::decoders::wordpiece::WordPiece as WordPieceDecoder;
+        use crate::models::wordlevel::WordLevel;
+        use crate::models::wordpiece::WordPiece;
+        use crate::pre_tokenizers::bert::BertPreTokenizer;
+        use crate::pre_tokenizers::byte_level::ByteLevel;
+        use crate::pre_tokenizers::whitespace::Whitespace;
+
+        let vocab = |tokens: &[&str]| {
+            tokens
+                .iter()
+                .enumerate()
+                .map(|(i, t)| (t.to_string(), i as u32))
+                .collect::<HashMap<_, _>>()
+        };
+
+        // Each byte has its own token, so nothing can get lost
+        let alphabet = ByteLevel::alphabet()
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        let alphabet = alphabet.iter().map(String::as_str).collect::<Vec<_>>();
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab(&alphabet), vec![])
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(bpe);
+        tokenizer
+            .with_pre_tokenizer(ByteLevel::default().add_prefix_space(false))
+            .with_decoder(ByteLevel::default());
+        let report = tokenizer
+            .is_roundtrip_lossless("Hello,  wörld! ✅")
+            .unwrap();
+        assert!(report.is_lossless());
+        assert_eq!(report.decoded, "Hello,  wörld! ✅");
+
+        // WordPiece moves the spaces around the punctuation
+        let wordpiece = WordPiece::builder()
+            .vocab(vocab(&["[UNK]", "hello", ",", "world", "!"]))
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(wordpiece);
+        tokenizer
+            .with_pre_tokenizer(BertPreTokenizer)
+            .with_decoder(WordPieceDecoder::default());
+        let reports = tokenizer
+            .is_roundtrip_lossless_batch(&["hello, world!", "hello ,world", "hello wörld"])
+            .unwrap();
+        assert_eq!(reports[0].first_divergence, None);
+        assert_eq!(reports[1].decoded, "hello, world");
+        assert_eq!(reports[1].first_divergence, Some(5));
+        assert_eq!(reports[2].decoded, "hello [UNK]");
+        assert_eq!(reports[2].first_divergence, Some(6));
+
+        // Without any decoder, the tokens get joined with a single space
+        let wordlevel = WordLevel::builder()
+            .vocab(vocab(&["<unk>", "hello", "world"]))
+            .unk_token("<unk>".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(wordlevel);
+        tokenizer.with_pre_tokenizer(Whitespace::default());
+        assert!(tokenizer
+            .is_roundtrip_lossless("hello world")
+            .unwrap()
+            .is_lossless());
+        let report = tokenizer.is_roundtrip_lossless("hello world ").unwrap();
+        assert_eq!(report.decoded, "hello world");
+        assert_eq!(report.first_divergence, Some(11));
+        let report = tokenizer.is_roundtrip_lossless("helloworld").unwrap();
+        assert_eq!(report.first_divergence, Some(0));
+        assert_eq!(
+            RoundtripReport::new("ab", "abc".into()).first_divergence,
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn component_accessors() {
+        use crate::decoders::DecoderWrapper;
+        use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+        use crate::pre_tokenizers::PreTokenizerWrapper;
+
+        let mut tokenizer: Tokenizer = r#"{
+            "version": "1.0",
+            "truncation": null,
+            "padding": null,
+            "added_tokens": [],
+            "normalizer": {"type": "Lowercase"},
+            "pre_tokenizer": {"type": "Whitespace"},
+            "post_processor": null,
+            "decoder": {"type": "BPEDecoder", "suffix": "</w>"},
+            "model": {
+                "type": "WordLevel",
+                "vocab": {"<unk>": 0, "hello": 1, "!": 2, "hello!": 3},
+                "unk_token": "<unk>"
+            }
+        }"#
+        .parse()
+        .unwrap();
+        assert!(matches!(
+            tokenizer.get_pre_tokenizer(),
+            Some(PreTokenizerWrapper::Whitespace(_))
+        ));
+        assert!(matches!(
+            tokenizer.get_normalizer(),
+            Some(NormalizerWrapper::Lowercase(_))
+        ));
+        assert!(tokenizer.get_post_processor().is_none());
+        assert!(tokenizer.get_post_processor_mut().is_none());
+        assert_eq!(tokenizer.encode("Hello!", false).unwrap().get_ids(), [1, 2]);
+
+        *tokenizer.get_pre_tokenizer_mut().unwrap() = WhitespaceSplit::default().into();
+        assert_eq!(tokenizer.encode("Hello!", false).unwrap().get_ids(), [3]);
+
+        match tokenizer.get_decoder_mut() {
+            Some(DecoderWrapper::BPE(decoder)) => decoder.suffix = "_".into(),
+            _ => panic!("Expected a BPEDecoder"),
+        }
+        let serialized = tokenizer.to_string(false).unwrap();
+        assert!(serialized.contains(r#""pre_tokenizer":{"type":"WhitespaceSplit"}"#));
+        assert!(serialized.contains(r#""suffix":"_""#));
+    }
+
+    #[test]
+    fn train_from_counters() {
+        use crate::models::wordlevel::{WordLevel, WordLevelTrainer};
+        use crate::models::TrainerWrapper;
+
+        let counter = |words: &[(&str, u32)]| {
+            words
+                .iter()
+                .map(|(w, c)| (w.to_string(), *c))
+                .collect::<HashMap<_, _>>()
+        };
+        let common = counter(&[("the", 10), ("cat", 8), ("dog", 6)]);
+        let rare = counter(&[("axolotl", 3), ("the", 1)]);
+        let train = |rare_weight: f64| {
+            let mut tokenizer = Tokenizer::new(WordLevel::default());
+            let mut trainer: TrainerWrapper = WordLevelTrainer::builder()
+                .show_progress(false)
+                .vocab_size(3)
+                .build()
+                .unwrap()
+                .into();
+            tokenizer
+                .train_from_counters(
+                    &mut trainer,
+                    vec![(common.clone(), 1.0), (rare.clone(), rare_weight)],
+                )
+                .unwrap();
+            tokenizer.get_vocab(false)
+        };
+
+        let vocab = train(1.0);
+        assert!(vocab.contains_key("dog"));
+        assert!(!vocab.contains_key("axolotl"));
+
+        // Upweighting the rare corpus makes its words survive the vocab cap
+        let vocab = train(2.5);
+        assert!(vocab.contains_key("axolotl"));
+        assert!(!vocab.contains_key("dog"));
+
+        // The counts saturate instead of overflowing
+        let mut tokenizer = Tokenizer::new(WordLevel::default());
+        let mut trainer: TrainerWrapper = WordLevelTrainer::builder()
+            .show_progress(false)
+            .build()
+            .unwrap()
+            .into();
+        tokenizer
+            .train_from_counters(
+                &mut trainer,
+                vec![(common.clone(), f64::MAX), (common, 1.0), (rare, 0.0)],
+            )
+            .unwrap();
+        assert_eq!(tokenizer.get_vocab_size(false), 3);
+    }
+
+    #[test]
+    fn empty_inputs() {
+        use crate::models::{
+            unigram::Unigram, wordlevel::WordLevel, wordpiece::WordPiece, ModelWrapper,
+        };
+        use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+        use crate::processors::bert::BertProcessing;
+
+        let models: Vec<ModelWrapper> = vec![
+            BPE::default().into(),
+            WordPiece::default().into(),
+            Unigram::default().into(),
+            WordLevel::default().into(),
+        ];
+        for model in models {
+            let mut tokenizer = Tokenizer::new(model);
+            tokenizer
+                .with_pre_tokenizer(WhitespaceSplit::default())
+                .with_post_processor(BertProcessing::new(
+                    ("[SEP]".into(), 2),
+                    ("[CLS]".into(), 1),
+                ));
+
+            assert!(tokenizer.encode("", false).unwrap().is_empty());
+            let encoding = tokenizer.encode("", true).unwrap();
+            assert_eq!(encoding.get_tokens(), ["[CLS]", "[SEP]"]);
+            let encoding = tokenizer.encode(("", ""), true).unwrap();
+            assert_eq!(encoding.get_tokens(), ["[CLS]", "[SEP]", "[SEP]"]);
+            assert_eq!(tokenizer.decode(vec![], true).unwrap(), "");
+
+            // Padding to a length of 0 leaves everything as it is
+            tokenizer.with_padding(Some(PaddingParams {
+                strategy: PaddingStrategy::Fixed(0),
+                ..Default::default()
+            }));
+            let encodings = tokenizer.encode_batch(vec!["", " "], true).unwrap();
+            assert!(encodings.iter().all(|e| e.len() == 2));
+
+            // There is no room left for any token once the special ones are added
+            tokenizer.with_truncation(Some(TruncationParams {
+                max_length: 0,
+                ..Default::default()
+            }));
+            let encoding = tokenizer.encode("", true).unwrap();
+            assert_eq!(encoding.get_tokens(), ["[CLS]", "[SEP]"]);
+            assert!(tokenizer.encode("", false).unwrap().is_empty());
+            let encoding = tokenizer.encode(("", ""), true).unwrap();
+            assert_eq!(encoding.len(), 3);
+        }
+    }
+
+    #[test]
+    fn truncation_stride_longer_than_sequence() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+
+        let vocab = [("[UNK]", 0), ("a", 1), ("b", 2)]
+            .iter()
+            .map(|(t, i)| (t.to_string(), *i))
+            .collect::<HashMap<_, _>>();
+        let mut tokenizer = Tokenizer::new(
+            WordLevel::builder()
+                .vocab(vocab)
+                .unk_token("[UNK]".into())
+                .build()
+                .unwrap(),
+        );
+        tokenizer
+            .with_pre_tokenizer(WhitespaceSplit::default())
+            .with_truncation(Some(TruncationParams {
+                max_length: 3,
+                stride: 2,
+                ..Default::default()
+            }));
+
+        // Each sequence of the pair gets fewer tokens than the stride, which is reduced
+        let encoding = tokenizer.encode(("a a a a", "b b b b"), false).unwrap();
+        assert_eq!(encoding.len(), 3);
+        assert!(!encoding.get_overflowing().is_empty());
+    }
+
+    #[test]
+    fn vocab_breakdown() {
+        use crate::models::wordlevel::WordLevel;
+
+        let vocab = [("[UNK]", 0), ("[CLS]", 1), ("hello", 2), ("world", 3)]
+            .iter()
+            .map(|(t, i)| (t.to_string(), *i))
+            .collect::<HashMap<_, _>>();
+        let mut tokenizer = Tokenizer::new(
+            WordLevel::builder()
+                .vocab(vocab)
+                .unk_token("[UNK]".into())
+                .build()
+                .unwrap(),
+        );
+        tokenizer
+            .add_special_tokens(&[
+                AddedToken::from("[CLS]", true),
+                AddedToken::from("[SEP]", true),
+                AddedToken::from("[MASK]", true),
+            ])
+            .unwrap();
+        tokenizer
+            .add_tokens(&[
+                AddedToken::from("hello", false),
+                AddedToken::from("foo", false),
+            ])
+            .unwrap();
+
+        let breakdown = tokenizer.vocab_breakdown();
+        assert_eq!(
+            breakdown,
+            VocabBreakdown {
+                base: 4,
+                added_special: 2,
+                added_regular: 1,
+            }
+        );
+        assert_eq!(
+            breakdown.base + breakdown.added_special + breakdown.added_regular,
+            tokenizer.get_vocab_size(true)
+        );
+        assert_eq!(breakdown.base, tokenizer.get_vocab_size(false));
+    }
+
+    #[test]
+    fn token_surface_from_original() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::normalizers::Lowercase;
+        use crate::pre_tokenizers::whitespace::Whitespace;
+
+        let vocab = [("[UNK]", 0), ("hello", 1), ("world", 2)]
+            .iter()
+            .map(|(t, i)| (t.to_string(), *i))
+            .collect::<HashMap<_, _>>();
+        let mut tokenizer = Tokenizer::new(
+            WordLevel::builder()
+                .vocab(vocab)
+                .unk_token("[UNK]".into())
+                .build()
+                .unwrap(),
+        );
+        tokenizer
+            .with_normalizer(Lowercase)
+            .with_pre_tokenizer(Whitespace::default());
+
+        let original = "Hello World";
+        let encoding = tokenizer.encode(original, false).unwrap();
+        assert_eq!(encoding.get_tokens(), ["hello", "world"]);
+        assert_eq!(
+            encoding.token_surface_from_original(0, original),
+            Some("Hello")
+        );
+        assert_eq!(
+            encoding.token_surface_from_original(1, original),
+            Some("World")
+        );
+        assert_eq!(encoding.token_surface_from_original(2, original), None);
+    }
+
+    #[test]
+    fn encode_truncated() {
+        use crate::models::wordlevel::{WordLevel, WordLevelFallback};
+        use crate::models::UnkPolicy;
+        use crate::pre_tokenizers::whitespace::Whitespace;
+        use crate::processors::bert::BertProcessing;
+
+        let vocab = [
+            ("[CLS]", 0),
+            ("[SEP]", 1),
+            ("hel", 2),
+            ("lo", 3),
+            ("world", 4),
+        ]
+        .iter()
+        .map(|(t, i)| (t.to_string(), *i))
+        .collect::<HashMap<_, _>>();
+        let mut tokenizer = Tokenizer::new(
+            WordLevel::builder()
+                .vocab(vocab)
+                .unk_policy(UnkPolicy::Error)
+                .fallback(WordLevelFallback::CharNgram { n: 3 })
+                .build()
+                .unwrap(),
+        );
+        tokenizer
+            .with_pre_tokenizer(Whitespace::default())
+            .with_post_processor(BertProcessing::new(
+                ("[SEP]".into(), 1),
+                ("[CLS]".into(), 0),
+            ));
+
+        // The same as truncating, but without any overflowing encoding
+        let input = "hello world hello world";
+        let truncated = |max_tokens: usize| {
+            let mut truncating = tokenizer.clone();
+            truncating.with_truncation(Some(TruncationParams {
+                max_length: max_tokens + 2,
+                ..Default::default()
+            }));
+            let expected = truncating.encode(input, true).unwrap();
+            let encoding = tokenizer.encode_truncated(input, max_tokens).unwrap();
+            assert_eq!(encoding.get_ids(), expected.get_ids());
+            assert_eq!(encoding.get_type_ids(), expected.get_type_ids());
+            assert_eq!(encoding.get_offsets(), expected.get_offsets());
+            assert_eq!(encoding.get_word_ids(), expected.get_word_ids());
+            assert_eq!(encoding.get_sequence_ids(), expected.get_sequence_ids());
+            assert_eq!(
+                encoding.get_special_tokens_mask(),
+                expected.get_special_tokens_mask()
+            );
+            assert_eq!(encoding.get_attention_mask(), expected.get_attention_mask());
+            assert!(encoding.get_overflowing().is_empty());
+            encoding
+        };
+        assert_eq!(
+            truncated(3).get_tokens(),
+            ["[CLS]", "hel", "lo", "world", "[SEP]"]
+        );
+        // The last word is cut too, when its split goes past `max_tokens`
+        let encoding = truncated(4);
+        assert_eq!(
+            encoding.get_tokens(),
+            ["[CLS]", "hel", "lo", "world", "hel", "[SEP]"]
+        );
+        assert_eq!(encoding.get_offsets()[4], (12, 15));
+
+        // The tail is never tokenized, so its unknown words don't matter
+        let input = "hello world unknown";
+        assert!(tokenizer.encode(input, true).is_err());
+        let encoding = tokenizer.encode_truncated(input, 3).unwrap();
+        assert_eq!(encoding.get_ids(), [0, 2, 3, 4, 1]);
+
+        let encoding = tokenizer
+            .encode_truncated(&["hello", "world", "unknown"][..], 2)
+            .unwrap();
+        assert_eq!(encoding.get_ids(), [0, 2, 3, 1]);
+        assert_eq!(encoding.get_word_ids(), [None, Some(0), Some(0), None]);
+    }
+
+    #[test]
+    fn unk_error_offset() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::models::{UnkError, UnkPolicy};
+        use crate::pre_tokenizers::whitespace::Whitespace;
+
+        let vocab = [("fi".to_string(), 0)].iter().cloned().collect();
+        let mut tokenizer = Tokenizer::new(
+            WordLevel::builder()
+                .vocab(vocab)
+                .unk_policy(UnkPolicy::Error)
+                .build()
+                .unwrap(),
+        );
+        tokenizer
+            .with_normalizer(NFKC)
+            .with_pre_tokenizer(Whitespace::default());
+
+        // The offset is in the original sequence, where `ﬁ` takes 3 bytes
+        let error = tokenizer.encode("ﬁ fi unknown", false).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<UnkError>(),
+            Some(&UnkError {
+                substring: "unknown".into(),
+                offset: 7,
+            })
+        );
+    }
+
+    #[test]
+    fn added_tokens_offsets_mid_text() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::pre_tokenizers::whitespace::Whitespace;
+        use crate::processors::bert::BertProcessing;
+
+        let vocab = [("[UNK]", 0), ("[CLS]", 1), ("[SEP]", 2)]
+            .iter()
+            .map(|(t, i)| (t.to_string(), *i))
+            .collect::<HashMap<_, _>>();
+        let mut tokenizer = Tokenizer::new(
+            WordLevel::builder()
+                .vocab(vocab)
+                .unk_token("[UNK]".into())
+                .build()
+                .unwrap(),
+        );
+        tokenizer
+            .with_normalizer(Sequence::new(vec![NFKC.into(), Lowercase.into()]))
+            .with_pre_tokenizer(Whitespace::default())
+            .with_post_processor(BertProcessing::new(
+                ("[SEP]".into(), 2),
+                ("[CLS]".into(), 1),
+            ));
+        tokenizer
+            .add_special_tokens(&[AddedToken::from("<sep>", true)])
+            .unwrap();
+        tokenizer
+            .add_tokens(&[AddedToken::from("<Mid>", false).normalized(false)])
+            .unwrap();
+
+        // The normalizer changes the length of what comes before the added tokens
+        let input = "Ｈｅｌｌｏ ﬁne <sep> my<Mid>friend <sep>";
+        for (encoding, in_chars) in [
+            (tokenizer.encode(input, true).unwrap(), false),
+            (tokenizer.encode_char_offsets(input, true).unwrap(), true),
+        ]
+        .iter()
+        {
+            let text = |(start, end): (usize, usize)| -> String {
+                if *in_chars {
+                    input.chars().skip(start).take(end - start).collect()
+                } else {
+                    input[start..end].to_owned()
+                }
+            };
+            let added = encoding
+                .get_tokens()
+                .iter()
+                .zip(encoding.get_offsets())
+                .filter(|(token, _)| token.starts_with('<'))
+                .map(|(token, offsets)| (token.as_str(), text(*offsets)))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                added,
+                vec![
+                    ("<sep>", "<sep>".into()),
+                    ("<Mid>", "<Mid>".into()),
+                    ("<sep>", "<sep>".into())
+                ]
+            );
+        }
+
+        let encoding = tokenizer.encode(input, true).unwrap();
+        assert_eq!(encoding.get_offsets()[3], (22, 27));
+        assert_eq!(encoding.get_tokens()[3], "<sep>");
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn encode_traced() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::pre_tokenizers::whitespace::Whitespace;
+        use crate::processors::bert::BertProcessing;
+        use std::time::Duration;
+
+        let vocab = [("[UNK]", 0), ("[CLS]", 1), ("[SEP]", 2), ("hello", 3)]
+            .iter()
+            .map(|(t, i)| (t.to_string(), *i))
+            .collect::<HashMap<_, _>>();
+        let mut tokenizer = Tokenizer::new(
+            WordLevel::builder()
+                .vocab(vocab)
+                .unk_token("[UNK]".into())
+                .build()
+                .unwrap(),
+        );
+        tokenizer
+            .with_normalizer(Sequence::new(vec![NFKC.into(), Lowercase.into()]))
+            .with_pre_tokenizer(Whitespace::default())
+            .with_post_processor(BertProcessing::new(
+                ("[SEP]".into(), 2),
+                ("[CLS]".into(), 1),
+            ));
+
+        let input = vec!["Hello there"; 100].join(" ");
+        let (encoding, trace) = tokenizer
+            .encode_traced((input.as_str(), input.as_str()), true)
+            .unwrap();
+        assert_eq!(
+            encoding,
+            tokenizer
+                .encode((input.as_str(), input.as_str()), true)
+                .unwrap()
+        );
+        assert!(trace.normalize > Duration::ZERO);
+        assert!(trace.pre_tokenize > Duration::ZERO);
+        assert!(trace.model > Duration::ZERO);
+        assert!(trace.post_process > Duration::ZERO);
+
+        let (encoding, _) = tokenizer
+            .encode_traced(&["Hello", "there"][..], false)
+            .unwrap();
+        assert_eq!(encoding.get_word_ids(), [Some(0), Some(1)]);
+
+        // The input limits apply as with `encode`
+        tokenizer.with_input_limits(Some(10), None);
+        assert!(tokenizer.encode_traced("Hello there", false).is_err());
+        tokenizer.with_input_limits(None, Some(1));
+        assert!(tokenizer
+            .encode_traced(&["Hello", "there"][..], false)
+            .is_err());
+        assert!(tokenizer.encode_traced("Hello", false).is_ok());
+    }
+
+    #[test]
+    fn encode_batch_into() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+
+        let vocab = [("[UNK]", 0), ("[PAD]", 1), ("a", 2), ("b", 3)]
+            .iter()
+            .map(|(t, i)| (t.to_string(), *i))
+            .collect::<HashMap<_, _>>();
+        let mut tokenizer = Tokenizer::new(
+            WordLevel::builder()
+                .vocab(vocab)
+                .unk_token("[UNK]".into())
+                .build()
+                .unwrap(),
+        );
+        tokenizer
+            .with_pre_tokenizer(WhitespaceSplit::default())
+            .with_padding(Some(PaddingParams::default()))
+            .with_truncation(Some(TruncationParams {
+                max_length: 4,
+                ..Default::default()
+            }));
+
+        let mut out = vec![];
+        let batch = vec!["a b a b a", "b", "a c"];
+        tokenizer
+            .encode_batch_into(batch.clone(), false, &mut out)
+            .unwrap();
+        assert_eq!(out, tokenizer.encode_batch(batch, false).unwrap());
+        let ids = out[0].get_ids().as_ptr();
+        let tokens = out[1].get_tokens().as_ptr();
+
+        // The existing encodings are refilled, and the extra ones are dropped
+        let batch = vec!["b a", "a a a"];
+        tokenizer
+            .encode_batch_into(batch.clone(), false, &mut out)
+            .unwrap();
+        assert_eq!(out, tokenizer.encode_batch(batch, false).unwrap());
+        assert_eq!(out[0].get_ids().as_ptr(), ids);
+        assert_eq!(out[1].get_tokens().as_ptr(), tokens);
+
+        let batch = vec!["b", "a b", "b b b b b b"];
+        tokenizer
+            .encode_batch_into(batch.clone(), false, &mut out)
+            .unwrap();
+        assert_eq!(out, tokenizer.encode_batch(batch, false).unwrap());
+        assert_eq!(out[2].get_overflowing().len(), 1);
+    }
+
+    #[test]
+    fn thread_pool() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+        use crate::{DecoderWrapper, PostProcessorWrapper, PreTokenizerWrapper};
+        use std::collections::HashSet;
+        use std::sync::Mutex;
+
+        /// Records the threads it runs on, and whether they belong to a rayon pool
+        #[derive(Default)]
+        struct ThreadRecorder(Mutex<HashSet<(std::thread::ThreadId, Option<usize>)>>);
+        impl Normalizer for ThreadRecorder {
+            fn normalize(&self, _: &mut NormalizedString) -> Result<()> {
+                let thread = (std::thread::current().id(), rayon::current_thread_index());
+                self.0.lock().unwrap().insert(thread);
+                Ok(())
+            }
+        }
+
+        let vocab: HashMap<String, u32> = [("[UNK]", 0), ("a", 1), ("b", 2)]
+            .iter()
+            .map(|(t, id)| (t.to_string(), *id))
+            .collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let mut tokenizer: TokenizerImpl<
+            WordLevel,
+            ThreadRecorder,
+            PreTokenizerWrapper,
+            PostProcessorWrapper,
+            DecoderWrapper,
+        > = TokenizerImpl::new(model);
+        tokenizer
+            .with_normalizer(ThreadRecorder::default())
+            .with_pre_tokenizer(WhitespaceSplit::default())
+            .with_padding(Some(PaddingParams::default()));
+        let inputs = (0..1000)
+            .map(|i| if i % 2 == 0 { "a b" } else { "b c a" })
+            .collect::<Vec<_>>();
+        let expected = tokenizer.encode_batch(inputs.clone(), false).unwrap();
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        tokenizer.set_thread_pool(Some(Arc::new(pool)));
+        assert!(tokenizer.get_thread_pool().is_some());
+        tokenizer
+            .get_normalizer()
+            .unwrap()
+            .0
+            .lock()
+            .unwrap()
+            .clear();
+        let encodings = tokenizer.encode_batch(inputs, false).unwrap();
+        assert_eq!(encodings, expected);
+        assert_eq!(encodings[0].get_attention_mask(), [1, 1, 0]);
+        assert_eq!(encodings[1].get_ids(), [2, 0, 1]);
+
+        // All the work happened on the 2 threads of the pool
+        let threads = tokenizer
+            .get_normalizer()
+            .unwrap()
+            .0
+            .lock()
+            .unwrap()
+            .clone();
+        assert!(!threads.is_empty() && threads.len() <= 2);
+        assert!(threads.iter().all(|(_, index)| index.is_some()));
+        assert_eq!(
+            tokenizer
+                .decode_batch(vec![vec![1, 2], vec![2]], false)
+                .unwrap(),
+            ["a b", "b"]
+        );
+
+        tokenizer.set_thread_pool(None);
+        assert!(tokenizer.get_thread_pool().is_none());
+    }
+
+    #[test]
+    fn input_limits() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::normalizers::replace::Replace;
+        use crate::pre_tokenizers::whitespace::Whitespace;
+
+        let vocab: HashMap<String, u32> = [("[UNK]", 0), ("hello", 1), ("world", 2), ("!", 3)]
+            .iter()
+            .map(|(t, id)| (t.to_string(), *id))
+            .collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(Whitespace::default());
+        assert_eq!(*tokenizer.get_input_limits(), InputLimits::default());
+        let too_large = |result: Result<_>| -> InputTooLarge {
+            result
+                .map(|_: Encoding| ())
+                .unwrap_err()
+                .downcast_ref::<InputTooLarge>()
+                .cloned()
+                .unwrap()
+        };
+
+        tokenizer.with_input_limits(Some(11), None);
+        assert!(tokenizer.encode("hello world", false).is_ok());
+        assert_eq!(
+            too_large(tokenizer.encode("hello world!", false)),
+            InputTooLarge::Bytes {
+                bytes: 12,
+                limit: 11
+            }
+        );
+        assert!(tokenizer.count_tokens("hello world!", false).is_err());
+        assert!(tokenizer
+            .encode_pretokenized(&["hello", "world", "!!"], false)
+            .is_err());
+
+        // The normalized sequence must fit too
+        tokenizer.with_normalizer(Replace::new("!", "!!!").unwrap());
+        tokenizer.with_input_limits(Some(12), None);
+        assert_eq!(
+            too_large(tokenizer.encode("hello world!", false)),
+            InputTooLarge::Bytes {
+                bytes: 14,
+                limit: 12
+            }
+        );
+
+        // The pre-tokens of all the words of a pre-tokenized sequence add up
+        tokenizer.with_input_limits(None, Some(2));
+        assert!(tokenizer.encode("hello world", false).is_ok());
+        assert_eq!(
+            too_large(tokenizer.encode(vec!["hello", "world", "hello"], false)),
+            InputTooLarge::PreTokens {
+                pretokens: 3,
+                limit: 2
+            }
+        );
+        assert!(tokenizer.count_tokens("hello world hello", false).is_err());
+        assert!(tokenizer
+            .encode_pretokenized(&["hello", "world", "hello"], false)
+            .is_err());
+        assert!(tokenizer.encode("hello world hello", false).is_err());
+
+        tokenizer.with_input_limits(None, None);
+        assert!(tokenizer.encode("hello world hello!", false).is_ok());
+    }
+
+    #[test]
+    fn decode_into() {
+        use crate::decoders::wordpiece::WordPiece;
+        use crate::models::wordlevel::WordLevel;
+        use crate::pre_tokenizers::metaspace::Metaspace;
+
+        let vocab: HashMap<String, u32> = [("[UNK]", 0), ("▁hello", 1), ("▁world", 2), ("##s", 3)]
+            .iter()
+            .map(|(t, id)| (t.to_string(), *id))
+            .collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer
+            .add_special_tokens(&[AddedToken::from("[CLS]", true)])
+            .unwrap();
+        tokenizer
+            .add_tokens(&[AddedToken::from("▁added", false)])
+            .unwrap();
+        let ids = [4, 1, 9, 2, 3, 5, 0];
+
+        // The same as decoding the tokens, which allocates each of them
+        let decoders: [Option<DecoderWrapper>; 3] = [
+            None,
+            Some(Metaspace::default().into()),
+            Some(WordPiece::default().into()),
+        ];
+        for decoder in decoders {
+            tokenizer.0.decoder = decoder;
+            for skip_special_tokens in [false, true] {
+                let tokens = tokenizer.decode_tokens(&ids, skip_special_tokens).unwrap();
+                let expected = match tokenizer.get_decoder() {
+                    Some(decoder) => decoder.decode(tokens).unwrap(),
+                    None => tokens.join(" "),
+                };
+                let mut out = String::from("> ");
+                tokenizer
+                    .decode_into(&ids, skip_special_tokens, &mut out)
+                    .unwrap();
+                assert_eq!(out, format!("> {}", expected));
+                assert_eq!(
+                    tokenizer.decode(ids.to_vec(), skip_special_tokens).unwrap(),
+                    expected
+                );
+            }
+        }
+
+        tokenizer.with_decoder(Metaspace::default());
+        assert_eq!(
+            tokenizer.decode(ids.to_vec(), true).unwrap(),
+            "hello world##s added[UNK]"
+        );
+    }
+
+    #[test]
+    fn added_tokens_id_collision() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+
+        // A vocabulary with a hole, its size being the id of `[UNK]`
+        let vocab: HashMap<String, u32> = [("a", 0), ("b", 1), ("[UNK]", 3)]
+            .iter()
+            .map(|(t, id)| (t.to_string(), *id))
+            .collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(WhitespaceSplit::default());
+        let tokens = [AddedToken::from("[CLS]", true)];
+
+        assert!(tokenizer.try_add_special_tokens(&tokens, true).is_err());
+        assert_eq!(tokenizer.token_to_id("[CLS]"), None);
+
+        assert_eq!(tokenizer.try_add_special_tokens(&tokens, false).unwrap(), 1);
+        let encoding = tokenizer.encode("[CLS] a c", false).unwrap();
+        assert_eq!(encoding.get_ids(), [4, 0, 3]);
+        assert_eq!(encoding.get_tokens(), ["[CLS]", "a", "[UNK]"]);
+        assert_eq!(tokenizer.id_to_token(3).as_deref(), Some("[UNK]"));
+        assert_eq!(tokenizer.id_to_token(4).as_deref(), Some("[CLS]"));
+    }
+
+    #[test]
+    fn decode_with_cleanup() {
+        use crate::models::wordlevel::WordLevel;
+
+        let vocab: HashMap<String, u32> = [("[UNK]", 0), ("hello", 1), (".", 2), ("world", 3)]
+            .iter()
+            .map(|(t, id)| (t.to_string(), *id))
+            .collect();
+        let tokenizer = Tokenizer::new(WordLevel::builder().vocab(vocab).build().unwrap());
+        let ids = [1, 2, 3];
+
+        // Off by default, the tokens just get joined with spaces
+        assert_eq!(
+            tokenizer.decode(ids.to_vec(), false).unwrap(),
+            "hello . world"
+        );
+        assert_eq!(
+            tokenizer.decode_with_cleanup(&ids, false, false).unwrap(),
+            "hello . world"
+        );
+        assert_eq!(
+            tokenizer.decode_with_cleanup(&ids, false, true).unwrap(),
+            "hello. world"
+        );
+    }
+
+    #[test]
+    fn encode_batch_mixed_inputs() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+        use crate::processors::bert::BertProcessing;
+
+        let vocab: HashMap<String, u32> = [("[UNK]", 0), ("[CLS]", 1), ("[SEP]", 2), ("a", 3)]
+            .iter()
+            .map(|(t, id)| (t.to_string(), *id))
+            .collect();
+        let mut tokenizer = Tokenizer::new(
+            WordLevel::builder()
+                .vocab(vocab)
+                .unk_token("[UNK]".into())
+                .build()
+                .unwrap(),
+        );
+        tokenizer
+            .with_pre_tokenizer(WhitespaceSplit::default())
+            .with_post_processor(BertProcessing::new(
+                ("[SEP]".into(), 2),
+                ("[CLS]".into(), 1),
+            ));
+
+        let inputs: Vec<EncodeInput> = vec!["a b".into(), ("a", "a a").into()];
+        let encodings = tokenizer.encode_batch(inputs.clone(), true).unwrap();
+        for (input, encoding) in inputs.into_iter().zip(&encodings) {
+            assert_eq!(&tokenizer.encode(input, true).unwrap(), encoding);
+        }
+        assert_eq!(encodings[0].get_ids(), [1, 3, 0, 2]);
+        assert_eq!(encodings[0].get_type_ids(), [0, 0, 0, 0]);
+        assert_eq!(encodings[1].get_ids(), [1, 3, 2, 3, 3, 2]);
+        assert_eq!(encodings[1].get_type_ids(), [0, 0, 0, 1, 1, 1]);
+        assert_eq!(encodings[1].get_special_tokens_mask(), [1, 0, 1, 0, 0, 1]);
+    }
+
+    #[test]
+    fn freeze() {
+        use crate::models::wordlevel::{WordLevel, WordLevelTrainer};
+        use crate::models::TrainerWrapper;
+
+        let vocab: HashMap<String, u32> = [("[UNK]", 0), ("a", 1)]
+            .iter()
+            .map(|(t, id)| (t.to_string(), *id))
+            .collect();
+        let mut tokenizer = Tokenizer::new(
+            WordLevel::builder()
+                .vocab(vocab)
+                .unk_token("[UNK]".into())
+                .build()
+                .unwrap(),
+        );
+        tokenizer
+            .add_tokens(&[AddedToken::from("b", false)])
+            .unwrap();
+        let vocab = tokenizer.get_vocab(true);
+        assert!(!tokenizer.is_frozen());
+        tokenizer.freeze();
+        assert!(tokenizer.is_frozen());
+
+        let err = tokenizer
+            .try_add_tokens(&[AddedToken::from("c", false)], false)
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<TokenizerError>(),
+            Some(&TokenizerError::Frozen)
+        );
+        assert!(tokenizer
+            .try_add_special_tokens(&[AddedToken::from("[CLS]", true)], false)
+            .is_err());
+        let err = tokenizer
+            .add_tokens(&[AddedToken::from("c", false)])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<TokenizerError>(),
+            Some(&TokenizerError::Frozen)
+        );
+        assert!(tokenizer
+            .add_special_tokens(&[AddedToken::from("[CLS]", true)])
+            .is_err());
+        assert!(tokenizer.set_padding_token("a").is_err());
+        let mut trainer: TrainerWrapper = WordLevelTrainer::default().into();
+        assert!(tokenizer
+            .train_from_counter(&mut trainer, HashMap::from([("d".to_string(), 1)]))
+            .is_err());
+        assert!(tokenizer.with_model(WordLevel::default()).is_err());
+        assert_eq!(tokenizer.get_vocab(true), vocab);
+
+        // Reads still work, and a clone stays frozen
+        assert_eq!(tokenizer.encode("ab", false).unwrap().get_ids(), [1, 2]);
+        assert_eq!(tokenizer.decode(vec![1, 2], false).unwrap(), "a b");
+        assert!(tokenizer.clone().is_frozen());
+    }
+}
@@ -156,6 +156,57 @@ impl NormalizedString {
         )
     }
 
+    /// Whether this string hasn't been normalized in any way yet
+    pub(crate) fn is_unmodified(&self) -> bool {
+        self.normalized == self.original
+            && self.alignments.len() == self.original.len()
+            && self.original.char_indices().all(|(b, c)| {
+                let len = c.len_utf8();
+                self.alignments[b..b + len]
+                    .iter()
+                    .all(|offsets| *offsets == (b, b + len))
+            })
+    }
+
+    /// Take the normalized content and alignments of `other`, which must have the same
+    /// original string. The offsets of `self` in its own original string are kept.
+    pub(crate) fn set_normalized_from(&mut self, other: &NormalizedString) {
+        debug_assert_eq!(self.original, other.original);
+        self.normalized.clone_from(&other.normalized);
+        self.alignments.clone_from(&other.alignments);
+    }
+
+    /// Start over with the given original string, as if it was just converted into a
+    /// `NormalizedString`, while keeping the buffers already allocated
+    pub(crate) fn reset(&mut self, original: &str) {
+        self.original.clear();
+        self.original.push_str(original);
+        self.normalized.clear();
+        self.normalized.push_str(original);
+        self.alignments.clear();
+        self.alignments.extend(identity_alignments(original));
+        self.original_shift = 0;
+    }
+
+    /// Return the ranges of the original string that don't appear at all in the
+    /// normalized one, because the normalization removed them
+    pub fn removed_ranges(&self) -> Vec<Offsets> {
+        let mut kept = vec![false; self.len_original()];
+        for (start, end) in &self.alignments {
+            kept[*start..*end].iter_mut().for_each(|k| *k = true);
+        }
+
+        let mut ranges: Vec<Offsets> = vec![];
+        for (i, _) in kept.iter().enumerate().filter(|(_, kept)| !**kept) {
+            let i = self.original_shift + i;
+            match ranges.last_mut() {
+                Some(range) if range.1 == i => range.1 += 1,
+                _ => ranges.push((i, i + 1)),
+            }
+        }
+        ranges
+    }
+
     /// Convert the given offsets range from one referential to the other one:
     /// `Original => Normalized` or `Normalized => Original`
     ///
@@ -719,6 +770,34 @@ impl NormalizedString {
         self.lrstrip(true, true)
     }
 
+    /// Replace each run of consecutive whitespaces by a single space, aligned with the
+    /// whole run in the original string
+    pub fn collapse_whitespace(&mut self) -> &mut Self {
+        let mut normalized = String::with_capacity(self.normalized.len());
+        let mut alignments = Vec::with_capacity(self.alignments.len());
+        let mut in_run = false;
+        for (i, c) in self.normalized.char_indices() {
+            let char_alignments = &self.alignments[i..i + c.len_utf8()];
+            if !c.is_whitespace() {
+                normalized.push(c);
+                alignments.extend_from_slice(char_alignments);
+            } else {
+                let (start, end) = char_alignments[0];
+                match alignments.last_mut() {
+                    Some(last) if in_run => last.1 = last.1.max(end),
+                    _ => {
+                        normalized.push(' ');
+                        alignments.push((start, end));
+                    }
+                }
+            }
+            in_run = c.is_whitespace();
+        }
+        self.normalized = normalized;
+        self.alignments = alignments;
+        self
+    }
+
     fn lrstrip(&mut self, left: bool, right: bool) -> &mut Self {
         let leading_spaces = if left {
             self.get().chars().take_while(|c| c.is_whitespace()).count()
@@ -917,15 +996,17 @@ pub fn char_to_bytes(s: &str, range: std::ops::Range<usize>) -> Option<std::ops:
     Some(start?..end?)
 }
 
+/// The alignments of an unmodified string, with each byte aligned to its whole char
+fn identity_alignments(s: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    s.char_indices().flat_map(|(b, c)| {
+        let len = c.len_utf8();
+        (0..len).map(move |_| (b, b + len))
+    })
+}
+
 impl From<String> for NormalizedString {
     fn from(s: String) -> Self {
-        let alignments = s
-            .char_indices()
-            .flat_map(|(b, c)| {
-                let len = c.len_utf8();
-                (0..len).map(move |_| (b, b + len))
-            })
-            .collect::<Vec<_>>();
+        let alignments = identity_alignments(&s).collect::<Vec<_>>();
         Self {
             original: s.clone(),
             normalized: s,
@@ -983,6 +1064,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn removed_ranges() {
+        // The marks are already decomposed here, so they get removed on their own
+        let mut n = NormalizedString::from("e\u{301}le\u{301}gant\u{7}");
+        n.filter(|c| !c.is_mark_nonspacing() && !c.is_control());
+        assert_eq!(n.get(), "elegant");
+        assert_eq!(n.removed_ranges(), vec![(1, 3), (5, 7), (11, 12)]);
+
+        // Nothing is removed when a char gets replaced
+        let mut n = NormalizedString::from("élégant");
+        n.nfd().filter(|c| !c.is_mark_nonspacing());
+        assert_eq!(n.removed_ranges(), vec![]);
+
+        // The ranges are relative to the whole original string
+        let mut slice = n.slice(Range::Original(2..9)).unwrap();
+        slice.clear();
+        assert_eq!(slice.removed_ranges(), vec![(2, 9)]);
+    }
+
     #[test]
     fn remove_chars_added_by_nfd() {
         let mut n = NormalizedString::from("élégant");
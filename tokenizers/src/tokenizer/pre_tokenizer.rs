@@ -1,3 +1,4 @@
+use crate::models::UnkError;
 use crate::{
     normalizer::Range, Encoding, NormalizedString, OffsetReferential, Offsets, Result, Token,
 };
@@ -10,6 +11,23 @@ pub enum OffsetType {
     Char,
 }
 
+/// Tokenize the given split, shifting the offset of an [`UnkError`] from the split to the
+/// original sequence
+fn tokenize_split<F>(tokenize: &F, normalized: &NormalizedString) -> Result<Vec<Token>>
+where
+    F: Fn(&NormalizedString) -> Result<Vec<Token>>,
+{
+    tokenize(normalized).map_err(|mut error| {
+        if let Some(unk) = error.downcast_mut::<UnkError>() {
+            let range = unk.offset..unk.offset + unk.substring.len();
+            if let Some(original) = normalized.convert_offsets(Range::Normalized(range)) {
+                unk.offset = normalized.offsets_original().0 + original.start;
+            }
+        }
+        error
+    })
+}
+
 /// Wrapper for a subpart of a `NormalizedString`.
 ///
 /// This Split contains the underlying `NormalizedString` as well as its offsets
@@ -49,10 +67,13 @@ impl From<(NormalizedString, Option<Vec<Token>>)> for Split {
 /// Once everything has been normalized and tokenized, the `PreTokenizedString` is able
 /// to build an `Encoding` with all the relevant offsets and word ids, relative to the
 /// original string.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct PreTokenizedString {
     original: String,
     splits: Vec<Split>,
+    /// An always empty buffer, kept to swap with `splits` when splitting, so that
+    /// each split doesn't need a new allocation
+    spare: Vec<Split>,
 }
 
 impl PreTokenizedString {
@@ -75,7 +96,8 @@ impl PreTokenizedString {
         R: Into<Split>,
     {
         // new_splits is at least as big as self.splits
-        let mut new_splits = Vec::with_capacity(self.splits.len());
+        let mut new_splits = std::mem::take(&mut self.spare);
+        new_splits.reserve(self.splits.len());
         for (i, original_split) in self.splits.drain(..).enumerate() {
             if original_split.tokens.is_some() {
                 new_splits.push(original_split);
@@ -95,11 +117,51 @@ impl PreTokenizedString {
                     }),
             );
         }
-        self.splits = new_splits;
+        self.spare = std::mem::replace(&mut self.splits, new_splits);
 
         Ok(())
     }
 
+    /// Start over with the given sequence, as if it was just converted into a
+    /// `PreTokenizedString`, while keeping the buffers already allocated
+    pub(crate) fn reset(&mut self, sequence: &str) {
+        self.original.clear();
+        self.original.push_str(sequence);
+        let recycled = self.splits.pop();
+        self.splits.clear();
+        if !sequence.is_empty() {
+            let normalized = match recycled {
+                Some(Split { mut normalized, .. }) => {
+                    normalized.reset(sequence);
+                    normalized
+                }
+                None => sequence.into(),
+            };
+            self.splits.push(normalized.into());
+        }
+    }
+
+    /// Whether there is no split left, in which case there is nothing to pre-tokenize
+    pub fn is_empty(&self) -> bool {
+        self.splits.is_empty()
+    }
+
+    /// The number of splits
+    pub(crate) fn len(&self) -> usize {
+        self.splits.len()
+    }
+
+    /// The total length in bytes of the normalized splits
+    pub(crate) fn normalized_len(&self) -> usize {
+        self.splits.iter().map(|split| split.normalized.len()).sum()
+    }
+
+    /// The `NormalizedString` of each split, for a pre-tokenizer to reuse the splits of
+    /// another one
+    pub(crate) fn into_normalized_splits(self) -> impl Iterator<Item = NormalizedString> {
+        self.splits.into_iter().map(|split| split.normalized)
+    }
+
     /// Normalized all the splits that do not have attached `Tokens`, using the provided
     /// `normalize` function.
     pub fn normalize<F>(&mut self, normalize: F) -> Result<()>
@@ -119,12 +181,35 @@ impl PreTokenizedString {
         F: Fn(&NormalizedString) -> Result<Vec<Token>>,
     {
         for split in self.splits.iter_mut().filter(|s| s.tokens.is_none()) {
-            split.tokens = Some(tokenize(&split.normalized)?);
+            split.tokens = Some(tokenize_split(&tokenize, &split.normalized)?);
         }
 
         Ok(())
     }
 
+    /// Tokenize the splits in order like `tokenize`, but only until they have at least
+    /// `max_tokens` tokens. The splits after them are removed, without being tokenized.
+    pub(crate) fn tokenize_until<F>(&mut self, tokenize: F, max_tokens: usize) -> Result<()>
+    where
+        F: Fn(&NormalizedString) -> Result<Vec<Token>>,
+    {
+        let mut n_tokens = 0;
+        let mut end = self.splits.len();
+        for (i, split) in self.splits.iter_mut().enumerate() {
+            if n_tokens >= max_tokens {
+                end = i;
+                break;
+            }
+            if split.tokens.is_none() {
+                split.tokens = Some(tokenize_split(&tokenize, &split.normalized)?);
+            }
+            n_tokens += split.tokens.as_ref().map_or(0, Vec::len);
+        }
+        self.splits.truncate(end);
+
+        Ok(())
+    }
+
     /// Transform the current `PreTokenizedString` into an `Encoding`.
     ///
     /// If a `word_idx` is provided, any word in the generated `Encoding`
@@ -137,6 +222,40 @@ impl PreTokenizedString {
         word_idx: Option<u32>,
         type_id: u32,
         offset_type: OffsetType,
+    ) -> Result<Encoding> {
+        self.into_encoding_with(word_idx, type_id, offset_type, None)
+    }
+
+    /// Transform the current `PreTokenizedString` into an `Encoding`, like `into_encoding`,
+    /// but with offsets relative to the normalized sequence tracked by `referential`.
+    pub(crate) fn into_encoding_with_referential(
+        self,
+        word_idx: Option<u32>,
+        type_id: u32,
+        offset_type: OffsetType,
+        referential: &NormalizedReferential,
+    ) -> Result<Encoding> {
+        self.into_encoding_with(word_idx, type_id, offset_type, Some(referential))
+    }
+
+    fn into_encoding_with(
+        mut self,
+        word_idx: Option<u32>,
+        type_id: u32,
+        offset_type: OffsetType,
+        referential: Option<&NormalizedReferential>,
+    ) -> Result<Encoding> {
+        self.take_encoding(word_idx, type_id, offset_type, referential)
+    }
+
+    /// Build the `Encoding` like `into_encoding_with` does, but by taking the tokens of the
+    /// splits rather than consuming `self`, whose buffers can then be reused with `reset`.
+    pub(crate) fn take_encoding(
+        &mut self,
+        word_idx: Option<u32>,
+        type_id: u32,
+        offset_type: OffsetType,
+        referential: Option<&NormalizedReferential>,
     ) -> Result<Encoding> {
         if self.splits.is_empty() {
             Ok(Encoding::default())
@@ -144,26 +263,35 @@ impl PreTokenizedString {
             Err("Split has not been tokenized, call `PreTokenizedString::tokenize` first".into())
         } else {
             let offset_converter = match offset_type {
-                OffsetType::Char => Some(BytesToCharOffsetConverter::new(&self.original)),
+                OffsetType::Char => Some(BytesToCharOffsetConverter::new(
+                    referential.map_or(&self.original, |r| &r.normalized),
+                )),
                 OffsetType::Byte => None,
             };
 
             Ok(self
                 .splits
-                .into_iter()
+                .iter_mut()
                 .enumerate()
                 .flat_map(|(idx, split)| {
-                    let normalized = split.normalized;
+                    let normalized = &split.normalized;
                     let offsets = normalized.offsets_original();
                     let offset_converter = &offset_converter;
 
-                    split.tokens.unwrap().into_iter().map(move |token| {
+                    split.tokens.take().unwrap().into_iter().map(move |token| {
+                        // The offsets of the token are relative to its split, so if they can't
+                        // be converted, the whole split is the best we know of its location
                         let mut offsets = normalized
                             .convert_offsets(Range::Normalized(token.offsets.0..token.offsets.1))
-                            .map_or(token.offsets, |range| {
+                            .map_or(offsets, |range| {
                                 (offsets.0 + range.start, offsets.0 + range.end)
                             });
 
+                        // Express them in the normalized sequence if requested
+                        if let Some(referential) = referential {
+                            offsets = referential.convert(offsets).unwrap_or(offsets);
+                        }
+
                         // Convert to char offsets if relevant
                         if let Some(converter) = offset_converter {
                             offsets = converter.convert(offsets).unwrap_or(offsets);
@@ -186,6 +314,14 @@ impl PreTokenizedString {
         }
     }
 
+    /// Return the ranges of the original string that were removed by the normalization
+    pub(crate) fn removed_ranges(&self) -> Vec<Offsets> {
+        self.splits
+            .iter()
+            .flat_map(|split| split.normalized.removed_ranges())
+            .collect()
+    }
+
     /// Returns a list of splits, each of them being a slice of the normalized
     /// string, the associated offsets either in original or normalized
     /// referential, as well as the potention tokens
@@ -225,12 +361,21 @@ impl PreTokenizedString {
 
 impl From<NormalizedString> for PreTokenizedString {
     fn from(s: NormalizedString) -> Self {
-        Self {
-            original: s.get_original().to_owned(),
-            splits: vec![Split {
+        // An empty input doesn't have anything to split, so no pre-tokenizer could add
+        // anything to it, like a prefix space.
+        let original = s.get_original().to_owned();
+        let splits = if s.is_empty() {
+            vec![]
+        } else {
+            vec![Split {
                 normalized: s,
                 tokens: None,
-            }],
+            }]
+        };
+        Self {
+            original,
+            splits,
+            spare: vec![],
         }
     }
 }
@@ -249,6 +394,46 @@ impl From<String> for PreTokenizedString {
     }
 }
 
+/// Keeps track of the splits of a `PreTokenizedString` right after normalization, to be
+/// able to express offsets relative to the normalized sequence even after the
+/// pre-tokenization split or removed some parts of it.
+pub(crate) struct NormalizedReferential {
+    /// Each split, along with its start in the normalized sequence
+    splits: Vec<(usize, NormalizedString)>,
+    /// The full normalized sequence
+    normalized: String,
+}
+
+impl NormalizedReferential {
+    pub fn new(pretokenized: &PreTokenizedString) -> Self {
+        let mut normalized = String::new();
+        let splits = pretokenized
+            .splits
+            .iter()
+            .map(|split| {
+                let start = normalized.len();
+                normalized.push_str(split.normalized.get());
+                (start, split.normalized.clone())
+            })
+            .collect();
+
+        Self { splits, normalized }
+    }
+
+    /// Convert the given offsets from the original referential to the normalized one
+    pub fn convert(&self, offsets: Offsets) -> Option<Offsets> {
+        // Pre-tokenization only ever divides the splits, so any token lives in one of them
+        let (start, split) = self.splits.iter().find(|(_, split)| {
+            let (s, e) = split.offsets_original();
+            s <= offsets.0 && offsets.1 <= e
+        })?;
+        let shift = split.offsets_original().0;
+        split
+            .convert_offsets(Range::Original(offsets.0 - shift..offsets.1 - shift))
+            .map(|range| (start + range.start, start + range.end))
+    }
+}
+
 struct BytesToCharOffsetConverter {
     map: HashMap<usize, usize>,
 }
@@ -169,7 +169,9 @@ where
             }
         }
         let added_tokens: Vec<_> = tokens.into_iter().map(|token| token.token).collect();
-        tokenizer.add_tokens(&added_tokens[..]);
+        tokenizer
+            .add_tokens(&added_tokens[..])
+            .map_err(|e| V::Error::custom(e.to_string()))?;
 
         Ok(tokenizer)
     }
@@ -0,0 +1,135 @@
+use super::Tokenizer;
+use crate::models::{bpe::BPE, ModelWrapper};
+use std::collections::{BTreeSet, HashMap};
+
+/// A model-agnostic view of what a `Tokenizer` knows about, meant to compare two versions of
+/// a tokenizer, like before and after some retraining.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TokenizerSpec {
+    /// All the tokens with their id, added tokens included
+    pub vocab: HashMap<String, u32>,
+    /// The merges, ordered by rank, if the model is a BPE
+    pub merges: Vec<(String, String)>,
+    /// The pieces with their score, ordered by id, if the model is a Unigram
+    pub pieces: Vec<(String, f64)>,
+}
+
+/// The differences between two `TokenizerSpec`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SpecDiff {
+    /// The tokens only in the new spec
+    pub added_tokens: BTreeSet<String>,
+    /// The tokens only in the old spec
+    pub removed_tokens: BTreeSet<String>,
+    /// The merges only in the new spec
+    pub added_merges: BTreeSet<(String, String)>,
+    /// The merges only in the old spec
+    pub removed_merges: BTreeSet<(String, String)>,
+}
+
+impl SpecDiff {
+    /// Whether both specs contain the same tokens and merges. The ids, ranks and scores
+    /// might still be different.
+    pub fn is_empty(&self) -> bool {
+        self.added_tokens.is_empty()
+            && self.removed_tokens.is_empty()
+            && self.added_merges.is_empty()
+            && self.removed_merges.is_empty()
+    }
+}
+
+fn ordered_merges(bpe: &BPE) -> Vec<(String, String)> {
+    let mut merges = bpe.merges.iter().collect::<Vec<_>>();
+    merges.sort_unstable_by_key(|(_, (rank, _))| *rank);
+    merges
+        .into_iter()
+        .map(|((a, b), _)| (bpe.vocab_r[a].clone(), bpe.vocab_r[b].clone()))
+        .collect()
+}
+
+impl TokenizerSpec {
+    /// Compute what changed from `self` to `other`
+    pub fn diff(&self, other: &TokenizerSpec) -> SpecDiff {
+        fn added<T: Ord + Clone>(from: &BTreeSet<T>, to: &BTreeSet<T>) -> BTreeSet<T> {
+            to.difference(from).cloned().collect()
+        }
+
+        let tokens = self.vocab.keys().cloned().collect();
+        let other_tokens = other.vocab.keys().cloned().collect();
+        let merges = self.merges.iter().cloned().collect();
+        let other_merges = other.merges.iter().cloned().collect();
+        SpecDiff {
+            added_tokens: added(&tokens, &other_tokens),
+            removed_tokens: added(&other_tokens, &tokens),
+            added_merges: added(&merges, &other_merges),
+            removed_merges: added(&other_merges, &merges),
+        }
+    }
+}
+
+impl Tokenizer {
+    /// Export the vocabulary of this `Tokenizer`, with the merges or scores of its model when
+    /// relevant, in a form that can be compared across versions.
+    pub fn export_spec(&self) -> TokenizerSpec {
+        let (merges, pieces) = match self.get_model() {
+            ModelWrapper::BPE(bpe) => (ordered_merges(bpe), vec![]),
+            ModelWrapper::Unigram(unigram) => (vec![], unigram.iter().cloned().collect()),
+            ModelWrapper::WordPiece(_)
+            | ModelWrapper::WordLevel(_)
+            | ModelWrapper::HashedModel(_) => (vec![], vec![]),
+        };
+        TokenizerSpec {
+            vocab: self.get_vocab(true),
+            merges,
+            pieces,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::bpe::BpeTrainerBuilder;
+    use crate::models::TrainerWrapper;
+    use crate::pre_tokenizers::whitespace::Whitespace;
+    use crate::tokenizer::AddedToken;
+
+    fn train(lines: &[&str]) -> Tokenizer {
+        let mut trainer: TrainerWrapper = BpeTrainerBuilder::new()
+            .show_progress(false)
+            .vocab_size(100)
+            .build()
+            .into();
+        let mut tokenizer = Tokenizer::new(BPE::default());
+        tokenizer.with_pre_tokenizer(Whitespace::default());
+        tokenizer.train(&mut trainer, lines.iter()).unwrap();
+        tokenizer
+    }
+
+    #[test]
+    fn diff_trained_models() {
+        let old = train(&["aaa bbb", "aaa bbb"]);
+        let mut new = train(&["aaa bbb", "aaa bbb", "ccc"]);
+        assert_eq!(old.export_spec(), old.export_spec());
+        assert!(old.export_spec().diff(&old.export_spec()).is_empty());
+
+        let diff = old.export_spec().diff(&new.export_spec());
+        let strings = |tokens: &[&str]| tokens.iter().map(|t| t.to_string()).collect();
+        assert_eq!(diff.added_tokens, strings(&["c", "cc", "ccc"]));
+        assert!(diff.removed_tokens.is_empty());
+        assert_eq!(
+            diff.added_merges,
+            [("c", "c"), ("cc", "c")]
+                .iter()
+                .map(|(a, b)| (a.to_string(), b.to_string()))
+                .collect()
+        );
+        assert!(diff.removed_merges.is_empty());
+
+        // The added tokens are part of the spec too
+        new.add_tokens(&[AddedToken::from("[NEW]", false)]).unwrap();
+        let diff = new.export_spec().diff(&old.export_spec());
+        assert_eq!(diff.removed_tokens, strings(&["[NEW]", "c", "cc", "ccc"]));
+        assert!(diff.added_tokens.is_empty());
+    }
+}
@@ -1,11 +1,23 @@
 use std::borrow::Borrow;
 use std::collections::HashMap;
 use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::RwLock;
 
 /// The default capacity for a `BPE`'s internal cache.
 pub static DEFAULT_CACHE_CAPACITY: usize = 10_000;
 
+/// Statistics about the use of a cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The number of lookups that found their value
+    pub hits: usize,
+    /// The number of lookups that didn't find their value
+    pub misses: usize,
+    /// The number of entries currently in the cache
+    pub size: usize,
+}
+
 /// Provides a simple multithread cache to speed up BPE tokenization that will try to read values
 /// concurrently but won't block if another thread is writing.
 /// The goal is clearly not the accuracy of the content, both get and set
@@ -18,6 +30,8 @@ where
 {
     map: RwLock<HashMap<K, V>>,
     pub capacity: usize,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
 }
 
 // We dont really care about Cache comparison, so let's make them always equal
@@ -49,7 +63,12 @@ where
     /// Create new `Cache` with the given capacity.
     pub(crate) fn new(capacity: usize) -> Self {
         let map = RwLock::new(HashMap::with_capacity(capacity));
-        Cache { map, capacity }
+        Cache {
+            map,
+            capacity,
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
     }
 
     /// Create a fresh `Cache` with the same configuration.
@@ -57,9 +76,20 @@ where
         Self::new(self.capacity)
     }
 
-    /// Clear the cache.
+    /// Clear the cache, also resetting its statistics.
     pub(crate) fn clear(&self) {
         self.map.write().unwrap().clear();
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+
+    /// The statistics about the lookups since the creation of the cache or its last clear.
+    pub(crate) fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            size: self.map.read().unwrap().len(),
+        }
     }
 
     #[allow(dead_code)]
@@ -81,11 +111,18 @@ where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        if let Ok(ref mut cache) = self.map.try_read() {
+        let value = if let Ok(ref mut cache) = self.map.try_read() {
             cache.get(key).cloned()
         } else {
             None
-        }
+        };
+        let counter = if value.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
     }
 
     pub(crate) fn set_values<I>(&self, entries: I)
@@ -33,6 +33,15 @@ where
     ordered.serialize(serializer)
 }
 
+/// The 64 bits FNV-1a hash of the given string, starting from a basis mixed with the `seed`.
+/// Unlike the hashers of the standard library, its output is guaranteed to never change, so
+/// it can be used to seed some random generators or to pick buckets.
+pub(crate) fn fnv1a(s: &str, seed: u64) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325 ^ seed, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
 macro_rules! impl_enum_from (
     ($from_ty:ty, $enum:ty, $variant:ident) => {
         impl From<$from_ty> for $enum {
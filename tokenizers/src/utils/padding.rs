@@ -26,6 +26,18 @@ pub struct PaddingParams {
     pub pad_id: u32,
     pub pad_type_id: u32,
     pub pad_token: String,
+    /// The value of the attention mask for the padding tokens. With 1, the padding tokens
+    /// get attended to like the others, and only the `special_tokens_mask` tells them apart.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub pad_attention_value: u32,
+    /// Whether to check that the `pad_id` is part of the vocabulary each time we pad, failing
+    /// otherwise
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub validate_pad_id: bool,
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
 }
 
 impl Default for PaddingParams {
@@ -37,6 +49,8 @@ impl Default for PaddingParams {
             pad_id: 0,
             pad_type_id: 0,
             pad_token: String::from("[PAD]"),
+            pad_attention_value: 0,
+            validate_pad_id: false,
         }
     }
 }
@@ -47,18 +61,21 @@ pub enum PaddingStrategy {
     Fixed(usize),
 }
 
-pub fn pad_encodings(encodings: &mut [Encoding], params: &PaddingParams) -> Result<()> {
-    if encodings.is_empty() {
-        return Ok(());
-    }
+/// The length to which the given encodings must be padded
+fn padding_length(encodings: &[Encoding], params: &PaddingParams) -> usize {
+    let longest = encodings
+        .maybe_par_iter()
+        .map(|e| e.get_ids().len())
+        .max()
+        .unwrap_or(0);
+    padding_length_for(longest, params)
+}
 
+/// The length to which encodings must be padded, when the longest one has `longest` tokens
+pub(crate) fn padding_length_for(longest: usize, params: &PaddingParams) -> usize {
     let mut pad_length = match params.strategy {
         PaddingStrategy::Fixed(size) => size,
-        PaddingStrategy::BatchLongest => encodings
-            .maybe_par_iter()
-            .map(|e| e.get_ids().len())
-            .max()
-            .unwrap(),
+        PaddingStrategy::BatchLongest => longest,
     };
 
     if let Some(multiple) = params.pad_to_multiple_of {
@@ -67,12 +84,23 @@ pub fn pad_encodings(encodings: &mut [Encoding], params: &PaddingParams) -> Resu
         }
     }
 
+    pad_length
+}
+
+pub fn pad_encodings(encodings: &mut [Encoding], params: &PaddingParams) -> Result<()> {
+    if encodings.is_empty() {
+        return Ok(());
+    }
+
+    let pad_length = padding_length(encodings, params);
+
     encodings.maybe_par_iter_mut().for_each(|encoding| {
-        encoding.pad(
+        encoding.pad_with_attention_value(
             pad_length,
             params.pad_id,
             params.pad_type_id,
             &params.pad_token,
+            params.pad_attention_value,
             params.direction,
         )
     });
@@ -80,10 +108,47 @@ pub fn pad_encodings(encodings: &mut [Encoding], params: &PaddingParams) -> Resu
     Ok(())
 }
 
+/// Pad the ids of the given encodings, and stack them in a single contiguous buffer,
+/// in row-major order. This doesn't modify the encodings, and returns the buffer
+/// along with its shape `(batch_size, sequence_length)`.
+///
+/// This fails if some encoding is longer than the `Fixed` padding length.
+pub fn pad_and_stack(
+    encodings: &[Encoding],
+    params: &PaddingParams,
+) -> Result<(Vec<u32>, (usize, usize))> {
+    let pad_length = padding_length(encodings, params);
+    if let Some(encoding) = encodings.iter().find(|e| e.len() > pad_length) {
+        return Err(format!(
+            "Cannot stack an encoding of length {} with a padding length of {}",
+            encoding.len(),
+            pad_length
+        )
+        .into());
+    }
+
+    let mut stacked = Vec::with_capacity(encodings.len() * pad_length);
+    for encoding in encodings {
+        let padding = (encoding.len()..pad_length).map(|_| params.pad_id);
+        match params.direction {
+            PaddingDirection::Left => {
+                stacked.extend(padding);
+                stacked.extend_from_slice(encoding.get_ids());
+            }
+            PaddingDirection::Right => {
+                stacked.extend_from_slice(encoding.get_ids());
+                stacked.extend(padding);
+            }
+        }
+    }
+
+    Ok((stacked, (encodings.len(), pad_length)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tokenizer::Encoding;
+    use crate::tokenizer::{Encoding, Token};
     use std::collections::HashMap;
 
     #[test]
@@ -124,6 +189,7 @@ mod tests {
             pad_id: 0,
             pad_type_id: 0,
             pad_token: String::from("[PAD]"),
+            ..Default::default()
         };
         pad_encodings(&mut encodings, &params).unwrap();
         assert!(encodings.iter().all(|e| e.get_ids().len() == 8));
@@ -139,4 +205,100 @@ mod tests {
         params.pad_to_multiple_of = Some(0);
         pad_encodings(&mut encodings, &params).unwrap();
     }
+
+    #[test]
+    fn pad_attention_value() {
+        let mut encoding = Encoding::from_tokens(
+            vec![
+                Token::new(1, "a".into(), (0, 1)),
+                Token::new(2, "b".into(), (1, 2)),
+            ],
+            0,
+        );
+        encoding.set_overflowing(vec![Encoding::from_tokens(
+            vec![Token::new(3, "c".into(), (2, 3))],
+            0,
+        )]);
+        let params = PaddingParams {
+            strategy: PaddingStrategy::Fixed(4),
+            direction: PaddingDirection::Left,
+            pad_id: 9,
+            pad_type_id: 1,
+            pad_attention_value: 1,
+            ..Default::default()
+        };
+        let mut encodings = [encoding];
+        pad_encodings(&mut encodings, &params).unwrap();
+        assert_eq!(encodings[0].get_ids(), [9, 9, 1, 2]);
+        assert_eq!(encodings[0].get_type_ids(), [1, 1, 0, 0]);
+        assert_eq!(encodings[0].get_attention_mask(), [1, 1, 1, 1]);
+        assert_eq!(encodings[0].get_special_tokens_mask(), [1, 1, 0, 0]);
+        let overflowing = &encodings[0].get_overflowing()[0];
+        assert_eq!(overflowing.get_ids(), [9, 9, 9, 3]);
+        assert_eq!(overflowing.get_attention_mask(), [1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn serialization() {
+        let params = PaddingParams::default();
+        let serialized = serde_json::to_string(&params).unwrap();
+        assert!(!serialized.contains("pad_attention_value"));
+        assert!(!serialized.contains("validate_pad_id"));
+
+        let params = PaddingParams {
+            pad_attention_value: 1,
+            validate_pad_id: true,
+            ..Default::default()
+        };
+        let serialized = serde_json::to_string(&params).unwrap();
+        let deserialized: PaddingParams = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.pad_attention_value, 1);
+        assert!(deserialized.validate_pad_id);
+    }
+
+    #[test]
+    fn stack() {
+        let encodings = [
+            Encoding::from_tokens(
+                vec![
+                    Token::new(1, "a".into(), (0, 1)),
+                    Token::new(2, "b".into(), (1, 2)),
+                    Token::new(3, "c".into(), (2, 3)),
+                ],
+                0,
+            ),
+            Encoding::from_tokens(vec![Token::new(4, "d".into(), (0, 1))], 0),
+        ];
+
+        let mut params = PaddingParams {
+            pad_id: 9,
+            ..Default::default()
+        };
+        let (stacked, shape) = pad_and_stack(&encodings, &params).unwrap();
+        assert_eq!(shape, (2, 3));
+        assert_eq!(stacked, vec![1, 2, 3, 4, 9, 9]);
+
+        params.direction = PaddingDirection::Left;
+        params.pad_to_multiple_of = Some(4);
+        let (stacked, shape) = pad_and_stack(&encodings, &params).unwrap();
+        assert_eq!(shape, (2, 4));
+        assert_eq!(stacked, vec![9, 1, 2, 3, 9, 9, 9, 4]);
+
+        // It matches what we get with `pad_encodings`
+        let mut padded = encodings.to_vec();
+        pad_encodings(&mut padded, &params).unwrap();
+        let flat = padded
+            .into_iter()
+            .flat_map(|e| e.into_parts().ids)
+            .collect::<Vec<_>>();
+        assert_eq!(stacked, flat);
+
+        params.strategy = PaddingStrategy::Fixed(2);
+        params.pad_to_multiple_of = None;
+        assert!(pad_and_stack(&encodings, &params).is_err());
+
+        let (stacked, shape) = pad_and_stack(&[], &PaddingParams::default()).unwrap();
+        assert_eq!(shape, (0, 0));
+        assert!(stacked.is_empty());
+    }
 }
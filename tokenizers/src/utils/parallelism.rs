@@ -137,6 +137,19 @@ where
     }
 }
 
+/// Run `op` inside the given thread pool, so that the parallel iterators it uses run on the
+/// threads of this pool rather than on the global one. Without a pool, `op` runs as is.
+pub fn install_in<OP, R>(pool: Option<&rayon::ThreadPool>, op: OP) -> R
+where
+    OP: FnOnce() -> R + Send,
+    R: Send,
+{
+    match pool {
+        Some(pool) => pool.install(op),
+        None => op(),
+    }
+}
+
 /// Converts any serial iterator into a CondIterator, that can either run parallelly or serially.
 pub trait MaybeParallelBridge<T, S>
 where
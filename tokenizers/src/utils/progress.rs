@@ -30,3 +30,108 @@ mod progressbar {
 }
 #[cfg(not(feature = "progressbar"))]
 pub(crate) use progressbar::{ProgressBar, ProgressStyle};
+
+use crate::tokenizer::TrainerProgress;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// The `TrainerProgress` given to a trainer, if any. It doesn't take part in the
+/// comparison or serialization of the trainer.
+#[derive(Clone, Default)]
+pub(crate) struct ProgressCallback(pub(crate) Option<Arc<dyn TrainerProgress>>);
+
+impl From<Option<Box<dyn TrainerProgress>>> for ProgressCallback {
+    fn from(callback: Option<Box<dyn TrainerProgress>>) -> Self {
+        Self(callback.map(Arc::from))
+    }
+}
+
+impl std::fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(_) => f.write_str("Some(TrainerProgress)"),
+            None => f.write_str("None"),
+        }
+    }
+}
+
+impl PartialEq for ProgressCallback {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+impl Eq for ProgressCallback {}
+
+/// Reports the progress of a training, either to the given `TrainerProgress`, or with a
+/// progress bar if asked to show progress
+pub(crate) struct Progress {
+    bar: Option<ProgressBar>,
+    callback: Option<Arc<dyn TrainerProgress>>,
+    phase: RwLock<(String, usize)>,
+    current: AtomicUsize,
+}
+
+impl Progress {
+    pub(crate) fn new(show_progress: bool, callback: &ProgressCallback) -> Self {
+        let bar = if show_progress && callback.0.is_none() {
+            let p = ProgressBar::new(0);
+            p.set_style(
+                ProgressStyle::default_bar()
+                    .template("[{elapsed_precise}] {msg:<40!} {wide_bar} {pos:<9!}/{len:>9!}"),
+            );
+            Some(p)
+        } else {
+            None
+        };
+        Self {
+            bar,
+            callback: callback.0.clone(),
+            phase: RwLock::new((String::new(), 0)),
+            current: AtomicUsize::new(0),
+        }
+    }
+
+    /// Start a new phase, with the given number of steps
+    pub(crate) fn start(&self, phase: &str, len: usize) {
+        if let Some(p) = &self.bar {
+            p.set_message(phase);
+            p.set_length(len as u64);
+            p.set_draw_delta(len as u64 / 100);
+            p.reset();
+        }
+        if let Some(callback) = &self.callback {
+            *self.phase.write().unwrap() = (phase.to_owned(), len);
+            self.current.store(0, Ordering::SeqCst);
+            callback.on_phase_start(phase, len);
+        }
+    }
+
+    /// Advance the current phase by the given number of steps
+    pub(crate) fn inc(&self, steps: usize) {
+        if let Some(p) = &self.bar {
+            p.inc(steps as u64);
+        }
+        if let Some(callback) = &self.callback {
+            let current = self.current.fetch_add(steps, Ordering::SeqCst) + steps;
+            let phase = self.phase.read().unwrap();
+            callback.on_advance(&phase.0, current, phase.1);
+        }
+    }
+
+    /// Finish the current phase, after the given number of steps
+    pub(crate) fn finish(&self, final_len: usize) {
+        if let Some(p) = &self.bar {
+            p.set_length(final_len as u64);
+            p.finish();
+            println!();
+        }
+        if let Some(callback) = &self.callback {
+            let phase = self.phase.read().unwrap();
+            callback.on_phase_end(&phase.0, final_len);
+        }
+    }
+}
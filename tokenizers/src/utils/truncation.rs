@@ -1,6 +1,7 @@
 use crate::tokenizer::{Encoding, Result};
 use serde::{Deserialize, Serialize};
 use std::cmp;
+use std::collections::BinaryHeap;
 use std::mem;
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Eq)]
@@ -30,6 +31,18 @@ pub struct TruncationParams {
     pub max_length: usize,
     pub strategy: TruncationStrategy,
     pub stride: usize,
+    /// Whether the special tokens added by the post-processor count in the `max_length`,
+    /// in which case the content is truncated so that the final encoding fits exactly
+    #[serde(default = "default_true")]
+    pub reserve_special_tokens: bool,
+    /// Whether to log a warning each time an input gets truncated, with the number of
+    /// tokens that were dropped
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub warn_on_truncation: bool,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 impl Default for TruncationParams {
@@ -39,6 +52,8 @@ impl Default for TruncationParams {
             strategy: TruncationStrategy::default(),
             stride: 0,
             direction: TruncationDirection::default(),
+            reserve_special_tokens: true,
+            warn_on_truncation: false,
         }
     }
 }
@@ -76,33 +91,26 @@ impl std::convert::AsRef<str> for TruncationStrategy {
     }
 }
 
-pub fn truncate_encodings(
-    mut encoding: Encoding,
-    mut pair_encoding: Option<Encoding>,
+/// The lengths that sequences of the given lengths will have once truncated
+pub(crate) fn truncated_lengths(
+    length: usize,
+    pair_length: Option<usize>,
     params: &TruncationParams,
-) -> Result<(Encoding, Option<Encoding>)> {
+) -> Result<(usize, Option<usize>)> {
     if params.max_length == 0 {
-        encoding.truncate(0, params.stride, params.direction);
-        if let Some(other_encoding) = pair_encoding.as_mut() {
-            other_encoding.truncate(0, params.stride, params.direction);
-        }
-        return Ok((encoding, pair_encoding));
+        return Ok((0, pair_length.map(|_| 0)));
     }
 
-    let total_length = encoding.get_ids().len()
-        + pair_encoding
-            .as_ref()
-            .map(|e| e.get_ids().len())
-            .unwrap_or(0);
+    let total_length = length + pair_length.unwrap_or(0);
     let to_remove = if total_length > params.max_length {
         total_length - params.max_length
     } else {
-        return Ok((encoding, pair_encoding));
+        return Ok((length, pair_length));
     };
 
     match params.strategy {
         TruncationStrategy::LongestFirst => {
-            if let Some(other_encoding) = pair_encoding.as_mut() {
+            if let Some(pair_length) = pair_length {
                 // Assuming n1 <= n2, there are 3 cases
                 // Case 1:
                 //   No truncation needs to be performed.
@@ -116,8 +124,8 @@ pub fn truncate_encodings(
                 //   n1 = max_length / 2
                 //   n2 = n1 + max_length % 2
 
-                let mut n1 = encoding.get_ids().len();
-                let mut n2 = other_encoding.get_ids().len();
+                let mut n1 = length;
+                let mut n2 = pair_length;
                 let mut swap = false;
 
                 // Ensure n1 is the length of the shortest input
@@ -144,32 +152,81 @@ pub fn truncate_encodings(
                 if swap {
                     mem::swap(&mut n1, &mut n2);
                 }
-                encoding.truncate(n1, params.stride, params.direction);
-                other_encoding.truncate(n2, params.stride, params.direction);
+                Ok((cmp::min(n1, length), Some(cmp::min(n2, pair_length))))
             } else {
-                encoding.truncate(total_length - to_remove, params.stride, params.direction);
+                Ok((total_length - to_remove, None))
             }
         }
         TruncationStrategy::OnlyFirst | TruncationStrategy::OnlySecond => {
-            let target = if params.strategy == TruncationStrategy::OnlyFirst {
-                Ok(&mut encoding)
-            } else if let Some(encoding) = pair_encoding.as_mut() {
-                Ok(encoding)
+            let target_len = if params.strategy == TruncationStrategy::OnlyFirst {
+                length
             } else {
-                Err(Box::new(TruncationError::SecondSequenceNotProvided))
-            }?;
+                pair_length.ok_or(TruncationError::SecondSequenceNotProvided)?
+            };
+            if target_len <= to_remove {
+                return Err(Box::new(TruncationError::SequenceTooShort));
+            }
 
-            let target_len = target.get_ids().len();
-            if target_len > to_remove {
-                target.truncate(target_len - to_remove, params.stride, params.direction);
+            if params.strategy == TruncationStrategy::OnlyFirst {
+                Ok((target_len - to_remove, pair_length))
             } else {
-                return Err(Box::new(TruncationError::SequenceTooShort));
+                Ok((length, Some(target_len - to_remove)))
             }
         }
     }
+}
+
+pub fn truncate_encodings(
+    mut encoding: Encoding,
+    mut pair_encoding: Option<Encoding>,
+    params: &TruncationParams,
+) -> Result<(Encoding, Option<Encoding>)> {
+    let (length, pair_length) = truncated_lengths(
+        encoding.get_ids().len(),
+        pair_encoding.as_ref().map(|e| e.get_ids().len()),
+        params,
+    )?;
+    // A sequence can get fewer tokens than the stride, which must then be reduced so that
+    // each overflowing part still moves forward
+    let stride = |length: usize| params.stride.min(length.saturating_sub(1));
+    encoding.truncate(length, stride(length), params.direction);
+    if let (Some(other_encoding), Some(pair_length)) = (pair_encoding.as_mut(), pair_length) {
+        other_encoding.truncate(pair_length, stride(pair_length), params.direction);
+    }
     Ok((encoding, pair_encoding))
 }
 
+/// Remove tokens from the given encodings until the sum of their lengths fits in
+/// `total_budget`, always from the one that is currently the longest (the first one in case
+/// of a tie). The special tokens are kept: we remove the last token that isn't special, and
+/// leave the encodings made only of special tokens as they are. This should be done before
+/// padding the encodings.
+pub fn truncate_batch(encodings: &mut [Encoding], total_budget: usize) {
+    let mut total: usize = encodings.iter().map(|e| e.len()).sum();
+    let mut longest = encodings
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (e.len(), cmp::Reverse(i)))
+        .collect::<BinaryHeap<_>>();
+    while total > total_budget {
+        let i = match longest.pop() {
+            Some((_, cmp::Reverse(i))) => i,
+            None => break,
+        };
+        let encoding = &mut encodings[i];
+        if let Some(index) = encoding
+            .get_special_tokens_mask()
+            .iter()
+            .rposition(|mask| *mask == 0)
+        {
+            encoding.remove_token(index);
+            encoding.set_num_truncated_tokens(encoding.num_truncated_tokens() + 1);
+            total -= 1;
+            longest.push((encoding.len(), cmp::Reverse(i)));
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -287,6 +344,8 @@ mod tests {
             strategy: TruncationStrategy::LongestFirst,
             stride: 0,
             direction: TruncationDirection::Right,
+            reserve_special_tokens: true,
+            warn_on_truncation: false,
         };
 
         truncate_and_assert(get_empty(), get_empty(), &params, 0, 0);
@@ -317,6 +376,8 @@ mod tests {
             strategy: TruncationStrategy::LongestFirst,
             stride: 0,
             direction: TruncationDirection::Right,
+            reserve_special_tokens: true,
+            warn_on_truncation: false,
         };
 
         truncate_and_assert(get_empty(), get_short(), &params, 0, 0);
@@ -331,5 +392,58 @@ mod tests {
         let params: TruncationParams = serde_json::from_str(old_truncation_params).unwrap();
 
         assert_eq!(params.direction, TruncationDirection::Right);
+        assert!(params.reserve_special_tokens);
+    }
+
+    #[test]
+    fn truncate_batch_longest_first() {
+        // `[CLS] a b .. [SEP]`, of the given length
+        let get_encoding = |len: usize| {
+            let mut encoding = Encoding::new(
+                (0..len as u32).collect(),
+                vec![0; len],
+                (0..len).map(|i| i.to_string()).collect(),
+                (0..len as u32).map(Some).collect(),
+                (0..len).map(|i| (i, i + 1)).collect(),
+                (0..len)
+                    .map(|i| u32::from(i == 0 || i == len - 1))
+                    .collect(),
+                vec![1; len],
+                vec![],
+                HashMap::new(),
+            );
+            encoding.set_sequence_id_range(1..len - 1, 0).unwrap();
+            encoding
+        };
+        let mut encodings = [get_encoding(10), get_encoding(8), get_encoding(6)];
+
+        truncate_batch(&mut encodings, 18);
+        let lengths = encodings.iter().map(|e| e.len()).collect::<Vec<_>>();
+        assert!(lengths.iter().sum::<usize>() <= 18);
+        assert_eq!(lengths, [6, 6, 6]);
+        for encoding in &encodings {
+            assert_eq!(encoding.get_special_tokens_mask(), &[1, 0, 0, 0, 0, 1]);
+            assert_eq!(encoding.get_ids()[..5], [0, 1, 2, 3, 4]);
+            assert_eq!(
+                encoding.get_offsets()[1..5],
+                [(1, 2), (2, 3), (3, 4), (4, 5)]
+            );
+            assert_eq!(encoding.token_to_sequence(4), Some(0));
+            assert_eq!(encoding.token_to_sequence(5), None);
+        }
+
+        // The longest one is reduced first
+        let mut encodings = [get_encoding(10), get_encoding(8), get_encoding(6)];
+        truncate_batch(&mut encodings, 21);
+        let lengths = encodings.iter().map(|e| e.len()).collect::<Vec<_>>();
+        assert_eq!(lengths, [7, 8, 6]);
+        assert_eq!(encodings[0].num_truncated_tokens(), 3);
+        assert_eq!(encodings[1].num_truncated_tokens(), 0);
+
+        // The special tokens are never removed
+        let mut encodings = [get_encoding(4), get_encoding(2)];
+        truncate_batch(&mut encodings, 0);
+        let lengths = encodings.iter().map(|e| e.len()).collect::<Vec<_>>();
+        assert_eq!(lengths, [2, 2]);
     }
 }
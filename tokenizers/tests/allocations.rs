@@ -0,0 +1,99 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use tokenizers::models::wordlevel::WordLevel;
+use tokenizers::normalizers::Lowercase;
+use tokenizers::pre_tokenizers::whitespace::WhitespaceSplit;
+use tokenizers::utils::parallelism::set_parallelism;
+use tokenizers::Tokenizer;
+
+/// Counts all the allocations, to check how many of them encoding some inputs takes
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn count_allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let result = f();
+    (result, ALLOCATIONS.load(Ordering::Relaxed) - before)
+}
+
+fn get_tokenizer() -> Tokenizer {
+    let vocab = ["<unk>", "hello", "world", "!"]
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (t.to_string(), i as u32))
+        .collect();
+    let mut tokenizer = Tokenizer::new(WordLevel::builder().vocab(vocab).build().unwrap());
+    tokenizer
+        .with_normalizer(Lowercase)
+        .with_pre_tokenizer(WhitespaceSplit::default());
+    tokenizer
+}
+
+#[test]
+fn encode_batch_reuses_buffers() {
+    // Everything must happen on the current thread for the counts to make sense
+    set_parallelism(false);
+    let tokenizer = get_tokenizer();
+    let inputs = (0..10_000)
+        .map(|i| format!("Hello world{} hello {}!", "!".repeat(i % 3), i))
+        .collect::<Vec<_>>();
+    let encode = |input: &String| tokenizer.encode(input.as_str(), false).unwrap();
+    // Make sure everything that is lazily initialized already is
+    let expected = encode(&inputs[0]);
+
+    // A new thread needs to allocate its buffers on its first encoding only
+    let ((first, first_count), (second, second_count)) = std::thread::scope(|s| {
+        s.spawn(|| {
+            (
+                count_allocations(|| encode(&inputs[0])),
+                count_allocations(|| encode(&inputs[0])),
+            )
+        })
+        .join()
+        .unwrap()
+    });
+    assert_eq!(first, expected);
+    assert_eq!(second, expected);
+    assert!(
+        second_count < first_count,
+        "{} allocations, {} for the first encoding",
+        second_count,
+        first_count
+    );
+
+    let (encodings, batch_count) = count_allocations(|| {
+        tokenizer
+            .encode_batch(inputs.iter().map(|s| s.as_str()).collect(), false)
+            .unwrap()
+    });
+    assert_eq!(encodings[0], expected);
+    // Even when counting the batch itself, each input needs less than on a new thread
+    let per_input = batch_count / inputs.len();
+    assert!(
+        per_input < first_count,
+        "{} allocations per input, {} for the first encoding",
+        per_input,
+        first_count
+    );
+}
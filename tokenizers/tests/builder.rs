@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+use tokenizers::decoders::byte_level::ByteLevel;
+use tokenizers::decoders::wordpiece::WordPiece as WordPieceDecoder;
+use tokenizers::models::bpe::BPE;
+use tokenizers::models::wordlevel::WordLevel;
+use tokenizers::pre_tokenizers::sequence::Sequence;
+use tokenizers::pre_tokenizers::whitespace::Whitespace;
+use tokenizers::processors::bert::BertProcessing;
+use tokenizers::utils::padding::{PaddingParams, PaddingStrategy};
+use tokenizers::utils::truncation::TruncationParams;
+use tokenizers::{
+    DecoderWrapper, ModelWrapper, NormalizerWrapper, PostProcessorWrapper, PreTokenizerWrapper,
+    TokenizerBuilder, TokenizerImpl,
+};
+
+type Builder = TokenizerBuilder<
+    WordLevel,
+    NormalizerWrapper,
+    PreTokenizerWrapper,
+    PostProcessorWrapper,
+    DecoderWrapper,
+>;
+type Tokenizer = TokenizerImpl<
+    WordLevel,
+    NormalizerWrapper,
+    PreTokenizerWrapper,
+    PostProcessorWrapper,
+    DecoderWrapper,
+>;
+
+fn get_builder(truncation: Option<TruncationParams>, padding: Option<PaddingParams>) -> Builder {
+    let vocab: HashMap<String, u32> = ["[PAD]", "[UNK]", "[CLS]", "[SEP]", "a", "b", "c", "d"]
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (t.to_string(), i as u32))
+        .collect();
+
+    TokenizerBuilder::new()
+        .with_model(
+            WordLevel::builder()
+                .vocab(vocab)
+                .unk_token("[UNK]".into())
+                .build()
+                .unwrap(),
+        )
+        .with_pre_tokenizer(Some(Whitespace::default().into()))
+        .with_post_processor(Some(
+            BertProcessing::new(("[SEP]".into(), 3), ("[CLS]".into(), 2)).into(),
+        ))
+        .with_truncation(truncation)
+        .with_padding(padding)
+}
+
+#[test]
+fn build_with_truncation_and_padding() {
+    let tokenizer: Tokenizer = get_builder(
+        Some(TruncationParams {
+            max_length: 5,
+            ..Default::default()
+        }),
+        Some(PaddingParams {
+            strategy: PaddingStrategy::Fixed(8),
+            ..Default::default()
+        }),
+    )
+    .build()
+    .unwrap();
+
+    let encoding = tokenizer.encode("a b c d a b", true).unwrap();
+    assert_eq!(encoding.get_ids(), &[2, 4, 5, 6, 3, 0, 0, 0]);
+    assert_eq!(encoding.get_attention_mask(), &[1, 1, 1, 1, 1, 0, 0, 0]);
+    assert_eq!(encoding.get_overflowing().len(), 1);
+}
+
+#[test]
+fn build_validates_truncation() {
+    // Not even enough room for the special tokens
+    let error = get_builder(
+        Some(TruncationParams {
+            max_length: 2,
+            ..Default::default()
+        }),
+        None,
+    )
+    .build()
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Truncation max_length (2) must be greater than the 2 special tokens added by the \
+         post-processor."
+    );
+
+    // The stride doesn't fit in what's left once the special tokens are added
+    let error = get_builder(
+        Some(TruncationParams {
+            max_length: 5,
+            stride: 3,
+            ..Default::default()
+        }),
+        None,
+    )
+    .build()
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Truncation stride (3) must be smaller than the effective max length of 3 \
+         (= 5 max_length - 2 added special tokens)."
+    );
+
+    // Fixed padding shorter than the truncated encodings
+    let error = get_builder(
+        Some(TruncationParams {
+            max_length: 5,
+            ..Default::default()
+        }),
+        Some(PaddingParams {
+            strategy: PaddingStrategy::Fixed(4),
+            ..Default::default()
+        }),
+    )
+    .build()
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Fixed padding length (4) must not be smaller than the truncation max_length (5)."
+    );
+}
+
+#[test]
+fn truncation_reserve_special_tokens() {
+    let input = ["a", "b", "c"].repeat(5).join(" ");
+
+    let tokenizer: Tokenizer = get_builder(
+        Some(TruncationParams {
+            max_length: 10,
+            ..Default::default()
+        }),
+        None,
+    )
+    .build()
+    .unwrap();
+    let encoding = tokenizer.encode(input.as_str(), true).unwrap();
+    assert_eq!(encoding.len(), 10);
+    assert_eq!(encoding.get_ids()[..2], [2, 4]);
+    assert_eq!(encoding.get_ids()[9], 3);
+
+    // Without reserving room for them, the special tokens come on top of the max_length
+    let tokenizer: Tokenizer = get_builder(
+        Some(TruncationParams {
+            max_length: 10,
+            reserve_special_tokens: false,
+            ..Default::default()
+        }),
+        None,
+    )
+    .build()
+    .unwrap();
+    let encoding = tokenizer.encode(input.as_str(), true).unwrap();
+    assert_eq!(encoding.len(), 12);
+}
+
+#[test]
+fn build_checks_decoder() {
+    // A WordPiece decoder doesn't go with a WordLevel model
+    let builder = || get_builder(None, None).with_decoder(Some(WordPieceDecoder::default().into()));
+    let error = builder().with_decoder_check(true).build().unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "The WordPiece decoder needs a WordPiece model."
+    );
+    // Only a warning when not strict, and nothing when not checked
+    assert!(builder().with_decoder_check(false).build().is_ok());
+    assert!(builder().build().is_ok());
+
+    // A ByteLevel decoder needs a ByteLevel pre-tokenizer
+    let error = get_builder(None, None)
+        .with_decoder(Some(ByteLevel::default().into()))
+        .with_decoder_check(true)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "The ByteLevel decoder needs a ByteLevel pre-tokenizer."
+    );
+
+    let tokenizer = TokenizerBuilder::<
+        ModelWrapper,
+        NormalizerWrapper,
+        PreTokenizerWrapper,
+        PostProcessorWrapper,
+        DecoderWrapper,
+    >::new()
+    .with_model(BPE::default().into())
+    .with_pre_tokenizer(Some(
+        Sequence::new(vec![
+            Whitespace::default().into(),
+            ByteLevel::default().into(),
+        ])
+        .into(),
+    ))
+    .with_decoder(Some(ByteLevel::default().into()))
+    .with_decoder_check(true)
+    .build();
+    assert!(tokenizer.is_ok());
+}
@@ -159,7 +159,9 @@ fn split_on_added_tokens_bert() {
     let input = "Yesterday I saw a [MASK] far away";
 
     let mut tokenizer = get_bert();
-    tokenizer.add_special_tokens(&[AddedToken::from("[MASK]", true)]);
+    tokenizer
+        .add_special_tokens(&[AddedToken::from("[MASK]", true)])
+        .unwrap();
     let output = tokenizer.encode(input, false).unwrap();
 
     assert_eq!(
@@ -191,3 +193,77 @@ fn split_on_added_tokens_bert() {
         ]
     );
 }
+
+#[test]
+fn normalized_referential() {
+    use std::collections::HashMap;
+    use tokenizers::models::wordlevel::WordLevel;
+    use tokenizers::normalizers::bert::BertNormalizer;
+    use tokenizers::pre_tokenizers::bert::BertPreTokenizer;
+    use tokenizers::utils::truncation::TruncationParams;
+    use tokenizers::{OffsetReferential, OffsetType, Tokenizer};
+
+    let vocab: HashMap<String, u32> = ["[UNK]", "cafe", "creme", "brulee"]
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (t.to_string(), i as u32))
+        .collect();
+    let mut tokenizer = Tokenizer::new(WordLevel::builder().vocab(vocab).build().unwrap());
+    tokenizer
+        .with_normalizer(BertNormalizer::new(true, true, Some(true), true))
+        .with_pre_tokenizer(BertPreTokenizer);
+    tokenizer
+        .add_special_tokens(&[AddedToken::from("[MASK]", true)])
+        .unwrap();
+
+    let input = "Café [MASK] Crème brûlée";
+    let normalized = "cafe [MASK] creme brulee";
+
+    let output = tokenizer
+        .encode_with_referential(input, false, OffsetReferential::Original, OffsetType::Byte)
+        .unwrap();
+    assert_eq!(
+        output.get_offsets(),
+        tokenizer.encode(input, false).unwrap().get_offsets()
+    );
+    assert_eq!(output.get_offsets(), &[(0, 5), (6, 12), (13, 19), (20, 28)]);
+
+    let output = tokenizer
+        .encode_with_referential(
+            input,
+            false,
+            OffsetReferential::Normalized,
+            OffsetType::Byte,
+        )
+        .unwrap();
+    assert_eq!(output.get_offsets(), &[(0, 4), (5, 11), (12, 17), (18, 24)]);
+    check_offsets!(normalized, output, 0, "cafe");
+    check_offsets!(normalized, output, 1, "[MASK]");
+    check_offsets!(normalized, output, 3, "brulee");
+
+    let output = tokenizer
+        .encode_with_referential(
+            input,
+            false,
+            OffsetReferential::Normalized,
+            OffsetType::Char,
+        )
+        .unwrap();
+    assert_eq!(output.get_offsets(), &[(0, 4), (5, 11), (12, 17), (18, 24)]);
+
+    // The overflowing encodings use the same referential
+    tokenizer.with_truncation(Some(TruncationParams {
+        max_length: 3,
+        ..Default::default()
+    }));
+    let output = tokenizer
+        .encode_with_referential(
+            input,
+            false,
+            OffsetReferential::Normalized,
+            OffsetType::Byte,
+        )
+        .unwrap();
+    assert_eq!(output.get_offsets(), &[(0, 4), (5, 11), (12, 17)]);
+    assert_eq!(output.get_overflowing()[0].get_offsets(), &[(18, 24)]);
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use tokenizers::decoders::byte_fallback::ByteFallback;
+use tokenizers::decoders::byte_level::ByteLevel;
+use tokenizers::decoders::wordpiece::WordPiece as WordPieceDecoder;
+use tokenizers::models::bpe::BPE;
+use tokenizers::models::wordlevel::WordLevel;
+use tokenizers::Tokenizer;
+
+fn vocab(tokens: &[&str]) -> HashMap<String, u32> {
+    tokens
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (t.to_string(), i as u32))
+        .collect()
+}
+
+#[test]
+fn decode_stream_byte_fallback() {
+    let bpe = BPE::builder()
+        .vocab_and_merges(vocab(&["a", "<0xE2>", "<0x9C>", "<0x85>", "b"]), vec![])
+        .build()
+        .unwrap();
+    let mut tokenizer = Tokenizer::new(bpe);
+    tokenizer.with_decoder(ByteFallback);
+
+    let mut stream = tokenizer.decode_stream(false);
+    assert_eq!(stream.step(0).unwrap(), Some("a".to_string()));
+    // The bytes of `✅` only make a character once they are all there
+    assert_eq!(stream.step(1).unwrap(), None);
+    assert_eq!(stream.step(2).unwrap(), None);
+    assert_eq!(stream.step(3).unwrap(), Some("✅".to_string()));
+    assert_eq!(stream.step(4).unwrap(), Some("b".to_string()));
+    assert_eq!(stream.finish().unwrap(), None);
+
+    // The incomplete character only gets yielded once the stream is finished
+    let mut stream = tokenizer.decode_stream(false);
+    assert_eq!(stream.step(0).unwrap(), Some("a".to_string()));
+    assert_eq!(stream.step(1).unwrap(), None);
+    assert_eq!(stream.step(2).unwrap(), None);
+    assert_eq!(
+        stream.finish().unwrap(),
+        Some("\u{FFFD}\u{FFFD}".to_string())
+    );
+}
+
+#[test]
+fn decode_stream_spaces() {
+    let wordlevel = WordLevel::builder()
+        .vocab(vocab(&["<unk>", "Hello", "world", "##s", "!"]))
+        .build()
+        .unwrap();
+    let mut tokenizer = Tokenizer::new(wordlevel);
+    tokenizer.with_decoder(WordPieceDecoder::default());
+
+    let ids = [1, 2, 3, 4];
+    let full = tokenizer.decode(ids.to_vec(), false).unwrap();
+    assert_eq!(full, "Hello worlds!");
+
+    let mut stream = tokenizer.decode_stream(false);
+    let streamed = ids
+        .iter()
+        .map(|id| stream.step(*id).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        streamed,
+        vec![
+            Some("Hello".to_string()),
+            Some(" world".to_string()),
+            Some("s".to_string()),
+            Some("!".to_string())
+        ]
+    );
+    assert_eq!(streamed.into_iter().flatten().collect::<String>(), full);
+}
+
+#[test]
+fn decode_stream_byte_level() {
+    // `Ġ` is the space, and `âľ` + `ħ` the bytes of `✅`
+    let bpe = BPE::builder()
+        .vocab_and_merges(vocab(&["Hello", "Ġworld", "Ġâľ", "ħ"]), vec![])
+        .build()
+        .unwrap();
+    let mut tokenizer = Tokenizer::new(bpe);
+    tokenizer.with_decoder(ByteLevel::default());
+
+    let mut stream = tokenizer.decode_stream(false);
+    assert_eq!(stream.step(0).unwrap(), Some("Hello".to_string()));
+    assert_eq!(stream.step(1).unwrap(), Some(" world".to_string()));
+    assert_eq!(stream.step(2).unwrap(), None);
+    assert_eq!(stream.step(3).unwrap(), Some(" ✅".to_string()));
+    assert_eq!(stream.finish().unwrap(), None);
+}